@@ -61,6 +61,12 @@ pub async fn main() -> Result<()> {
                 Sub::QueryResult(QueryResult(args)) => {
                     rpc::query_result(ctx, args).await;
                 }
+                Sub::QueryStorage(QueryStorage(args)) => {
+                    rpc::query_storage(args).await;
+                }
+                Sub::QueryRaw(QueryRaw(args)) => {
+                    rpc::query_raw(args).await;
+                }
                 // Gossip cmds
                 Sub::Intent(Intent(args)) => {
                     gossip::gossip_intent(ctx, args).await;
@@ -71,6 +77,12 @@ pub async fn main() -> Result<()> {
                 Sub::SubscribeTopic(SubscribeTopic(args)) => {
                     gossip::subscribe_topic(ctx, args).await;
                 }
+                Sub::SubscribeMatches(SubscribeMatches(args)) => {
+                    gossip::subscribe_matches(ctx, args).await;
+                }
+                Sub::InspectIntent(InspectIntent(args)) => {
+                    gossip::inspect_intent(ctx, args).await;
+                }
             }
         }
         cli::AnomaClient::WithoutContext(cmd, global_args) => match cmd {
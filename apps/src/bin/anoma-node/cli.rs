@@ -2,6 +2,7 @@
 
 use anoma_apps::cli::{self, args, cmds};
 use anoma_apps::node::{gossip, ledger, matchmaker};
+use byte_unit::Byte;
 use eyre::{Context, Result};
 
 pub fn main() -> Result<()> {
@@ -11,14 +12,61 @@ pub fn main() -> Result<()> {
     }
     match cmd {
         cmds::AnomaNode::Ledger(sub) => match sub {
-            cmds::Ledger::Run(_) => {
+            cmds::Ledger::Run(cmds::LedgerRun(args::LedgerRun { force })) => {
+                ctx.config.ledger.shell.force_chain_id = force;
+                ctx.config
+                    .ledger
+                    .check_ports()
+                    .wrap_err("Cannot start the Anoma ledger")?;
                 let wasm_dir = ctx.wasm_dir();
+                ctx.config
+                    .ledger
+                    .check_dirs(&wasm_dir)
+                    .wrap_err("Cannot start the Anoma ledger")?;
                 ledger::run(ctx.config.ledger, wasm_dir);
             }
             cmds::Ledger::Reset(_) => {
                 ledger::reset(ctx.config.ledger)
                     .wrap_err("Failed to reset Anoma node")?;
             }
+            cmds::Ledger::Compact(_) => {
+                let (size_before, size_after) =
+                    ledger::compact(ctx.config.ledger)
+                        .wrap_err("Failed to compact Anoma node's storage")?;
+                println!(
+                    "Compacted the storage from {} to {}, reclaimed {}.",
+                    Byte::from_bytes(size_before as u128)
+                        .get_appropriate_unit(true),
+                    Byte::from_bytes(size_after as u128)
+                        .get_appropriate_unit(true),
+                    Byte::from_bytes(
+                        size_before.saturating_sub(size_after) as u128
+                    )
+                    .get_appropriate_unit(true),
+                );
+            }
+            cmds::Ledger::DumpState(cmds::LedgerDumpState(
+                args::LedgerDumpState { output },
+            )) => {
+                let count = ledger::dump_state(ctx.config.ledger, &output)
+                    .wrap_err("Failed to dump Anoma node's state")?;
+                println!(
+                    "Dumped {} storage keys to {}.",
+                    count,
+                    output.to_string_lossy()
+                );
+            }
+            cmds::Ledger::ImportState(cmds::LedgerImportState(
+                args::LedgerImportState { input, force },
+            )) => {
+                let (root, count) =
+                    ledger::import_state(ctx.config.ledger, &input, force)
+                        .wrap_err("Failed to import Anoma node's state")?;
+                println!(
+                    "Imported {} storage keys, the Merkle root is {}.",
+                    count, root
+                );
+            }
         },
         cmds::AnomaNode::Gossip(sub) => match sub {
             cmds::Gossip::Run(cmds::GossipRun(args::GossipRun {
@@ -35,6 +83,7 @@ pub fn main() -> Result<()> {
                         .shell
                         .base_dir
                         .join(ctx.global_config.default_chain_id.as_str()),
+                    config.ledger.tendermint.rpc_address,
                 )
                 .wrap_err("Failed to run gossip service")?;
             }
@@ -43,6 +92,9 @@ pub fn main() -> Result<()> {
             intent_gossiper_addr,
             matchmaker_path,
             tx_code_path,
+            tick_interval,
+            working_dir,
+            dry_run,
             ledger_addr,
             tx_signing_key,
             tx_source_address,
@@ -59,6 +111,15 @@ pub fn main() -> Result<()> {
             if tx_code_path.is_some() {
                 mm_config.tx_code_path = tx_code_path;
             }
+            if tick_interval.is_some() {
+                mm_config.tick_interval = tick_interval;
+            }
+            if working_dir.is_some() {
+                mm_config.working_dir = working_dir;
+            }
+            if dry_run {
+                mm_config.dry_run = true;
+            }
 
             matchmaker::run(
                 mm_config,
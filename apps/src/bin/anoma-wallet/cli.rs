@@ -1,6 +1,6 @@
 //! Anoma Wallet CLI.
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, Write};
 
 use anoma::types::key::*;
@@ -36,6 +36,12 @@ pub fn main() -> Result<()> {
                 address_add(ctx, args)
             }
         },
+        cmds::AnomaWallet::Export(cmds::WalletExport(args)) => {
+            wallet_export(ctx, args)
+        }
+        cmds::AnomaWallet::Import(cmds::WalletImport(args)) => {
+            wallet_import(ctx, args)
+        }
     }
     Ok(())
 }
@@ -220,3 +226,43 @@ fn address_add(ctx: Context, args: args::AddressAdd) {
         args.alias.to_lowercase()
     );
 }
+
+/// Export the wallet keys and addresses to an encrypted backup file.
+fn wallet_export(
+    ctx: Context,
+    args::WalletExport { output }: args::WalletExport,
+) {
+    let wallet = ctx.wallet;
+    let data = wallet.export();
+    fs::write(&output, data).unwrap_or_else(|err| {
+        eprintln!("Failed to write the wallet backup to {:?}: {}", output, err);
+        cli::safe_exit(1)
+    });
+    println!("Exported the wallet to file {}", output.to_string_lossy());
+}
+
+/// Import the wallet keys and addresses from an encrypted backup file.
+fn wallet_import(
+    ctx: Context,
+    args::WalletImport { input, force }: args::WalletImport,
+) {
+    let mut wallet = ctx.wallet;
+    let data = fs::read(&input).unwrap_or_else(|err| {
+        eprintln!("Failed to read the wallet backup from {:?}: {}", input, err);
+        cli::safe_exit(1)
+    });
+    let (keys, addresses) = wallet.import(&data, force).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        cli::safe_exit(1)
+    });
+    wallet.save().unwrap_or_else(|err| {
+        eprintln!("Failed to save the imported wallet: {}", err);
+        cli::safe_exit(1)
+    });
+    println!(
+        "Successfully imported {} keys and {} addresses from {}",
+        keys,
+        addresses,
+        input.to_string_lossy()
+    );
+}
@@ -181,10 +181,14 @@ pub mod cmds {
                 .subcommand(QueryVotingPower::def().display_order(3))
                 .subcommand(QuerySlashes::def().display_order(3))
                 .subcommand(QueryResult::def().display_order(3))
+                .subcommand(QueryStorage::def().display_order(3))
+                .subcommand(QueryRaw::def().display_order(3))
                 // Intents
                 .subcommand(Intent::def().display_order(4))
                 .subcommand(AuctionIntent::def().display_order(4))
                 .subcommand(SubscribeTopic::def().display_order(4))
+                .subcommand(SubscribeMatches::def().display_order(4))
+                .subcommand(InspectIntent::def().display_order(4))
                 // Utils
                 .subcommand(Utils::def().display_order(5))
         }
@@ -209,9 +213,14 @@ pub mod cmds {
                 Self::parse_with_ctx(matches, QueryVotingPower);
             let query_slashes = Self::parse_with_ctx(matches, QuerySlashes);
             let query_result = Self::parse_with_ctx(matches, QueryResult);
+            let query_storage = Self::parse_with_ctx(matches, QueryStorage);
+            let query_raw = Self::parse_with_ctx(matches, QueryRaw);
             let intent = Self::parse_with_ctx(matches, Intent);
             let auction_intent = Self::parse_with_ctx(matches, AuctionIntent);
             let subscribe_topic = Self::parse_with_ctx(matches, SubscribeTopic);
+            let subscribe_matches =
+                Self::parse_with_ctx(matches, SubscribeMatches);
+            let inspect_intent = Self::parse_with_ctx(matches, InspectIntent);
             let utils = SubCmd::parse(matches).map(Self::WithoutContext);
             tx_custom
                 .or(tx_transfer)
@@ -229,9 +238,13 @@ pub mod cmds {
                 .or(query_voting_power)
                 .or(query_slashes)
                 .or(query_result)
+                .or(query_storage)
+                .or(query_raw)
                 .or(intent)
                 .or(auction_intent)
                 .or(subscribe_topic)
+                .or(subscribe_matches)
+                .or(inspect_intent)
                 .or(utils)
         }
     }
@@ -284,10 +297,14 @@ pub mod cmds {
         QueryBonds(QueryBonds),
         QueryVotingPower(QueryVotingPower),
         QuerySlashes(QuerySlashes),
+        QueryStorage(QueryStorage),
+        QueryRaw(QueryRaw),
         // Gossip cmds
         Intent(Intent),
         AuctionIntent(AuctionIntent),
         SubscribeTopic(SubscribeTopic),
+        SubscribeMatches(SubscribeMatches),
+        InspectIntent(InspectIntent),
     }
 
     #[derive(Clone, Debug)]
@@ -296,18 +313,26 @@ pub mod cmds {
         Key(WalletKey),
         /// Address management commands
         Address(WalletAddress),
+        /// Export the wallet to an encrypted backup file
+        Export(WalletExport),
+        /// Import the wallet from an encrypted backup file
+        Import(WalletImport),
     }
 
     impl Cmd for AnomaWallet {
         fn add_sub(app: App) -> App {
             app.subcommand(WalletKey::def())
                 .subcommand(WalletAddress::def())
+                .subcommand(WalletExport::def())
+                .subcommand(WalletImport::def())
         }
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             let key = SubCmd::parse(matches).map(Self::Key);
             let address = SubCmd::parse(matches).map(Self::Address);
-            key.or(address)
+            let export = SubCmd::parse(matches).map(Self::Export);
+            let import = SubCmd::parse(matches).map(Self::Import);
+            key.or(address).or(export).or(import)
         }
     }
 
@@ -563,10 +588,59 @@ pub mod cmds {
         }
     }
 
+    /// Export the wallet keys and addresses to an encrypted backup file
+    #[derive(Clone, Debug)]
+    pub struct WalletExport(pub args::WalletExport);
+
+    impl SubCmd for WalletExport {
+        const CMD: &'static str = "export";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::WalletExport::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Export the keys and addresses from the wallet to a \
+                     password-protected backup file.",
+                )
+                .add_args::<args::WalletExport>()
+        }
+    }
+
+    /// Import the wallet keys and addresses from an encrypted backup file
+    #[derive(Clone, Debug)]
+    pub struct WalletImport(pub args::WalletImport);
+
+    impl SubCmd for WalletImport {
+        const CMD: &'static str = "import";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::WalletImport::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Import the keys and addresses into the wallet from a \
+                     password-protected backup file.",
+                )
+                .add_args::<args::WalletImport>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Ledger {
         Run(LedgerRun),
         Reset(LedgerReset),
+        Compact(LedgerCompact),
+        DumpState(LedgerDumpState),
+        ImportState(LedgerImportState),
     }
 
     impl SubCmd for Ledger {
@@ -576,9 +650,20 @@ pub mod cmds {
             matches.subcommand_matches(Self::CMD).and_then(|matches| {
                 let run = SubCmd::parse(matches).map(Self::Run);
                 let reset = SubCmd::parse(matches).map(Self::Reset);
+                let compact = SubCmd::parse(matches).map(Self::Compact);
+                let dump_state = SubCmd::parse(matches).map(Self::DumpState);
+                let import_state =
+                    SubCmd::parse(matches).map(Self::ImportState);
                 run.or(reset)
+                    .or(compact)
+                    .or(dump_state)
+                    .or(import_state)
                     // The `run` command is the default if no sub-command given
-                    .or(Some(Self::Run(LedgerRun)))
+                    .or_else(|| {
+                        Some(Self::Run(LedgerRun(args::LedgerRun::parse(
+                            matches,
+                        ))))
+                    })
             })
         }
 
@@ -590,21 +675,29 @@ pub mod cmds {
                 )
                 .subcommand(LedgerRun::def())
                 .subcommand(LedgerReset::def())
+                .subcommand(LedgerCompact::def())
+                .subcommand(LedgerDumpState::def())
+                .subcommand(LedgerImportState::def())
+                .add_args::<args::LedgerRun>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerRun;
+    pub struct LedgerRun(pub args::LedgerRun);
 
     impl SubCmd for LedgerRun {
         const CMD: &'static str = "run";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| LedgerRun(args::LedgerRun::parse(matches)))
         }
 
         fn def() -> App {
-            App::new(Self::CMD).about("Run Anoma ledger node.")
+            App::new(Self::CMD)
+                .about("Run Anoma ledger node.")
+                .add_args::<args::LedgerRun>()
         }
     }
 
@@ -626,6 +719,70 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct LedgerCompact;
+
+    impl SubCmd for LedgerCompact {
+        const CMD: &'static str = "compact";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Compact Anoma ledger node's storage data to reclaim disk \
+                 space. The ledger node must not be running.",
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerDumpState(pub args::LedgerDumpState);
+
+    impl SubCmd for LedgerDumpState {
+        const CMD: &'static str = "dump-state";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                LedgerDumpState(args::LedgerDumpState::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Dump the last committed state of Anoma ledger node's \
+                     storage to a JSON file for inspection. The ledger node \
+                     must not be running.",
+                )
+                .add_args::<args::LedgerDumpState>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerImportState(pub args::LedgerImportState);
+
+    impl SubCmd for LedgerImportState {
+        const CMD: &'static str = "import-state";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                LedgerImportState(args::LedgerImportState::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Import a JSON state dump into Anoma ledger node's \
+                     storage and print the resulting Merkle root. The ledger \
+                     node must not be running.",
+                )
+                .add_args::<args::LedgerImportState>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Gossip {
         Run(GossipRun),
@@ -1012,6 +1169,50 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct QueryStorage(pub args::QueryStorage);
+
+    impl SubCmd for QueryStorage {
+        const CMD: &'static str = "query-storage";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryStorage(args::QueryStorage::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query a raw storage value and print it decoded by the \
+                     type of its key, if known.",
+                )
+                .add_args::<args::QueryStorage>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryRaw(pub args::QueryRaw);
+
+    impl SubCmd for QueryRaw {
+        const CMD: &'static str = "query-raw";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryRaw(args::QueryRaw::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send an ABCI query with an arbitrary path and data and \
+                     print the raw response.",
+                )
+                .add_args::<args::QueryRaw>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxInitNft(pub args::NftCreate);
 
@@ -1118,6 +1319,51 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct SubscribeMatches(pub args::SubscribeMatches);
+
+    impl SubCmd for SubscribeMatches {
+        const CMD: &'static str = "subscribe-matches";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                SubscribeMatches(args::SubscribeMatches::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Subscribe to the notices of matched intents signed by an \
+                     address at an intent gossip node and print them as they \
+                     are received.",
+                )
+                .add_args::<args::SubscribeMatches>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InspectIntent(pub args::InspectIntent);
+
+    impl SubCmd for InspectIntent {
+        const CMD: &'static str = "inspect-intent";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                InspectIntent(args::InspectIntent::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Decode a signed intent file, verify the signatures of \
+                     its exchanges or auctions and print its content.",
+                )
+                .add_args::<args::InspectIntent>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Utils {
         JoinNetwork(JoinNetwork),
@@ -1225,7 +1471,7 @@ pub mod args {
     use anoma::types::chain::{ChainId, ChainIdPrefix};
     use anoma::types::intent::{Auction, CreateAuction, DecimalWrapper, Exchange, PlaceBid};
     use anoma::types::key::*;
-    use anoma::types::storage::{BlockHeight, Epoch};
+    use anoma::types::storage::{self, BlockHeight, Epoch};
     use anoma::types::token;
     use anoma::types::transaction::GasLimit;
     use libp2p::Multiaddr;
@@ -1250,6 +1496,7 @@ pub mod args {
     const ALIAS: Arg<String> = arg("alias");
     const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
     const AMOUNT: Arg<token::Amount> = arg("amount");
+    const AUCTION_LISTING_OPT: ArgOpt<PathBuf> = arg_opt("auction-listing");
     const BASE_DIR: ArgDefault<PathBuf> = arg_default(
         "base-dir",
         DefaultFn(|| match env::var("ANOMA_BASE_DIR") {
@@ -1261,6 +1508,7 @@ pub mod args {
     const CHAIN_ID: Arg<ChainId> = arg("chain-id");
     const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
     const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
+    const CHECK_INTENT: ArgFlag = flag("check");
     const CODE_PATH: Arg<PathBuf> = arg("code-path");
     const CODE_PATH_OPT: ArgOpt<PathBuf> = CODE_PATH.opt();
     const CONSENSUS_TIMEOUT_COMMIT: ArgDefault<Timeout> = arg_default(
@@ -1273,6 +1521,7 @@ pub mod args {
     const DONT_ARCHIVE: ArgFlag = flag("dont-archive");
     const DRY_RUN_TX: ArgFlag = flag("dry-run");
     const EPOCH: ArgOpt<Epoch> = arg_opt("epoch");
+    const EXTERNAL_SIGNER: ArgOpt<String> = arg_opt("external-signer");
     const FEE_AMOUNT: ArgDefault<token::Amount> =
         arg_default("fee-amount", DefaultFn(|| token::Amount::from(0)));
     const FEE_TOKEN: ArgDefaultFromCtx<WalletAddress> =
@@ -1281,6 +1530,8 @@ pub mod args {
     const GAS_LIMIT: ArgDefault<token::Amount> =
         arg_default("gas-limit", DefaultFn(|| token::Amount::from(0)));
     const GENESIS_PATH: Arg<PathBuf> = arg("genesis-path");
+    const HEIGHT: ArgOpt<u64> = arg_opt("height");
+    const INPUT_PATH: Arg<PathBuf> = arg("input");
     const INTENT_GOSSIPER_ADDR: ArgDefault<SocketAddr> = arg_default(
         "intent-gossiper",
         DefaultFn(|| {
@@ -1288,6 +1539,7 @@ pub mod args {
             SocketAddr::from_str(raw).unwrap()
         }),
     );
+    const INTENT_PATH: Arg<PathBuf> = arg("path");
     const LEDGER_ADDRESS_ABOUT: &str =
         "Address of a ledger node as \"{scheme}://{host}:{port}\". If the \
          scheme is not supplied, it is assumed to be TCP.";
@@ -1298,26 +1550,43 @@ pub mod args {
         }));
     const LEDGER_ADDRESS: Arg<TendermintAddress> = arg("ledger-address");
     const LOCALHOST: ArgFlag = flag("localhost");
+    const MATCHMAKER_DRY_RUN: ArgFlag = flag("dry-run");
     const MATCHMAKER_PATH: ArgOpt<PathBuf> = arg_opt("matchmaker-path");
+    const MATCHMAKER_WORKING_DIR: ArgOpt<PathBuf> = arg_opt("working-dir");
     const MODE: ArgOpt<String> = arg_opt("mode");
     const MULTIADDR_OPT: ArgOpt<Multiaddr> = arg_opt("address");
     const NODE_OPT: ArgOpt<String> = arg_opt("node");
     const NODE: Arg<String> = arg("node");
     const NFT_ADDRESS: Arg<Address> = arg("nft-address");
+    const NONCE: ArgOpt<u64> = arg_opt("nonce");
+    const OUTPUT_PATH_OPT: ArgOpt<PathBuf> = OUTPUT_PATH.opt();
+    const OUTPUT_PATH: Arg<PathBuf> = arg("output");
     const OWNER: ArgOpt<WalletAddress> = arg_opt("owner");
+    const PRINT_AUCTION_ID: ArgFlag = flag("print-id");
     const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
+    const PROVE: ArgFlag = flag("prove");
     const PUBLIC_KEY: Arg<WalletPublicKey> = arg("public-key");
+    const QUERY_DATA: ArgOpt<String> = arg_opt("data");
+    const QUERY_PATH: Arg<String> = arg("path");
     const RAW_ADDRESS: Arg<Address> = arg("address");
     const RAW_PUBLIC_KEY_OPT: ArgOpt<common::PublicKey> = arg_opt("public-key");
     const REWARDS_CODE_PATH: ArgOpt<PathBuf> = arg_opt("rewards-code-path");
     const REWARDS_KEY: ArgOpt<WalletPublicKey> = arg_opt("rewards-key");
+    const RPC_RETRIES: ArgDefault<u32> =
+        arg_default("rpc-retries", DefaultFn(|| 5));
+    const RPC_RETRY_INTERVAL: ArgDefault<Timeout> = arg_default(
+        "rpc-retry-interval",
+        DefaultFn(|| Timeout::from_str("500ms").unwrap()),
+    );
     const RPC_SOCKET_ADDR: ArgOpt<SocketAddr> = arg_opt("rpc");
     const SIGNER: ArgOpt<WalletAddress> = arg_opt("signer");
     const SIGNING_KEY_OPT: ArgOpt<WalletKeypair> = SIGNING_KEY.opt();
     const SIGNING_KEY: Arg<WalletKeypair> = arg("signing-key");
     const SOURCE: Arg<WalletAddress> = arg("source");
+    const STORAGE_KEY: Arg<storage::Key> = arg("key");
     const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
     const TARGET: Arg<WalletAddress> = arg("target");
+    const TICK_INTERVAL: ArgOpt<Timeout> = arg_opt("tick-interval");
     const TO_STDOUT: ArgFlag = flag("stdout");
     const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
     const TOKEN: Arg<WalletAddress> = arg("token");
@@ -1413,6 +1682,81 @@ pub mod args {
         }
     }
 
+    /// Query storage value arguments
+    #[derive(Clone, Debug)]
+    pub struct QueryStorage {
+        /// Common query args
+        pub query: Query,
+        /// Storage key of the value
+        pub key: storage::Key,
+    }
+
+    impl Args for QueryStorage {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let key = STORAGE_KEY.parse(matches);
+            Self { query, key }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(STORAGE_KEY.def().about("The storage key of the value."))
+        }
+    }
+
+    /// Raw ABCI query arguments
+    #[derive(Clone, Debug)]
+    pub struct QueryRaw {
+        /// Common query args
+        pub query: Query,
+        /// The ABCI query path
+        pub path: String,
+        /// The ABCI query data
+        pub data: Vec<u8>,
+        /// The block height to query at, the last committed block if not set
+        pub height: Option<u64>,
+        /// Request a proof of the response
+        pub prove: bool,
+    }
+
+    impl Args for QueryRaw {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let path = QUERY_PATH.parse(matches);
+            let data = match QUERY_DATA.parse(matches) {
+                Some(data) => hex::decode(&data).unwrap_or_else(|err| {
+                    eprintln!("The data {} is not valid hex: {}", data, err);
+                    safe_exit(1)
+                }),
+                None => vec![],
+            };
+            let height = HEIGHT.parse(matches);
+            let prove = PROVE.parse(matches);
+            Self {
+                query,
+                path,
+                data,
+                height,
+                prove,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(QUERY_PATH.def().about("The ABCI query path."))
+                .arg(
+                    QUERY_DATA
+                        .def()
+                        .about("The ABCI query data as a hex string."),
+                )
+                .arg(HEIGHT.def().about(
+                    "The block height to query at. Defaults to the last \
+                     committed block.",
+                ))
+                .arg(PROVE.def().about("Request a proof of the response."))
+        }
+    }
+
     /// Custom transaction arguments
     #[derive(Clone, Debug)]
     pub struct TxCustom {
@@ -1940,7 +2284,11 @@ pub mod args {
         /// The block height at which the auction ends
         pub auction_start: String,
         /// The block height at which the auction ends
-        pub auction_end: String
+        pub auction_end: String,
+        /// The minimum amount by which a bid must exceed the highest bid,
+        /// zero if not given
+        #[serde(default)]
+        pub min_bid_increment: Option<String>,
     }
 
     /// Helper struct for generating intents
@@ -1948,6 +2296,8 @@ pub mod args {
     pub struct PlaceBidDefinition {
         /// The bid
         pub amount: String,
+        /// The token in which the bid is paid
+        pub token: String,
         /// The auction id
         pub auction_id: String
     }
@@ -1983,7 +2333,16 @@ pub mod args {
                         .expect("Amount of tokens must be convertable to number"),
                     // auction_end: BlockHeight(x.auction_end.parse::<u64>().expect("End of the auction must be convertable to number"))
                     auction_start: x.auction_start.parse::<u64>().expect("Start of the auction must be convertable to number"),
-                    auction_end: x.auction_end.parse::<u64>().expect("End of the auction must be convertable to number")
+                    auction_end: x.auction_end.parse::<u64>().expect("End of the auction must be convertable to number"),
+                    min_bid_increment: x
+                        .min_bid_increment
+                        .map(|increment| {
+                            token::Amount::from_str(&increment).expect(
+                                "Minimum bid increment must be convertable \
+                                 to number",
+                            )
+                        })
+                        .unwrap_or_default(),
                 }),
                 None    => None,
             };
@@ -1992,6 +2351,8 @@ pub mod args {
                 Some(x) => Some(PlaceBid {
                     amount: token::Amount::from_str(&x.amount)
                         .expect("Amount of tokens must be convertable to number"),
+                    token: Address::decode(x.token)
+                        .expect("Token should be a valid address"),
                     //auction_id: x.auction_id.expect("Amount of tokens must be convertable to number"),
                     auction_id: x.auction_id
 
@@ -2122,6 +2483,15 @@ pub mod args {
         pub ledger_address: TendermintAddress,
         /// Print output to stdout
         pub to_stdout: bool,
+        /// Only check the intent against the subscription filter
+        pub check: bool,
+        /// The nonce of the intent, if not the current time
+        pub nonce: Option<u64>,
+        /// Retries of the connection to the gossip node
+        pub rpc_retry: RpcRetry,
+        /// The shell command of an external signer, if not signing with the
+        /// wallet's keys
+        pub external_signer: Option<String>,
     }
 
     impl Args for Intent {
@@ -2131,6 +2501,7 @@ pub mod args {
             let source = SOURCE_OPT.parse(matches);
             let signing_key = SIGNING_KEY_OPT.parse(matches);
             let to_stdout = TO_STDOUT.parse(matches);
+            let check = CHECK_INTENT.parse(matches);
             let topic = TOPIC_OPT.parse(matches);
 
             let file = File::open(&data_path).expect("File must exist.");
@@ -2148,6 +2519,9 @@ pub mod args {
                 })
                 .collect();
             let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
+            let nonce = NONCE.parse(matches);
+            let rpc_retry = RpcRetry::parse(matches);
+            let external_signer = EXTERNAL_SIGNER.parse(matches);
 
             Self {
                 node_addr,
@@ -2157,6 +2531,10 @@ pub mod args {
                 exchanges,
                 ledger_address,
                 to_stdout,
+                check,
+                nonce,
+                rpc_retry,
+                external_signer,
             }
         }
 
@@ -2176,7 +2554,8 @@ pub mod args {
                     .def()
                     .about(
                         "Sign the intent with the key of a given address or \
-                         address alias from your wallet.",
+                         address alias from your wallet. It must be the \
+                         address of the exchanges, which is the default.",
                     )
                     .conflicts_with(SIGNING_KEY_OPT.name),
             )
@@ -2185,15 +2564,42 @@ pub mod args {
                     .def()
                     .about(
                         "Sign the intent with the key for the given public \
-                         key, public key hash or alias from your wallet.",
+                         key, public key hash or alias from your wallet. It \
+                         must be the key of the exchanges' address.",
                     )
                     .conflicts_with(SOURCE_OPT.name),
             )
+            .arg(
+                EXTERNAL_SIGNER
+                    .def()
+                    .about(
+                        "Sign the intent with an external signer, e.g. a \
+                         hardware wallet, instead of a key from your wallet. \
+                         The given shell command receives the hex-encoded \
+                         bytes to sign on its standard input and it must \
+                         print the hex-encoded signature to its standard \
+                         output. The signature must verify with the public \
+                         key of the exchanges' address.",
+                    )
+                    .conflicts_with_all(&[
+                        SOURCE_OPT.name,
+                        SIGNING_KEY_OPT.name,
+                    ]),
+            )
             .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
+            .arg(NONCE.def().about(
+                "The nonce of the intent, which must be greater than the \
+                 nonces of your intents that have already been matched. \
+                 Defaults to the current time in milliseconds.",
+            ))
             .arg(
                 TOPIC_OPT
                     .def()
-                    .about("The subnetwork where the intent should be sent to.")
+                    .about(
+                        "The subnetwork where the intent should be sent to. \
+                         If not specified, the topic is derived from the \
+                         token pair of the exchanges.",
+                    )
                     .conflicts_with(TO_STDOUT.name),
             )
             .arg(
@@ -2206,6 +2612,17 @@ pub mod args {
                     )
                     .conflicts_with_all(&[NODE_OPT.name, TOPIC.name]),
             )
+            .arg(
+                CHECK_INTENT
+                    .def()
+                    .about(
+                        "Only check if the intent's topic would be accepted by \
+                         the subscription filter of the configured intent \
+                         gossiper. The intent won't be submitted.",
+                    )
+                    .conflicts_with_all(&[NODE_OPT.name, TO_STDOUT.name]),
+            )
+            .add_args::<RpcRetry>()
         }
     }
 
@@ -2224,6 +2641,19 @@ pub mod args {
         pub ledger_address: TendermintAddress,
         /// Print output to stdout
         pub to_stdout: bool,
+        /// Only check the intent against the subscription filter
+        pub check: bool,
+        /// Only print the IDs of the created auctions
+        pub print_id: bool,
+        /// Write the signed intent to the given file instead of submitting it
+        pub output_path: Option<PathBuf>,
+        /// The matchmaker's listing of the open auctions to check bids against
+        pub auction_listing: Option<PathBuf>,
+        /// Retries of the connection to the gossip node
+        pub rpc_retry: RpcRetry,
+        /// The shell command of an external signer, if not signing with the
+        /// wallet's keys
+        pub external_signer: Option<String>,
     }
 
     impl Args for AuctionIntent {
@@ -2232,6 +2662,7 @@ pub mod args {
             let data_path = DATA_PATH.parse(matches);
             let signing_key = SIGNING_KEY_OPT.parse(matches);
             let to_stdout = TO_STDOUT.parse(matches);
+            let check = CHECK_INTENT.parse(matches);
             let topic = TOPIC_OPT.parse(matches);
 
             let file = File::open(&data_path).expect("File must exist.");
@@ -2249,6 +2680,11 @@ pub mod args {
                 })
                 .collect();
             let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
+            let print_id = PRINT_AUCTION_ID.parse(matches);
+            let output_path = OUTPUT_PATH_OPT.parse(matches);
+            let auction_listing = AUCTION_LISTING_OPT.parse(matches);
+            let rpc_retry = RpcRetry::parse(matches);
+            let external_signer = EXTERNAL_SIGNER.parse(matches);
 
             Self {
                 node_addr,
@@ -2257,6 +2693,12 @@ pub mod args {
                 auctions,
                 ledger_address,
                 to_stdout,
+                check,
+                print_id,
+                output_path,
+                auction_listing,
+                rpc_retry,
+                external_signer,
             }
         }
 
@@ -2296,6 +2738,60 @@ pub mod args {
                         )
                         .conflicts_with_all(&[NODE_OPT.name, TOPIC.name]),
                 )
+                .arg(
+                    CHECK_INTENT
+                        .def()
+                        .about(
+                            "Only check if the intent's topic would be \
+                             accepted by the subscription filter of the \
+                             configured intent gossiper. The intent won't be \
+                             submitted.",
+                        )
+                        .requires(TOPIC.name)
+                        .conflicts_with_all(&[NODE_OPT.name, TO_STDOUT.name]),
+                )
+                .arg(
+                    PRINT_AUCTION_ID
+                        .def()
+                        .about(
+                            "Print the IDs of the created auctions, by which \
+                             bidders can refer to them. The intent won't be \
+                             submitted.",
+                        )
+                        .conflicts_with_all(&[NODE_OPT.name, TO_STDOUT.name]),
+                )
+                .arg(
+                    OUTPUT_PATH_OPT
+                        .def()
+                        .about(
+                            "Write the signed intent to the given file. The \
+                             intent won't be submitted.",
+                        )
+                        .conflicts_with_all(&[NODE_OPT.name, TO_STDOUT.name]),
+                )
+                .arg(AUCTION_LISTING_OPT.def().about(
+                    "The matchmaker's listing of the open auctions. The bids \
+                     are checked against the highest bids of their auctions \
+                     before the intent is submitted. Required to place bids, \
+                     as the exchanges of the bids are crafted from their \
+                     auctions.",
+                ))
+                .arg(
+                    EXTERNAL_SIGNER
+                        .def()
+                        .about(
+                            "Sign the intent with an external signer, e.g. a \
+                             hardware wallet, instead of a key from your \
+                             wallet. The given shell command receives the \
+                             hex-encoded bytes to sign on its standard input \
+                             and it must print the hex-encoded signature to \
+                             its standard output. The signature must verify \
+                             with the public key of the auctions' address, so \
+                             the auctions must be of a single address.",
+                        )
+                        .conflicts_with(SIGNING_KEY_OPT.name),
+                )
+                .add_args::<RpcRetry>()
         }
     }
 
@@ -2306,21 +2802,191 @@ pub mod args {
         pub node_addr: String,
         /// Intent topic
         pub topic: String,
+        /// Retries of the connection to the gossip node
+        pub rpc_retry: RpcRetry,
     }
 
     impl Args for SubscribeTopic {
         fn parse(matches: &ArgMatches) -> Self {
             let node_addr = NODE.parse(matches);
             let topic = TOPIC.parse(matches);
-            Self { node_addr, topic }
+            let rpc_retry = RpcRetry::parse(matches);
+            Self {
+                node_addr,
+                topic,
+                rpc_retry,
+            }
         }
 
         fn def(app: App) -> App {
-            app.arg(NODE.def().about("The gossip node address.")).arg(
-                TOPIC
-                    .def()
-                    .about("The new topic of interest for that node."),
-            )
+            app.arg(NODE.def().about("The gossip node address."))
+                .arg(
+                    TOPIC
+                        .def()
+                        .about("The new topic of interest for that node."),
+                )
+                .add_args::<RpcRetry>()
+        }
+    }
+
+    /// Subscribe to match notices arguments
+    #[derive(Clone, Debug)]
+    pub struct SubscribeMatches {
+        /// Gossip node address
+        pub node_addr: String,
+        /// The address of the intents' signer
+        pub address: WalletAddress,
+        /// Retries of the connection to the gossip node
+        pub rpc_retry: RpcRetry,
+    }
+
+    impl Args for SubscribeMatches {
+        fn parse(matches: &ArgMatches) -> Self {
+            let node_addr = NODE.parse(matches);
+            let address = ADDRESS.parse(matches);
+            let rpc_retry = RpcRetry::parse(matches);
+            Self {
+                node_addr,
+                address,
+                rpc_retry,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(NODE.def().about("The gossip node address."))
+                .arg(ADDRESS.def().about(
+                    "The address whose signed intents' matches to receive.",
+                ))
+                .add_args::<RpcRetry>()
+        }
+    }
+
+    /// Inspect intent arguments
+    #[derive(Clone, Debug)]
+    pub struct InspectIntent {
+        /// The path to the signed intent file
+        pub path: PathBuf,
+        /// The address of the ledger node to query the signers' public keys
+        pub ledger_address: TendermintAddress,
+    }
+
+    impl Args for InspectIntent {
+        fn parse(matches: &ArgMatches) -> Self {
+            let path = INTENT_PATH.parse(matches);
+            let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
+            Self {
+                path,
+                ledger_address,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(INTENT_PATH.def().about(
+                "The path to a signed intent file, e.g. as printed by the \
+                 intent command with the --stdout flag.",
+            ))
+            .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
+        }
+    }
+
+    /// Common arguments for retrying the connection to a gossip node's RPC
+    #[derive(Clone, Debug)]
+    pub struct RpcRetry {
+        /// The maximum number of retries after a failed connection
+        pub retries: u32,
+        /// The interval before the first retry, doubled on every next retry
+        pub interval: Timeout,
+    }
+
+    impl Args for RpcRetry {
+        fn parse(matches: &ArgMatches) -> Self {
+            let retries = RPC_RETRIES.parse(matches);
+            let interval = RPC_RETRY_INTERVAL.parse(matches);
+            Self { retries, interval }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(RPC_RETRIES.def().about(
+                "The maximum number of times to retry connecting to the \
+                 gossip node, when it's not reachable. Defaults to 5.",
+            ))
+            .arg(RPC_RETRY_INTERVAL.def().about(
+                "The interval before the first retry of the connection to \
+                 the gossip node, doubled on every next retry. Defaults to \
+                 500ms.",
+            ))
+        }
+    }
+
+    /// Ledger run arguments
+    #[derive(Clone, Debug)]
+    pub struct LedgerRun {
+        /// Start even if the chain ID stored in the DB doesn't match the
+        /// configured one
+        pub force: bool,
+    }
+
+    impl Args for LedgerRun {
+        fn parse(matches: &ArgMatches) -> Self {
+            let force = FORCE.parse(matches);
+            Self { force }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(FORCE.def().about(
+                "Start the ledger even if the chain ID stored in the DB \
+                 doesn't match the configured chain ID. The stored chain ID \
+                 is overwritten.",
+            ))
+        }
+    }
+
+    /// Ledger dump state arguments
+    #[derive(Clone, Debug)]
+    pub struct LedgerDumpState {
+        /// The path of the JSON file to write the state to
+        pub output: PathBuf,
+    }
+
+    impl Args for LedgerDumpState {
+        fn parse(matches: &ArgMatches) -> Self {
+            let output = OUTPUT_PATH.parse(matches);
+            Self { output }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(OUTPUT_PATH.def().about(
+                "The path of the JSON file to write the storage keys and \
+                 values to. An existing file is overwritten.",
+            ))
+        }
+    }
+
+    /// Ledger import state arguments
+    #[derive(Clone, Debug)]
+    pub struct LedgerImportState {
+        /// The path of the JSON state dump to import
+        pub input: PathBuf,
+        /// Overwrite the state already stored in the DB, if any
+        pub force: bool,
+    }
+
+    impl Args for LedgerImportState {
+        fn parse(matches: &ArgMatches) -> Self {
+            let input = INPUT_PATH.parse(matches);
+            let force = FORCE.parse(matches);
+            Self { input, force }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(INPUT_PATH.def().about(
+                "The path of the JSON state dump written by the `dump-state` \
+                 command.",
+            ))
+            .arg(FORCE.def().about(
+                "Import the state even if the DB already contains a state. \
+                 The stored state is deleted.",
+            ))
         }
     }
 
@@ -2351,6 +3017,9 @@ pub mod args {
     pub struct Matchmaker {
         pub matchmaker_path: Option<PathBuf>,
         pub tx_code_path: Option<PathBuf>,
+        pub tick_interval: Option<Timeout>,
+        pub working_dir: Option<PathBuf>,
+        pub dry_run: bool,
         pub intent_gossiper_addr: SocketAddr,
         pub ledger_addr: TendermintAddress,
         pub tx_signing_key: WalletKeypair,
@@ -2362,6 +3031,9 @@ pub mod args {
             let intent_gossiper_addr = INTENT_GOSSIPER_ADDR.parse(matches);
             let matchmaker_path = MATCHMAKER_PATH.parse(matches);
             let tx_code_path = TX_CODE_PATH.parse(matches);
+            let tick_interval = TICK_INTERVAL.parse(matches);
+            let working_dir = MATCHMAKER_WORKING_DIR.parse(matches);
+            let dry_run = MATCHMAKER_DRY_RUN.parse(matches);
             let ledger_addr = LEDGER_ADDRESS_DEFAULT.parse(matches);
             let tx_signing_key = SIGNING_KEY.parse(matches);
             let tx_source_address = SOURCE.parse(matches);
@@ -2369,6 +3041,9 @@ pub mod args {
                 intent_gossiper_addr,
                 matchmaker_path,
                 tx_code_path,
+                tick_interval,
+                working_dir,
+                dry_run,
                 ledger_addr,
                 tx_signing_key,
                 tx_source_address,
@@ -2389,6 +3064,22 @@ pub mod args {
                     .def()
                     .about("The transaction code to use with the matchmaker."),
             )
+            .arg(TICK_INTERVAL.def().about(
+                "Run the matchmaker in the batched mode, in which the intents \
+                 are accumulated and matched together on every tick of the \
+                 given interval (e.g. \"1s\"). Without it, every intent is \
+                 matched as soon as it's received.",
+            ))
+            .arg(MATCHMAKER_WORKING_DIR.def().about(
+                "The directory under which the matchmaker's snapshots and \
+                 logs are written. The directory is created if it doesn't \
+                 exist.",
+            ))
+            .arg(MATCHMAKER_DRY_RUN.def().about(
+                "Only log the matches found by the matchmaker with their \
+                 transfers, without injecting any transactions into the \
+                 ledger.",
+            ))
             .arg(LEDGER_ADDRESS_DEFAULT.def().about(
                 "The address of the ledger as \"{scheme}://{host}:{port}\" \
                  that the matchmaker must send transactions to. If the scheme \
@@ -2431,6 +3122,9 @@ pub mod args {
         pub signing_key: Option<WalletKeypair>,
         /// Sign the tx with the keypair of the public key of the given address
         pub signer: Option<WalletAddress>,
+        /// The shell command of an external signer, if not signing with the
+        /// wallet's keys
+        pub external_signer: Option<String>,
     }
 
     impl Args for Tx {
@@ -2482,6 +3176,20 @@ pub mod args {
                     )
                     .conflicts_with(SIGNING_KEY_OPT.name),
             )
+            .arg(
+                EXTERNAL_SIGNER
+                    .def()
+                    .about(
+                        "Sign the transaction with an external signer, e.g. a \
+                         hardware wallet, instead of a key from your wallet. \
+                         The given shell command receives the hex-encoded \
+                         bytes to sign on its standard input and it must \
+                         print the hex-encoded signature to its standard \
+                         output. The signature must verify with the public \
+                         key of the signer's address.",
+                    )
+                    .conflicts_with(SIGNING_KEY_OPT.name),
+            )
         }
 
         fn parse(matches: &ArgMatches) -> Self {
@@ -2496,6 +3204,7 @@ pub mod args {
 
             let signing_key = SIGNING_KEY_OPT.parse(matches);
             let signer = SIGNER.parse(matches);
+            let external_signer = EXTERNAL_SIGNER.parse(matches);
             Self {
                 dry_run,
                 force,
@@ -2507,6 +3216,7 @@ pub mod args {
                 gas_limit,
                 signing_key,
                 signer,
+                external_signer,
             }
         }
     }
@@ -2708,6 +3418,54 @@ pub mod args {
         }
     }
 
+    /// Wallet export arguments
+    #[derive(Clone, Debug)]
+    pub struct WalletExport {
+        pub output: PathBuf,
+    }
+
+    impl Args for WalletExport {
+        fn parse(matches: &ArgMatches) -> Self {
+            let output = OUTPUT_PATH.parse(matches);
+            Self { output }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                OUTPUT_PATH
+                    .def()
+                    .about("The path of the backup file to write."),
+            )
+        }
+    }
+
+    /// Wallet import arguments
+    #[derive(Clone, Debug)]
+    pub struct WalletImport {
+        pub input: PathBuf,
+        pub force: bool,
+    }
+
+    impl Args for WalletImport {
+        fn parse(matches: &ArgMatches) -> Self {
+            let input = INPUT_PATH.parse(matches);
+            let force = FORCE.parse(matches);
+            Self { input, force }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                INPUT_PATH
+                    .def()
+                    .about("The path of the backup file to import."),
+            )
+            .arg(FORCE.def().about(
+                "Overwrite the keys and addresses whose aliases already exist \
+                 in the wallet.",
+            ))
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct JoinNetwork {
         pub chain_id: ChainId,
@@ -259,7 +259,16 @@ impl ArgFromContext for Address {
                 ctx.wallet
                     .find_address(raw)
                     .unwrap_or_else(|| {
-                        eprintln!("Unknown address {}", raw);
+                        let mut known_aliases: Vec<String> =
+                            ctx.wallet.get_addresses().into_keys().collect();
+                        known_aliases.sort();
+                        eprintln!(
+                            "Unknown address {}. It must be either a bech32m \
+                             encoded address or an alias of an address from \
+                             the wallet. The known address aliases are: {}",
+                            raw,
+                            known_aliases.join(", ")
+                        );
                         safe_exit(1)
                     })
                     .clone()
@@ -1,22 +1,35 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anoma::proto::Signed;
-use anoma::types::intent::{Auction, AuctionIntent, Exchange, FungibleTokenIntent};
+use anoma::proto::{IntentId, Signed};
+use anoma::types::address::Address;
+use anoma::types::intent::{
+    Auction, AuctionIntent, CreateAuction, Exchange, FungibleTokenIntent,
+    IntentData,
+};
+use anoma::types::storage::BlockHeight;
+use anoma::types::token;
 use borsh::BorshSerialize;
+use serde::Deserialize;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
+use tonic::transport::Channel;
 
-use super::signing;
+use super::rpc;
+use super::signing::{self, Signer, WalletSigner};
 use crate::cli::{self, args, Context};
+use crate::config::SubscriptionFilter;
 use crate::proto::services::rpc_service_client::RpcServiceClient;
 use crate::proto::{services, RpcMessage};
 use crate::wallet::Wallet;
 
 /// Create an intent, sign it and submit it to the gossip node (unless
-/// `to_stdout` is `true`).
+/// `to_stdout` is `true`). With `check`, only check if the intent's topic
+/// would be accepted by the configured intent gossiper.
 pub async fn gossip_intent(
     mut ctx: Context,
     args::Intent {
@@ -27,36 +40,90 @@ pub async fn gossip_intent(
         exchanges,
         ledger_address,
         to_stdout,
+        check,
+        nonce,
+        rpc_retry,
+        external_signer,
     }: args::Intent,
 ) {
+    // When the topic is not specified, derive it from the exchanges
+    let topic = if topic.is_none() && !to_stdout {
+        Some(exchanges_topic(&exchanges))
+    } else {
+        topic
+    };
+
+    if check {
+        let topic = topic.expect("The topic must be defined to check it.");
+        let filter = &ctx.config.intent_gossiper.subscription_filter;
+        if !check_intent_topic(filter, &topic) {
+            cli::safe_exit(1)
+        }
+        return;
+    }
+
+    // The intent must be signed by the exchanges' address, so that the signed
+    // exchanges cannot be re-wrapped into another intent
+    let exchange_addrs: HashSet<Address> = exchanges
+        .iter()
+        .map(|exchange| exchange.addr.clone())
+        .collect();
+    if exchange_addrs.len() > 1 {
+        eprintln!(
+            "All the exchanges of an intent must be of the same address."
+        );
+        cli::safe_exit(1)
+    }
+    let exchange_addr = exchange_addrs.into_iter().next();
+
     let mut signed_exchanges: HashSet<Signed<Exchange>> =
         HashSet::with_capacity(exchanges.len());
     for exchange in exchanges {
-        let signed =
-            sign_exchange(&mut ctx.wallet, exchange, ledger_address.clone())
-                .await;
+        let signed = sign_exchange(
+            &mut ctx.wallet,
+            external_signer.as_deref(),
+            exchange,
+            ledger_address.clone(),
+        )
+        .await;
         signed_exchanges.insert(signed);
     }
 
-    let source_keypair = match ctx.get_opt_cached(&signing_key) {
-        Some(key) => key,
+    let signing_key = ctx.get_opt_cached(&signing_key);
+    let source_signer: Box<dyn Signer> = match signing_key {
+        Some(key) => Box::new(WalletSigner::new(key)),
         None => {
-            let source = ctx.get_opt(&source).unwrap_or_else(|| {
-                eprintln!("A source or a signing key is required.");
-                cli::safe_exit(1)
-            });
-            signing::find_keypair(
+            let source = ctx
+                .get_opt(&source)
+                .or_else(|| exchange_addr.clone())
+                .unwrap_or_else(|| {
+                    eprintln!("A source or a signing key is required.");
+                    cli::safe_exit(1)
+                });
+            if let Some(exchange_addr) = &exchange_addr {
+                if source != *exchange_addr {
+                    eprintln!(
+                        "The source {} must be the address of the \
+                         exchanges {}.",
+                        source, exchange_addr
+                    );
+                    cli::safe_exit(1)
+                }
+            }
+            signing::find_signer(
                 &mut ctx.wallet,
+                external_signer.as_deref(),
                 &source,
                 ledger_address.clone(),
             )
             .await
         }
     };
-    let signed_ft: Signed<FungibleTokenIntent> = Signed::new(
-        &*source_keypair,
+    let signed_ft: Signed<FungibleTokenIntent> = signing::sign(
+        &*source_signer,
         FungibleTokenIntent {
             exchange: signed_exchanges,
+            nonce: nonce.unwrap_or_else(default_nonce),
         },
     );
     let data_bytes = signed_ft.try_to_vec().unwrap();
@@ -73,7 +140,7 @@ pub async fn gossip_intent(
             "The topic must be defined to submit the intent to a gossip node.",
         );
 
-        match RpcServiceClient::connect(node_addr.clone()).await {
+        match connect_rpc_client(node_addr.clone(), &rpc_retry).await {
             Ok(mut client) => {
                 let intent = anoma::proto::Intent::new(data_bytes);
                 let message: services::RpcMessage =
@@ -93,7 +160,8 @@ pub async fn gossip_intent(
     }
 }
 /// Create an intent, sign it and submit it to the gossip node (unless
-/// `to_stdout` is `true`).
+/// `to_stdout` is `true`). With `check`, only check if the intent's topic
+/// would be accepted by the configured intent gossiper.
 pub async fn gossip_auction_intent(
     mut ctx: Context,
     args::AuctionIntent {
@@ -103,33 +171,152 @@ pub async fn gossip_auction_intent(
         auctions,
         ledger_address,
         to_stdout,
+        check,
+        print_id,
+        output_path,
+        auction_listing,
+        rpc_retry,
+        external_signer,
     }: args::AuctionIntent,
 ) {
-    let mut signed_auctions: HashSet<Signed<Auction>> =
-        HashSet::with_capacity(auctions.len());
-    for auction in auctions {
-        let signed =
-            sign_auction(&mut ctx.wallet, auction, ledger_address.clone())
-                .await;
-        signed_auctions.insert(signed);
+    if check {
+        let topic = topic.expect("The topic must be defined to check it.");
+        let filter = &ctx.config.intent_gossiper.subscription_filter;
+        if !check_intent_topic(filter, &topic) {
+            cli::safe_exit(1)
+        }
+        return;
     }
 
-    let source_keypair = match ctx.get_opt_cached(&signing_key) {
-        Some(key) => key,
-        None => {
-            eprintln!("A source or a signing key is required.");
+    // Reject the auctions that would already be over before signing them.
+    // When the IDs are printed or the intent is written to a file, it's
+    // crafted offline, without contacting a node, so the check is skipped.
+    let offline = print_id || output_path.is_some();
+    if !offline
+        && auctions
+            .iter()
+            .any(|auction| auction.create_auction.is_some())
+    {
+        let current_height =
+            rpc::query_block_height(ledger_address.clone()).await;
+        if !check_auction_deadlines(&auctions, current_height) {
+            cli::safe_exit(1)
+        }
+    }
+
+    // Reject the bids that the matchmaker would refuse. The matchmaker still
+    // checks the bids itself, as the listing may be outdated.
+    let listing = match auction_listing {
+        Some(auction_listing) => {
+            let listing = read_auction_listing(auction_listing);
+            if !check_bids(&auctions, &listing) {
+                cli::safe_exit(1)
+            }
+            listing
+        }
+        None if auctions.iter().any(|auction| auction.place_bid.is_some()) => {
+            eprintln!(
+                "The auction listing is required to place bids, as the \
+                 exchanges of the bids are crafted from their auctions."
+            );
             cli::safe_exit(1)
         }
+        None => vec![],
     };
-    let signed_ac: Signed<AuctionIntent> = Signed::new(
-        &source_keypair,
+
+    // Every address signs its auctions and bids together with the exchanges
+    // that they commit to, which the matchmaker needs to settle the auctions
+    let mut auctions_by_addr: HashMap<Address, Vec<Auction>> = HashMap::new();
+    for auction in auctions {
+        auctions_by_addr
+            .entry(auction.addr.clone())
+            .or_default()
+            .push(auction);
+    }
+    let auction_addrs: Vec<Address> =
+        auctions_by_addr.keys().cloned().collect();
+    let mut signed_auctions: HashSet<Signed<Auction>> = HashSet::new();
+    let mut exchange_intents: HashMap<Address, Signed<FungibleTokenIntent>> =
+        HashMap::new();
+    for (addr, auctions) in auctions_by_addr {
+        let signer = signing::find_signer(
+            &mut ctx.wallet,
+            external_signer.as_deref(),
+            &addr,
+            ledger_address.clone(),
+        )
+        .await;
+        let mut exchanges: HashSet<Signed<Exchange>> = HashSet::new();
+        for auction in auctions {
+            if let Some(exchange) = auction_exchange(&auction, &listing) {
+                exchanges.insert(signing::sign(&*signer, exchange));
+            }
+            signed_auctions.insert(signing::sign(&*signer, auction));
+        }
+        let exchange_intent = signing::sign(
+            &*signer,
+            FungibleTokenIntent {
+                exchange: exchanges,
+                nonce: default_nonce(),
+            },
+        );
+        exchange_intents.insert(addr, exchange_intent);
+    }
+
+    let source_signer: Box<dyn Signer> =
+        match (external_signer, ctx.get_opt_cached(&signing_key)) {
+            // The signature of the external signer is verified with the
+            // public key of the auctions' address
+            (Some(command), _) => match auction_addrs.as_slice() {
+                [addr] => {
+                    signing::find_signer(
+                        &mut ctx.wallet,
+                        Some(&command),
+                        addr,
+                        ledger_address.clone(),
+                    )
+                    .await
+                }
+                _ => {
+                    eprintln!(
+                        "The auctions of an intent signed by an external \
+                         signer must be of a single address."
+                    );
+                    cli::safe_exit(1)
+                }
+            },
+            (None, Some(key)) => Box::new(WalletSigner::new(key)),
+            (None, None) => {
+                eprintln!("A signing key or an external signer is required.");
+                cli::safe_exit(1)
+            }
+        };
+    let signed_ac: Signed<AuctionIntent> = signing::sign(
+        &*source_signer,
         AuctionIntent {
             auctions: signed_auctions,
+            exchange_intents,
         },
     );
     let data_bytes = signed_ac.try_to_vec().unwrap();
 
-    if to_stdout {
+    if offline {
+        if print_id {
+            for id in auction_ids(&signed_ac.data) {
+                println!("{}", id);
+            }
+        }
+        if let Some(output_path) = output_path {
+            std::fs::write(&output_path, &data_bytes).unwrap_or_else(|err| {
+                eprintln!(
+                    "Failed to write the intent to {}: {}",
+                    output_path.to_string_lossy(),
+                    err
+                );
+                cli::safe_exit(1)
+            });
+        }
+    } else if to_stdout {
         let mut out = std::io::stdout();
         out.write_all(&data_bytes).unwrap();
         out.flush().unwrap();
@@ -141,7 +328,7 @@ pub async fn gossip_auction_intent(
             "The topic must be defined to submit the intent to a gossip node.",
         );
 
-        match RpcServiceClient::connect(node_addr.clone()).await {
+        match connect_rpc_client(node_addr.clone(), &rpc_retry).await {
             Ok(mut client) => {
                 let intent = anoma::proto::Intent::new(data_bytes);
                 let message: services::RpcMessage =
@@ -165,9 +352,21 @@ pub async fn gossip_auction_intent(
 /// topic.
 pub async fn subscribe_topic(
     _ctx: Context,
-    args::SubscribeTopic { node_addr, topic }: args::SubscribeTopic,
+    args::SubscribeTopic {
+        node_addr,
+        topic,
+        rpc_retry,
+    }: args::SubscribeTopic,
 ) {
-    let mut client = RpcServiceClient::connect(node_addr).await.unwrap();
+    let mut client = match connect_rpc_client(node_addr.clone(), &rpc_retry)
+        .await
+    {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error connecting RPC client to {}: {}", node_addr, e);
+            cli::safe_exit(1)
+        }
+    };
     let message: services::RpcMessage = RpcMessage::new_topic(topic).into();
     let response = client
         .send_message(message)
@@ -176,22 +375,716 @@ pub async fn subscribe_topic(
     println!("{:#?}", response);
 }
 
+/// Subscribe to the notices of the matched intents signed by an address at an
+/// intent gossip node and print them as they are received, until the gossip
+/// node closes the subscription.
+pub async fn subscribe_matches(
+    ctx: Context,
+    args::SubscribeMatches {
+        node_addr,
+        address,
+        rpc_retry,
+    }: args::SubscribeMatches,
+) {
+    let address = ctx.get(&address);
+    let mut client = match connect_rpc_client(node_addr.clone(), &rpc_retry)
+        .await
+    {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error connecting RPC client to {}: {}", node_addr, e);
+            cli::safe_exit(1)
+        }
+    };
+    let request = services::SubscribeMatchesRequest {
+        address: address.encode(),
+    };
+    let mut notifications = match client.subscribe_matches(request).await {
+        Ok(response) => response.into_inner(),
+        Err(err) => {
+            eprintln!(
+                "Failed to subscribe to the matches of {}: {}",
+                address, err
+            );
+            cli::safe_exit(1)
+        }
+    };
+    println!(
+        "Waiting for the matches of the intents signed by {}...",
+        address
+    );
+    loop {
+        match notifications.message().await {
+            Ok(Some(notification)) => println!(
+                "Intent ID {} has been matched into the transaction {}.",
+                IntentId(notification.intent_id),
+                notification.tx_hash
+            ),
+            Ok(None) => {
+                println!("The gossip node has closed the subscription.");
+                return;
+            }
+            Err(err) => {
+                eprintln!("Failed to receive a match notice: {}", err);
+                cli::safe_exit(1)
+            }
+        }
+    }
+}
+
+/// Decode a signed intent file, print its content and verify the signatures
+/// of its exchanges or auctions with the signers' public keys from the ledger.
+pub async fn inspect_intent(
+    _ctx: Context,
+    args::InspectIntent {
+        path,
+        ledger_address,
+    }: args::InspectIntent,
+) {
+    let intent = read_intent(&path);
+    print!("{}", format_intent(&intent));
+
+    let mut pks = HashMap::new();
+    for signer in intent.signers() {
+        if let Some(pk) =
+            rpc::get_public_key(signer, ledger_address.clone()).await
+        {
+            pks.insert(signer.clone(), pk);
+        }
+    }
+    match intent.verify_signatures(|addr| pks.get(addr)) {
+        Ok(()) => println!("The signatures of the intent are valid."),
+        Err(err) => {
+            eprintln!("The signatures of the intent are invalid: {}", err);
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// The longest interval between two retries of the connection to a gossip
+/// node's RPC
+const MAX_RPC_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Connect to the RPC of a gossip node. On a failed connection, e.g. when the
+/// gossip node is still starting, the connection is retried up to the given
+/// number of times with an exponential backoff.
+async fn connect_rpc_client(
+    node_addr: String,
+    args::RpcRetry { retries, interval }: &args::RpcRetry,
+) -> Result<RpcServiceClient<Channel>, tonic::transport::Error> {
+    let mut interval: Duration = **interval;
+    let mut attempt = 0;
+    loop {
+        match RpcServiceClient::connect(node_addr.clone()).await {
+            Ok(client) => return Ok(client),
+            Err(err) if attempt < *retries => {
+                attempt += 1;
+                eprintln!(
+                    "Failed to connect to the gossip node at {}: {}. \
+                     Retrying in {:?} ({}/{})...",
+                    node_addr, err, interval, attempt, retries
+                );
+                tokio::time::sleep(interval).await;
+                interval = (interval * 2).min(MAX_RPC_RETRY_INTERVAL);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Get the IDs of the auctions created by an auction intent. See
+/// [`anoma::types::intent::CreateAuction::id`].
+fn auction_ids(intent: &AuctionIntent) -> Vec<String> {
+    let mut ids: Vec<String> = intent
+        .auctions
+        .iter()
+        .filter_map(|auction| auction.data.create_auction.as_ref())
+        .map(|create_auction| create_auction.id())
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// Check that the created auctions end after the given height of the last
+/// committed block and report the auctions that don't. Returns `true` when
+/// all the auctions end in the future.
+fn check_auction_deadlines(
+    auctions: &[Auction],
+    current_height: BlockHeight,
+) -> bool {
+    let mut valid = true;
+    for create_auction in auctions
+        .iter()
+        .filter_map(|auction| auction.create_auction.as_ref())
+    {
+        if create_auction.auction_end <= current_height.0 {
+            eprintln!(
+                "The auction {} ends at height {}, which is not after the \
+                 height of the last committed block {}.",
+                create_auction.id(),
+                create_auction.auction_end,
+                current_height
+            );
+            valid = false;
+        }
+    }
+    valid
+}
+
+/// An open auction as listed by the matchmaker for bidders
+#[derive(Debug, Clone, Deserialize)]
+struct AuctionListing {
+    /// The ID by which bids refer to the auction
+    id: String,
+    create_auction: CreateAuction,
+    /// The highest bid so far, if any
+    highest_bid: Option<token::Amount>,
+}
+
+/// Read the matchmaker's listing of the open auctions from the given file
+fn read_auction_listing(path: impl AsRef<Path>) -> Vec<AuctionListing> {
+    let path = path.as_ref();
+    let listing = std::fs::read(path).and_then(|bytes| {
+        serde_json::from_slice(&bytes).map_err(std::io::Error::from)
+    });
+    listing.unwrap_or_else(|err| {
+        eprintln!(
+            "Failed to read the auction listing from {}: {}",
+            path.to_string_lossy(),
+            err
+        );
+        cli::safe_exit(1)
+    })
+}
+
+/// Read a signed fungible token or auction intent from the given file
+fn read_intent(path: impl AsRef<Path>) -> IntentData {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).unwrap_or_else(|err| {
+        eprintln!(
+            "Failed to read the intent file {}: {}",
+            path.to_string_lossy(),
+            err
+        );
+        cli::safe_exit(1)
+    });
+    IntentData::try_from_slice(&bytes).unwrap_or_else(|err| {
+        eprintln!(
+            "The file {} doesn't contain a valid signed intent: {}",
+            path.to_string_lossy(),
+            err
+        );
+        cli::safe_exit(1)
+    })
+}
+
+/// Format the content of an intent for printing. The exchanges and auctions
+/// are sorted by their addresses.
+fn format_intent(intent: &IntentData) -> String {
+    let mut lines = Vec::new();
+    match intent {
+        IntentData::FungibleToken(intent) => {
+            lines.push(format!(
+                "Fungible token intent with nonce {}:",
+                intent.data.nonce
+            ));
+            let mut exchanges: Vec<&Exchange> =
+                intent.data.exchange.iter().map(|e| &e.data).collect();
+            exchanges.sort_by(|a, b| a.addr.cmp(&b.addr));
+            for exchange in exchanges {
+                lines.push(format!(
+                    "  Exchange signed by {}: sell at most {} of {} to buy \
+                     at least {} of {} at a minimum rate of {}",
+                    exchange.addr,
+                    exchange.max_sell,
+                    exchange.token_sell,
+                    exchange.min_buy,
+                    exchange.token_buy,
+                    exchange.rate_min.0
+                ));
+            }
+        }
+        IntentData::Auction(intent) => {
+            lines.push("Auction intent:".to_string());
+            let mut auctions: Vec<&Auction> =
+                intent.data.auctions.iter().map(|a| &a.data).collect();
+            auctions.sort_by(|a, b| a.addr.cmp(&b.addr));
+            for auction in auctions {
+                lines.push(format!("  Auction signed by {}:", auction.addr));
+                if let Some(create_auction) = &auction.create_auction {
+                    lines.push(format!(
+                        "    Create auction {}: sell {} of {} for {} from \
+                         block {} to block {} with a minimum bid increment \
+                         of {}",
+                        create_auction.id(),
+                        create_auction.amount,
+                        create_auction.token_sell,
+                        create_auction.token_buy,
+                        create_auction.auction_start,
+                        create_auction.auction_end,
+                        create_auction.min_bid_increment
+                    ));
+                }
+                if let Some(place_bid) = &auction.place_bid {
+                    lines.push(format!(
+                        "    Place a bid of {} of {} on auction {}",
+                        place_bid.amount, place_bid.token, place_bid.auction_id
+                    ));
+                }
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Check that the placed bids are for listed auctions, that they are in the
+/// auctions' payment tokens and that they exceed the highest bids of their
+/// auctions by the auctions' minimum bid increment and report the bids that
+/// don't. Returns `true` when all the bids would be accepted.
+fn check_bids(auctions: &[Auction], listing: &[AuctionListing]) -> bool {
+    let mut valid = true;
+    for place_bid in auctions
+        .iter()
+        .filter_map(|auction| auction.place_bid.as_ref())
+    {
+        match listing
+            .iter()
+            .find(|entry| entry.id == place_bid.auction_id)
+        {
+            Some(entry)
+                if entry.create_auction.token_buy != place_bid.token =>
+            {
+                eprintln!(
+                    "The bid in {} for the auction {} is not in its payment \
+                     token {}.",
+                    place_bid.token,
+                    place_bid.auction_id,
+                    entry.create_auction.token_buy
+                );
+                valid = false;
+            }
+            Some(entry) => {
+                if !entry
+                    .create_auction
+                    .accepts_bid(entry.highest_bid, place_bid.amount)
+                {
+                    eprintln!(
+                        "The bid of {} for the auction {} doesn't exceed the \
+                         highest bid {} by the minimum bid increment {}.",
+                        place_bid.amount,
+                        place_bid.auction_id,
+                        entry.highest_bid.unwrap_or_default(),
+                        entry.create_auction.min_bid_increment
+                    );
+                    valid = false;
+                }
+            }
+            None => {
+                eprintln!(
+                    "The auction {} is not listed as open.",
+                    place_bid.auction_id
+                );
+                valid = false;
+            }
+        }
+    }
+    valid
+}
+
+/// The default nonce of an intent is the current time in milliseconds, which
+/// is greater than the nonces of the signer's previous intents
+fn default_nonce() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("The current time should be after the UNIX epoch")
+        .as_millis() as u64
+}
+
+/// Derive the topic of an intent from the token pair of its exchanges. See
+/// [`anoma::types::intent::token_pair_topic`].
+fn exchanges_topic(exchanges: &[Exchange]) -> String {
+    let topics: HashSet<String> =
+        exchanges.iter().map(Exchange::topic).collect();
+    let mut topics = topics.into_iter();
+    match (topics.next(), topics.next()) {
+        (Some(topic), None) => topic,
+        (None, _) => {
+            eprintln!(
+                "The topic cannot be derived for an intent without any \
+                 exchanges."
+            );
+            cli::safe_exit(1)
+        }
+        (Some(_), Some(_)) => {
+            eprintln!(
+                "The exchanges of the intent are for different token pairs, \
+                 so the topic cannot be derived from them. Please specify the \
+                 topic."
+            );
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Check locally if an intent submitted to the given topic would be accepted
+/// by the given subscription filter and report the result. Returns `true`
+/// when the intent would be accepted.
+fn check_intent_topic(filter: &SubscriptionFilter, topic: &str) -> bool {
+    let accepted = filter.accepts_topic(topic);
+    if accepted {
+        println!(
+            "The intent would be accepted by the intent gossiper on the \
+             topic \"{}\".",
+            topic
+        );
+    } else {
+        println!(
+            "The intent would be rejected by the intent gossiper, because \
+             the topic \"{}\" is not allowed by its subscription filter.",
+            topic
+        );
+    }
+    accepted
+}
+
 async fn sign_exchange(
     wallet: &mut Wallet,
+    external_signer: Option<&str>,
     exchange: Exchange,
     ledger_address: TendermintAddress,
 ) -> Signed<Exchange> {
-    let source_keypair =
-        signing::find_keypair(wallet, &exchange.addr, ledger_address).await;
-    Signed::new(&*source_keypair, exchange.clone())
+    let signer = signing::find_signer(
+        wallet,
+        external_signer,
+        &exchange.addr,
+        ledger_address,
+    )
+    .await;
+    signing::sign(&*signer, exchange)
 }
 
-async fn sign_auction(
-    wallet: &mut Wallet,
-    auction: Auction,
-    ledger_address: TendermintAddress,
-) -> Signed<Auction> {
-    let source_keypair =
-        signing::find_keypair(wallet, &auction.addr, ledger_address).await;
-    Signed::new(&source_keypair, auction.clone())
+/// Craft the exchange that the creator or the bidder of an auction commits
+/// to. The exchange of a bid is crafted from its auction in the listing, so
+/// `None` is returned for a bid on an auction that is not listed.
+fn auction_exchange(
+    auction: &Auction,
+    listing: &[AuctionListing],
+) -> Option<Exchange> {
+    let addr = auction.addr.clone();
+    match (&auction.create_auction, &auction.place_bid) {
+        (Some(create_auction), _) => {
+            Some(create_auction.creator_exchange(addr))
+        }
+        (None, Some(place_bid)) => listing
+            .iter()
+            .find(|entry| entry.id == place_bid.auction_id)
+            .map(|entry| {
+                entry.create_auction.bidder_exchange(addr, place_bid.amount)
+            }),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use anoma::types::address::{self, Address};
+    use anoma::types::intent::{
+        token_pair_topic, CreateAuction, DecimalWrapper, PlaceBid,
+    };
+    use anoma::types::key::RefTo;
+    use anoma::types::token;
+    use regex::Regex;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::node::gossip::intent_gossiper::MatchSubscriptions;
+    use crate::node::gossip::rpc::client::rpc_server;
+
+    /// Test that the topic derived from exchanges of the same token pair in
+    /// opposite directions is the same
+    #[test]
+    fn test_exchanges_topic() {
+        let exchange = |token_sell: Address, token_buy: Address| Exchange {
+            addr: address::testing::established_address_1(),
+            token_sell,
+            rate_min: DecimalWrapper::from_str("1").unwrap(),
+            max_sell: token::Amount::whole(100),
+            token_buy,
+            min_buy: token::Amount::whole(1),
+            vp: None,
+        };
+        let xan = address::xan();
+        let btc = address::btc();
+        let topic = exchanges_topic(&[
+            exchange(xan.clone(), btc.clone()),
+            exchange(btc.clone(), xan.clone()),
+        ]);
+        assert_eq!(topic, token_pair_topic(&xan, &btc));
+    }
+
+    /// Test that the IDs are printed only for the auctions that are created by
+    /// an intent
+    #[test]
+    fn test_auction_ids() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let auction = |create_auction, place_bid| {
+            Signed::new(
+                &keypair,
+                Auction {
+                    addr: address::testing::established_address_1(),
+                    create_auction,
+                    place_bid,
+                },
+            )
+        };
+        let place_bid = PlaceBid {
+            auction_id: create_auction.id(),
+            amount: token::Amount::whole(10),
+            token: address::btc(),
+        };
+        let intent = AuctionIntent {
+            auctions: HashSet::from([
+                auction(Some(create_auction.clone()), None),
+                auction(None, Some(place_bid)),
+            ]),
+            exchange_intents: HashMap::default(),
+        };
+        assert_eq!(auction_ids(&intent), vec![create_auction.id()]);
+    }
+
+    /// Test that an auction that doesn't end after the last committed block is
+    /// rejected
+    #[test]
+    fn test_check_auction_deadlines() {
+        let auction = |auction_end| Auction {
+            addr: address::testing::established_address_1(),
+            create_auction: Some(CreateAuction {
+                token_sell: address::xan(),
+                token_buy: address::btc(),
+                amount: token::Amount::whole(100),
+                auction_start: 0,
+                auction_end,
+                min_bid_increment: token::Amount::default(),
+            }),
+            place_bid: None,
+        };
+        let current_height = BlockHeight(10);
+        assert!(check_auction_deadlines(&[auction(11)], current_height));
+        assert!(!check_auction_deadlines(&[auction(10)], current_height));
+        assert!(!check_auction_deadlines(
+            &[auction(20), auction(5)],
+            current_height
+        ));
+    }
+
+    /// Test that a bid that doesn't exceed the highest bid of its auction by
+    /// the minimum bid increment is rejected
+    #[test]
+    fn test_check_bids() {
+        let create_auction = CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::whole(5),
+        };
+        let listing = |highest_bid| {
+            vec![AuctionListing {
+                id: create_auction.id(),
+                create_auction: create_auction.clone(),
+                highest_bid,
+            }]
+        };
+        let bid = |auction_id: &str, amount| Auction {
+            addr: address::testing::established_address_1(),
+            create_auction: None,
+            place_bid: Some(PlaceBid {
+                amount: token::Amount::whole(amount),
+                token: address::btc(),
+                auction_id: auction_id.to_string(),
+            }),
+        };
+        let id = create_auction.id();
+
+        // Any first bid is accepted
+        assert!(check_bids(&[bid(&id, 1)], &listing(None)));
+
+        let highest_bid = Some(token::Amount::whole(20));
+        assert!(check_bids(&[bid(&id, 25)], &listing(highest_bid)));
+        assert!(!check_bids(&[bid(&id, 24)], &listing(highest_bid)));
+        assert!(!check_bids(&[bid(&id, 20)], &listing(highest_bid)));
+
+        // A bid in another token than the auction's payment token is rejected
+        let mut other_token = bid(&id, 100);
+        other_token.place_bid.as_mut().unwrap().token = address::eth();
+        assert!(!check_bids(&[other_token], &listing(highest_bid)));
+
+        // A bid for an auction that isn't listed is rejected
+        assert!(!check_bids(&[bid("unknown", 100)], &listing(highest_bid)));
+    }
+
+    /// Test that the exchanges of an auction's creator and of its bidder are
+    /// counterparts of each other and that a bid's exchange can only be
+    /// crafted for a listed auction
+    #[test]
+    fn test_auction_exchange() {
+        let creator = address::testing::established_address_1();
+        let bidder = address::testing::established_address_2();
+        let create_auction = CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let listing = vec![AuctionListing {
+            id: create_auction.id(),
+            create_auction: create_auction.clone(),
+            highest_bid: None,
+        }];
+        let auction = Auction {
+            addr: creator.clone(),
+            create_auction: Some(create_auction.clone()),
+            place_bid: None,
+        };
+        let bid = Auction {
+            addr: bidder.clone(),
+            create_auction: None,
+            place_bid: Some(PlaceBid {
+                amount: token::Amount::whole(20),
+                token: address::btc(),
+                auction_id: create_auction.id(),
+            }),
+        };
+
+        let sell = auction_exchange(&auction, &[]).unwrap();
+        assert_eq!(sell.addr, creator);
+        assert_eq!(sell.max_sell, token::Amount::whole(100));
+        let buy = auction_exchange(&bid, &listing).unwrap();
+        assert_eq!(buy.addr, bidder);
+        assert_eq!(buy.token_sell, sell.token_buy);
+        assert_eq!(buy.token_buy, sell.token_sell);
+        assert_eq!(buy.max_sell, token::Amount::whole(20));
+        assert_eq!(buy.min_buy, sell.max_sell);
+
+        assert!(auction_exchange(&bid, &[]).is_none());
+    }
+
+    /// Test that a signed intent written to a file is decoded, its signatures
+    /// verified and its content printed
+    #[test]
+    fn test_inspect_intent() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let addr = address::testing::established_address_1();
+        let exchange = Exchange {
+            addr: addr.clone(),
+            token_sell: address::xan(),
+            rate_min: DecimalWrapper::from_str("0.5").unwrap(),
+            max_sell: token::Amount::whole(100),
+            token_buy: address::btc(),
+            min_buy: token::Amount::whole(20),
+            vp: None,
+        };
+        let intent = Signed::new(
+            &keypair,
+            FungibleTokenIntent {
+                exchange: HashSet::from([Signed::new(&keypair, exchange)]),
+                nonce: 7,
+            },
+        );
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&intent.try_to_vec().unwrap()).unwrap();
+
+        let decoded = read_intent(file.path());
+        let pks = HashMap::from([(addr.clone(), keypair.ref_to())]);
+        assert!(decoded.verify_signatures(|addr| pks.get(addr)).is_ok());
+
+        let printed = format_intent(&decoded);
+        assert!(printed.contains("nonce 7"));
+        assert!(printed.contains(&format!("Exchange signed by {}", addr)));
+        assert!(printed.contains(&format!(
+            "sell at most {} of {}",
+            token::Amount::whole(100),
+            address::xan()
+        )));
+        assert!(printed.contains(&format!(
+            "at least {} of {}",
+            token::Amount::whole(20),
+            address::btc()
+        )));
+        assert!(printed.contains("minimum rate of 0.5"));
+
+        // A corrupt intent can't be decoded
+        assert!(IntentData::try_from_slice(&[1, 2, 3]).is_err());
+    }
+
+    /// Test that an intent topic is checked against a whitelist filter
+    #[test]
+    fn test_check_intent_topic_whitelist() {
+        let filter = SubscriptionFilter::WhitelistFilter(vec![
+            "asset_v0".to_string(),
+            "auction_v0".to_string(),
+        ]);
+        assert!(check_intent_topic(&filter, "asset_v0"));
+        assert!(check_intent_topic(&filter, "auction_v0"));
+        assert!(!check_intent_topic(&filter, "asset_v1"));
+        assert!(!check_intent_topic(&filter, "asset"));
+    }
+
+    /// Test that an intent topic is checked against a regex filter
+    #[test]
+    fn test_check_intent_topic_regex() {
+        let filter = SubscriptionFilter::RegexFilter(
+            Regex::new("^asset_v\\d{1}$").unwrap(),
+        );
+        assert!(check_intent_topic(&filter, "asset_v0"));
+        assert!(!check_intent_topic(&filter, "auction_v0"));
+    }
+
+    /// Test that the connection to a gossip node's RPC that becomes available
+    /// only after a delay is retried until it succeeds
+    #[tokio::test]
+    async fn test_connect_rpc_client_retry() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (rpc_sender, _rpc_receiver) = mpsc::channel(1);
+        let (status_sender, _status_receiver) = mpsc::channel(1);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            rpc_server(
+                addr,
+                rpc_sender,
+                status_sender,
+                MatchSubscriptions::default(),
+            )
+            .await
+        });
+
+        let node_addr = format!("http://{}", addr);
+        let retry = |retries| args::RpcRetry {
+            retries,
+            interval: Duration::from_millis(100).into(),
+        };
+        // Without any retries, the connection fails as the server is not yet
+        // running
+        assert!(connect_rpc_client(node_addr.clone(), &retry(0))
+            .await
+            .is_err());
+        assert!(connect_rpc_client(node_addr, &retry(10)).await.is_ok());
+    }
 }
@@ -1,6 +1,7 @@
 pub mod gossip;
 pub mod rpc;
 pub mod signing;
+pub mod storage_value;
 mod tendermint_websocket_client;
 pub mod tx;
 pub mod utils;
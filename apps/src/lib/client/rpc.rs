@@ -1,8 +1,9 @@
 //! Client RPC queries
 
 use std::borrow::Cow;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::io::{self, Write};
+use std::str::FromStr;
 
 use anoma::ledger::pos::types::{
     Epoch as PosEpoch, VotingPower, WeightedValidator,
@@ -12,12 +13,14 @@ use anoma::ledger::pos::{
 };
 use anoma::types::address::Address;
 use anoma::types::key::*;
-use anoma::types::storage::{Epoch, PrefixValue};
+use anoma::types::storage::{BlockHeight, Epoch, PrefixValue};
 use anoma::types::{address, storage, token};
 use borsh::BorshDeserialize;
 use itertools::Itertools;
 #[cfg(not(feature = "ABCI"))]
-use tendermint::abci::Code;
+use tendermint::abci::{Code, Path as AbciPath};
+#[cfg(not(feature = "ABCI"))]
+use tendermint::block::Height;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
@@ -39,9 +42,12 @@ use tendermint_rpc_abci::{Client, HttpClient};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::{Order, SubscriptionClient, WebSocketClient};
 #[cfg(feature = "ABCI")]
-use tendermint_stable::abci::Code;
+use tendermint_stable::abci::{Code, Path as AbciPath};
+#[cfg(feature = "ABCI")]
+use tendermint_stable::block::Height;
 
 use crate::cli::{self, args, Context};
+use crate::client::storage_value;
 use crate::client::tx::TxResponse;
 use crate::node::ledger::rpc::Path;
 
@@ -73,6 +79,122 @@ pub async fn query_epoch(args: args::Query) -> Epoch {
     cli::safe_exit(1)
 }
 
+/// Query the height of the last committed block
+pub async fn query_block_height(
+    ledger_address: TendermintAddress,
+) -> BlockHeight {
+    let client = HttpClient::new(ledger_address).unwrap();
+    match client.status().await {
+        Ok(status) => BlockHeight(status.sync_info.latest_block_height.value()),
+        Err(err) => {
+            eprintln!("Error querying the last committed block: {}", err);
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Query a raw storage value and print it decoded by the type registered for
+/// its key pattern, or as hex for an unknown key
+pub async fn query_storage(args: args::QueryStorage) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let path = Path::Value(args.key.clone());
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            let value = storage_value::format_value(&args.key, &response.value);
+            println!("{}", value);
+            return;
+        }
+        Code::Err(err) => {
+            if err == 1 {
+                println!("No value found for key {}", args.key);
+                return;
+            } else {
+                eprintln!(
+                    "Error in the query {} (error code {})",
+                    response.info, err
+                )
+            }
+        }
+    }
+    cli::safe_exit(1)
+}
+
+/// Send an ABCI query with an arbitrary path and data and print the raw
+/// response
+pub async fn query_raw(args: args::QueryRaw) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let path = match AbciPath::from_str(&args.path) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("Invalid ABCI query path {}: {}", args.path, err);
+            cli::safe_exit(1)
+        }
+    };
+    let height = match args.height.map(Height::try_from).transpose() {
+        Ok(height) => height,
+        Err(err) => {
+            eprintln!("Invalid block height: {}", err);
+            cli::safe_exit(1)
+        }
+    };
+    let response = match client
+        .abci_query(Some(path), args.data, height, args.prove)
+        .await
+    {
+        Ok(response) => response,
+        Err(err) => {
+            eprintln!("Error in the query: {}", err);
+            cli::safe_exit(1)
+        }
+    };
+    println!("Code: {}", response.code.value());
+    println!("Log: {}", response.log);
+    println!("Info: {}", response.info);
+    println!("Height: {}", response.height);
+    println!("Value: {}", hex::encode(&response.value));
+    if let Some(proof) = response.proof {
+        println!("Proof: {:#?}", proof);
+    }
+}
+
+/// Query the PoS bonds and unbonds of the given bond ID
+pub async fn query_bonds_and_unbonds(
+    client: HttpClient,
+    bond_id: &pos::BondId,
+) -> (Option<Bonds>, Option<Unbonds>) {
+    let path = Path::Bonds {
+        owner: bond_id.source.clone(),
+        validator: bond_id.validator.clone(),
+    };
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            match <(Option<Bonds>, Option<Unbonds>)>::try_from_slice(
+                &response.value[..],
+            ) {
+                Ok(bonds_and_unbonds) => return bonds_and_unbonds,
+                Err(err) => {
+                    eprintln!("Error decoding the bonds value: {}", err)
+                }
+            }
+        }
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
+
 /// Query token balance(s)
 pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
     let client = HttpClient::new(args.query.ledger_address).unwrap();
@@ -173,18 +295,11 @@ pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
         (Some(owner), Some(validator)) => {
             let source = ctx.get(&owner);
             let validator = ctx.get(&validator);
-            // Find owner's delegations to the given validator
+            // Find owner's delegations to the given validator and owner's
+            // unbonded delegations from the given validator
             let bond_id = pos::BondId { source, validator };
-            let bond_key = pos::bond_key(&bond_id);
-            let bonds =
-                query_storage_value::<pos::Bonds>(client.clone(), bond_key)
-                    .await;
-            // Find owner's unbonded delegations from the given
-            // validator
-            let unbond_key = pos::unbond_key(&bond_id);
-            let unbonds =
-                query_storage_value::<pos::Unbonds>(client.clone(), unbond_key)
-                    .await;
+            let (bonds, unbonds) =
+                query_bonds_and_unbonds(client.clone(), &bond_id).await;
             // Find validator's slashes, if any
             let slashes_key = pos::validator_slashes_key(&bond_id.validator);
             let slashes =
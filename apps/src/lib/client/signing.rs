@@ -1,19 +1,203 @@
 //! Helpers for making digital signatures using cryptographic keys from the
-//! wallet.
+//! wallet or with an external signer.
 
+use std::io::Write;
+use std::process::{Command, Stdio};
 use std::rc::Rc;
 
+use anoma::proto::{Signed, Tx};
 use anoma::types::address::{Address, ImplicitAddress};
 use anoma::types::key::*;
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
+use thiserror::Error;
 
 use super::rpc;
 use crate::cli;
 use crate::wallet::Wallet;
 
+#[derive(Error, Debug)]
+pub enum SignerError {
+    #[error("Failed to run the external signer: {0}")]
+    ExternalSignerIo(std::io::Error),
+    #[error("The external signer failed with {0}")]
+    ExternalSignerFailed(std::process::ExitStatus),
+    #[error("The external signer returned an invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error(
+        "The signature of the external signer does not verify with the \
+         expected public key {0}"
+    )]
+    SignatureMismatch(common::PublicKey),
+}
+
+/// A producer of signatures of arbitrary bytes
+pub trait Signer {
+    /// The public key that verifies the signatures of the signer
+    fn public_key(&self) -> &common::PublicKey;
+
+    /// Sign the given bytes
+    fn sign(&self, data: &[u8]) -> Result<common::Signature, SignerError>;
+}
+
+/// A signer with a keypair loaded from the wallet
+pub struct WalletSigner {
+    keypair: Rc<common::SecretKey>,
+    public_key: common::PublicKey,
+}
+
+impl WalletSigner {
+    /// Create a signer for the given keypair
+    pub fn new(keypair: Rc<common::SecretKey>) -> Self {
+        let public_key = keypair.ref_to();
+        Self {
+            keypair,
+            public_key,
+        }
+    }
+}
+
+impl Signer for WalletSigner {
+    fn public_key(&self) -> &common::PublicKey {
+        &self.public_key
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<common::Signature, SignerError> {
+        Ok(common::SigScheme::sign(&*self.keypair, data))
+    }
+}
+
+/// An external signer, e.g. a hardware wallet, that is invoked as a shell
+/// command. The command is given the hex-encoded bytes to be signed on its
+/// standard input and it must print the hex-encoded Borsh serialization of the
+/// signature to its standard output. The signature is only accepted if it
+/// verifies with the expected public key.
+#[derive(Clone, Debug)]
+pub struct ExternalSigner {
+    command: String,
+    public_key: common::PublicKey,
+}
+
+impl ExternalSigner {
+    /// Create a signer that runs the given shell command, whose signatures
+    /// must verify with the given public key
+    pub fn new(
+        command: impl Into<String>,
+        public_key: common::PublicKey,
+    ) -> Self {
+        Self {
+            command: command.into(),
+            public_key,
+        }
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn public_key(&self) -> &common::PublicKey {
+        &self.public_key
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<common::Signature, SignerError> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(SignerError::ExternalSignerIo)?;
+        child
+            .stdin
+            .take()
+            .expect("The signer's stdin should be piped")
+            .write_all(hex::encode(data).as_bytes())
+            .map_err(SignerError::ExternalSignerIo)?;
+        let output = child
+            .wait_with_output()
+            .map_err(SignerError::ExternalSignerIo)?;
+        if !output.status.success() {
+            return Err(SignerError::ExternalSignerFailed(output.status));
+        }
+        let sig_hex = String::from_utf8_lossy(&output.stdout);
+        let sig_bytes = hex::decode(sig_hex.trim())
+            .map_err(|err| SignerError::InvalidSignature(err.to_string()))?;
+        let sig = common::Signature::try_from_slice(&sig_bytes)
+            .map_err(|err| SignerError::InvalidSignature(err.to_string()))?;
+        common::SigScheme::verify_signature_raw(&self.public_key, data, &sig)
+            .map_err(|_| {
+                SignerError::SignatureMismatch(self.public_key.clone())
+            })?;
+        Ok(sig)
+    }
+}
+
+/// Find a signer for the given address. This is the external signer with the
+/// given shell command, if any, whose signatures must verify with the
+/// address' public key, as found by [`find_public_key`]. Otherwise, it's the
+/// address' keypair from the wallet, as found by [`find_keypair`].
+pub async fn find_signer(
+    wallet: &mut Wallet,
+    external_signer: Option<&str>,
+    addr: &Address,
+    ledger_address: TendermintAddress,
+) -> Box<dyn Signer> {
+    match external_signer {
+        Some(command) => Box::new(ExternalSigner::new(
+            command,
+            find_public_key(addr, ledger_address).await,
+        )),
+        None => Box::new(WalletSigner::new(
+            find_keypair(wallet, addr, ledger_address).await,
+        )),
+    }
+}
+
+/// Sign the given data with the signer. Exits if the signing fails.
+pub fn sign<T>(signer: &dyn Signer, data: T) -> Signed<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    Signed::try_new_with(data, |bytes| signer.sign(bytes)).unwrap_or_else(
+        |err| {
+            eprintln!("Failed to sign the data: {}", err);
+            cli::safe_exit(1)
+        },
+    )
+}
+
+/// Sign the given tx with the signer. Exits if the signing fails.
+pub fn sign_tx(signer: &dyn Signer, tx: Tx) -> Tx {
+    tx.try_sign_with(|bytes| signer.sign(bytes))
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to sign the transaction: {}", err);
+            cli::safe_exit(1)
+        })
+}
+
+/// Look-up the public key of the given address from the ledger. Exits if
+/// the key cannot be found.
+pub async fn find_public_key(
+    addr: &Address,
+    ledger_address: TendermintAddress,
+) -> common::PublicKey {
+    if let Address::Internal(_) = addr {
+        eprintln!("Internal address {} doesn't have any signing keys.", addr);
+        cli::safe_exit(1)
+    }
+    println!(
+        "Looking-up public key of {} from the ledger...",
+        addr.encode()
+    );
+    rpc::get_public_key(addr, ledger_address)
+        .await
+        .unwrap_or_else(|| {
+            eprintln!("No public key found for the address {}", addr.encode());
+            cli::safe_exit(1);
+        })
+}
+
 /// Find the public key for the given address and try to load the keypair
 /// for it from the wallet. Panics if the key cannot be found or loaded.
 pub async fn find_keypair(
@@ -23,19 +207,7 @@ pub async fn find_keypair(
 ) -> Rc<common::SecretKey> {
     match addr {
         Address::Established(_) => {
-            println!(
-                "Looking-up public key of {} from the ledger...",
-                addr.encode()
-            );
-            let public_key = rpc::get_public_key(addr, ledger_address)
-                .await
-                .unwrap_or_else(|| {
-                    eprintln!(
-                        "No public key found for the address {}",
-                        addr.encode()
-                    );
-                    cli::safe_exit(1);
-                });
+            let public_key = find_public_key(addr, ledger_address).await;
             wallet.find_key_by_pk(&public_key).unwrap_or_else(|err| {
                 eprintln!(
                     "Unable to load the keypair from the wallet for public \
@@ -65,3 +237,82 @@ pub async fn find_keypair(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use anoma::proto::SignedTxData;
+    use anoma::types::intent::Exchange;
+    use anoma::types::{address, key, token};
+
+    use super::*;
+
+    fn exchange() -> Exchange {
+        Exchange {
+            addr: address::testing::established_address_1(),
+            token_sell: address::xan(),
+            rate_min: "1".parse().unwrap(),
+            max_sell: token::Amount::whole(100),
+            token_buy: address::btc(),
+            min_buy: token::Amount::whole(1),
+            vp: None,
+        }
+    }
+
+    /// Test that an exchange signed by a mock external signer, which only
+    /// signs the expected bytes, verifies with the signer's public key
+    #[test]
+    fn test_external_signer() {
+        let keypair = key::testing::keypair_1();
+        let exchange = exchange();
+        let bytes = exchange.try_to_vec().unwrap();
+        let sig = common::SigScheme::sign(&keypair, &bytes);
+        let command = format!(
+            "[ \"$(cat)\" = \"{}\" ] && echo {}",
+            hex::encode(&bytes),
+            hex::encode(sig.try_to_vec().unwrap())
+        );
+        let signer = ExternalSigner::new(&command, keypair.ref_to());
+
+        let signed = sign(&signer, exchange.clone());
+        assert!(signed.verify(&keypair.ref_to()).is_ok());
+
+        // The mock signer refuses to sign any other bytes
+        let err = signer.sign(b"other bytes").unwrap_err();
+        assert!(matches!(err, SignerError::ExternalSignerFailed(_)));
+
+        // A signature that doesn't verify with the expected public key is
+        // rejected
+        let other_pk = key::testing::keypair_2().ref_to();
+        let signer = ExternalSigner::new(command, other_pk.clone());
+        let err = signer.sign(&bytes).unwrap_err();
+        assert!(
+            matches!(err, SignerError::SignatureMismatch(pk) if pk == other_pk)
+        );
+    }
+
+    /// Test that an invalid output of an external signer is rejected
+    #[test]
+    fn test_external_signer_invalid_signature() {
+        let signer = ExternalSigner::new(
+            "cat > /dev/null && echo 'not hex'",
+            key::testing::keypair_1().ref_to(),
+        );
+        let err = signer.sign(b"bytes").unwrap_err();
+        assert!(matches!(err, SignerError::InvalidSignature(_)));
+    }
+
+    /// Test that an exchange signed by the wallet signer verifies
+    #[test]
+    fn test_wallet_signer() {
+        let keypair = key::testing::keypair_1();
+        let signer = WalletSigner::new(Rc::new(keypair.clone()));
+        assert_eq!(signer.public_key(), &keypair.ref_to());
+        let signed = sign(&signer, exchange());
+        assert!(signed.verify(&keypair.ref_to()).is_ok());
+
+        let tx = sign_tx(&signer, Tx::new(vec![], Some(b"data".to_vec())));
+        let signed_data =
+            SignedTxData::try_from_slice(&tx.data.clone().unwrap()).unwrap();
+        assert!(tx.verify_sig(&keypair.ref_to(), &signed_data.sig).is_ok());
+    }
+}
@@ -0,0 +1,126 @@
+//! Decoding of raw storage values for display. The type of a value is looked
+//! up from a registry of well-known storage key patterns.
+
+use anoma::ledger::pos::{self, Bonds, PosParams, Unbonds};
+use anoma::types::key::{self, common};
+use anoma::types::storage::Key;
+use anoma::types::token;
+use borsh::BorshDeserialize;
+
+/// The type of the values stored under a well-known storage key pattern
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoredType {
+    /// A token balance
+    Balance,
+    /// The public key of an account
+    PublicKey,
+    /// The WASM code of a validity predicate
+    ValidityPredicate,
+    /// The PoS system parameters
+    PosParams,
+    /// The PoS bonds of a bond ID
+    Bonds,
+    /// The PoS unbonds of a bond ID
+    Unbonds,
+}
+
+/// The registry of the well-known storage key patterns with the type of their
+/// values. The first matching pattern is used.
+const REGISTRY: &[(fn(&Key) -> bool, StoredType)] = &[
+    (is_balance_key, StoredType::Balance),
+    (is_pk_key, StoredType::PublicKey),
+    (is_vp_key, StoredType::ValidityPredicate),
+    (pos::is_params_key, StoredType::PosParams),
+    (is_bond_key, StoredType::Bonds),
+    (is_unbond_key, StoredType::Unbonds),
+];
+
+fn is_balance_key(key: &Key) -> bool {
+    token::is_any_token_balance_key(key).is_some()
+}
+
+fn is_pk_key(key: &Key) -> bool {
+    key::is_pk_key(key).is_some()
+}
+
+fn is_vp_key(key: &Key) -> bool {
+    key.is_validity_predicate().is_some()
+}
+
+fn is_bond_key(key: &Key) -> bool {
+    pos::is_bond_key(key).is_some()
+}
+
+fn is_unbond_key(key: &Key) -> bool {
+    pos::is_unbond_key(key).is_some()
+}
+
+/// Find the type of the values stored under the given key, if it matches any
+/// of the well-known key patterns.
+pub fn stored_type(key: &Key) -> Option<StoredType> {
+    REGISTRY
+        .iter()
+        .find(|(matches, _)| matches(key))
+        .map(|(_, stored_type)| *stored_type)
+}
+
+/// Decode a value read from storage under the given key and format it for
+/// display. The values of unknown keys and the values that cannot be decoded
+/// as their registered type are formatted as hex.
+pub fn format_value(key: &Key, value: &[u8]) -> String {
+    stored_type(key)
+        .and_then(|stored_type| decode_value(stored_type, value))
+        .unwrap_or_else(|| hex::encode(value))
+}
+
+/// Decode a value of the given type and format it for display
+fn decode_value(stored_type: StoredType, value: &[u8]) -> Option<String> {
+    let formatted = match stored_type {
+        StoredType::Balance => decode::<token::Amount>(value)?.to_string(),
+        StoredType::PublicKey => {
+            decode::<common::PublicKey>(value)?.to_string()
+        }
+        StoredType::ValidityPredicate => {
+            format!("WASM code of {} bytes", value.len())
+        }
+        StoredType::PosParams => format!("{:#?}", decode::<PosParams>(value)?),
+        StoredType::Bonds => format!("{:#?}", decode::<Bonds>(value)?),
+        StoredType::Unbonds => format!("{:#?}", decode::<Unbonds>(value)?),
+    };
+    Some(formatted)
+}
+
+fn decode<T: BorshDeserialize>(value: &[u8]) -> Option<T> {
+    T::try_from_slice(value).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use anoma::types::address::{self, xan};
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    /// Test that a balance is printed as a decoded amount
+    #[test]
+    fn test_format_balance() {
+        let owner = address::testing::established_address_1();
+        let key = token::balance_key(&xan(), &owner);
+        let value = token::Amount::whole(10).try_to_vec().unwrap();
+        assert_eq!(stored_type(&key), Some(StoredType::Balance));
+        assert_eq!(format_value(&key, &value), "10");
+    }
+
+    /// Test that the value of an unknown key, or a value that cannot be
+    /// decoded as its registered type, is printed as hex
+    #[test]
+    fn test_format_fallback_to_hex() {
+        let key = Key::parse("unknown").unwrap();
+        assert_eq!(stored_type(&key), None);
+        assert_eq!(format_value(&key, &[0xab, 0xcd]), "abcd");
+
+        let owner = address::testing::established_address_1();
+        let key = token::balance_key(&xan(), &owner);
+        assert_eq!(format_value(&key, &[0xab]), "ab");
+    }
+}
@@ -3,7 +3,7 @@ use std::convert::TryFrom;
 use std::fs::File;
 
 use anoma::ledger::pos::{BondId, Bonds, Unbonds};
-use anoma::proto::Tx;
+use anoma::proto::{Tx, TxBuilder};
 use anoma::types::address::Address;
 use anoma::types::key::*;
 use anoma::types::nft::{self, Nft, NftToken};
@@ -36,7 +36,8 @@ use tendermint_rpc_abci::query::{EventType, Query};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::{Client, HttpClient};
 
-use super::{rpc, signing};
+use super::rpc;
+use super::signing::{self, Signer, WalletSigner};
 use crate::cli::context::WalletAddress;
 use crate::cli::{args, safe_exit, Context};
 use crate::client::tendermint_websocket_client::{
@@ -78,7 +79,10 @@ pub async fn submit_custom(ctx: Context, args: args::TxCustom) {
     let data = args.data_path.map(|data_path| {
         std::fs::read(data_path).expect("Expected a file at given data path")
     });
-    let tx = Tx::new(tx_code, data);
+    let tx = match data {
+        Some(data) => TxBuilder::new().code(tx_code).raw_data(data).build(),
+        None => TxBuilder::new().code(tx_code).build(),
+    };
     let (ctx, initialized_accounts) = process_tx(ctx, &args.tx, tx, None).await;
     save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
 }
@@ -131,9 +135,8 @@ pub async fn submit_update_vp(ctx: Context, args: args::TxUpdateVp) {
     let tx_code = ctx.read_wasm(TX_UPDATE_VP_WASM);
 
     let data = UpdateVp { addr, vp_code };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
 
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&data).build();
     process_tx(ctx, &args.tx, tx, Some(&args.addr)).await;
 }
 
@@ -156,9 +159,8 @@ pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
         public_key,
         vp_code,
     };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
 
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&data).build();
     let (ctx, initialized_accounts) =
         process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
     save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
@@ -265,8 +267,7 @@ pub async fn submit_init_validator(
         validator_vp_code,
         rewards_vp_code,
     };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&data).build();
     let (mut ctx, initialized_accounts) =
         process_tx(ctx, &tx_args, tx, Some(&source)).await;
     if !tx_args.dry_run {
@@ -439,11 +440,8 @@ pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
         amount: args.amount,
     };
     tracing::debug!("Transfer data {:?}", transfer);
-    let data = transfer
-        .try_to_vec()
-        .expect("Encoding tx data shouldn't fail");
 
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&transfer).build();
     process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
 }
 
@@ -470,13 +468,9 @@ pub async fn submit_init_nft(ctx: Context, args: args::NftCreate) {
         tokens: nft.tokens,
     };
 
-    let data = data.try_to_vec().expect(
-        "Encoding transfer data to initialize a new account shouldn't fail",
-    );
-
     let tx_code = ctx.read_wasm(TX_INIT_NFT);
 
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&data).build();
     process_tx(ctx, &args.tx, tx, signer.as_ref()).await;
 }
 
@@ -508,13 +502,9 @@ pub async fn submit_mint_nft(ctx: Context, args: args::NftMint) {
         tokens: nft_tokens,
     };
 
-    let data = data.try_to_vec().expect(
-        "Encoding transfer data to initialize a new account shouldn't fail",
-    );
-
     let tx_code = ctx.read_wasm(TX_MINT_NFT);
 
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&data).build();
     process_tx(ctx, &args.tx, tx, signer.as_ref()).await;
 }
 
@@ -554,8 +544,7 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
             if balance < args.amount {
                 eprintln!(
                     "The balance of the source {} is lower than the amount to \
-                     be transferred. Amount to transfer is {} and the balance \
-                     is {}.",
+                     be bonded. Amount to bond is {} and the balance is {}.",
                     bond_source, args.amount, balance
                 );
                 if !args.tx.force {
@@ -576,9 +565,7 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
         amount: args.amount,
         source,
     };
-    let data = bond.try_to_vec().expect("Encoding tx data shouldn't fail");
-
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&bond).build();
     let default_signer = args.source.as_ref().unwrap_or(&args.validator);
     process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
 }
@@ -644,9 +631,8 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
         amount: args.amount,
         source,
     };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
 
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&data).build();
     let default_signer = args.source.as_ref().unwrap_or(&args.validator);
     process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
 }
@@ -712,9 +698,8 @@ pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
     }
 
     let data = pos::Withdraw { validator, source };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
 
-    let tx = Tx::new(tx_code, Some(data));
+    let tx = TxBuilder::new().code(tx_code).data(&data).build();
     let default_signer = args.source.as_ref().unwrap_or(&args.validator);
     process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
 }
@@ -733,24 +718,24 @@ async fn sign_tx(
     args: &args::Tx,
     default: Option<&WalletAddress>,
 ) -> (Context, TxBroadcastData) {
-    let (tx, keypair) = if let Some(signing_key) = &args.signing_key {
-        let signing_key = ctx.get_cached(signing_key);
-        (tx.sign(&signing_key), signing_key)
+    let signer: Box<dyn Signer> = if let Some(signing_key) = &args.signing_key {
+        Box::new(WalletSigner::new(ctx.get_cached(signing_key)))
     } else if let Some(signer) = args.signer.as_ref().or(default) {
         let signer = ctx.get(signer);
-        let signing_key = signing::find_keypair(
+        signing::find_signer(
             &mut ctx.wallet,
+            args.external_signer.as_deref(),
             &signer,
             args.ledger_address.clone(),
         )
-        .await;
-        (tx.sign(&signing_key), signing_key)
+        .await
     } else {
         panic!(
             "All transactions must be signed; please either specify the key \
              or the address from which to look up the signing key."
         );
     };
+    let tx = signing::sign_tx(&*signer, tx);
     let epoch = rpc::query_epoch(args::Query {
         ledger_address: args.ledger_address.clone(),
     })
@@ -758,7 +743,7 @@ async fn sign_tx(
     let broadcast_data = if args.dry_run {
         TxBroadcastData::DryRun(tx)
     } else {
-        sign_wrapper(&ctx, args, epoch, tx, &keypair).await
+        sign_wrapper(&ctx, args, epoch, tx, &*signer).await
     };
     (ctx, broadcast_data)
 }
@@ -771,15 +756,15 @@ async fn sign_wrapper(
     args: &args::Tx,
     epoch: Epoch,
     tx: Tx,
-    keypair: &common::SecretKey,
+    signer: &dyn Signer,
 ) -> TxBroadcastData {
     let tx = {
-        WrapperTx::new(
+        WrapperTx::new_with_pk(
             Fee {
                 amount: args.fee_amount,
                 token: ctx.get(&args.fee_token),
             },
-            keypair,
+            signer.public_key().clone(),
             epoch,
             args.gas_limit.clone(),
             tx,
@@ -802,9 +787,12 @@ async fn sign_wrapper(
         None
     };
     TxBroadcastData::Wrapper {
-        tx: tx
-            .sign(keypair)
-            .expect("Wrapper tx signing keypair should be correct"),
+        tx: tx.try_sign_with(|bytes| signer.sign(bytes)).unwrap_or_else(
+            |err| {
+                eprintln!("Failed to sign the wrapper transaction: {}", err);
+                safe_exit(1)
+            },
+        ),
         wrapper_hash,
         decrypted_hash,
     }
@@ -1082,6 +1070,7 @@ pub struct TxResponse {
     pub code: String,
     pub gas_used: String,
     pub initialized_accounts: Vec<Address>,
+    pub rejected_vps: Vec<Address>,
 }
 
 /// Parse the JSON payload received from a subscription
@@ -1120,6 +1109,10 @@ fn parse(
         Some(values) => serde_json::from_str(&values).unwrap(),
         _ => vec![],
     };
+    let rejected_vps = match event.take("rejected_vps") {
+        Some(values) => serde_json::from_str(&values).unwrap(),
+        _ => vec![],
+    };
     TxResponse {
         info,
         log,
@@ -1128,6 +1121,7 @@ fn parse(
         code,
         gas_used,
         initialized_accounts,
+        rejected_vps,
     }
 }
 
@@ -1195,6 +1189,19 @@ impl TxResponse {
             }
             _ => vec![],
         };
+        let rejected_vps = selector(&format!(
+            "$.events.['{}.rejected_vps'][{}]",
+            evt_key, index
+        ));
+        let rejected_vps = match rejected_vps {
+            Ok(values) if !values.is_empty() => {
+                // Encoded in the same way as the initialized accounts above
+                let raw: String =
+                    serde_json::from_value(values[0].clone()).unwrap();
+                serde_json::from_str(&raw).unwrap()
+            }
+            _ => vec![],
+        };
         TxResponse {
             info: serde_json::from_value(info[0].clone()).unwrap(),
             log: serde_json::from_value(log[0].clone()).unwrap(),
@@ -1203,6 +1210,7 @@ impl TxResponse {
             code: serde_json::from_value(code[0].clone()).unwrap(),
             gas_used: serde_json::from_value(gas_used[0].clone()).unwrap(),
             initialized_accounts,
+            rejected_vps,
         }
     }
 }
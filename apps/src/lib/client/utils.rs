@@ -6,9 +6,9 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use anoma::types::address;
 use anoma::types::chain::ChainId;
 use anoma::types::key::*;
-use anoma::types::{address, token};
 use borsh::BorshSerialize;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
@@ -31,7 +31,7 @@ use crate::cli::{self, args};
 use crate::config::genesis::genesis_config;
 use crate::config::global::GlobalConfig;
 use crate::config::{
-    self, genesis, Config, IntentGossiper, PeerAddress, TendermintMode,
+    self, Config, IntentGossiper, PeerAddress, TendermintMode,
 };
 use crate::node::gossip;
 use crate::node::ledger::tendermint_node;
@@ -332,7 +332,8 @@ pub fn init_network(
         // Add a Intent gossiper bootstrap peer from the validator's IP
         let mut gossiper_config = IntentGossiper::default();
         // Generate P2P identity
-        let p2p_idenity = gossip::p2p::Identity::gen(&chain_dir);
+        let p2p_idenity = gossip::p2p::Identity::gen(&chain_dir)
+            .expect("Couldn't generate the P2P identity");
         let peer_id = p2p_idenity.peer_id();
         let ledger_addr =
             SocketAddr::from_str(config.net_address.as_ref().unwrap()).unwrap();
@@ -452,6 +453,9 @@ pub fn init_network(
                         let matchmaker_config = config::Matchmaker {
                             matchmaker_path: Some(mm_code.clone().into()),
                             tx_code_path: Some(tx_code.clone().into()),
+                            tick_interval: None,
+                            working_dir: None,
+                            dry_run: false,
                         };
                         matchmaker_configs
                             .insert(name.clone(), matchmaker_config);
@@ -862,13 +866,14 @@ pub fn init_genesis_validator(
 
 /// Initialize genesis validator's address, staking reward address,
 /// consensus key, validator account key and staking rewards key and use
-/// it in the ledger's node.
+/// it in the ledger's node. Prints and returns the validator's genesis
+/// config.
 fn init_genesis_validator_aux(
     wallet: &mut Wallet,
     config: &Config,
     alias: String,
     unsafe_dont_encrypt: bool,
-) -> genesis::Validator {
+) -> genesis_config::ValidatorConfig {
     // Generate validator address
     let validator_address =
         address::gen_established_address("genesis validator address");
@@ -942,32 +947,46 @@ fn init_genesis_validator_aux(
     println!("Protocol signing key {}", &protocol_key);
     println!("DKG public key {}", &dkg_public_key);
 
-    let genesis_validator = genesis::Validator {
-        pos_data: anoma::ledger::pos::GenesisValidator {
-            address: validator_address,
-            staking_reward_address: rewards_address,
-            tokens: token::Amount::whole(200_000),
-            consensus_key: consensus_key.ref_to(),
-            staking_reward_key: rewards_key.ref_to(),
-        },
-        account_key: validator_key.ref_to(),
-        protocol_key,
-        dkg_public_key,
-        non_staked_balance: token::Amount::whole(100_000),
+    let validator_config = genesis_config::ValidatorConfig {
+        consensus_public_key: Some(genesis_config::HexString(
+            consensus_key.ref_to().to_string(),
+        )),
+        account_public_key: Some(genesis_config::HexString(
+            validator_key.ref_to().to_string(),
+        )),
+        staking_reward_public_key: Some(genesis_config::HexString(
+            rewards_key.ref_to().to_string(),
+        )),
+        protocol_public_key: Some(genesis_config::HexString(
+            protocol_key.to_string(),
+        )),
+        dkg_public_key: Some(genesis_config::HexString(
+            dkg_public_key.to_string(),
+        )),
+        address: Some(validator_address.to_string()),
+        staking_reward_address: Some(rewards_address.to_string()),
+        tokens: 200_000,
+        non_staked_balance: 100_000,
         // TODO replace with https://github.com/anoma/anoma/issues/25)
-        validator_vp_code_path: "wasm/vp_user.wasm".into(),
-        // TODO: very fake hash
-        validator_vp_sha256: [0; 32],
-        reward_vp_code_path: "wasm/vp_user.wasm".into(),
-        // TODO: very fake hash
-        reward_vp_sha256: [0; 32],
+        validator_vp: Some("vp_user".into()),
+        staking_reward_vp: Some("vp_user".into()),
+        net_address: None,
+        matchmaker_account: None,
+        matchmaker_code: None,
+        matchmaker_tx: None,
+        intent_gossip_seed: None,
     };
-    println!("Validator account key {}", validator_key.ref_to());
-    println!("Consensus key {}", consensus_key.ref_to());
-    println!("Staking reward key {}", rewards_key.ref_to());
-    // TODO print in toml format after we have https://github.com/anoma/anoma/issues/425
-    println!("Genesis validator config: {:#?}", genesis_validator);
-    genesis_validator
+    println!();
+    println!(
+        "The validator's config section to be added to a genesis config \
+         file:"
+    );
+    println!();
+    println!(
+        "{}",
+        genesis_config::validator_config_toml(&alias, &validator_config)
+    );
+    validator_config
 }
 
 async fn download_file(url: impl AsRef<str>) -> Vec<u8> {
@@ -989,3 +1008,69 @@ async fn download_file(url: impl AsRef<str>) -> Vec<u8> {
         })
         .to_vec()
 }
+
+#[cfg(test)]
+mod test {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    /// Test that the printed validator's config section parses back into a
+    /// genesis validator config with all the keys and addresses present.
+    #[test]
+    fn test_init_genesis_validator_config_section() {
+        let base_dir = tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let mut wallet =
+            Wallet::load_or_new(&base_dir.path().join(chain_id.as_str()));
+        let config =
+            Config::new(base_dir.path(), chain_id, TendermintMode::Validator);
+        let alias = "validator-1".to_string();
+        let validator_config = init_genesis_validator_aux(
+            &mut wallet,
+            &config,
+            alias.clone(),
+            true,
+        );
+
+        let section =
+            genesis_config::validator_config_toml(&alias, &validator_config);
+        let mut parsed: HashMap<
+            String,
+            HashMap<String, genesis_config::ValidatorConfig>,
+        > = toml::from_str(&section).unwrap();
+        let parsed = parsed
+            .remove("validator")
+            .and_then(|mut validators| validators.remove(&alias))
+            .expect("The section should contain the validator");
+
+        let keys = [
+            &parsed.consensus_public_key,
+            &parsed.account_public_key,
+            &parsed.staking_reward_public_key,
+            &parsed.protocol_public_key,
+        ];
+        for key in keys {
+            key.as_ref()
+                .expect("The public key should be present")
+                .to_public_key()
+                .expect("The public key should be valid");
+        }
+        parsed
+            .dkg_public_key
+            .as_ref()
+            .expect("The DKG public key should be present")
+            .to_dkg_public_key()
+            .expect("The DKG public key should be valid");
+        let addresses = [&parsed.address, &parsed.staking_reward_address];
+        for address in addresses {
+            address::Address::decode(
+                address.as_ref().expect("The address should be present"),
+            )
+            .expect("The address should be valid");
+        }
+        assert_eq!(parsed.address, validator_config.address);
+        assert_eq!(parsed.tokens, validator_config.tokens);
+        assert_eq!(parsed.validator_vp, validator_config.validator_vp);
+    }
+}
@@ -24,7 +24,10 @@ pub mod genesis_config {
     use std::path::Path;
     use std::str::FromStr;
 
-    use anoma::ledger::parameters::{EpochDuration, Parameters};
+    use anoma::ledger::gas::GasSchedule;
+    use anoma::ledger::parameters::{
+        EpochDuration, Parameters, WasmLimitsParams,
+    };
     use anoma::ledger::pos::types::BasisPoints;
     use anoma::ledger::pos::{GenesisValidator, PosParams};
     use anoma::types::address::Address;
@@ -200,6 +203,12 @@ pub mod genesis_config {
         // Hashes of whitelisted txs array. `None` value or an empty array
         // disables whitelisting.
         pub tx_whitelist: Option<Vec<String>>,
+        // Gas costs of the host environment operations. `None` value uses
+        // the default gas schedule.
+        pub gas_schedule: Option<GasSchedule>,
+        // Limits of the resources available to the wasm code. `None` value
+        // uses the default limits.
+        pub wasm_limits: Option<WasmLimitsParams>,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -491,6 +500,8 @@ pub mod genesis_config {
             .into(),
             vp_whitelist: config.parameters.vp_whitelist.unwrap_or_default(),
             tx_whitelist: config.parameters.tx_whitelist.unwrap_or_default(),
+            gas_schedule: config.parameters.gas_schedule.unwrap_or_default(),
+            wasm_limits: config.parameters.wasm_limits.unwrap_or_default(),
         };
 
         let pos_params = PosParams {
@@ -536,6 +547,20 @@ pub mod genesis_config {
         std::fs::write(path, toml).unwrap();
     }
 
+    /// Render the given validator's config as a TOML section of a genesis
+    /// config, i.e. under `[validator.{alias}]`.
+    pub fn validator_config_toml(
+        alias: impl AsRef<str>,
+        config: &ValidatorConfig,
+    ) -> String {
+        let section = HashMap::from([(
+            "validator",
+            HashMap::from([(alias.as_ref(), config)]),
+        )]);
+        toml::to_string(&section)
+            .expect("Validator config should be serializable to TOML")
+    }
+
     pub fn read_genesis_config(path: impl AsRef<Path>) -> Genesis {
         load_genesis_config(open_genesis_config(path))
     }
@@ -708,6 +733,8 @@ pub fn genesis() -> Genesis {
         max_expected_time_per_block: anoma::types::time::DurationSecs(30),
         vp_whitelist: vec![],
         tx_whitelist: vec![],
+        gas_schedule: Default::default(),
+        wasm_limits: Default::default(),
     };
     let albert = EstablishedAccount {
         address: wallet::defaults::albert_address(),
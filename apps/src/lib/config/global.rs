@@ -1,7 +1,6 @@
 //! Global configuration
 
-use std::fs::{create_dir_all, File};
-use std::io::Write;
+use std::fs::create_dir_all;
 use std::path::{Path, PathBuf};
 
 use anoma::types::chain::ChainId;
@@ -57,14 +56,14 @@ impl GlobalConfig {
         let file_path = Self::file_path(base_dir.as_ref());
         let file_dir = file_path.parent().unwrap();
         create_dir_all(file_dir).map_err(Error::WriteError)?;
-        let mut file = File::create(file_path).map_err(Error::WriteError)?;
         let toml = toml::ser::to_string(&self).map_err(|err| {
             if let toml::ser::Error::ValueAfterTable = err {
                 tracing::error!("{}", super::VALUE_AFTER_TABLE_ERROR_MSG);
             }
             Error::TomlError(err)
         })?;
-        file.write_all(toml.as_bytes()).map_err(Error::WriteError)
+        super::utils::write_atomically(file_path, toml)
+            .map_err(Error::WriteError)
     }
 
     /// Get the file path to the global config
@@ -6,8 +6,7 @@ pub mod utils;
 
 use std::collections::HashSet;
 use std::fmt::Display;
-use std::fs::{create_dir_all, File};
-use std::io::Write;
+use std::fs::create_dir_all;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -90,10 +89,24 @@ pub struct Ledger {
     pub tendermint: Tendermint,
 }
 
+/// The storage backend of the ledger
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DbType {
+    /// The state is persisted in RocksDB under the ledger's DB directory
+    #[serde(rename = "rocksdb")]
+    RocksDb,
+    /// The state is only kept in memory and it's lost when the ledger shuts
+    /// down. Useful for tests and ephemeral nodes.
+    #[serde(rename = "in-memory")]
+    InMemory,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Shell {
     pub base_dir: PathBuf,
     pub ledger_address: SocketAddr,
+    /// The storage backend, either `"rocksdb"` or `"in-memory"`
+    pub db_type: DbType,
     /// RocksDB block cache maximum size in bytes.
     /// When not set, defaults to 1/3 of the available memory.
     pub block_cache_bytes: Option<u64>,
@@ -103,9 +116,31 @@ pub struct Shell {
     /// Tx WASM compilation in-memory cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
     pub tx_wasm_compilation_cache_bytes: Option<u64>,
-    /// Use the [`Ledger::db_dir()`] method to read the value.
+    /// The number of the last block heights whose state is kept for
+    /// historical queries. Older heights are pruned from the DB on commit.
+    /// When not set, the state of all the heights is kept.
+    pub storage_retained_heights: Option<u64>,
+    /// Start even if the chain ID stored in the DB doesn't match the
+    /// configured chain ID. This is only set from the command-line and it's
+    /// never written to the config file.
+    #[serde(skip)]
+    pub force_chain_id: bool,
+    /// Re-derive the Merkle root from the DB after every commit and stop the
+    /// ledger if it doesn't match the committed root. This is a safety net
+    /// for catching storage bugs that slows down commits, so it's off by
+    /// default.
+    pub verify_merkle_root: bool,
+    /// The number of VPs of a tx that are validated concurrently. Set it to 1
+    /// to validate the VPs sequentially, e.g. for debugging. When not set,
+    /// the VPs are validated on the global Rayon thread pool.
+    pub vp_parallelism: Option<usize>,
+    /// The DB directory, relative to the chain directory or absolute, e.g.
+    /// to keep the DB on a faster drive. Use the [`Ledger::db_dir()`] method
+    /// to read the value.
     db_dir: PathBuf,
-    /// Use the [`Ledger::tendermint_dir()`] method to read the value.
+    /// The Tendermint home directory, relative to the chain directory or
+    /// absolute. Use the [`Ledger::tendermint_dir()`] method to read the
+    /// value.
     tendermint_dir: PathBuf,
 }
 
@@ -123,10 +158,24 @@ pub struct Tendermint {
     /// How long we wait after committing a block, before starting on the new
     /// height
     pub consensus_timeout_commit: Timeout,
+    /// Produce blocks even when there are no txs. Empty blocks keep the
+    /// block time advancing.
+    pub consensus_create_empty_blocks: bool,
+    /// The minimum interval between empty blocks, if they are produced. When
+    /// not set, Tendermint's default is used.
+    pub consensus_create_empty_blocks_interval: Option<Timeout>,
     pub tendermint_mode: TendermintMode,
     pub instrumentation_prometheus: bool,
     pub instrumentation_prometheus_listen_addr: SocketAddr,
     pub instrumentation_namespace: String,
+    /// How often to check that the Tendermint RPC is responsive
+    pub health_check_interval: Timeout,
+    /// The number of consecutive failed health checks of the Tendermint RPC
+    /// after which the ledger shuts down
+    pub health_check_failure_threshold: u64,
+    /// How long to wait for the Tendermint RPC to respond for the first time
+    /// before the failed health checks start to count
+    pub health_check_startup_grace: Timeout,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -136,14 +185,44 @@ pub struct IntentGossiper {
     pub topics: HashSet<String>,
     /// The server address to which matchmakers can connect to receive intents
     pub matchmakers_server_addr: SocketAddr,
+    /// The maximum size in bytes of a gossiped message. Larger messages are
+    /// refused before being published and dropped when received from peers.
+    pub max_transmit_size: usize,
+    /// The maximum number of established inbound connections. Any inbound
+    /// connection beyond this limit is refused. The configured peers are
+    /// dialed by this node, so they are always connected regardless of it.
+    pub max_peers: u32,
+    /// The namespace of the topics, which is prefixed to all the subscribed
+    /// and published topics as `{namespace}/{topic}`, so that networks that
+    /// share the same infrastructure don't receive each other's intents. The
+    /// topics and the subscription filter are configured without the
+    /// namespace.
+    pub topic_namespace: Option<String>,
 
     // Nested structures ⚠️ no simple values below any of these ⚠️
     pub subscription_filter: SubscriptionFilter,
+    pub gossipsub: Gossipsub,
     pub seed_peers: HashSet<PeerAddress>,
     pub rpc: Option<RpcServer>,
     pub discover_peer: Option<DiscoverPeer>,
 }
 
+/// The tuning of the gossipsub protocol, which depends on the size of the
+/// network. The mesh sizes must satisfy `mesh_n_low <= mesh_n <= mesh_n_high`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Gossipsub {
+    /// The interval between two heartbeats, on which the mesh is maintained
+    pub heartbeat_interval: Timeout,
+    /// The minimum number of peers in the mesh of a topic. Below it, more
+    /// peers are added on the next heartbeat.
+    pub mesh_n_low: usize,
+    /// The target number of peers in the mesh of a topic
+    pub mesh_n: usize,
+    /// The maximum number of peers in the mesh of a topic. Above it, peers
+    /// are removed on the next heartbeat.
+    pub mesh_n_high: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RpcServer {
     pub address: SocketAddr,
@@ -153,6 +232,17 @@ pub struct RpcServer {
 pub struct Matchmaker {
     pub matchmaker_path: Option<PathBuf>,
     pub tx_code_path: Option<PathBuf>,
+    /// When set, the matchmaker runs in the batched mode, in which the
+    /// intents are accumulated and matched together on every tick of this
+    /// interval. Otherwise, every intent is matched as soon as it's received.
+    pub tick_interval: Option<Timeout>,
+    /// The directory under which the matchmaker's snapshots and logs are
+    /// written, if any
+    pub working_dir: Option<PathBuf>,
+    /// When set, the matches are only logged with their transfers and no
+    /// transactions are injected into the ledger
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 impl Ledger {
@@ -170,9 +260,14 @@ impl Ledger {
                     IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                     26658,
                 ),
+                db_type: DbType::RocksDb,
                 block_cache_bytes: None,
                 vp_wasm_compilation_cache_bytes: None,
                 tx_wasm_compilation_cache_bytes: None,
+                storage_retained_heights: None,
+                force_chain_id: false,
+                verify_merkle_root: false,
+                vp_parallelism: None,
                 db_dir: DB_DIR.into(),
                 tendermint_dir: TENDERMINT_DIR.into(),
             },
@@ -189,6 +284,8 @@ impl Ledger {
                 p2p_pex: true,
                 p2p_allow_duplicate_ip: false,
                 consensus_timeout_commit: Timeout::from_str("1s").unwrap(),
+                consensus_create_empty_blocks: true,
+                consensus_create_empty_blocks_interval: None,
                 tendermint_mode: mode,
                 instrumentation_prometheus: false,
                 instrumentation_prometheus_listen_addr: SocketAddr::new(
@@ -196,6 +293,9 @@ impl Ledger {
                     26661,
                 ),
                 instrumentation_namespace: "anoman_tm".to_string(),
+                health_check_interval: Timeout::from_str("10s").unwrap(),
+                health_check_failure_threshold: 3,
+                health_check_startup_grace: Timeout::from_str("60s").unwrap(),
             },
         }
     }
@@ -214,6 +314,84 @@ impl Ledger {
     pub fn tendermint_dir(&self) -> PathBuf {
         self.shell.tendermint_dir(&self.chain_id)
     }
+
+    /// Check that the ports of the ledger's ABCI server and of Tendermint's
+    /// RPC and P2P are distinct and that none of them is already in use, so
+    /// that the node fails early instead of clashing with another node running
+    /// on the same host.
+    pub fn check_ports(&self) -> Result<()> {
+        let addresses = [
+            ("ledger", self.shell.ledger_address),
+            ("Tendermint RPC", self.tendermint.rpc_address),
+            ("Tendermint P2P", self.tendermint.p2p_address),
+        ];
+        for (ix, (name, address)) in addresses.iter().enumerate() {
+            if let Some((other_name, _)) = addresses[..ix]
+                .iter()
+                .find(|(_, other)| other.port() == address.port())
+            {
+                return Err(Error::DuplicatePort {
+                    port: address.port(),
+                    first: *other_name,
+                    second: *name,
+                });
+            }
+        }
+        for (name, address) in addresses {
+            // The listener is dropped right away to release the port
+            if let Err(err) = std::net::TcpListener::bind(address) {
+                return Err(if err.kind() == std::io::ErrorKind::AddrInUse {
+                    Error::PortInUse {
+                        port: address.port(),
+                        name,
+                        address,
+                    }
+                } else {
+                    Error::BindError {
+                        name,
+                        address,
+                        error: err,
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the DB, Tendermint and WASM directories are distinct, that
+    /// the DB and Tendermint directories exist or can be created and that the
+    /// WASM directory exists, so that the node fails early on a misconfigured
+    /// path.
+    pub fn check_dirs(&self, wasm_dir: impl AsRef<Path>) -> Result<()> {
+        let wasm_dir = wasm_dir.as_ref();
+        let dirs = [
+            ("DB", self.db_dir()),
+            ("Tendermint", self.tendermint_dir()),
+            ("WASM", wasm_dir.to_owned()),
+        ];
+        for (ix, (name, path)) in dirs.iter().enumerate() {
+            if let Some((other_name, _)) =
+                dirs[..ix].iter().find(|(_, other)| other == path)
+            {
+                return Err(Error::DuplicateDir {
+                    path: path.clone(),
+                    first: *other_name,
+                    second: *name,
+                });
+            }
+        }
+        for (name, path) in &dirs[..2] {
+            create_dir_all(path).map_err(|error| Error::DirError {
+                name: *name,
+                path: path.clone(),
+                error,
+            })?;
+        }
+        if !wasm_dir.is_dir() {
+            return Err(Error::WasmDirNotFound(wasm_dir.to_owned()));
+        }
+        Ok(())
+    }
 }
 
 impl Shell {
@@ -244,6 +422,18 @@ pub enum SubscriptionFilter {
     WhitelistFilter(Vec<String>),
 }
 
+impl SubscriptionFilter {
+    /// Check if the given topic can be subscribed to with this filter
+    pub fn accepts_topic(&self, topic: &str) -> bool {
+        match self {
+            SubscriptionFilter::RegexFilter(regex) => regex.is_match(topic),
+            SubscriptionFilter::WhitelistFilter(topics) => {
+                topics.iter().any(|accepted| accepted == topic)
+            }
+        }
+    }
+}
+
 // TODO peer_id can be part of Multiaddr, mayby this splitting is not useful ?
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct PeerAddress {
@@ -266,6 +456,15 @@ pub struct DiscoverPeer {
 pub enum Error {
     #[error("Error while reading config: {0}")]
     ReadError(config::ConfigError),
+    #[error("The config file {0} doesn't exist")]
+    FileNotFound(PathBuf),
+    #[error("Error while reading config file: {0}")]
+    FileReadError(std::io::Error),
+    #[error("The config file {path} is not valid TOML: {error}")]
+    ParseError {
+        path: PathBuf,
+        error: toml::de::Error,
+    },
     #[error("Error while deserializing config: {0}")]
     DeserializationError(config::ConfigError),
     #[error("Error while serializing to toml: {0}")]
@@ -279,6 +478,44 @@ pub enum Error {
          {{protocol}}/{{ip}}/tcp/{{port}}/p2p/{{peerid}}"
     )]
     BadBootstrapPeerFormat(String),
+    #[error(
+        "The {first} and {second} addresses are both configured with port \
+         {port}"
+    )]
+    DuplicatePort {
+        port: u16,
+        first: &'static str,
+        second: &'static str,
+    },
+    #[error("Port {port} already in use, cannot use {address} for the {name}")]
+    PortInUse {
+        port: u16,
+        name: &'static str,
+        address: SocketAddr,
+    },
+    #[error("Cannot bind the {name} address {address}: {error}")]
+    BindError {
+        name: &'static str,
+        address: SocketAddr,
+        error: std::io::Error,
+    },
+    #[error(
+        "The {first} and {second} directories are both configured as {}",
+        .path.display()
+    )]
+    DuplicateDir {
+        path: PathBuf,
+        first: &'static str,
+        second: &'static str,
+    },
+    #[error("Cannot create the {name} directory {}: {error}", .path.display())]
+    DirError {
+        name: &'static str,
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    #[error("The WASM directory {} doesn't exist", .0.display())]
+    WasmDirNotFound(PathBuf),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -318,7 +555,16 @@ impl Config {
         mode: Option<TendermintMode>,
     ) -> Self {
         let base_dir = base_dir.as_ref();
-        match Self::read(base_dir, chain_id, mode) {
+        let config = match Self::read(base_dir, chain_id, mode.clone()) {
+            Err(Error::FileNotFound(_)) => Self::generate(
+                base_dir,
+                chain_id,
+                mode.unwrap_or(TendermintMode::Validator),
+                true,
+            ),
+            result => result,
+        };
+        match config {
             Ok(mut config) => {
                 config.ledger.shell.base_dir = base_dir.to_path_buf();
                 config
@@ -334,20 +580,37 @@ impl Config {
         }
     }
 
-    /// Read the config from a file, or generate a default one and write it to
-    /// a file if it doesn't already exist. Keys that are expected but not set
-    /// in the config file are filled in with default values.
+    /// Read the config from a file. Keys that are expected but not set in the
+    /// config file are filled in with default values. Environment variables
+    /// with `ANOMA_` prefix and `__` separator for nested keys (e.g.
+    /// `ANOMA_LEDGER__SHELL__LEDGER_ADDRESS`) take precedence over both the
+    /// file and the default values. If the file doesn't
+    /// exist, returns [`Error::FileNotFound`], so that the caller may
+    /// [`Config::generate`] a default one instead. A file that is not valid
+    /// TOML fails with [`Error::ParseError`], which includes the location of
+    /// the error.
     pub fn read(
         base_dir: &Path,
         chain_id: &ChainId,
         mode: Option<TendermintMode>,
     ) -> Result<Self> {
         let file_path = Self::file_path(base_dir, chain_id);
-        let file_name = file_path.to_str().expect("Expected UTF-8 file path");
         let mode = mode.unwrap_or(TendermintMode::Validator);
-        if !file_path.exists() {
-            return Self::generate(base_dir, chain_id, mode, true);
+        let contents = match std::fs::read_to_string(&file_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::FileNotFound(file_path));
+            }
+            Err(err) => return Err(Error::FileReadError(err)),
         };
+        // Check the syntax first to tell a malformed file apart from the
+        // other errors
+        toml::from_str::<toml::Value>(&contents).map_err(|error| {
+            Error::ParseError {
+                path: file_path.clone(),
+                error,
+            }
+        })?;
         let defaults = config::Config::try_from(&Self::new(
             base_dir,
             chain_id.clone(),
@@ -357,7 +620,12 @@ impl Config {
         let mut config = config::Config::new();
         config
             .merge(defaults)
-            .and_then(|c| c.merge(config::File::with_name(file_name)))
+            .and_then(|c| {
+                c.merge(config::File::from_str(
+                    &contents,
+                    config::FileFormat::Toml,
+                ))
+            })
             .and_then(|c| {
                 c.merge(
                     config::Environment::with_prefix("anoma").separator("__"),
@@ -392,15 +660,13 @@ impl Config {
         if file_path.exists() && !replace {
             Err(Error::AlreadyExistingConfig(file_path))
         } else {
-            let mut file =
-                File::create(file_path).map_err(Error::WriteError)?;
             let toml = toml::ser::to_string(&self).map_err(|err| {
                 if let toml::ser::Error::ValueAfterTable = err {
                     tracing::error!("{}", VALUE_AFTER_TABLE_ERROR_MSG);
                 }
                 Error::TomlError(err)
             })?;
-            file.write_all(toml.as_bytes()).map_err(Error::WriteError)
+            utils::write_atomically(file_path, toml).map_err(Error::WriteError)
         }
     }
 
@@ -423,9 +689,13 @@ impl Default for IntentGossiper {
                 IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                 26661,
             ),
+            max_transmit_size: 16 * 1024 * 1024,
+            max_peers: 25,
+            topic_namespace: None,
             subscription_filter: SubscriptionFilter::RegexFilter(
                 Regex::new("asset_v\\d{1,2}").unwrap(),
             ),
+            gossipsub: Gossipsub::default(),
             seed_peers: HashSet::default(),
             rpc: None,
             discover_peer: Some(DiscoverPeer::default()),
@@ -495,6 +765,17 @@ impl<'de> Deserialize<'de> for PeerAddress {
     }
 }
 
+impl Default for Gossipsub {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Timeout::from_str("1s").unwrap(),
+            mesh_n_low: 2,
+            mesh_n: 3,
+            mesh_n_high: 6,
+        }
+    }
+}
+
 impl Default for DiscoverPeer {
     fn default() -> Self {
         Self {
@@ -527,3 +808,238 @@ And this is correct
        nested:Nested,
     }
 "#;
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_read_missing_config() {
+        let base_dir = tempdir().unwrap();
+        let chain_id = ChainId::default();
+        match Config::read(base_dir.path(), &chain_id, None) {
+            Err(Error::FileNotFound(path)) => {
+                assert_eq!(path, Config::file_path(base_dir.path(), &chain_id))
+            }
+            other => panic!("Expected a file not found error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_malformed_config() {
+        let base_dir = tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let file_path = Config::file_path(base_dir.path(), &chain_id);
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(&file_path, "wasm_dir = \"wasm\"\n[ledger\n").unwrap();
+        match Config::read(base_dir.path(), &chain_id, None) {
+            Err(Error::ParseError { path, error }) => {
+                assert_eq!(path, file_path);
+                let (line, _col) =
+                    error.line_col().expect("The error should have a location");
+                // The location is zero-indexed
+                assert_eq!(line, 1);
+            }
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_valid_config() {
+        let base_dir = tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let generated = Config::generate(
+            base_dir.path(),
+            &chain_id,
+            TendermintMode::Full,
+            false,
+        )
+        .unwrap();
+        let config = Config::read(
+            base_dir.path(),
+            &chain_id,
+            Some(TendermintMode::Full),
+        )
+        .unwrap();
+        assert_eq!(config.ledger.chain_id, generated.ledger.chain_id);
+        assert_eq!(config.wasm_dir, generated.wasm_dir);
+    }
+
+    #[test]
+    fn test_read_config_env_override() {
+        let base_dir = tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let mut config = Config::new(
+            base_dir.path(),
+            chain_id.clone(),
+            TendermintMode::Full,
+        );
+        let file_addr: SocketAddr = "127.0.0.1:36658".parse().unwrap();
+        config.ledger.shell.ledger_address = file_addr;
+        config.write(base_dir.path(), &chain_id, false).unwrap();
+
+        // The file takes precedence over the defaults
+        let config = Config::read(base_dir.path(), &chain_id, None).unwrap();
+        assert_eq!(config.ledger.shell.ledger_address, file_addr);
+
+        // The environment takes precedence over the file
+        let env_addr: SocketAddr = "127.0.0.1:46658".parse().unwrap();
+        let env_key = "ANOMA_LEDGER__SHELL__LEDGER_ADDRESS";
+        std::env::set_var(env_key, env_addr.to_string());
+        let result = Config::read(base_dir.path(), &chain_id, None);
+        std::env::remove_var(env_key);
+        assert_eq!(result.unwrap().ledger.shell.ledger_address, env_addr);
+    }
+
+    /// Find a port that is currently free on the localhost
+    fn free_address() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_check_ports() {
+        let base_dir = tempdir().unwrap();
+        let mut config = Ledger::new(
+            base_dir.path(),
+            ChainId::default(),
+            TendermintMode::Full,
+        );
+        config.shell.ledger_address = free_address();
+        config.tendermint.rpc_address = free_address();
+        config.tendermint.p2p_address = free_address();
+        config.check_ports().unwrap();
+
+        // A port that is already bound by someone else is detected
+        let bound = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let bound_addr = bound.local_addr().unwrap();
+        let mut in_use = config.clone();
+        in_use.tendermint.rpc_address = bound_addr;
+        match in_use.check_ports() {
+            Err(err @ Error::PortInUse { .. }) => {
+                assert_eq!(
+                    err.to_string(),
+                    format!(
+                        "Port {} already in use, cannot use {} for the \
+                         Tendermint RPC",
+                        bound_addr.port(),
+                        bound_addr
+                    )
+                )
+            }
+            other => panic!("Expected a port in use error, got {:?}", other),
+        }
+        drop(bound);
+
+        // The same port cannot be used for more than one address
+        let mut duplicate = config;
+        duplicate
+            .tendermint
+            .p2p_address
+            .set_port(duplicate.shell.ledger_address.port());
+        match duplicate.check_ports() {
+            Err(Error::DuplicatePort {
+                port,
+                first,
+                second,
+            }) => {
+                assert_eq!(port, duplicate.shell.ledger_address.port());
+                assert_eq!(first, "ledger");
+                assert_eq!(second, "Tendermint P2P");
+            }
+            other => panic!("Expected a duplicate port error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_dirs() {
+        let base_dir = tempdir().unwrap();
+        let db_dir = tempdir().unwrap();
+        let tendermint_dir = tempdir().unwrap();
+        let wasm_dir = tempdir().unwrap();
+        let mut config = Ledger::new(
+            base_dir.path(),
+            ChainId::default(),
+            TendermintMode::Full,
+        );
+        config.shell.db_dir = db_dir.path().join("db");
+        config.shell.tendermint_dir = tendermint_dir.path().join("home");
+        config.check_dirs(wasm_dir.path()).unwrap();
+
+        // Each directory is used from its own configured path and the missing
+        // ones are created
+        assert_eq!(config.db_dir(), db_dir.path().join("db"));
+        assert_eq!(config.tendermint_dir(), tendermint_dir.path().join("home"));
+        assert!(config.db_dir().is_dir());
+        assert!(config.tendermint_dir().is_dir());
+        assert!(!config.chain_dir().join(DB_DIR).exists());
+        assert!(!config.chain_dir().join(TENDERMINT_DIR).exists());
+
+        // The WASM directory must already exist
+        let missing = wasm_dir.path().join("missing");
+        match config.check_dirs(&missing) {
+            Err(Error::WasmDirNotFound(path)) => assert_eq!(path, missing),
+            other => panic!("Expected a WASM dir error, got {:?}", other),
+        }
+
+        // The same directory cannot be used for more than one purpose
+        let mut duplicate = config;
+        duplicate.shell.tendermint_dir = db_dir.path().join("db");
+        match duplicate.check_dirs(wasm_dir.path()) {
+            Err(Error::DuplicateDir {
+                path,
+                first,
+                second,
+            }) => {
+                assert_eq!(path, db_dir.path().join("db"));
+                assert_eq!(first, "DB");
+                assert_eq!(second, "Tendermint");
+            }
+            other => panic!("Expected a duplicate dir error, got {:?}", other),
+        }
+    }
+
+    /// Test that a config write that is interrupted before the temporary file
+    /// is renamed into place leaves the config absent or with its previous
+    /// contents, but never partially written
+    #[test]
+    fn test_write_config_atomically() {
+        let base_dir = tempdir().unwrap();
+        let chain_id = ChainId::default();
+        let file_path = Config::file_path(base_dir.path(), &chain_id);
+        let tmp_path = utils::tmp_file_path(&file_path);
+        let config = Config::new(
+            base_dir.path(),
+            chain_id.clone(),
+            TendermintMode::Full,
+        );
+        let toml = toml::ser::to_string(&config).unwrap();
+        let partial = &toml[..toml.len() / 2];
+
+        // Simulate a write that has been killed mid-write of the temporary
+        // file: the config is absent
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(&tmp_path, partial).unwrap();
+        assert!(matches!(
+            Config::read(base_dir.path(), &chain_id, None),
+            Err(Error::FileNotFound(_))
+        ));
+
+        // A complete write replaces the leftover temporary file
+        config.write(base_dir.path(), &chain_id, false).unwrap();
+        assert!(!tmp_path.exists());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), toml);
+
+        // Another interrupted write leaves the previous config intact
+        fs::write(&tmp_path, partial).unwrap();
+        let read = Config::read(base_dir.path(), &chain_id, None).unwrap();
+        assert_eq!(read.ledger.chain_id, chain_id);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), toml);
+    }
+}
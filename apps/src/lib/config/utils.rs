@@ -1,5 +1,8 @@
 //! Configuration utilities
 
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::{cmp, env};
 
@@ -45,6 +48,37 @@ fn num_of_threads_aux(
     }
 }
 
+/// Write the contents to a temporary file in the same directory as the given
+/// path, which is then atomically renamed to the path. If the process is
+/// killed mid-write, the file at the path is either absent or left with its
+/// previous contents, but never partially written.
+pub fn write_atomically(
+    path: impl AsRef<Path>,
+    contents: impl AsRef<[u8]>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let tmp_path = tmp_file_path(path);
+    let result = File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// The path of the temporary file used by [`write_atomically`] for the given
+/// path
+pub fn tmp_file_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
 #[cfg(test)]
 mod test {
     use std::panic;
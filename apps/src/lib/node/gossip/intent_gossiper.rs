@@ -1,12 +1,87 @@
+use std::collections::HashMap;
 use std::net::ToSocketAddrs;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use anoma::proto::{Intent, IntentId};
+use anoma::proto::{
+    Intent, IntentCancellation, IntentGossipMessage, IntentId, Signed,
+};
+use anoma::types::address::Address;
+use anoma::types::intent::{self, IntentData};
+use anoma::types::key::*;
+use anoma::types::matchmaker::MatchNotice;
+use borsh::BorshDeserialize;
+#[cfg(not(feature = "ABCI"))]
+use tendermint::abci::Code;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_stable::abci::Code;
+use thiserror::Error;
+use tokio::sync::mpsc;
 
 use super::mempool::IntentMempool;
 use super::rpc::matchmakers::{
     MsgFromClient, MsgFromServer, ServerDialer, ServerListener,
 };
+use crate::node::ledger::rpc::Path;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Error decoding the data of intent ID {0}: {1}")]
+    IntentDecoding(IntentId, std::io::Error),
+    #[error("Invalid signature of intent ID {0}: {1}")]
+    InvalidSignature(IntentId, intent::Error),
+    #[error("Intent ID {0} is already in the mempool")]
+    DuplicateIntent(IntentId),
+    #[error("Cannot cancel intent ID {0}, it's not in the mempool")]
+    UnknownIntent(IntentId),
+    #[error("Invalid cancellation of intent ID {0}: {1}")]
+    InvalidCancellation(IntentId, VerifySigError),
+    #[error("Cannot verify intent ID {0}, the ledger is unreachable: {1}")]
+    LedgerUnreachable(IntentId, String),
+}
+
+impl Error {
+    /// Whether the message is invalid, as opposed to a message that this node
+    /// cannot apply, e.g. because it has been applied already or because the
+    /// public keys of its signers cannot be looked up. Only the peers that
+    /// propagate invalid messages should be penalized.
+    pub fn is_invalid_message(&self) -> bool {
+        !matches!(
+            self,
+            Error::DuplicateIntent(_)
+                | Error::LedgerUnreachable(_, _)
+                | Error::InvalidSignature(
+                    _,
+                    intent::Error::UnknownPublicKey(_)
+                )
+        )
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The maximum time to wait for the ledger to respond to a public key query
+const PUBLIC_KEY_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long an address whose public key was not found on the ledger is not
+/// looked up again
+const UNKNOWN_PUBLIC_KEY_TTL: Duration = Duration::from_secs(60);
+
+/// How long a public key found on the ledger is used before it's looked up
+/// again, so that a rotated key is picked up
+const PUBLIC_KEY_TTL: Duration = Duration::from_secs(60);
+
+/// The maximum number of match notices that may be waiting to be received by
+/// a subscribed client. A client that doesn't keep up misses the notices.
+const MATCH_NOTICES_CAPACITY: usize = 100;
 
 /// A server for connected matchmakers that can receive intents from the intent
 /// gossiper node and send back the results from their filter, if any, or from
@@ -18,8 +93,18 @@ pub struct MatchmakersServer {
     listener: Option<ServerListener>,
     /// Known intents mempool, shared with [`IntentGossiper`].
     mempool: Arc<RwLock<IntentMempool>>,
+    /// The clients subscribed to the match notices, shared with
+    /// [`IntentGossiper`].
+    match_subscriptions: MatchSubscriptions,
 }
 
+/// The clients subscribed to the notices of matched intents, by the addresses
+/// of the intents' signers. This handle can be cloned and is thread safe.
+#[derive(Clone, Debug, Default)]
+pub struct MatchSubscriptions(
+    Arc<RwLock<HashMap<Address, Vec<mpsc::Sender<Signed<MatchNotice>>>>>>,
+);
+
 /// Intent gossiper handle can be cloned and is thread safe.
 #[derive(Clone, Debug)]
 pub struct IntentGossiper {
@@ -27,26 +112,49 @@ pub struct IntentGossiper {
     mempool: Arc<RwLock<IntentMempool>>,
     /// A dialer can send messages to the connected matchmaker
     dialer: ServerDialer,
+    /// The ledger from which the public keys of intents' signers are looked
+    /// up, if any
+    ledger_address: Option<TendermintAddress>,
+    /// The known public keys of intents' signers, with the time of the lookup
+    public_keys: HashMap<Address, (common::PublicKey, Instant)>,
+    /// The intents' signers whose public keys were not found on the ledger,
+    /// with the time of the lookup
+    unknown_public_keys: HashMap<Address, Instant>,
+    /// The maximum time to wait for the ledger to respond to a query
+    ledger_query_timeout: Duration,
+    /// The clients subscribed to the match notices, shared with
+    /// [`MatchmakersServer`].
+    match_subscriptions: MatchSubscriptions,
 }
 
 impl MatchmakersServer {
-    /// Create a new gossip intent app with a matchmaker, if enabled.
+    /// Create a new gossip intent app with a matchmaker, if enabled. The
+    /// signatures of intents are verified with public keys looked up from the
+    /// given ledger.
     pub fn new_pair(
         matchmakers_server_addr: impl ToSocketAddrs,
+        ledger_address: Option<TendermintAddress>,
     ) -> (Self, IntentGossiper) {
         // Prepare a server for matchmakers connections
         let (listener, dialer) =
             ServerListener::new_pair(matchmakers_server_addr);
 
         let mempool = Arc::new(RwLock::new(IntentMempool::default()));
+        let match_subscriptions = MatchSubscriptions::default();
         let intent_gossiper = IntentGossiper {
             mempool: mempool.clone(),
             dialer,
+            ledger_address,
+            public_keys: HashMap::default(),
+            unknown_public_keys: HashMap::default(),
+            ledger_query_timeout: PUBLIC_KEY_QUERY_TIMEOUT,
+            match_subscriptions: match_subscriptions.clone(),
         };
         (
             Self {
                 listener: Some(listener),
                 mempool,
+                match_subscriptions,
             },
             intent_gossiper,
         )
@@ -92,24 +200,202 @@ impl MatchmakersServer {
                     let id = IntentId(id);
                     tracing::info!("No match found for intent ID {}", id);
                 }
+                MsgFromClient::MatchNotices { notices } => {
+                    for notice in notices {
+                        self.notify_match(notice);
+                    }
+                }
             })
             .await
     }
+
+    /// Send a match notice to the clients subscribed to the signers of the
+    /// matched intent. The intent must still be in the mempool to find its
+    /// signers.
+    fn notify_match(&self, notice: Signed<MatchNotice>) {
+        let id = IntentId(notice.data.intent_id.clone());
+        let r_mempool = self.mempool.read().unwrap();
+        let data = match r_mempool.get(&id) {
+            Some(intent) => IntentData::try_from_slice(&intent.data),
+            None => {
+                tracing::debug!(
+                    "Cannot notify the match of intent ID {}, it's not in the \
+                     mempool",
+                    id
+                );
+                return;
+            }
+        };
+        drop(r_mempool);
+        match data {
+            Ok(data) => {
+                let notified =
+                    self.match_subscriptions.notify(data.signers(), &notice);
+                tracing::info!(
+                    "Notified {} subscribed client(s) of the match of intent \
+                     ID {}",
+                    notified,
+                    id
+                );
+            }
+            Err(err) => tracing::warn!(
+                "Cannot notify the match of intent ID {}: {}",
+                id,
+                err
+            ),
+        }
+    }
+}
+
+impl MatchSubscriptions {
+    /// Subscribe to the notices of the matched intents signed by the given
+    /// address. The subscription is dropped when the returned receiver is
+    /// dropped.
+    pub fn subscribe(
+        &self,
+        addr: Address,
+    ) -> mpsc::Receiver<Signed<MatchNotice>> {
+        let (send, recv) = mpsc::channel(MATCH_NOTICES_CAPACITY);
+        let mut w_subscriptions = self.0.write().unwrap();
+        w_subscriptions.entry(addr).or_default().push(send);
+        recv
+    }
+
+    /// Send a match notice to the clients subscribed to any of the given
+    /// addresses and drop the subscriptions of the clients that are gone.
+    /// Returns the number of the notified clients.
+    fn notify<'a>(
+        &self,
+        addrs: impl IntoIterator<Item = &'a Address>,
+        notice: &Signed<MatchNotice>,
+    ) -> usize {
+        use tokio::sync::mpsc::error::TrySendError;
+
+        let mut notified = 0;
+        let mut w_subscriptions = self.0.write().unwrap();
+        for addr in addrs {
+            let senders = match w_subscriptions.get_mut(addr) {
+                Some(senders) => senders,
+                None => continue,
+            };
+            senders.retain(|sender| match sender.try_send(notice.clone()) {
+                Ok(()) => {
+                    notified += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    tracing::warn!(
+                        "A client subscribed to {} doesn't keep up, a match \
+                         notice has been dropped",
+                        addr
+                    );
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            });
+            if senders.is_empty() {
+                w_subscriptions.remove(addr);
+            }
+        }
+        notified
+    }
 }
 
 impl IntentGossiper {
-    // Apply the logic to a new intent. It only tries to apply the matchmaker if
-    // this one exists. If no matchmaker then returns true.
-    pub async fn add_intent(&mut self, intent: Intent) {
+    /// Apply an intent or an intent cancellation received on the given topic.
+    /// An intent whose signatures cannot be verified is rejected before it's
+    /// added to the mempool.
+    pub async fn apply_message(
+        &mut self,
+        message: IntentGossipMessage,
+        topic: String,
+    ) -> Result<()> {
+        match message {
+            IntentGossipMessage::Intent(intent) => {
+                self.verify_intent(&intent).await?;
+                self.add_intent(intent, topic).await
+            }
+            IntentGossipMessage::Cancellation(cancellation) => {
+                self.cancel_intent(cancellation)
+            }
+        }
+    }
+
+    /// Decode the signed data of an intent and verify its signatures against
+    /// the public keys of the addresses that claim to have signed it. The
+    /// public keys that are not known yet or that were looked up too long ago
+    /// are looked up from the ledger, except for the addresses whose public
+    /// keys were recently not found.
+    pub async fn verify_intent(&mut self, intent: &Intent) -> Result<()> {
+        let data = match IntentData::try_from_slice(&intent.data) {
+            Ok(data) => data,
+            Err(intent::Error::IntentDataDecodingError(err)) => {
+                return Err(Error::IntentDecoding(intent.id(), err));
+            }
+            Err(err) => return Err(Error::InvalidSignature(intent.id(), err)),
+        };
+        if let Some(ledger_address) = self.ledger_address.as_ref() {
+            for addr in data.signers() {
+                if let Some((_, looked_up)) = self.public_keys.get(addr) {
+                    if looked_up.elapsed() < PUBLIC_KEY_TTL {
+                        continue;
+                    }
+                }
+                if let Some(looked_up) = self.unknown_public_keys.get(addr) {
+                    if looked_up.elapsed() < UNKNOWN_PUBLIC_KEY_TTL {
+                        continue;
+                    }
+                }
+                match query_public_key(
+                    ledger_address,
+                    addr,
+                    self.ledger_query_timeout,
+                )
+                .await
+                {
+                    Ok(Some(pk)) => {
+                        self.unknown_public_keys.remove(addr);
+                        self.public_keys.retain(|_, (_, looked_up)| {
+                            looked_up.elapsed() < PUBLIC_KEY_TTL
+                        });
+                        self.public_keys
+                            .insert(addr.clone(), (pk, Instant::now()));
+                    }
+                    Ok(None) => {
+                        self.public_keys.remove(addr);
+                        self.unknown_public_keys.retain(|_, looked_up| {
+                            looked_up.elapsed() < UNKNOWN_PUBLIC_KEY_TTL
+                        });
+                        self.unknown_public_keys
+                            .insert(addr.clone(), Instant::now());
+                    }
+                    Err(err) => {
+                        return Err(Error::LedgerUnreachable(intent.id(), err));
+                    }
+                }
+            }
+        }
+        data.verify_signatures(|addr| {
+            self.public_keys.get(addr).map(|(pk, _)| pk)
+        })
+        .map_err(|err| Error::InvalidSignature(intent.id(), err))
+    }
+
+    // Apply the logic to a new intent received on the given topic. It only
+    // tries to apply the matchmaker if this one exists. An intent that is
+    // already in the mempool is not applied again.
+    pub async fn add_intent(
+        &mut self,
+        intent: Intent,
+        topic: String,
+    ) -> Result<()> {
         let id = intent.id();
 
-        let r_mempool = self.mempool.read().unwrap();
-        let is_known = r_mempool.contains(&id);
-        drop(r_mempool);
-        if !is_known {
-            let mut w_mempool = self.mempool.write().unwrap();
-            w_mempool.insert(intent.clone());
+        let mut w_mempool = self.mempool.write().unwrap();
+        if !w_mempool.insert(topic, intent.clone()) {
+            return Err(Error::DuplicateIntent(id));
         }
+        drop(w_mempool);
 
         tracing::info!(
             "Sending intent ID {} to connected matchmakers, if any",
@@ -118,6 +404,473 @@ impl IntentGossiper {
         self.dialer.send(MsgFromServer::AddIntent {
             id: id.0,
             data: intent.data,
-        })
+        });
+        Ok(())
+    }
+
+    /// Remove a cancelled intent from the mempool and from the connected
+    /// matchmakers, if the cancellation is signed by the intent's signer.
+    pub fn cancel_intent(
+        &mut self,
+        cancellation: IntentCancellation,
+    ) -> Result<()> {
+        let id = cancellation.intent_id.clone();
+
+        let mut w_mempool = self.mempool.write().unwrap();
+        let intent = match w_mempool.get(&id) {
+            Some(intent) => intent,
+            None => return Err(Error::UnknownIntent(id)),
+        };
+        if let Err(err) = cancellation.verify(intent) {
+            return Err(Error::InvalidCancellation(id, err));
+        }
+        w_mempool.remove(&id);
+        drop(w_mempool);
+
+        tracing::info!(
+            "Removing cancelled intent ID {} from connected matchmakers, if any",
+            id
+        );
+        self.dialer.send(MsgFromServer::RemoveIntent { id: id.0 });
+        Ok(())
+    }
+
+    /// List the IDs of the intents in the mempool on the given topic. The IDs
+    /// are sorted, so that they can be paginated with the `offset` of the
+    /// first listed ID and the `limit` on the number of listed IDs. Also
+    /// returns the total number of intents on the topic.
+    pub fn list_intents(
+        &self,
+        topic: &str,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<IntentId>, usize) {
+        let r_mempool = self.mempool.read().unwrap();
+        let mut ids: Vec<IntentId> =
+            r_mempool.intents(topic).map(Intent::id).collect();
+        drop(r_mempool);
+        ids.sort_by(|a, b| a.0.cmp(&b.0));
+        let total = ids.len();
+        let ids = ids.into_iter().skip(offset).take(limit).collect();
+        (ids, total)
+    }
+
+    /// Get a handle of the clients subscribed to the match notices.
+    pub fn match_subscriptions(&self) -> MatchSubscriptions {
+        self.match_subscriptions.clone()
+    }
+
+    /// Get the number of intents in the mempool on each topic.
+    pub fn mempool_sizes(&self) -> HashMap<String, u64> {
+        let r_mempool = self.mempool.read().unwrap();
+        r_mempool
+            .sizes()
+            .map(|(topic, size)| (topic.clone(), size as u64))
+            .collect()
+    }
+}
+
+/// Look up the public key of an address from the ledger. Returns `None` when
+/// the address has no public key or an error when the ledger cannot be
+/// queried within the given timeout.
+async fn query_public_key(
+    ledger_address: &TendermintAddress,
+    addr: &Address,
+    timeout: Duration,
+) -> std::result::Result<Option<common::PublicKey>, String> {
+    let client = HttpClient::new(ledger_address.clone())
+        .map_err(|err| err.to_string())?;
+    let path = Path::Value(pk_key(addr));
+    let query = client.abci_query(Some(path.into()), vec![], None, false);
+    let response = tokio::time::timeout(timeout, query)
+        .await
+        .map_err(|_| "The public key query timed out".to_string())?
+        .map_err(|err| {
+            tracing::warn!(
+                "Failed to query the public key of {}: {}",
+                addr,
+                err
+            );
+            err.to_string()
+        })?;
+    match response.code {
+        Code::Ok => {
+            Ok(common::PublicKey::try_from_slice(&response.value[..]).ok())
+        }
+        Code::Err(_) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    use anoma::proto::Signed;
+    use anoma::types::address;
+    use anoma::types::intent::{DecimalWrapper, Exchange, FungibleTokenIntent};
+    use anoma::types::token;
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    /// Make a new intent gossiper with an intent signed by the given keypair
+    /// in its mempool
+    fn setup(keypair: &common::SecretKey) -> (IntentGossiper, Intent) {
+        let (_server, intent_gossiper) =
+            MatchmakersServer::new_pair("127.0.0.1:0", None);
+        let intent = exchange_intent(
+            keypair,
+            address::testing::established_address_1(),
+            false,
+        );
+        intent_gossiper
+            .mempool
+            .write()
+            .unwrap()
+            .insert("asset_v0", intent.clone());
+        (intent_gossiper, intent)
+    }
+
+    /// Test that an intent is removed on a cancellation signed by its signer
+    #[test]
+    fn test_cancel_intent() {
+        let keypair = testing::keypair_1();
+        let (mut intent_gossiper, intent) = setup(&keypair);
+
+        let cancellation = IntentCancellation::new(intent.id(), &keypair);
+        assert!(intent_gossiper.cancel_intent(cancellation).is_ok());
+        assert!(!intent_gossiper
+            .mempool
+            .read()
+            .unwrap()
+            .contains(&intent.id()));
+
+        // Cancelling an intent that is no longer known fails
+        let cancellation = IntentCancellation::new(intent.id(), &keypair);
+        assert!(matches!(
+            intent_gossiper.cancel_intent(cancellation),
+            Err(Error::UnknownIntent(_))
+        ));
+    }
+
+    /// Test that a cancellation signed by a different key than the intent's
+    /// signer is ignored
+    #[test]
+    fn test_cancel_intent_wrong_signer() {
+        let (mut intent_gossiper, intent) = setup(&testing::keypair_1());
+
+        let cancellation =
+            IntentCancellation::new(intent.id(), &testing::keypair_2());
+        assert!(matches!(
+            intent_gossiper.cancel_intent(cancellation),
+            Err(Error::InvalidCancellation(_, _))
+        ));
+        assert!(intent_gossiper
+            .mempool
+            .read()
+            .unwrap()
+            .contains(&intent.id()));
+    }
+
+    /// Make a fungible token intent with an exchange signed by the given
+    /// keypair, optionally tampered with after it has been signed
+    fn exchange_intent(
+        keypair: &common::SecretKey,
+        addr: Address,
+        tamper: bool,
+    ) -> Intent {
+        let exchange = Exchange {
+            addr,
+            token_sell: address::xan(),
+            rate_min: DecimalWrapper::from_str("1").unwrap(),
+            max_sell: token::Amount::whole(100),
+            token_buy: address::btc(),
+            min_buy: token::Amount::whole(1),
+            vp: None,
+        };
+        let mut signed_exchange = Signed::new(keypair, exchange);
+        if tamper {
+            signed_exchange.data.max_sell = token::Amount::whole(1_000_000);
+        }
+        let data = Signed::new(
+            keypair,
+            FungibleTokenIntent {
+                exchange: HashSet::from([signed_exchange]),
+                nonce: 0,
+            },
+        )
+        .try_to_vec()
+        .unwrap();
+        Intent::new(data)
+    }
+
+    /// Test that a validly signed intent is added to the mempool, while an
+    /// intent that has been tampered with after signing is rejected before
+    /// it's added
+    #[tokio::test]
+    async fn test_apply_signed_intent() {
+        let keypair = testing::keypair_1();
+        let addr = address::testing::established_address_1();
+        let (_server, mut intent_gossiper) =
+            MatchmakersServer::new_pair("127.0.0.1:0", None);
+        intent_gossiper
+            .public_keys
+            .insert(addr.clone(), (keypair.ref_to(), Instant::now()));
+        let topic = "asset_v0".to_owned();
+
+        let intent = exchange_intent(&keypair, addr.clone(), false);
+        let message = IntentGossipMessage::new(intent.clone());
+        assert!(intent_gossiper
+            .apply_message(message, topic.clone())
+            .await
+            .is_ok());
+        assert!(intent_gossiper
+            .mempool
+            .read()
+            .unwrap()
+            .contains(&intent.id()));
+
+        // The same intent cannot be applied twice
+        let message = IntentGossipMessage::new(intent.clone());
+        assert!(matches!(
+            intent_gossiper.apply_message(message, topic.clone()).await,
+            Err(Error::DuplicateIntent(_))
+        ));
+
+        let intent = exchange_intent(&keypair, addr, true);
+        let message = IntentGossipMessage::new(intent.clone());
+        assert!(matches!(
+            intent_gossiper.apply_message(message, topic.clone()).await,
+            Err(Error::InvalidSignature(_, _))
+        ));
+        assert!(!intent_gossiper
+            .mempool
+            .read()
+            .unwrap()
+            .contains(&intent.id()));
+
+        // An intent signed by an address with an unknown public key is also
+        // rejected
+        let addr = address::testing::established_address_2();
+        let intent = exchange_intent(&keypair, addr, false);
+        let message = IntentGossipMessage::new(intent);
+        assert!(matches!(
+            intent_gossiper.apply_message(message, topic.clone()).await,
+            Err(Error::InvalidSignature(
+                _,
+                intent::Error::UnknownPublicKey(_)
+            ))
+        ));
+
+        // Data that is not an intent cannot be decoded
+        let message = IntentGossipMessage::new(Intent::new(vec![1, 2, 3]));
+        assert!(matches!(
+            intent_gossiper.apply_message(message, topic).await,
+            Err(Error::IntentDecoding(_, _))
+        ));
+    }
+
+    /// Test that an intent that cannot be verified because the ledger doesn't
+    /// respond or because the public key of its signer has recently not been
+    /// found is not treated as an invalid message
+    #[tokio::test]
+    async fn test_unverifiable_intent() {
+        // A ledger that accepts connections, but never responds
+        let ledger = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let ledger_address = TendermintAddress::from_str(&format!(
+            "tcp://{}",
+            ledger.local_addr().unwrap()
+        ))
+        .unwrap();
+        let (_server, mut intent_gossiper) =
+            MatchmakersServer::new_pair("127.0.0.1:0", Some(ledger_address));
+        intent_gossiper.ledger_query_timeout = Duration::from_millis(100);
+        let keypair = testing::keypair_1();
+        let addr = address::testing::established_address_1();
+        let intent = exchange_intent(&keypair, addr.clone(), false);
+
+        let err = intent_gossiper.verify_intent(&intent).await.unwrap_err();
+        assert!(matches!(err, Error::LedgerUnreachable(_, _)));
+        assert!(!err.is_invalid_message());
+
+        // The address isn't looked up again, so the unresponsive ledger
+        // doesn't delay the verification
+        intent_gossiper
+            .unknown_public_keys
+            .insert(addr.clone(), Instant::now());
+        let err = intent_gossiper.verify_intent(&intent).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidSignature(_, intent::Error::UnknownPublicKey(_))
+        ));
+        assert!(!err.is_invalid_message());
+
+        // A tampered intent is invalid
+        let intent = exchange_intent(&keypair, addr.clone(), true);
+        intent_gossiper
+            .public_keys
+            .insert(addr, (keypair.ref_to(), Instant::now()));
+        let err = intent_gossiper.verify_intent(&intent).await.unwrap_err();
+        assert!(err.is_invalid_message());
+    }
+
+    /// Test that a public key that was looked up too long ago is looked up
+    /// again, instead of verifying the intent with a possibly rotated key
+    #[tokio::test]
+    async fn test_expired_public_key_looked_up_again() {
+        // A ledger that accepts connections, but never responds
+        let ledger = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let ledger_address = TendermintAddress::from_str(&format!(
+            "tcp://{}",
+            ledger.local_addr().unwrap()
+        ))
+        .unwrap();
+        let (_server, mut intent_gossiper) =
+            MatchmakersServer::new_pair("127.0.0.1:0", Some(ledger_address));
+        intent_gossiper.ledger_query_timeout = Duration::from_millis(100);
+        let keypair = testing::keypair_1();
+        let addr = address::testing::established_address_1();
+        let intent = exchange_intent(&keypair, addr.clone(), false);
+
+        // A recently looked up key is used without querying the ledger
+        intent_gossiper
+            .public_keys
+            .insert(addr.clone(), (keypair.ref_to(), Instant::now()));
+        assert!(intent_gossiper.verify_intent(&intent).await.is_ok());
+
+        // An expired key is looked up again
+        let expired = Instant::now().checked_sub(PUBLIC_KEY_TTL).unwrap();
+        intent_gossiper
+            .public_keys
+            .insert(addr, (keypair.ref_to(), expired));
+        let err = intent_gossiper.verify_intent(&intent).await.unwrap_err();
+        assert!(matches!(err, Error::LedgerUnreachable(_, _)));
+    }
+
+    /// Test that a client subscribed to the signer of an intent receives the
+    /// signed notice of the intent's match from a connected matchmaker
+    #[tokio::test]
+    async fn test_match_notification() {
+        use std::time::Duration;
+
+        use anoma::types::hash::Hash;
+
+        use crate::node::gossip::rpc::client::rpc_server;
+        use crate::node::gossip::rpc::matchmakers::ClientListener;
+        use crate::proto::services::rpc_service_client::RpcServiceClient;
+        use crate::proto::services::SubscribeMatchesRequest;
+
+        let keypair = testing::keypair_1();
+        let addr = address::testing::established_address_1();
+        let (server, mut intent_gossiper) =
+            MatchmakersServer::new_pair("127.0.0.1:0", None);
+        let server_addr = server.listener.as_ref().unwrap().address;
+        let match_subscriptions = intent_gossiper.match_subscriptions();
+        tokio::spawn(async move { server.listen().await });
+
+        // Start the RPC server and subscribe to the matches of the address
+        let rpc_addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let (rpc_sender, _rpc_receiver) = tokio::sync::mpsc::channel(1);
+        let (status_sender, _status_receiver) = tokio::sync::mpsc::channel(1);
+        tokio::spawn(async move {
+            rpc_server(rpc_addr, rpc_sender, status_sender, match_subscriptions)
+                .await
+        });
+        let mut client = loop {
+            match RpcServiceClient::connect(format!("http://{}", rpc_addr))
+                .await
+            {
+                Ok(client) => break client,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        let mut notifications = client
+            .subscribe_matches(SubscribeMatchesRequest {
+                address: addr.encode(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        // Submit an intent
+        let intent = exchange_intent(&keypair, addr.clone(), false);
+        let intent_id = intent.id();
+        intent_gossiper
+            .add_intent(intent, "asset_v0".to_owned())
+            .await
+            .unwrap();
+
+        // A matchmaker matches the intent into a tx
+        let (mm_listener, mut mm_dialer) =
+            ClientListener::new_pair(server_addr);
+        let mm_handle = std::thread::spawn(move || mm_listener.listen(|_| {}));
+        while !mm_dialer.is_connected() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mm_keypair = testing::keypair_2();
+        let tx_hash = Hash([1; 32]);
+        let notice = Signed::new(
+            &mm_keypair,
+            MatchNotice {
+                intent_id: intent_id.0.clone(),
+                tx_hash: tx_hash.clone(),
+            },
+        );
+        mm_dialer.send(MsgFromClient::MatchNotices {
+            notices: vec![notice],
+        });
+        mm_dialer.send(MsgFromClient::Matched {
+            intent_ids: HashSet::from([intent_id.0.clone()]),
+        });
+
+        let notification = tokio::time::timeout(
+            Duration::from_secs(10),
+            notifications.message(),
+        )
+        .await
+        .expect("no match notice received")
+        .unwrap()
+        .expect("the subscription has been closed");
+        assert_eq!(notification.intent_id, intent_id.0);
+        assert_eq!(notification.tx_hash, tx_hash.to_string());
+        let notice =
+            Signed::<MatchNotice>::try_from_slice(&notification.signed_notice)
+                .unwrap();
+        assert!(notice.verify(&mm_keypair.ref_to()).is_ok());
+
+        mm_dialer.shutdown();
+        mm_handle.join().unwrap();
+    }
+
+    /// Test that every error can be formatted
+    #[test]
+    fn test_error_display() {
+        let id = IntentId::from(vec![0xab, 0xcd]);
+        let addr = address::testing::established_address_1();
+        let errors = [
+            Error::IntentDecoding(
+                id.clone(),
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "eof"),
+            ),
+            Error::InvalidSignature(
+                id.clone(),
+                intent::Error::UnknownPublicKey(addr),
+            ),
+            Error::DuplicateIntent(id.clone()),
+            Error::UnknownIntent(id.clone()),
+            Error::InvalidCancellation(
+                id.clone(),
+                VerifySigError::SigVerifyError("invalid".to_owned()),
+            ),
+            Error::LedgerUnreachable(id, "timed out".to_owned()),
+        ];
+        for error in errors {
+            let message = error.to_string();
+            assert!(message.contains("abcd"), "{}", message);
+        }
     }
 }
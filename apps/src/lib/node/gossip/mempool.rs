@@ -2,25 +2,90 @@ use std::collections::HashMap;
 
 use anoma::proto::{Intent, IntentId};
 
-/// In-memory intent mempool
+/// In-memory intent mempool, partitioned by the gossip topics of the intents
 #[derive(Clone, Debug, Default)]
-pub struct IntentMempool(HashMap<IntentId, Intent>);
+pub struct IntentMempool(HashMap<String, HashMap<IntentId, Intent>>);
 
 impl IntentMempool {
-    /// Insert a new intent. If the mempool didn't have this intent present,
-    /// returns `true`.
-    pub fn insert(&mut self, intent: Intent) -> bool {
-        self.0.insert(intent.id(), intent).is_none()
+    /// Insert a new intent into the partition of the given topic. If the
+    /// partition didn't have this intent present, returns `true`.
+    pub fn insert(&mut self, topic: impl Into<String>, intent: Intent) -> bool {
+        self.0
+            .entry(topic.into())
+            .or_default()
+            .insert(intent.id(), intent)
+            .is_none()
     }
 
-    /// Remove an intent from mempool. If the mempool didn't have this intent
-    /// present, returns `true`. in the mempool.
+    /// Remove an intent from mempool, whichever topic it belongs to. If the
+    /// mempool had this intent present, returns `true`.
     pub fn remove(&mut self, intent_id: &IntentId) -> bool {
-        self.0.remove(intent_id).is_some()
+        let mut removed = false;
+        self.0.retain(|_topic, intents| {
+            removed |= intents.remove(intent_id).is_some();
+            !intents.is_empty()
+        });
+        removed
     }
 
-    /// Returns `true` if the map contains intent with specified ID.
+    /// Returns `true` if the mempool contains intent with specified ID in any
+    /// of the topics.
     pub fn contains(&self, intent_id: &IntentId) -> bool {
-        self.0.contains_key(intent_id)
+        self.0
+            .values()
+            .any(|intents| intents.contains_key(intent_id))
+    }
+
+    /// Get an intent with specified ID from any of the topics.
+    pub fn get(&self, intent_id: &IntentId) -> Option<&Intent> {
+        self.0.values().find_map(|intents| intents.get(intent_id))
+    }
+
+    /// Iterate over the intents of the given topic.
+    pub fn intents<'a>(
+        &'a self,
+        topic: &str,
+    ) -> impl Iterator<Item = &'a Intent> + 'a {
+        self.0
+            .get(topic)
+            .into_iter()
+            .flat_map(|intents| intents.values())
+    }
+
+    /// Iterate over the topics with the number of intents in each of them.
+    pub fn sizes(&self) -> impl Iterator<Item = (&String, usize)> {
+        self.0.iter().map(|(topic, intents)| (topic, intents.len()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Test that the intents applied on different topics land in their own
+    /// partitions
+    #[test]
+    fn test_intents_partitioned_by_topic() {
+        let mut mempool = IntentMempool::default();
+        let asset_v0 = Intent::new(b"asset_v0 intent".to_vec());
+        let auction = Intent::new(b"auction intent".to_vec());
+        assert!(mempool.insert("asset_v0", asset_v0.clone()));
+        assert!(mempool.insert("auction", auction.clone()));
+        assert!(!mempool.insert("asset_v0", asset_v0.clone()));
+
+        let ids = |topic| {
+            mempool
+                .intents(topic)
+                .map(|intent| intent.id())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("asset_v0"), vec![asset_v0.id()]);
+        assert_eq!(ids("auction"), vec![auction.id()]);
+        assert!(ids("unknown").is_empty());
+
+        assert!(mempool.remove(&auction.id()));
+        assert!(!mempool.contains(&auction.id()));
+        assert!(mempool.contains(&asset_v0.id()));
+        assert!(ids("auction").is_empty());
     }
 }
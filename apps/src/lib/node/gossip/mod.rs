@@ -3,21 +3,30 @@ mod mempool;
 pub mod p2p;
 pub mod rpc;
 
+use std::net::SocketAddr;
 use std::path::Path;
+use std::str::FromStr;
 
-use anoma::proto::Intent;
+use libp2p::gossipsub::MessageAcceptance;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
 use thiserror::Error;
 use tokio::sync::mpsc;
 
 use self::intent_gossiper::IntentGossiper;
+use self::p2p::behaviour::PeerMessage;
 use self::p2p::P2P;
 use crate::config;
-use crate::proto::services::{rpc_message, RpcResponse};
+use crate::proto::services::{rpc_message, RpcResponse, StatusResponse};
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Error initializing p2p: {0}")]
     P2pInit(p2p::Error),
+    #[error("Invalid ledger RPC address {0}: {1}")]
+    InvalidLedgerAddress(SocketAddr, String),
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -28,15 +37,28 @@ pub type RpcReceiver = tokio::sync::mpsc::Receiver<(
     tokio::sync::oneshot::Sender<RpcResponse>,
 )>;
 
+/// RPC status requests async receiver end of the channel
+pub type StatusReceiver =
+    tokio::sync::mpsc::Receiver<tokio::sync::oneshot::Sender<StatusResponse>>;
+
 #[tokio::main]
 pub async fn run(
     config: config::IntentGossiper,
     base_dir: impl AsRef<Path>,
+    ledger_rpc_address: SocketAddr,
 ) -> Result<()> {
-    // Prepare matchmakers server and dialer
+    // Prepare matchmakers server and dialer. The public keys of intents'
+    // signers are looked up from the ledger.
+    let ledger_address = TendermintAddress::from_str(
+        &ledger_rpc_address.to_string(),
+    )
+    .map_err(|err| {
+        Error::InvalidLedgerAddress(ledger_rpc_address, err.to_string())
+    })?;
     let (matchmakers_server, intent_gossiper) =
         intent_gossiper::MatchmakersServer::new_pair(
             &config.matchmakers_server_addr,
+            Some(ledger_address),
         );
 
     // Async channel for intents received from peer
@@ -54,17 +76,29 @@ pub async fn run(
     });
 
     // Start the RPC server, if enabled in the config
-    let rpc_receiver = config.rpc.map(|rpc_config| {
-        let (rpc_sender, rpc_receiver) = mpsc::channel(100);
-        tokio::spawn(async move {
-            rpc::client::start_rpc_server(&rpc_config, rpc_sender).await
-        });
-        rpc_receiver
-    });
+    let (rpc_receiver, status_receiver) = match config.rpc {
+        Some(rpc_config) => {
+            let (rpc_sender, rpc_receiver) = mpsc::channel(100);
+            let (status_sender, status_receiver) = mpsc::channel(100);
+            let match_subscriptions = intent_gossiper.match_subscriptions();
+            tokio::spawn(async move {
+                rpc::client::start_rpc_server(
+                    &rpc_config,
+                    rpc_sender,
+                    status_sender,
+                    match_subscriptions,
+                )
+                .await
+            });
+            (Some(rpc_receiver), Some(status_receiver))
+        }
+        None => (None, None),
+    };
 
     dispatcher(
         p2p,
         rpc_receiver,
+        status_receiver,
         peer_intent_recv,
         intent_gossiper,
         mms_join_handle,
@@ -79,7 +113,8 @@ pub async fn run(
 pub async fn dispatcher(
     mut p2p: P2P,
     mut rpc_receiver: Option<RpcReceiver>,
-    mut peer_intent_recv: tokio::sync::mpsc::Receiver<Intent>,
+    mut status_receiver: Option<StatusReceiver>,
+    mut peer_intent_recv: tokio::sync::mpsc::Receiver<PeerMessage>,
     mut intent_gossiper: IntentGossiper,
     _mms_join_handle: tokio::task::JoinHandle<()>,
 ) -> Result<()> {
@@ -87,16 +122,41 @@ pub async fn dispatcher(
         tokio::select! {
             Some((event, inject_response)) = recv_rpc_option(rpc_receiver.as_mut()), if rpc_receiver.is_some() =>
             {
-                let gossip_sub = &mut p2p.0.behaviour_mut().intent_gossip_behaviour;
-                let (response, maybe_intent) = rpc::client::handle_rpc_event(event, gossip_sub).await;
-                inject_response.send(response).expect("failed to send response to rpc server");
+                let behaviour = p2p.0.behaviour_mut();
+                let (response, maybe_message) = rpc::client::handle_rpc_event(event, behaviour, &intent_gossiper).await;
+                if inject_response.send(response).is_err() {
+                    tracing::warn!("failed to send response to rpc server");
+                }
 
-                if let Some(intent) = maybe_intent {
-                    intent_gossiper.add_intent(intent).await;
+                if let Some((message, topic)) = maybe_message {
+                    if let Err(err) = intent_gossiper.apply_message(message, topic).await {
+                        tracing::info!("Failed to apply the message from the RPC: {}", err);
+                    }
+                }
+            },
+            Some(inject_status) = recv_status_option(status_receiver.as_mut()), if status_receiver.is_some() =>
+            {
+                let status = rpc::client::handle_status_request(&p2p.0, &intent_gossiper);
+                if inject_status.send(status).is_err() {
+                    tracing::warn!("failed to send status response to rpc server");
                 }
             },
-            Some(intent) = peer_intent_recv.recv() => {
-                intent_gossiper.add_intent(intent).await;
+            Some(PeerMessage { message, topic, pending_validation }) = peer_intent_recv.recv() => {
+                let result = intent_gossiper.apply_message(message, topic).await;
+                if let Err(err) = &result {
+                    tracing::info!("Failed to apply the message from a peer: {}", err);
+                }
+                if let Some(pending_validation) = pending_validation {
+                    let validity = match result {
+                        Ok(()) => MessageAcceptance::Accept,
+                        Err(err) if err.is_invalid_message() => MessageAcceptance::Reject,
+                        // A message that cannot be applied (e.g. a duplicate
+                        // or an intent that cannot be verified) is not
+                        // propagated, but the peer isn't penalized for it
+                        Err(_) => MessageAcceptance::Ignore,
+                    };
+                    p2p.0.behaviour_mut().report_validation(&pending_validation, validity);
+                }
             }
             swarm_event = p2p.0.next() => {
                 // Never occurs, but call for the event must exists.
@@ -114,3 +174,9 @@ async fn recv_rpc_option(
 )> {
     x?.recv().await
 }
+
+async fn recv_status_option(
+    x: Option<&mut StatusReceiver>,
+) -> Option<tokio::sync::oneshot::Sender<StatusResponse>> {
+    x?.recv().await
+}
@@ -152,6 +152,13 @@ pub struct DiscoveryBehaviour {
     duration_to_next_kad: Duration,
     /// Events to return in priority when polled.
     pending_events: VecDeque<DiscoveryEvent>,
+    /// Addresses of peers discovered on the local network that are waiting
+    /// to be dialed.
+    pending_dials: VecDeque<Multiaddr>,
+    /// Peers injected in place of the discoveries of mDNS, whose multicast
+    /// socket is not available in every test environment.
+    #[cfg(test)]
+    mocked_mdns_discovered: VecDeque<Vec<(PeerId, Multiaddr)>>,
     /// Number of nodes we're currently connected to.
     num_connections: u64,
     /// Keeps hash set of peers connected.
@@ -251,6 +258,9 @@ impl DiscoveryBehaviour {
             next_kad_random_query: None,
             duration_to_next_kad: Duration::from_secs(1),
             pending_events: VecDeque::new(),
+            pending_dials: VecDeque::new(),
+            #[cfg(test)]
+            mocked_mdns_discovered: VecDeque::new(),
             num_connections: 0,
             peers,
             discovery_max,
@@ -258,6 +268,43 @@ impl DiscoveryBehaviour {
     }
 }
 
+impl DiscoveryBehaviour {
+    /// Add the peers discovered on the local network to Kademlia, if enabled,
+    /// and queue their addresses to be dialed, unless we're already connected
+    /// to them or we've reached the maximum number of connections.
+    fn handle_mdns_discovered(
+        &mut self,
+        list: impl IntoIterator<Item = (PeerId, Multiaddr)>,
+    ) {
+        if self.num_connections >= self.discovery_max {
+            tracing::info!(
+                "max reached {:?}, {:?}",
+                self.num_connections,
+                self.discovery_max
+            );
+            // Already over discovery max, don't add discovered peers. We
+            // could potentially buffer these addresses to be added later, but
+            // mdns is not an important use case and may be removed in future.
+            return;
+        }
+        for (peer_id, multiaddr) in list {
+            if let Some(kad) = self.kademlia.as_mut() {
+                kad.add_address(&peer_id, multiaddr.clone());
+            }
+            if !self.peers.contains(&peer_id)
+                && !self.pending_dials.contains(&multiaddr)
+            {
+                tracing::debug!(
+                    "Dialing peer {} discovered at {}",
+                    peer_id,
+                    multiaddr
+                );
+                self.pending_dials.push_back(multiaddr);
+            }
+        }
+    }
+}
+
 // Most function here are a wrapper around kad behaviour,
 impl NetworkBehaviour for DiscoveryBehaviour {
     type OutEvent = DiscoveryEvent;
@@ -419,6 +466,14 @@ impl NetworkBehaviour for DiscoveryBehaviour {
             return Poll::Ready(NetworkBehaviourAction::GenerateEvent(ev));
         }
 
+        // Dial the peers discovered on the local network. Once connected, they
+        // will join the gossipsub mesh of the topics that they subscribed to.
+        if let Some(address) = self.pending_dials.pop_front() {
+            return Poll::Ready(NetworkBehaviourAction::DialAddress {
+                address,
+            });
+        }
+
         // Poll Kademlia return every other event except kad event
         while let Poll::Ready(ev) = self.kademlia.poll(cx, params) {
             tracing::debug!("Kademlia event {:#?}", ev);
@@ -465,24 +520,7 @@ impl NetworkBehaviour for DiscoveryBehaviour {
             match ev {
                 NetworkBehaviourAction::GenerateEvent(event) => match event {
                     MdnsEvent::Discovered(list) => {
-                        if self.num_connections < self.discovery_max {
-                            // Add any discovered peers to Kademlia
-                            for (peer_id, multiaddr) in list {
-                                if let Some(kad) = self.kademlia.as_mut() {
-                                    kad.add_address(&peer_id, multiaddr);
-                                }
-                            }
-                        } else {
-                            tracing::info!(
-                                "max reached {:?}, {:?}",
-                                self.num_connections,
-                                self.discovery_max
-                            );
-                            // Already over discovery max, don't add discovered
-                            // peers. We could potentially buffer these
-                            // addresses to be added later, but mdns is not an
-                            // important use case and may be removed in future.
-                        }
+                        self.handle_mdns_discovered(list);
                     }
                     MdnsEvent::Expired(_) => {}
                 },
@@ -512,6 +550,102 @@ impl NetworkBehaviour for DiscoveryBehaviour {
                 }
             }
         }
+        #[cfg(test)]
+        while let Some(list) = self.mocked_mdns_discovered.pop_front() {
+            self.handle_mdns_discovered(list);
+        }
+
+        // Dial the peers that have just been discovered, as mDNS won't wake
+        // us up again for them
+        if let Some(address) = self.pending_dials.pop_front() {
+            return Poll::Ready(NetworkBehaviourAction::DialAddress {
+                address,
+            });
+        }
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::task::noop_waker_ref;
+    use libp2p::swarm::AddressRecord;
+
+    use super::*;
+
+    /// Poll parameters of a node that doesn't listen on any address
+    struct MockPollParameters(PeerId);
+
+    impl PollParameters for MockPollParameters {
+        type ExternalAddressesIter = std::vec::IntoIter<AddressRecord>;
+        type ListenedAddressesIter = std::vec::IntoIter<Multiaddr>;
+        type SupportedProtocolsIter = std::vec::IntoIter<Vec<u8>>;
+
+        fn supported_protocols(&self) -> Self::SupportedProtocolsIter {
+            vec![].into_iter()
+        }
+
+        fn listened_addresses(&self) -> Self::ListenedAddressesIter {
+            vec![].into_iter()
+        }
+
+        fn external_addresses(&self) -> Self::ExternalAddressesIter {
+            vec![].into_iter()
+        }
+
+        fn local_peer_id(&self) -> &PeerId {
+            &self.0
+        }
+    }
+
+    /// Test that polling the behaviour dials the address of a peer discovered
+    /// with mDNS, unless we're already connected to it. The mDNS discoveries
+    /// are mocked, because the multicast socket is not available in every
+    /// test environment.
+    #[tokio::test]
+    async fn test_mdns_discovered_peer_is_dialed() {
+        let local_peer_id = PeerId::random();
+        let config = DiscoveryConfigBuilder::default()
+            .with_kademlia(false)
+            .with_mdns(false)
+            .build()
+            .unwrap();
+        let mut discovery = DiscoveryBehaviour::new(local_peer_id, config)
+            .await
+            .unwrap();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut params = MockPollParameters(local_peer_id);
+
+        let discovered_peer = PeerId::random();
+        let discovered_addr: Multiaddr =
+            "/ip4/192.168.0.2/tcp/26659".parse().unwrap();
+        let connected_peer = PeerId::random();
+        discovery.inject_connected(&connected_peer);
+
+        // The same address discovered twice is only dialed once
+        discovery.mocked_mdns_discovered.extend([
+            vec![
+                (discovered_peer, discovered_addr.clone()),
+                (
+                    connected_peer,
+                    "/ip4/192.168.0.3/tcp/26659".parse().unwrap(),
+                ),
+            ],
+            vec![(discovered_peer, discovered_addr.clone())],
+        ]);
+        match discovery.poll(&mut cx, &mut params) {
+            Poll::Ready(NetworkBehaviourAction::DialAddress { address }) => {
+                assert_eq!(address, discovered_addr)
+            }
+            _ => panic!("Expected the discovered peer to be dialed"),
+        }
+        assert!(discovery.poll(&mut cx, &mut params).is_pending());
+
+        // Nothing is dialed once the maximum number of connections is reached
+        discovery.discovery_max = 1;
+        discovery
+            .mocked_mdns_discovered
+            .push_back(vec![(PeerId::random(), discovered_addr)]);
+        assert!(discovery.poll(&mut cx, &mut params).is_pending());
+    }
+}
@@ -2,9 +2,9 @@ mod discovery;
 use std::collections::hash_map::DefaultHasher;
 use std::convert::TryFrom;
 use std::hash::{Hash, Hasher};
-use std::time::Duration;
 
-use anoma::proto::{self, Intent, IntentGossipMessage};
+use anoma::proto::{self, Intent, IntentCancellation, IntentGossipMessage};
+use libp2p::gossipsub::error::PublishError;
 use libp2p::gossipsub::subscription_filter::regex::RegexSubscriptionFilter;
 use libp2p::gossipsub::subscription_filter::{
     TopicSubscriptionFilter, WhitelistSubscriptionFilter,
@@ -27,6 +27,7 @@ use crate::config;
 use crate::node::gossip::p2p::behaviour::discovery::{
     DiscoveryBehaviour, DiscoveryConfigBuilder,
 };
+use crate::node::gossip::p2p::Subscriptions;
 
 /// Behaviour is composed of a `DiscoveryBehaviour` and an GossipsubBehaviour`.
 /// It automatically connect to newly discovered peer, except specified
@@ -41,7 +42,28 @@ pub struct Behaviour {
     /// every established connection
     ping: Ping,
     #[behaviour(ignore)]
-    pub peer_intent_send: Sender<Intent>,
+    pub peer_intent_send: Sender<PeerMessage>,
+    /// The maximum size in bytes of a message that can be published
+    #[behaviour(ignore)]
+    max_transmit_size: usize,
+    /// The persisted subscriptions to topics, if any
+    #[behaviour(ignore)]
+    subscriptions: Option<Subscriptions>,
+    /// The namespace prefixed to all the subscribed and published topics, if
+    /// any
+    #[behaviour(ignore)]
+    topic_namespace: Option<String>,
+}
+
+/// An intent gossip message received from a peer
+#[derive(Debug)]
+pub struct PeerMessage {
+    pub message: IntentGossipMessage,
+    /// The topic on which the message has been received
+    pub topic: String,
+    /// The ID and the propagation source of a message whose validation
+    /// result is yet to be reported with [`Behaviour::report_validation`]
+    pub pending_validation: Option<(MessageId, PeerId)>,
 }
 
 #[derive(Error, Debug)]
@@ -52,12 +74,24 @@ pub enum Error {
     Filter(String),
     #[error("Failed initializing the gossip behaviour: {0}")]
     GossipConfig(String),
+    #[error(
+        "Invalid gossipsub mesh sizes, expected mesh_n_low ({low}) <= mesh_n \
+         ({n}) <= mesh_n_high ({high})"
+    )]
+    InvalidMesh { low: usize, n: usize, high: usize },
     #[error("Failed on the the discovery behaviour config: {0}")]
     DiscoveryConfig(String),
     #[error("Failed initializing the discovery behaviour: {0}")]
     Discovery(discovery::Error),
     #[error("Failed initializing mdns: {0}")]
     Mdns(std::io::Error),
+    #[error(
+        "The message of {size} bytes exceeds the maximum transmit size of \
+         {max_size} bytes"
+    )]
+    MessageTooLarge { size: usize, max_size: usize },
+    #[error("Failed to publish the message: {0:?}")]
+    Publish(PublishError),
 }
 
 pub type Gossipsub = libp2p::gossipsub::Gossipsub<
@@ -72,6 +106,26 @@ pub type Gossipsub = libp2p::gossipsub::Gossipsub<
 pub enum IntentGossipSubscriptionFilter {
     RegexFilter(RegexSubscriptionFilter),
     WhitelistFilter(WhitelistSubscriptionFilter),
+    /// Only accepts the topics in the namespace, whose names without the
+    /// namespace are accepted by the inner filter
+    NamespacedFilter {
+        namespace: String,
+        filter: Box<IntentGossipSubscriptionFilter>,
+    },
+}
+
+/// Prefix a topic with the given namespace
+pub fn namespaced_topic(namespace: &str, topic: &str) -> String {
+    format!("{}/{}", namespace, topic)
+}
+
+/// Strip the given namespace from a topic. Returns `None` if the topic is not
+/// in the namespace.
+pub fn strip_topic_namespace<'a>(
+    namespace: &str,
+    topic: &'a str,
+) -> Option<&'a str> {
+    topic.strip_prefix(namespace)?.strip_prefix('/')
 }
 
 /// IntentGossipEvent describe events received/sent in the gossipsub network.
@@ -134,6 +188,15 @@ impl TopicSubscriptionFilter for IntentGossipSubscriptionFilter {
             IntentGossipSubscriptionFilter::WhitelistFilter(filter) => {
                 filter.can_subscribe(topic_hash)
             }
+            IntentGossipSubscriptionFilter::NamespacedFilter {
+                namespace,
+                filter,
+            } => match strip_topic_namespace(namespace, topic_hash.as_str()) {
+                Some(topic) => {
+                    filter.can_subscribe(&TopicHash::from_raw(topic))
+                }
+                None => false,
+            },
         }
     }
 }
@@ -145,53 +208,88 @@ pub fn message_id(message: &GossipsubMessage) -> MessageId {
     MessageId::from(hasher.finish().to_string())
 }
 
+/// Make the subscription filter from the config. With a topic namespace, the
+/// configured filter is applied to the topics in the namespace.
+fn subscription_filter(
+    config: &config::IntentGossiper,
+) -> IntentGossipSubscriptionFilter {
+    let filter = match &config.subscription_filter {
+        crate::config::SubscriptionFilter::RegexFilter(regex) => {
+            IntentGossipSubscriptionFilter::RegexFilter(
+                RegexSubscriptionFilter(regex.clone()),
+            )
+        }
+        crate::config::SubscriptionFilter::WhitelistFilter(topics) => {
+            IntentGossipSubscriptionFilter::WhitelistFilter(
+                WhitelistSubscriptionFilter(
+                    topics
+                        .iter()
+                        .map(IdentTopic::new)
+                        .map(TopicHash::from)
+                        .collect(),
+                ),
+            )
+        }
+    };
+    match &config.topic_namespace {
+        Some(namespace) => IntentGossipSubscriptionFilter::NamespacedFilter {
+            namespace: namespace.clone(),
+            filter: Box::new(filter),
+        },
+        None => filter,
+    }
+}
+
 impl Behaviour {
-    /// Create a new behaviour based on the config given
+    /// Create a new behaviour based on the config given. Fails if the
+    /// configured gossipsub parameters are inconsistent.
     pub async fn new(
         key: Keypair,
         config: &config::IntentGossiper,
-        peer_intent_send: Sender<Intent>,
-    ) -> Self {
+        peer_intent_send: Sender<PeerMessage>,
+    ) -> Result<Self, Error> {
         let public_key = key.public();
         let peer_id = PeerId::from_public_key(public_key.clone());
 
-        // TODO remove hardcoded value and add them to the config Except
-        // validation_mode, protocol_id_prefix, message_id_fn and
-        // validate_messages
+        let config::Gossipsub {
+            heartbeat_interval,
+            mesh_n_low,
+            mesh_n,
+            mesh_n_high,
+        } = &config.gossipsub;
+        let (mesh_n_low, mesh_n, mesh_n_high) =
+            (*mesh_n_low, *mesh_n, *mesh_n_high);
+        if !(mesh_n_low <= mesh_n && mesh_n <= mesh_n_high) {
+            return Err(Error::InvalidMesh {
+                low: mesh_n_low,
+                n: mesh_n,
+                high: mesh_n_high,
+            });
+        }
+
         // Set a custom gossipsub for our use case
         let gossipsub_config = gossipsub::GossipsubConfigBuilder::default()
             .protocol_id_prefix("intent_gossip")
-            .heartbeat_interval(Duration::from_secs(1))
+            .heartbeat_interval(**heartbeat_interval)
             .validation_mode(ValidationMode::Strict)
             .message_id_fn(message_id)
-            .max_transmit_size(16 * 1024 * 1024)
+            .max_transmit_size(config.max_transmit_size)
             .validate_messages()
             .mesh_outbound_min(1)
             // TODO bootstrap peers should not be part of the mesh, so all the
             // `.mesh` args should be set to 0 https://github.com/libp2p/specs/blob/70d7fda47dda88d828b4db72775c1602de57e91b/pubsub/gossipsub/gossipsub-v1.1.md#recommendations-for-network-operators
-            .mesh_n_low(2)
-            .mesh_n(3)
-            .mesh_n_high(6)
+            .mesh_n_low(mesh_n_low)
+            .mesh_n(mesh_n)
+            .mesh_n_high(mesh_n_high)
             .build()
-            .unwrap();
+            .map_err(|err| Error::GossipConfig(err.to_string()))?;
 
-        let filter = match &config.subscription_filter {
-            crate::config::SubscriptionFilter::RegexFilter(regex) => {
-                IntentGossipSubscriptionFilter::RegexFilter(
-                    RegexSubscriptionFilter(regex.clone()),
-                )
-            }
-            crate::config::SubscriptionFilter::WhitelistFilter(topics) => {
-                IntentGossipSubscriptionFilter::WhitelistFilter(
-                    WhitelistSubscriptionFilter(
-                        topics
-                            .iter()
-                            .map(IdentTopic::new)
-                            .map(TopicHash::from)
-                            .collect(),
-                    ),
-                )
+        let filter = subscription_filter(config);
+        let topic = |topic: &str| match &config.topic_namespace {
+            Some(namespace) => {
+                IdentTopic::new(namespaced_topic(namespace, topic))
             }
+            None => IdentTopic::new(topic),
         };
 
         let mut intent_gossip_behaviour: Gossipsub =
@@ -206,9 +304,9 @@ impl Behaviour {
         config
             .topics
             .iter()
-            .try_for_each(|topic| {
+            .try_for_each(|topic_name| {
                 intent_gossip_behaviour
-                    .subscribe(&IdentTopic::new(topic))
+                    .subscribe(&topic(topic_name))
                     .map_err(Error::FailedSubscription)
                     // it returns bool signifying if it was already subscribed.
                     // discard because it can't be false as the config.topics is
@@ -237,7 +335,7 @@ impl Behaviour {
                 .await
                 .unwrap()
         };
-        Self {
+        Ok(Self {
             intent_gossip_behaviour,
             discover_behaviour,
             identify: Identify::new(IdentifyConfig::new(
@@ -246,28 +344,181 @@ impl Behaviour {
             )),
             ping: Ping::default(),
             peer_intent_send,
+            max_transmit_size: config.max_transmit_size,
+            subscriptions: None,
+            topic_namespace: config.topic_namespace.clone(),
+        })
+    }
+
+    /// The topic with the given name in the configured namespace, if any, on
+    /// which messages are published and received
+    pub fn topic(&self, topic: &IdentTopic) -> IdentTopic {
+        match &self.topic_namespace {
+            Some(namespace) => {
+                IdentTopic::new(namespaced_topic(namespace, &topic.to_string()))
+            }
+            None => topic.clone(),
         }
     }
 
-    /// tries to apply a new intent. Fails if the logic fails or if the intent
-    /// is rejected. If the matchmaker fails the message is only ignore
-    fn handle_intent(&mut self, intent: Intent) -> MessageAcceptance {
-        if let Err(err) = self.peer_intent_send.try_send(intent) {
+    /// The names of the subscribed topics, without the configured namespace
+    pub fn topics(&self) -> impl Iterator<Item = String> + '_ {
+        self.intent_gossip_behaviour.topics().map(move |topic| {
+            let topic = topic.as_str();
+            match &self.topic_namespace {
+                Some(namespace) => strip_topic_namespace(namespace, topic)
+                    .unwrap_or(topic)
+                    .to_string(),
+                None => topic.to_string(),
+            }
+        })
+    }
+
+    /// Subscribe to the persisted topics again and persist any new
+    /// subscription with [`Behaviour::subscribe`] from now on.
+    pub fn restore_subscriptions(&mut self, subscriptions: Subscriptions) {
+        for topic in subscriptions.topics() {
+            let namespaced = self.topic(&IdentTopic::new(topic));
+            if let Err(err) =
+                self.intent_gossip_behaviour.subscribe(&namespaced)
+            {
+                tracing::warn!(
+                    "Failed to restore the subscription to {}: {:?}",
+                    topic,
+                    err
+                );
+            }
+        }
+        self.subscriptions = Some(subscriptions);
+    }
+
+    /// Subscribe to a topic. The subscription is persisted, if the
+    /// subscriptions have been restored. It returns `true` if the node wasn't
+    /// already subscribed to this topic.
+    pub fn subscribe(&mut self, topic: &IdentTopic) -> Result<bool, Error> {
+        let is_new = self
+            .intent_gossip_behaviour
+            .subscribe(&self.topic(topic))
+            .map_err(Error::FailedSubscription)?;
+        if let Some(subscriptions) = self.subscriptions.as_mut() {
+            if let Err(err) = subscriptions.insert(topic.to_string()) {
+                tracing::warn!(
+                    "Failed to persist the subscription to {}: {}",
+                    topic,
+                    err
+                );
+            }
+        }
+        Ok(is_new)
+    }
+
+    /// Publish a message on the given topic. A message whose data exceeds the
+    /// configured maximum transmit size is refused before publishing.
+    pub fn publish(
+        &mut self,
+        topic: IdentTopic,
+        data: Vec<u8>,
+    ) -> Result<MessageId, Error> {
+        if data.len() > self.max_transmit_size {
+            return Err(Error::MessageTooLarge {
+                size: data.len(),
+                max_size: self.max_transmit_size,
+            });
+        }
+        self.intent_gossip_behaviour
+            .publish(self.topic(&topic), data)
+            .map_err(Error::Publish)
+    }
+
+    /// tries to apply a new intent. The intent's signatures are verified by
+    /// the intent gossiper, which then reports the validation result with
+    /// [`Behaviour::report_validation`], so this returns `None` once the
+    /// intent has been sent to it. If the matchmaker fails the message is only
+    /// ignore
+    fn handle_intent(
+        &mut self,
+        intent: Intent,
+        topic: String,
+        pending_validation: (MessageId, PeerId),
+    ) -> Option<MessageAcceptance> {
+        let message = PeerMessage {
+            message: IntentGossipMessage::new(intent),
+            topic,
+            pending_validation: Some(pending_validation),
+        };
+        if let Err(err) = self.peer_intent_send.try_send(message) {
             tracing::error!("Error sending intent to the matchmaker: {}", err);
             // The buffer is full or the channel is closed
+            return Some(MessageAcceptance::Ignore);
+        }
+        None
+    }
+
+    /// Report the validation result of an intent received from a peer, which
+    /// has been deferred to the intent gossiper.
+    pub fn report_validation(
+        &mut self,
+        (message_id, propagation_source): &(MessageId, PeerId),
+        validity: MessageAcceptance,
+    ) {
+        if let Err(err) = self
+            .intent_gossip_behaviour
+            .report_message_validation_result(
+                message_id,
+                propagation_source,
+                validity,
+            )
+        {
+            tracing::error!("Failed to validate the message: {:?}", err);
+        }
+    }
+
+    /// tries to apply an intent cancellation. Fails if the cancellation is not
+    /// validly signed. Whether the signer matches the intent's signer can only
+    /// be checked against the intent in the mempool, so this is left to the
+    /// intent gossiper.
+    fn handle_cancellation(
+        &mut self,
+        cancellation: IntentCancellation,
+        topic: String,
+    ) -> MessageAcceptance {
+        if let Err(err) = cancellation.verify_sig() {
+            tracing::info!(
+                "Invalid intent cancellation, rejecting it: {}",
+                err
+            );
+            return MessageAcceptance::Reject;
+        }
+        let message = PeerMessage {
+            message: IntentGossipMessage::new_cancellation(cancellation),
+            topic,
+            pending_validation: None,
+        };
+        if let Err(err) = self.peer_intent_send.try_send(message) {
+            tracing::error!("Error sending intent cancellation: {}", err);
+            // The buffer is full or the channel is closed
             return MessageAcceptance::Ignore;
         }
         MessageAcceptance::Accept
     }
 
     /// Tries to decoded the arbitrary data in an intent then call
-    /// [handle_intent]. fails if the data does not contains an intent
+    /// [handle_intent] or [handle_cancellation]. fails if the data does not
+    /// contains an intent or an intent cancellation. Returns `None` if the
+    /// validation of the message has been deferred.
     fn handle_raw_intent(
         &mut self,
         data: impl AsRef<[u8]>,
-    ) -> MessageAcceptance {
-        match IntentGossipMessage::try_from(data.as_ref()) {
-            Ok(message) => self.handle_intent(message.intent),
+        topic: String,
+        pending_validation: (MessageId, PeerId),
+    ) -> Option<MessageAcceptance> {
+        let validity = match IntentGossipMessage::try_from(data.as_ref()) {
+            Ok(IntentGossipMessage::Intent(intent)) => {
+                return self.handle_intent(intent, topic, pending_validation);
+            }
+            Ok(IntentGossipMessage::Cancellation(cancellation)) => {
+                self.handle_cancellation(cancellation, topic)
+            }
             Err(proto::Error::NoIntentError) => {
                 tracing::info!("Empty message, rejecting it");
                 MessageAcceptance::Reject
@@ -276,7 +527,52 @@ impl Behaviour {
                 tracing::info!("error while decoding the intent: {:?}", err);
                 MessageAcceptance::Reject
             }
-            _ => unreachable!(),
+            Err(proto::Error::IntentCancellationDecodingError(err)) => {
+                tracing::info!(
+                    "error while decoding the intent cancellation: {:?}",
+                    err
+                );
+                MessageAcceptance::Reject
+            }
+            Err(err) => {
+                tracing::info!(
+                    "Unexpected error while decoding the message, ignoring \
+                     it: {}",
+                    err
+                );
+                MessageAcceptance::Ignore
+            }
+        };
+        Some(validity)
+    }
+
+    /// Handle a message received from the network on the given topic. A
+    /// message on a topic outside of the configured namespace is logged and
+    /// ignored. Returns `None` if the validation of the message has been
+    /// deferred.
+    fn handle_message(
+        &mut self,
+        data: Vec<u8>,
+        topic: TopicHash,
+        pending_validation: (MessageId, PeerId),
+    ) -> Option<MessageAcceptance> {
+        let topic = topic.into_string();
+        let topic_name = match &self.topic_namespace {
+            Some(namespace) => strip_topic_namespace(namespace, &topic),
+            None => Some(topic.as_str()),
+        };
+        match topic_name {
+            Some(topic_name) => {
+                let topic_name = topic_name.to_string();
+                self.handle_raw_intent(data, topic_name, pending_validation)
+            }
+            None => {
+                tracing::warn!(
+                    "Ignoring a message received on an unexpected topic {}",
+                    topic
+                );
+                Some(MessageAcceptance::Ignore)
+            }
         }
     }
 }
@@ -292,15 +588,16 @@ impl NetworkBehaviourEventProcess<GossipsubEvent> for Behaviour {
                 message_id,
             } => {
                 // validity is the type of response return to the network
-                // (valid|reject|ignore)
-                let validity = self.handle_raw_intent(message.data);
-                self.intent_gossip_behaviour
-                    .report_message_validation_result(
-                        &message_id,
-                        &propagation_source,
-                        validity,
-                    )
-                    .expect("Failed to validate the message");
+                // (valid|reject|ignore), unless it's deferred
+                let pending_validation = (message_id, propagation_source);
+                let validity = self.handle_message(
+                    message.data,
+                    message.topic,
+                    pending_validation.clone(),
+                );
+                if let Some(validity) = validity {
+                    self.report_validation(&pending_validation, validity);
+                }
             }
             // When a peer subscribe to a new topic, this node also tries to
             // connect to it using the filter defined in the config
@@ -410,3 +707,258 @@ impl NetworkBehaviourEventProcess<PingEvent> for Behaviour {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anoma::proto::generated::types as proto_types;
+    use anoma::testing::LogCapture;
+    use prost::Message;
+
+    use super::*;
+
+    /// Test that a message exceeding the configured maximum transmit size is
+    /// refused before it's published
+    #[tokio::test]
+    async fn test_publish_oversized_message() {
+        let config = config::IntentGossiper {
+            max_transmit_size: 1024,
+            discover_peer: None,
+            ..Default::default()
+        };
+        let (peer_intent_send, _peer_intent_recv) =
+            tokio::sync::mpsc::channel(1);
+        let mut behaviour = Behaviour::new(
+            Keypair::generate_ed25519(),
+            &config,
+            peer_intent_send,
+        )
+        .await
+        .unwrap();
+
+        let topic = IdentTopic::new("asset_v0");
+        match behaviour.publish(topic.clone(), vec![0; 2048]) {
+            Err(Error::MessageTooLarge { size, max_size }) => {
+                assert_eq!(size, 2048);
+                assert_eq!(max_size, 1024);
+            }
+            result => panic!("Unexpected publish result {:?}", result),
+        }
+        // A message within the limit is not refused for its size, even though
+        // it cannot be published without any peers
+        assert!(!matches!(
+            behaviour.publish(topic, vec![0; 512]),
+            Err(Error::MessageTooLarge { .. })
+        ));
+    }
+
+    /// Test that with a topic namespace, the subscribed and published topics
+    /// carry the namespace prefix and the subscription filter only accepts the
+    /// topics in the namespace
+    #[tokio::test]
+    async fn test_topic_namespace() {
+        let config = config::IntentGossiper {
+            topic_namespace: Some("net1".to_owned()),
+            discover_peer: None,
+            ..Default::default()
+        };
+        let (peer_intent_send, _peer_intent_recv) =
+            tokio::sync::mpsc::channel(1);
+        let mut behaviour = Behaviour::new(
+            Keypair::generate_ed25519(),
+            &config,
+            peer_intent_send,
+        )
+        .await
+        .unwrap();
+
+        // The topics from the config are subscribed to in the namespace
+        let subscribed: Vec<_> = behaviour
+            .intent_gossip_behaviour
+            .topics()
+            .map(|topic| topic.to_string())
+            .collect();
+        assert_eq!(subscribed, vec!["net1/asset_v0".to_owned()]);
+        assert_eq!(behaviour.topics().collect::<Vec<_>>(), vec!["asset_v0"]);
+
+        // The messages are published in the namespace
+        let topic = IdentTopic::new("asset_v1");
+        assert_eq!(
+            behaviour.topic(&topic).hash(),
+            TopicHash::from_raw("net1/asset_v1")
+        );
+        assert!(behaviour.subscribe(&topic).unwrap());
+        assert!(behaviour
+            .intent_gossip_behaviour
+            .topics()
+            .any(|topic| topic.as_str() == "net1/asset_v1"));
+
+        // The default regex filter is applied within the namespace
+        let mut filter = subscription_filter(&config);
+        let mut can_subscribe =
+            |topic| filter.can_subscribe(&TopicHash::from_raw(topic));
+        assert!(can_subscribe("net1/asset_v0"));
+        assert!(can_subscribe("net1/asset_v12"));
+        assert!(!can_subscribe("asset_v0"));
+        assert!(!can_subscribe("net2/asset_v0"));
+        assert!(!can_subscribe("net1asset_v0"));
+        assert!(!can_subscribe("net1/other"));
+    }
+
+    /// Test that a message on a topic outside of the namespace and a message
+    /// that cannot be handled are logged and ignored, and that the node
+    /// continues to handle messages
+    #[tokio::test]
+    async fn test_unexpected_message_ignored() {
+        let config = config::IntentGossiper {
+            topic_namespace: Some("net1".to_owned()),
+            discover_peer: None,
+            ..Default::default()
+        };
+        let (peer_intent_send, mut peer_intent_recv) =
+            tokio::sync::mpsc::channel(1);
+        let mut behaviour = Behaviour::new(
+            Keypair::generate_ed25519(),
+            &config,
+            peer_intent_send,
+        )
+        .await
+        .unwrap();
+        let pending_validation = || (MessageId::from("id"), PeerId::random());
+        let intent_message =
+            IntentGossipMessage::new(Intent::new(b"intent".to_vec()));
+
+        let capture = LogCapture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            // A message on an unexpected topic
+            let validity = behaviour.handle_message(
+                intent_message.to_bytes(),
+                TopicHash::from_raw("net2/asset_v0"),
+                pending_validation(),
+            );
+            assert!(matches!(validity, Some(MessageAcceptance::Ignore)));
+
+            // An intent without a timestamp
+            let mut data = vec![];
+            proto_types::IntentGossipMessage {
+                msg: Some(proto_types::intent_gossip_message::Msg::Intent(
+                    proto_types::Intent {
+                        data: b"intent".to_vec(),
+                        timestamp: None,
+                    },
+                )),
+            }
+            .encode(&mut data)
+            .unwrap();
+            let validity = behaviour.handle_message(
+                data,
+                TopicHash::from_raw("net1/asset_v0"),
+                pending_validation(),
+            );
+            assert!(matches!(validity, Some(MessageAcceptance::Ignore)));
+        });
+        let logs = capture.logs();
+        assert!(logs.contains(
+            "Ignoring a message received on an unexpected topic net2/asset_v0"
+        ));
+        assert!(logs.contains("Unexpected error while decoding the message"));
+
+        // A valid intent on the namespaced topic is still handled
+        let validity = behaviour.handle_message(
+            intent_message.to_bytes(),
+            TopicHash::from_raw("net1/asset_v0"),
+            pending_validation(),
+        );
+        assert!(validity.is_none());
+        let received = peer_intent_recv.try_recv().unwrap();
+        assert_eq!(received.topic, "asset_v0");
+    }
+
+    /// Make a new behaviour with the subscriptions restored from the given
+    /// base directory
+    async fn restored_behaviour(
+        config: &config::IntentGossiper,
+        base_dir: &std::path::Path,
+    ) -> Behaviour {
+        let (peer_intent_send, _peer_intent_recv) =
+            tokio::sync::mpsc::channel(1);
+        let mut behaviour = Behaviour::new(
+            Keypair::generate_ed25519(),
+            config,
+            peer_intent_send,
+        )
+        .await
+        .unwrap();
+        behaviour.restore_subscriptions(Subscriptions::load(base_dir));
+        behaviour
+    }
+
+    /// Test that a topic subscribed to before a restart of the node is
+    /// subscribed to again from the persisted subscriptions
+    #[tokio::test]
+    async fn test_restore_subscriptions() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let config = config::IntentGossiper {
+            topics: Default::default(),
+            discover_peer: None,
+            ..Default::default()
+        };
+        let topic = IdentTopic::new("asset_v1");
+
+        let mut behaviour = restored_behaviour(&config, base_dir.path()).await;
+        assert_eq!(behaviour.intent_gossip_behaviour.topics().count(), 0);
+        assert!(behaviour.subscribe(&topic).unwrap());
+        drop(behaviour);
+
+        // Restart the node
+        let behaviour = restored_behaviour(&config, base_dir.path()).await;
+        let topics: Vec<_> =
+            behaviour.intent_gossip_behaviour.topics().collect();
+        assert_eq!(topics, vec![&topic.hash()]);
+    }
+
+    /// Test that the behaviour is constructed with valid custom gossipsub
+    /// parameters and that inconsistent mesh sizes are rejected
+    #[tokio::test]
+    async fn test_gossipsub_params() {
+        let new_behaviour = |gossipsub| {
+            let config = config::IntentGossiper {
+                gossipsub,
+                discover_peer: None,
+                ..Default::default()
+            };
+            let (peer_intent_send, _peer_intent_recv) =
+                tokio::sync::mpsc::channel(1);
+            async move {
+                Behaviour::new(
+                    Keypair::generate_ed25519(),
+                    &config,
+                    peer_intent_send,
+                )
+                .await
+            }
+        };
+        let params =
+            |heartbeat_interval: &str, low, n, high| config::Gossipsub {
+                heartbeat_interval: heartbeat_interval.parse().unwrap(),
+                mesh_n_low: low,
+                mesh_n: n,
+                mesh_n_high: high,
+            };
+
+        assert!(new_behaviour(params("5s", 4, 6, 12)).await.is_ok());
+        assert!(new_behaviour(params("500ms", 3, 3, 3)).await.is_ok());
+        assert!(matches!(
+            new_behaviour(params("1s", 6, 4, 12)).await,
+            Err(Error::InvalidMesh { .. })
+        ));
+        assert!(matches!(
+            new_behaviour(params("1s", 2, 8, 6)).await,
+            Err(Error::InvalidMesh { .. })
+        ));
+    }
+}
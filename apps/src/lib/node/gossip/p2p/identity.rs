@@ -4,11 +4,24 @@ use std::path::{Path, PathBuf};
 use libp2p::identity::ed25519::Keypair;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-
-use crate::cli;
+use thiserror::Error;
 
 const P2P_KEY_PATH: &str = "gossiper-p2p-private-key.json";
 
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Cannot read the P2P key file {}: {1}", .0.display())]
+    ReadKey(PathBuf, std::io::Error),
+    #[error("Unexpected encoding of the P2P key file {}: {1}", .0.display())]
+    DecodeKey(PathBuf, serde_json::Error),
+    #[error("Cannot open the P2P key file {}: {1}", .0.display())]
+    OpenKey(PathBuf, std::io::Error),
+    #[error("Cannot write the P2P key file {}: {1}", .0.display())]
+    WriteKey(PathBuf, serde_json::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
 /// ed255519 keypair + hash of public key. The keypair used to encrypted the
 /// data send in the libp2p network.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -28,7 +41,7 @@ mod keypair_serde {
     pub fn serialize<S>(
         value: &Keypair,
         serializer: S,
-    ) -> Result<S::Ok, S::Error>
+    ) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
@@ -36,7 +49,9 @@ mod keypair_serde {
         let string = hex::encode(&bytes[..]);
         string.serialize(serializer)
     }
-    pub fn deserialize<'d, D>(deserializer: D) -> Result<Keypair, D::Error>
+    pub fn deserialize<'d, D>(
+        deserializer: D,
+    ) -> std::result::Result<Keypair, D::Error>
     where
         D: Deserializer<'d>,
     {
@@ -57,14 +72,11 @@ impl Identity {
     }
 
     /// Load identity from file or generate a new one if none found.
-    pub fn load_or_gen(base_dir: impl AsRef<Path>) -> Identity {
+    pub fn load_or_gen(base_dir: impl AsRef<Path>) -> Result<Identity> {
         let file_path = Self::file_path(&base_dir);
         match OpenOptions::new().read(true).open(&file_path) {
-            Ok(file) => {
-                let gossiper: Identity = serde_json::from_reader(file)
-                    .expect("unexpected key encoding");
-                gossiper
-            }
+            Ok(file) => serde_json::from_reader(file)
+                .map_err(|err| Error::DecodeKey(file_path, err)),
             Err(err) => {
                 if let std::io::ErrorKind::NotFound = err.kind() {
                     tracing::info!(
@@ -74,30 +86,28 @@ impl Identity {
                     );
                     Self::gen(base_dir)
                 } else {
-                    eprintln!(
-                        "Cannot read {}: {}",
-                        file_path.to_string_lossy(),
-                        err
-                    );
-                    cli::safe_exit(1);
+                    Err(Error::ReadKey(file_path, err))
                 }
             }
         }
     }
 
     /// Generate a new identity.
-    pub fn gen(base_dir: impl AsRef<Path>) -> Identity {
+    pub fn gen(base_dir: impl AsRef<Path>) -> Result<Identity> {
         let file_path = Self::file_path(base_dir);
-        let file = OpenOptions::new()
+        let file = match OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
             .open(&file_path)
-            .expect("Couldn't open P2P key file");
+        {
+            Ok(file) => file,
+            Err(err) => return Err(Error::OpenKey(file_path, err)),
+        };
         let gossiper = Identity::new();
         serde_json::to_writer_pretty(file, &gossiper)
-            .expect("Couldn't write private validator key file");
-        gossiper
+            .map_err(|err| Error::WriteKey(file_path, err))?;
+        Ok(gossiper)
     }
 
     pub fn file_path(base_dir: impl AsRef<Path>) -> PathBuf {
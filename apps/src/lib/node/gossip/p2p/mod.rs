@@ -1,11 +1,11 @@
 pub mod behaviour;
 mod identity;
+mod subscriptions;
 
 use std::path::Path;
 use std::time::Duration;
 
-use anoma::proto::Intent;
-use behaviour::Behaviour;
+use behaviour::{Behaviour, PeerMessage};
 use libp2p::core::connection::ConnectionLimits;
 use libp2p::core::muxing::StreamMuxerBox;
 use libp2p::core::transport::Boxed;
@@ -19,6 +19,7 @@ use thiserror::Error;
 use tokio::sync::mpsc::Sender;
 
 pub use self::identity::Identity;
+pub use self::subscriptions::Subscriptions;
 use crate::config;
 
 pub type Swarm = libp2p::Swarm<Behaviour>;
@@ -27,6 +28,10 @@ pub type Swarm = libp2p::Swarm<Behaviour>;
 pub enum Error {
     #[error("Failed initializing the transport: {0}")]
     Transport(std::io::Error),
+    #[error("Failed generating the noise key: {0}")]
+    NoiseKey(noise::NoiseError),
+    #[error("Error loading the peer identity: {0}")]
+    Identity(identity::Error),
     #[error("Error with the network behavior: {0}")]
     Behavior(crate::node::gossip::p2p::behaviour::Error),
     #[error("Error while dialing: {0}")]
@@ -47,22 +52,25 @@ impl P2P {
     pub async fn new(
         config: &config::IntentGossiper,
         base_dir: impl AsRef<Path>,
-        peer_intent_send: Sender<Intent>,
+        peer_intent_send: Sender<PeerMessage>,
     ) -> Result<Self> {
-        let identity = Identity::load_or_gen(base_dir);
+        let identity =
+            Identity::load_or_gen(&base_dir).map_err(Error::Identity)?;
         let peer_key = identity.key();
         // Id of the node on the libp2p network derived from the public key
         let peer_id = identity.peer_id();
 
         tracing::info!("Peer id: {:?}", peer_id.clone());
 
-        let transport = build_transport(&peer_key).await;
+        let transport = build_transport(&peer_key).await?;
 
         // create intent gossip specific behaviour
         let intent_gossip_behaviour =
-            Behaviour::new(peer_key, config, peer_intent_send).await;
+            Behaviour::new(peer_key, config, peer_intent_send)
+                .await
+                .map_err(Error::Behavior)?;
 
-        let connection_limits = build_p2p_connections_limit();
+        let connection_limits = build_p2p_connections_limit(config.max_peers);
 
         // Swarm is
         let mut swarm =
@@ -74,6 +82,11 @@ impl P2P {
                 .connection_event_buffer_size(64)
                 .build();
 
+        // Subscribe again to the topics subscribed to before a restart
+        swarm
+            .behaviour_mut()
+            .restore_subscriptions(Subscriptions::load(&base_dir));
+
         swarm
             .listen_on(config.address.clone())
             .map_err(Error::Listening)?;
@@ -88,10 +101,12 @@ impl P2P {
 /// transport
 pub async fn build_transport(
     peer_key: &Keypair,
-) -> Boxed<(PeerId, StreamMuxerBox)> {
+) -> Result<Boxed<(PeerId, StreamMuxerBox)>> {
     let transport = {
         let tcp_transport = TcpConfig::new().nodelay(true);
-        let dns_tcp_transport = DnsConfig::system(tcp_transport).await.unwrap();
+        let dns_tcp_transport = DnsConfig::system(tcp_transport)
+            .await
+            .map_err(Error::Transport)?;
         let ws_dns_tcp_transport = WsConfig::new(dns_tcp_transport.clone());
         dns_tcp_transport.or_transport(ws_dns_tcp_transport)
     };
@@ -99,7 +114,7 @@ pub async fn build_transport(
     let auth_config = {
         let dh_keys = noise::Keypair::<noise::X25519Spec>::new()
             .into_authentic(peer_key)
-            .expect("Noise key generation failed. Should never happen.");
+            .map_err(Error::NoiseKey)?;
 
         noise::NoiseConfig::xx(dh_keys).into_authenticated()
     };
@@ -119,21 +134,116 @@ pub async fn build_transport(
         core::upgrade::SelectUpgrade::new(yamux_config, mplex_config)
     };
 
-    transport
+    Ok(transport
         .upgrade(core::upgrade::Version::V1)
         .authenticate(auth_config)
         .multiplex(mplex_config)
         .timeout(Duration::from_secs(20))
-        .boxed()
+        .boxed())
 }
 
 // TODO document choice made here
-// TODO inject it in the configuration instead of hard-coding it ?
-pub fn build_p2p_connections_limit() -> ConnectionLimits {
+/// Build the connection limits of the swarm. The number of established
+/// inbound connections is capped by `max_peers`, any inbound connection
+/// beyond it is refused. Outbound connections, including the ones to the
+/// configured peers, have their own limit so that the configured peers are
+/// preferred when the node is full.
+pub fn build_p2p_connections_limit(max_peers: u32) -> ConnectionLimits {
     ConnectionLimits::default()
         .with_max_pending_incoming(Some(10))
         .with_max_pending_outgoing(Some(30))
-        .with_max_established_incoming(Some(25))
+        .with_max_established_incoming(Some(max_peers))
         .with_max_established_outgoing(Some(25))
         .with_max_established_per_peer(Some(5))
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use libp2p::core::connection::PendingConnectionError;
+    use libp2p::ping::{Ping, PingConfig};
+    use libp2p::swarm::SwarmEvent;
+    use libp2p::Multiaddr;
+
+    use super::*;
+
+    /// Build a swarm with the gossip node's transport and connection limits
+    /// and a ping behaviour that keeps the connections alive.
+    async fn test_swarm(max_peers: u32) -> libp2p::Swarm<Ping> {
+        let key = Keypair::generate_ed25519();
+        let peer_id = PeerId::from_public_key(key.public());
+        let transport = build_transport(&key).await.unwrap();
+        let behaviour = Ping::new(PingConfig::new().with_keep_alive(true));
+        SwarmBuilder::new(transport, behaviour, peer_id)
+            .connection_limits(build_p2p_connections_limit(max_peers))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_max_peers() {
+        let mut listener = test_swarm(1).await;
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let address: Multiaddr =
+            format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap();
+        listener.listen_on(address.clone()).unwrap();
+
+        let mut dialer_1 = test_swarm(1).await;
+        let mut dialer_2 = test_swarm(1).await;
+        dialer_1.dial_addr(address.clone()).unwrap();
+        dialer_2.dial_addr(address).unwrap();
+
+        let mut established = 0;
+        let timeout = tokio::time::sleep(Duration::from_secs(10));
+        tokio::pin!(timeout);
+        loop {
+            tokio::select! {
+                event = listener.select_next_some() => match event {
+                    SwarmEvent::ConnectionEstablished { .. } => {
+                        established += 1
+                    }
+                    SwarmEvent::IncomingConnectionError {
+                        error: PendingConnectionError::ConnectionLimit(_),
+                        ..
+                    } => break,
+                    _ => {}
+                },
+                _ = dialer_1.select_next_some() => {}
+                _ = dialer_2.select_next_some() => {}
+                _ = &mut timeout => {
+                    panic!("The connection beyond the limit wasn't refused")
+                }
+            }
+        }
+        assert_eq!(established, 1);
+    }
+
+    /// Test that a node with a corrupted P2P key file fails to start with an
+    /// error pointing at the file, instead of panicking
+    #[tokio::test]
+    async fn test_invalid_identity() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let key_path = Identity::file_path(base_dir.path());
+        std::fs::write(&key_path, "not a key").unwrap();
+        let (peer_intent_send, _peer_intent_recv) =
+            tokio::sync::mpsc::channel(1);
+
+        let result = P2P::new(
+            &config::IntentGossiper::default(),
+            base_dir.path(),
+            peer_intent_send,
+        )
+        .await;
+        let err = match result {
+            Err(err @ Error::Identity(identity::Error::DecodeKey(..))) => err,
+            Err(err) => panic!("Unexpected error {}", err),
+            Ok(_) => panic!("The node mustn't start with an invalid key"),
+        };
+        let msg = err.to_string();
+        assert!(msg.contains("Unexpected encoding of the P2P key file"));
+        assert!(msg.contains(&key_path.display().to_string()));
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::utils::write_atomically;
+
+const SUBSCRIPTIONS_PATH: &str = "gossiper-subscriptions.json";
+
+/// The topics subscribed to via the RPC, e.g. by a matchmaker. These are
+/// persisted, so that the topics are subscribed to again when the node is
+/// restarted.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Subscriptions {
+    #[serde(skip)]
+    file_path: PathBuf,
+    topics: BTreeSet<String>,
+}
+
+impl Subscriptions {
+    /// Load the persisted subscriptions. If there are none yet, or they cannot
+    /// be read, starts with no subscriptions.
+    pub fn load(base_dir: impl AsRef<Path>) -> Self {
+        let file_path = Self::file_path(base_dir);
+        let topics = match OpenOptions::new().read(true).open(&file_path) {
+            Ok(file) => match serde_json::from_reader(file) {
+                Ok(Subscriptions { topics, .. }) => topics,
+                Err(err) => {
+                    tracing::warn!(
+                        "Ignoring invalid subscriptions in {}: {}",
+                        file_path.to_string_lossy(),
+                        err
+                    );
+                    BTreeSet::default()
+                }
+            },
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!(
+                        "Cannot read {}: {}",
+                        file_path.to_string_lossy(),
+                        err
+                    );
+                }
+                BTreeSet::default()
+            }
+        };
+        Self { file_path, topics }
+    }
+
+    /// Add a new subscription and persist the subscriptions. If they cannot
+    /// be persisted, the subscription is not added.
+    pub fn insert(&mut self, topic: impl Into<String>) -> std::io::Result<()> {
+        let topic = topic.into();
+        if self.topics.insert(topic.clone()) {
+            let result = serde_json::to_vec_pretty(self)
+                .map_err(std::io::Error::from)
+                .and_then(|json| write_atomically(&self.file_path, json));
+            if result.is_err() {
+                self.topics.remove(&topic);
+            }
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Iterate over the subscribed topics.
+    pub fn topics(&self) -> impl Iterator<Item = &String> {
+        self.topics.iter()
+    }
+
+    pub fn file_path(base_dir: impl AsRef<Path>) -> PathBuf {
+        base_dir.as_ref().join(SUBSCRIPTIONS_PATH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a subscription is persisted and loaded again, while a
+    /// subscription that cannot be persisted is not added
+    #[test]
+    fn test_insert_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut subscriptions = Subscriptions::load(dir.path());
+        subscriptions.insert("asset_v1").unwrap();
+        let loaded = Subscriptions::load(dir.path());
+        assert_eq!(loaded.topics().collect::<Vec<_>>(), vec!["asset_v1"]);
+
+        let mut subscriptions =
+            Subscriptions::load(dir.path().join("missing_dir"));
+        assert!(subscriptions.insert("asset_v1").is_err());
+        assert_eq!(subscriptions.topics().count(), 0);
+    }
+}
@@ -1,7 +1,10 @@
 use std::convert::TryFrom;
 use std::net::SocketAddr;
+use std::pin::Pin;
 
-use anoma::proto::{Intent, IntentGossipMessage};
+use anoma::proto::IntentGossipMessage;
+use anoma::types::address::Address;
+use futures::Stream;
 use libp2p::gossipsub::IdentTopic;
 use tokio::sync::mpsc::{self, Sender};
 use tokio::sync::oneshot;
@@ -9,21 +12,40 @@ use tonic::transport::Server;
 use tonic::{Request as TonicRequest, Response as TonicResponse, Status};
 
 use crate::config::RpcServer;
-use crate::node::gossip::p2p::behaviour::Gossipsub;
+use crate::node::gossip::intent_gossiper::{
+    IntentGossiper, MatchSubscriptions,
+};
+use crate::node::gossip::p2p::behaviour::Behaviour;
+use crate::node::gossip::p2p::Swarm;
 use crate::proto::services::rpc_service_server::{
     RpcService, RpcServiceServer,
 };
-use crate::proto::services::{rpc_message, RpcMessage, RpcResponse};
-use crate::proto::{IntentMessage, SubscribeTopicMessage};
+use crate::proto::services::{
+    rpc_message, MatchNotification, RpcMessage, RpcResponse, StatusRequest,
+    StatusResponse, SubscribeMatchesRequest,
+};
+use crate::proto::{
+    IntentCancellationMessage, IntentMessage, ListIntentsMessage,
+    SubscribeTopicMessage,
+};
+
+/// The maximum number of intent IDs listed in a single response. A request
+/// for more intents, or without a limit, is capped to this number.
+pub const MAX_LISTED_INTENTS: u64 = 1000;
 
 #[derive(Debug)]
 struct Rpc {
     inject_message:
         mpsc::Sender<(rpc_message::Message, oneshot::Sender<RpcResponse>)>,
+    inject_status: mpsc::Sender<oneshot::Sender<StatusResponse>>,
+    match_subscriptions: MatchSubscriptions,
 }
 
 #[tonic::async_trait]
 impl RpcService for Rpc {
+    type SubscribeMatchesStream =
+        Pin<Box<dyn Stream<Item = Result<MatchNotification, Status>> + Send>>;
+
     async fn send_message(
         &self,
         request: TonicRequest<RpcMessage>,
@@ -45,6 +67,44 @@ impl RpcService for Rpc {
             Ok(TonicResponse::new(RpcResponse::default()))
         }
     }
+
+    async fn status(
+        &self,
+        _request: TonicRequest<StatusRequest>,
+    ) -> Result<TonicResponse<StatusResponse>, Status> {
+        let (sender, receiver) = oneshot::channel();
+        self.inject_status.send(sender).await.map_err(|err| {
+            Status::cancelled(format!(
+                "failed to send status request to gossip app: {:?}",
+                err
+            ))
+        })?;
+        let response = receiver.await.map_err(|err| {
+            Status::data_loss(format!(
+                "failed to receive status from gossip app: {:?}",
+                err
+            ))
+        })?;
+        Ok(TonicResponse::new(response))
+    }
+
+    async fn subscribe_matches(
+        &self,
+        request: TonicRequest<SubscribeMatchesRequest>,
+    ) -> Result<TonicResponse<Self::SubscribeMatchesStream>, Status> {
+        let address =
+            Address::decode(&request.into_inner().address).map_err(|err| {
+                Status::invalid_argument(format!("invalid address: {}", err))
+            })?;
+        tracing::info!("A client subscribed to the matches of {}", address);
+        let receiver = self.match_subscriptions.subscribe(address);
+        let stream =
+            futures::stream::unfold(receiver, |mut receiver| async move {
+                let notice = receiver.recv().await?;
+                Some((Ok(MatchNotification::from(notice)), receiver))
+            });
+        Ok(TonicResponse::new(Box::pin(stream)))
+    }
 }
 
 pub async fn rpc_server(
@@ -53,31 +113,59 @@ pub async fn rpc_server(
         rpc_message::Message,
         oneshot::Sender<RpcResponse>,
     )>,
+    inject_status: Sender<oneshot::Sender<StatusResponse>>,
+    match_subscriptions: MatchSubscriptions,
 ) -> Result<(), tonic::transport::Error> {
-    let rpc = Rpc { inject_message };
+    let rpc = Rpc {
+        inject_message,
+        inject_status,
+        match_subscriptions,
+    };
     let svc = RpcServiceServer::new(rpc);
     Server::builder().add_service(svc).serve(addr).await
 }
 
 /// Start a rpc server in it's own thread. The used address to listen is in the
 /// `config` argument. All received event by the rpc are send to the channel
-/// return by this function.
+/// return by this function. The status requests are sent to the
+/// `status_sender` channel. The clients subscribed to match notices are added
+/// to the `match_subscriptions`.
 pub async fn start_rpc_server(
     config: &RpcServer,
     rpc_sender: mpsc::Sender<(
         rpc_message::Message,
         tokio::sync::oneshot::Sender<RpcResponse>,
     )>,
+    status_sender: mpsc::Sender<oneshot::Sender<StatusResponse>>,
+    match_subscriptions: MatchSubscriptions,
 ) {
     let addr = config.address;
     tracing::info!("RPC started at {}", config.address);
-    rpc_server(addr, rpc_sender).await.unwrap();
+    rpc_server(addr, rpc_sender, status_sender, match_subscriptions)
+        .await
+        .unwrap();
+}
+
+/// Report the status of the gossip node from the live state of its swarm and
+/// of its intent mempool.
+pub fn handle_status_request(
+    swarm: &Swarm,
+    intent_gossiper: &IntentGossiper,
+) -> StatusResponse {
+    let connected_peers = swarm.network_info().num_peers() as u64;
+    let topics = swarm.behaviour().topics().collect();
+    StatusResponse {
+        connected_peers,
+        topics,
+        mempool_sizes: intent_gossiper.mempool_sizes(),
+    }
 }
 
 pub async fn handle_rpc_event(
     event: rpc_message::Message,
-    gossip_sub: &mut Gossipsub,
-) -> (RpcResponse, Option<Intent>) {
+    behaviour: &mut Behaviour,
+    intent_gossiper: &IntentGossiper,
+) -> (RpcResponse, Option<(IntentGossipMessage, String)>) {
     match event {
         rpc_message::Message::Intent(message) => {
             match IntentMessage::try_from(message) {
@@ -87,8 +175,8 @@ pub async fn handle_rpc_event(
                         IntentGossipMessage::new(message.intent.clone());
                     let intent_bytes = gossip_message.to_bytes();
 
-                    let gossip_result = match gossip_sub
-                        .publish(IdentTopic::new(message.topic), intent_bytes)
+                    let gossip_result = match behaviour
+                        .publish(IdentTopic::new(&message.topic), intent_bytes)
                     {
                         Ok(message_id) => {
                             format!(
@@ -99,7 +187,7 @@ pub async fn handle_rpc_event(
                         }
                         Err(err) => {
                             format!(
-                                "Failed to publish intent in gossiper: {:?}",
+                                "Failed to publish intent in gossiper: {}",
                                 err
                             )
                         }
@@ -110,18 +198,97 @@ pub async fn handle_rpc_event(
                                 "Intent received. {}.",
                                 gossip_result,
                             ),
+                            ..Default::default()
                         },
-                        Some(message.intent),
+                        Some((
+                            IntentGossipMessage::new(message.intent),
+                            message.topic,
+                        )),
                     )
                 }
                 Err(err) => (
                     RpcResponse {
                         result: format!("Error decoding intent: {:?}", err),
+                        ..Default::default()
+                    },
+                    None,
+                ),
+            }
+        }
+        rpc_message::Message::Cancellation(message) => {
+            match IntentCancellationMessage::try_from(message) {
+                Ok(message) => {
+                    // Send the intent cancellation to gossip
+                    let gossip_message = IntentGossipMessage::new_cancellation(
+                        message.cancellation,
+                    );
+                    let gossip_result = match behaviour.publish(
+                        IdentTopic::new(&message.topic),
+                        gossip_message.to_bytes(),
+                    ) {
+                        Ok(message_id) => {
+                            format!(
+                                "Intent cancellation published in intent \
+                                 gossiper with message ID: {}",
+                                message_id
+                            )
+                        }
+                        Err(err) => {
+                            format!(
+                                "Failed to publish intent cancellation in \
+                                 gossiper: {}",
+                                err
+                            )
+                        }
+                    };
+                    (
+                        RpcResponse {
+                            result: format!(
+                                "Intent cancellation received. {}.",
+                                gossip_result,
+                            ),
+                            ..Default::default()
+                        },
+                        Some((gossip_message, message.topic)),
+                    )
+                }
+                Err(err) => (
+                    RpcResponse {
+                        result: format!(
+                            "Error decoding intent cancellation: {:?}",
+                            err
+                        ),
+                        ..Default::default()
                     },
                     None,
                 ),
             }
         }
+        rpc_message::Message::ListIntents(message) => {
+            let message = ListIntentsMessage::from(message);
+            let limit = match message.limit {
+                0 => MAX_LISTED_INTENTS,
+                limit => limit.min(MAX_LISTED_INTENTS),
+            };
+            let (intent_ids, total) = intent_gossiper.list_intents(
+                &message.topic,
+                message.offset as usize,
+                limit as usize,
+            );
+            (
+                RpcResponse {
+                    result: format!(
+                        "Listed {} of {} intents on topic {} from offset {}",
+                        intent_ids.len(),
+                        total,
+                        message.topic,
+                        message.offset
+                    ),
+                    intent_ids: intent_ids.into_iter().map(|id| id.0).collect(),
+                },
+                None,
+            )
+        }
         rpc_message::Message::Dkg(dkg_msg) => {
             tracing::debug!("dkg not yet implemented {:?}", dkg_msg);
             (
@@ -130,6 +297,7 @@ pub async fn handle_rpc_event(
                         "DKG application not yet
     implemented",
                     ),
+                    ..Default::default()
                 },
                 None,
             )
@@ -138,17 +306,23 @@ pub async fn handle_rpc_event(
             let topic = SubscribeTopicMessage::from(topic_message);
             let topic = IdentTopic::new(&topic.topic);
             (
-                match gossip_sub.subscribe(&topic) {
+                match behaviour.subscribe(&topic) {
                     Ok(true) => {
                         let result = format!("Node subscribed to {}", topic);
                         tracing::info!("{}", result);
-                        RpcResponse { result }
+                        RpcResponse {
+                            result,
+                            ..Default::default()
+                        }
                     }
                     Ok(false) => {
                         let result =
                             format!("Node already subscribed to {}", topic);
                         tracing::info!("{}", result);
-                        RpcResponse { result }
+                        RpcResponse {
+                            result,
+                            ..Default::default()
+                        }
                     }
                     Err(err) => {
                         let result = format!(
@@ -156,7 +330,10 @@ pub async fn handle_rpc_event(
                             topic, err
                         );
                         tracing::error!("{}", result);
-                        RpcResponse { result }
+                        RpcResponse {
+                            result,
+                            ..Default::default()
+                        }
                     }
                 },
                 None,
@@ -164,3 +341,114 @@ pub async fn handle_rpc_event(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anoma::proto::Intent;
+    use libp2p::identity::Keypair;
+
+    use super::*;
+    use crate::config;
+    use crate::node::gossip::intent_gossiper::MatchmakersServer;
+    use crate::node::gossip::p2p::P2P;
+    use crate::proto::{services, RpcMessage};
+
+    /// Test that the intents added on a topic are all listed
+    #[tokio::test]
+    async fn test_list_intents() {
+        let config = config::IntentGossiper {
+            discover_peer: None,
+            ..Default::default()
+        };
+        let (peer_intent_send, _peer_intent_recv) =
+            tokio::sync::mpsc::channel(1);
+        let mut behaviour = Behaviour::new(
+            Keypair::generate_ed25519(),
+            &config,
+            peer_intent_send,
+        )
+        .await
+        .unwrap();
+        let (_server, mut intent_gossiper) =
+            MatchmakersServer::new_pair("127.0.0.1:0", None);
+
+        let topic = "asset_v0".to_owned();
+        let mut ids = Vec::new();
+        for i in 0..3 {
+            let intent = Intent::new(format!("intent {}", i).into_bytes());
+            ids.push(intent.id().0);
+            intent_gossiper
+                .add_intent(intent, topic.clone())
+                .await
+                .unwrap();
+        }
+        // An intent on another topic is not listed
+        let intent = Intent::new(b"auction intent".to_vec());
+        intent_gossiper
+            .add_intent(intent, "auction".to_owned())
+            .await
+            .unwrap();
+
+        let list = |offset, limit| {
+            let message: services::RpcMessage =
+                RpcMessage::new_list_intents(topic.clone(), offset, limit)
+                    .into();
+            message.message.unwrap()
+        };
+        let (response, maybe_message) =
+            handle_rpc_event(list(0, 0), &mut behaviour, &intent_gossiper)
+                .await;
+        assert!(maybe_message.is_none());
+        let mut listed = response.intent_ids;
+        listed.sort();
+        ids.sort();
+        assert_eq!(listed, ids);
+
+        // The listed intents can be paginated
+        let (response, _) =
+            handle_rpc_event(list(1, 1), &mut behaviour, &intent_gossiper)
+                .await;
+        assert_eq!(response.intent_ids, vec![ids[1].clone()]);
+    }
+
+    /// Test that the status reports the subscribed topics and the number of
+    /// intents in the mempool on each topic
+    #[tokio::test]
+    async fn test_status() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let config = config::IntentGossiper {
+            address: "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+            topics: Default::default(),
+            discover_peer: None,
+            ..Default::default()
+        };
+        let (peer_intent_send, _peer_intent_recv) =
+            tokio::sync::mpsc::channel(1);
+        let mut p2p = P2P::new(&config, base_dir.path(), peer_intent_send)
+            .await
+            .unwrap();
+        let (_server, mut intent_gossiper) =
+            MatchmakersServer::new_pair("127.0.0.1:0", None);
+
+        let topic = "asset_v0".to_owned();
+        let message: services::RpcMessage =
+            RpcMessage::new_topic(topic.clone()).into();
+        handle_rpc_event(
+            message.message.unwrap(),
+            p2p.0.behaviour_mut(),
+            &intent_gossiper,
+        )
+        .await;
+        let intent = Intent::new(b"arbitrary data".to_vec());
+        intent_gossiper
+            .add_intent(intent, topic.clone())
+            .await
+            .unwrap();
+
+        let status = handle_status_request(&p2p.0, &intent_gossiper);
+        assert_eq!(status.connected_peers, 0);
+        assert_eq!(status.topics, vec![topic.clone()]);
+        assert_eq!(status.mempool_sizes.len(), 1);
+        assert_eq!(status.mempool_sizes.get(&topic), Some(&1));
+    }
+}
@@ -9,7 +9,10 @@ use std::fmt::Debug;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::sync::atomic::{self, AtomicBool};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use anoma::proto::Signed;
+use anoma::types::matchmaker::MatchNotice;
 use borsh::{BorshDeserialize, BorshSerialize};
 use derivative::Derivative;
 use message_io::network::{Endpoint, ResourceId, ToRemoteAddr, Transport};
@@ -22,6 +25,8 @@ use crate::cli;
 pub enum MsgFromServer {
     /// Try to match an intent
     AddIntent { id: Vec<u8>, data: Vec<u8> },
+    /// Remove a cancelled intent
+    RemoveIntent { id: Vec<u8> },
 }
 
 /// Message from a matchmaker to intent gossiper
@@ -36,6 +41,9 @@ pub enum MsgFromClient {
     /// Intents were matched into a tx. Remove the matched intents from mempool
     /// if the tx gets applied.
     Matched { intent_ids: HashSet<Vec<u8>> },
+    /// Signed notices of matched intents for the intents' submitters. These
+    /// are sent before the [`MsgFromClient::Matched`] of the same intents.
+    MatchNotices { notices: Vec<Signed<MatchNotice>> },
     /// An intent was accepted and added, but no match found yet. Gossip it
     Unmatched { id: Vec<u8> },
 }
@@ -299,7 +307,7 @@ impl ClientListener {
     /// can be used to send messages to the server and to shut down the client.
     pub fn new_pair(server_addr: impl ToRemoteAddr) -> (Self, ClientDialer) {
         let server_addr = server_addr.to_remote_addr().unwrap();
-        // Not using message-io signals
+        // The message-io signals are used for the listener's ticks
         let (handler, listener) = node::split::<()>();
 
         let (server, local_addr) = match handler
@@ -338,10 +346,27 @@ impl ClientListener {
 
     /// Start the client listener and call `on_msg` on every received message.
     /// The listener can be stopped early by [`ClientDialer::shutdown`].
-    pub fn listen(mut self, mut on_msg: impl FnMut(MsgFromServer)) {
+    pub fn listen(self, on_msg: impl FnMut(MsgFromServer)) {
+        self.listen_with_ticks(None, on_msg, || {})
+    }
+
+    /// Start the client listener and call `on_msg` on every received message
+    /// and `on_tick` on every tick of the given interval, if any. The listener
+    /// can be stopped early by [`ClientDialer::shutdown`].
+    pub fn listen_with_ticks(
+        mut self,
+        tick_interval: Option<Duration>,
+        mut on_msg: impl FnMut(MsgFromServer),
+        mut on_tick: impl FnMut(),
+    ) {
         // This is safe because `listen` consumes `self`
         let listener = self.listener.take().unwrap();
 
+        // Schedule the first tick
+        if let Some(interval) = tick_interval {
+            self.handler.signals().send_with_timer((), interval);
+        }
+
         // Start the blocking listener that will call `on_msg` on every message
         let server_addr = self.server.addr();
         let local_addr_port = self.local_addr.port();
@@ -407,7 +432,11 @@ impl ClientListener {
                     }
                 },
                 node::NodeEvent::Signal(()) => {
-                    // unused
+                    on_tick();
+                    // Schedule the next tick
+                    if let Some(interval) = tick_interval {
+                        self.handler.signals().send_with_timer((), interval);
+                    }
                 }
             }
         });
@@ -436,7 +465,7 @@ impl ClientDialer {
     }
 
     /// Force shut-down the [`ClientListener`] associated with this dialer.
-    pub fn shutdown(&mut self) {
+    pub fn shutdown(&self) {
         self.handler.stop();
     }
 }
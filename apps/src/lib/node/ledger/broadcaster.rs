@@ -1,9 +1,20 @@
+use std::future::Future;
+use std::time::Duration;
+
 #[cfg(not(feature = "ABCI"))]
 use tendermint_rpc::{Client, HttpClient};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::{Client, HttpClient};
 use tokio::sync::mpsc::UnboundedReceiver;
 
+/// The maximum number of attempts to broadcast a tx before giving up on it
+const MAX_BROADCAST_ATTEMPTS: u32 = 10;
+/// The time to wait before the first retry of a failed broadcast. It's doubled
+/// after every failed retry, up to [`MAX_BROADCAST_BACKOFF`].
+const INITIAL_BROADCAST_BACKOFF: Duration = Duration::from_millis(100);
+/// The maximum time to wait before a retry of a failed broadcast
+const MAX_BROADCAST_BACKOFF: Duration = Duration::from_secs(10);
+
 /// A service for broadcasting txs via an HTTP client.
 /// The receiver is for receiving message payloads for other services
 /// to be broadcast.
@@ -24,11 +35,38 @@ impl Broadcaster {
     }
 
     /// Loop forever, braodcasting messages that have been received
-    /// by the receiver
+    /// by the receiver. A message that couldn't be broadcast after the maximum
+    /// number of attempts is dropped. Returns only when all the senders have
+    /// been dropped.
     async fn run_loop(&mut self) {
-        loop {
-            if let Some(msg) = self.receiver.recv().await {
-                let _ = self.client.broadcast_tx_sync(msg.into()).await;
+        while let Some(msg) = self.receiver.recv().await {
+            let client = &self.client;
+            let result = broadcast_with_retry(
+                || client.broadcast_tx_sync(msg.clone().into()),
+                MAX_BROADCAST_ATTEMPTS,
+                INITIAL_BROADCAST_BACKOFF,
+                MAX_BROADCAST_BACKOFF,
+            )
+            .await;
+            match result {
+                Ok(response) => {
+                    // The tx has been delivered, but it may have been
+                    // rejected, in which case retrying wouldn't help
+                    if response.code.is_err() {
+                        tracing::error!(
+                            "Broadcast protocol tx was rejected: {}",
+                            response.log
+                        );
+                    }
+                }
+                Err(err) => {
+                    tracing::error!(
+                        "Dropping a protocol tx that failed to be broadcast \
+                         after {} attempts: {}",
+                        MAX_BROADCAST_ATTEMPTS,
+                        err
+                    );
+                }
             }
         }
     }
@@ -59,3 +97,93 @@ impl Broadcaster {
         }
     }
 }
+
+/// Try to broadcast with the given function until it succeeds or until the
+/// maximum number of attempts is reached. The errors returned by the function
+/// are transient failures to deliver the tx (e.g. connection refused or
+/// timeout), so the broadcast is retried with an exponential backoff, capped at
+/// the given maximum backoff.
+async fn broadcast_with_retry<F, Fut, T, E>(
+    mut broadcast: F,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let mut backoff = initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match broadcast().await {
+            Ok(response) => return Ok(response),
+            Err(err) if attempt < max_attempts => {
+                tracing::warn!(
+                    "Broadcast attempt {}/{} failed, retrying in {:?}: {}",
+                    attempt,
+                    max_attempts,
+                    backoff,
+                    err
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Test that a broadcast that fails transiently twice is retried and
+    /// succeeds on the third attempt
+    #[tokio::test]
+    async fn test_broadcast_retry() {
+        let attempts = Cell::new(0);
+        let result = broadcast_with_retry(
+            || {
+                attempts.set(attempts.get() + 1);
+                let attempt = attempts.get();
+                async move {
+                    if attempt <= 2 {
+                        Err("connection refused")
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            },
+            MAX_BROADCAST_ATTEMPTS,
+            Duration::from_millis(1),
+            Duration::from_millis(2),
+        )
+        .await;
+        assert_eq!(result, Ok(3));
+        assert_eq!(attempts.get(), 3);
+    }
+
+    /// Test that a broadcast that keeps failing is given up after the
+    /// maximum number of attempts
+    #[tokio::test]
+    async fn test_broadcast_retry_gives_up() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = broadcast_with_retry(
+            || {
+                attempts.set(attempts.get() + 1);
+                async { Err("connection refused") }
+            },
+            MAX_BROADCAST_ATTEMPTS,
+            Duration::from_millis(1),
+            Duration::from_millis(2),
+        )
+        .await;
+        assert_eq!(result, Err("connection refused"));
+        assert_eq!(attempts.get(), MAX_BROADCAST_ATTEMPTS);
+    }
+}
@@ -3,6 +3,7 @@ use std::fmt::{self, Display};
 use std::ops::{Index, IndexMut};
 
 use anoma::types::ibc::IbcEvent;
+use anoma::types::storage::{BlockHeight, Epoch};
 use anoma::types::transaction::{hash_tx, TxType};
 use borsh::BorshSerialize;
 #[cfg(not(feature = "ABCI"))]
@@ -27,6 +28,8 @@ pub enum EventType {
     Applied,
     // The IBC transaction was applied during block finalization
     Ibc(String),
+    // A new epoch began with the finalized block
+    NewEpoch,
 }
 
 #[cfg(not(feature = "ABCI"))]
@@ -36,6 +39,7 @@ impl Display for EventType {
             EventType::Accepted => write!(f, "accepted"),
             EventType::Applied => write!(f, "applied"),
             EventType::Ibc(t) => write!(f, "{}", t),
+            EventType::NewEpoch => write!(f, "new_epoch"),
         }?;
         Ok(())
     }
@@ -48,6 +52,7 @@ impl Display for EventType {
             EventType::Accepted => write!(f, "applied"),
             EventType::Applied => write!(f, "applied"),
             EventType::Ibc(t) => write!(f, "{}", t),
+            EventType::NewEpoch => write!(f, "new_epoch"),
         }?;
         Ok(())
     }
@@ -102,6 +107,18 @@ impl Event {
         event
     }
 
+    /// Creates a new event marking the beginning of the given epoch at the
+    /// given block height
+    pub fn new_epoch_event(epoch: Epoch, height: BlockHeight) -> Self {
+        let mut event = Event {
+            event_type: EventType::NewEpoch,
+            attributes: HashMap::new(),
+        };
+        event["epoch"] = epoch.to_string();
+        event["height"] = height.to_string();
+        event
+    }
+
     pub fn contains_key(&self, key: &str) -> bool {
         self.attributes.contains_key(key)
     }
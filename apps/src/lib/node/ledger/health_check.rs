@@ -0,0 +1,189 @@
+//! A health check of the Tendermint node's RPC. If the ABCI connection to
+//! Tendermint degrades without either of the processes terminating, the ledger
+//! would otherwise silently stop processing blocks.
+
+use std::time::{Duration, Instant};
+
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+
+/// A service that periodically pings the Tendermint RPC over an HTTP client.
+/// It stops when the RPC fails to respond to the given number of consecutive
+/// checks. Failures are not counted while Tendermint is starting up, i.e.
+/// until the RPC responds for the first time or until the startup grace period
+/// elapses, whichever comes first.
+pub struct HealthCheck {
+    client: HttpClient,
+    interval: Duration,
+    failure_threshold: u64,
+    startup_grace: Duration,
+}
+
+impl HealthCheck {
+    /// Create a new health check of the Tendermint RPC at the given url.
+    pub fn new(
+        url: &str,
+        interval: Duration,
+        failure_threshold: u64,
+        startup_grace: Duration,
+    ) -> Self {
+        Self {
+            client: HttpClient::new(format!("http://{}", url).as_str())
+                .unwrap(),
+            interval,
+            failure_threshold,
+            startup_grace,
+        }
+    }
+
+    /// Loop until the number of consecutive failed checks reaches the
+    /// failure threshold. A check that doesn't receive a response within the
+    /// check interval is failed.
+    async fn run_loop(&self) {
+        let start = Instant::now();
+        let mut started = false;
+        let mut failures: u64 = 0;
+        loop {
+            tokio::time::sleep(self.interval).await;
+            let result =
+                tokio::time::timeout(self.interval, self.client.health()).await;
+            started |= matches!(result, Ok(Ok(())));
+            if !started && start.elapsed() < self.startup_grace {
+                tracing::debug!("Tendermint RPC is not responding yet");
+                continue;
+            }
+            match result {
+                Ok(Ok(())) => {
+                    failures = 0;
+                }
+                Ok(Err(err)) => {
+                    failures += 1;
+                    tracing::warn!(
+                        "Tendermint RPC health check failed ({}/{}): {}",
+                        failures,
+                        self.failure_threshold,
+                        err
+                    );
+                }
+                Err(_) => {
+                    failures += 1;
+                    tracing::warn!(
+                        "Tendermint RPC health check timed out ({}/{})",
+                        failures,
+                        self.failure_threshold,
+                    );
+                }
+            }
+            if failures >= self.failure_threshold {
+                return;
+            }
+        }
+    }
+
+    /// Loop until an abort signal is received or until the Tendermint RPC
+    /// fails the health check too many times in a row.
+    pub async fn run(&self, abort_recv: tokio::sync::oneshot::Receiver<()>) {
+        tracing::info!("Starting Tendermint health check.");
+        tokio::select! {
+            _ = self.run_loop() => {
+                tracing::error!(
+                    "Tendermint RPC failed to respond to {} consecutive \
+                     health checks.",
+                    self.failure_threshold
+                );
+                tracing::info!("Shutting down Tendermint health check...");
+            },
+            resp_sender = abort_recv => {
+                match resp_sender {
+                    Ok(_) => {
+                        tracing::info!("Shutting down Tendermint health check...");
+                    },
+                    Err(err) => {
+                        tracing::error!("The Tendermint health check abort sender has unexpectedly dropped: {}", err);
+                        tracing::info!("Shutting down Tendermint health check...");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::node::ledger::Aborter;
+
+    /// Test that when the Tendermint RPC doesn't respond, the health check
+    /// sends the abort signal once the startup grace period has elapsed and
+    /// the failure threshold is reached.
+    #[tokio::test]
+    async fn test_unresponsive_rpc_aborts() {
+        // A listener that accepts connections, but never responds
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        let interval = Duration::from_millis(50);
+        let failure_threshold = 3;
+        let startup_grace = Duration::from_millis(300);
+
+        let (abort_send, mut abort_recv) =
+            tokio::sync::mpsc::unbounded_channel::<&'static str>();
+        let (_hc_abort_send, hc_abort_recv) =
+            tokio::sync::oneshot::channel::<()>();
+        let health_check =
+            HealthCheck::new(&url, interval, failure_threshold, startup_grace);
+        let start = Instant::now();
+        tokio::spawn(async move {
+            let aborter = Aborter {
+                sender: abort_send,
+                who: "Tendermint health check",
+            };
+            health_check.run(hc_abort_recv).await;
+            drop(aborter);
+        });
+
+        let who =
+            tokio::time::timeout(Duration::from_secs(10), abort_recv.recv())
+                .await
+                .expect("The abort signal should be sent")
+                .unwrap();
+        assert_eq!(who, "Tendermint health check");
+        // The checks in the grace period are not counted and every failed
+        // check after it waits for the interval and then times out
+        assert!(
+            start.elapsed()
+                >= startup_grace
+                    + interval * 2 * (failure_threshold - 1) as u32
+        );
+        drop(listener);
+    }
+
+    /// Test that the health check doesn't abort while Tendermint may still be
+    /// starting up.
+    #[tokio::test]
+    async fn test_startup_grace_period() {
+        // A listener that accepts connections, but never responds
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = listener.local_addr().unwrap().to_string();
+        let interval = Duration::from_millis(50);
+        let failure_threshold = 1;
+        let startup_grace = Duration::from_secs(60);
+
+        let (_hc_abort_send, hc_abort_recv) =
+            tokio::sync::oneshot::channel::<()>();
+        let health_check =
+            HealthCheck::new(&url, interval, failure_threshold, startup_grace);
+        let run = health_check.run(hc_abort_recv);
+
+        // Many more checks than the failure threshold fail in this time
+        let result = tokio::time::timeout(interval * 20, run).await;
+        assert!(
+            result.is_err(),
+            "The health check must not stop in the startup grace period"
+        );
+        drop(listener);
+    }
+}
@@ -1,5 +1,6 @@
 mod broadcaster;
 pub mod events;
+mod health_check;
 pub mod protocol;
 pub mod rpc;
 mod shell;
@@ -9,8 +10,10 @@ pub mod tendermint_node;
 
 use std::convert::TryInto;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use anoma::ledger::storage::mockdb::MockDB;
+use anoma::ledger::storage::{DBIter, MerkleRoot, DB};
 use byte_unit::Byte;
 use futures::future::TryFutureExt;
 use once_cell::unsync::Lazy;
@@ -27,8 +30,9 @@ use tower_abci_old::{response, split, Server};
 
 use self::shims::abcipp_shim::AbciService;
 use crate::config::utils::num_of_threads;
-use crate::config::TendermintMode;
+use crate::config::{DbType, TendermintMode};
 use crate::node::ledger::broadcaster::Broadcaster;
+use crate::node::ledger::health_check::HealthCheck;
 use crate::node::ledger::shell::{Error, MempoolTxType, Shell};
 use crate::node::ledger::shims::abcipp_shim::AbcippShim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::{Request, Response};
@@ -57,7 +61,10 @@ const ENV_VAR_RAYON_THREADS: &str = "ANOMA_RAYON_THREADS";
 //     }
 //```
 
-impl Shell {
+impl<D> Shell<D>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+{
     fn call(&mut self, req: Request) -> Result<Response, Error> {
         match req {
             Request::InitChain(init) => {
@@ -172,6 +179,32 @@ pub fn reset(config: config::Ledger) -> Result<(), shell::Error> {
     shell::reset(config)
 }
 
+/// Compacts the database files of a ledger node that is not running. Returns
+/// the size of the database in bytes before and after the compaction.
+pub fn compact(config: config::Ledger) -> Result<(u64, u64), shell::Error> {
+    shell::compact(config)
+}
+
+/// Dumps the last committed state of a ledger node that is not running to a
+/// JSON file. Returns the number of the dumped storage keys.
+pub fn dump_state(
+    config: config::Ledger,
+    output: &Path,
+) -> Result<u64, shell::Error> {
+    shell::dump_state(config, output)
+}
+
+/// Imports a JSON state dump into the storage of a ledger node that is not
+/// running. Returns the Merkle root of the imported state and the number of
+/// the imported storage keys.
+pub fn import_state(
+    config: config::Ledger,
+    input: &Path,
+    force: bool,
+) -> Result<(MerkleRoot, u64), shell::Error> {
+    shell::import_state(config, input, force)
+}
+
 /// Runs three concurrent tasks: A tendermint node, a shell which contains an
 /// ABCI, server for talking to the tendermint node, and a broadcaster so that
 /// the ledger may submit txs to the chain. All must be alive for correct
@@ -239,26 +272,38 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
     );
 
     // Setup DB cache, it must outlive the DB instance that's in the shell
-    let block_cache_size_bytes = match config.shell.block_cache_bytes {
-        Some(block_cache_bytes) => {
-            tracing::info!("Block cache set from the configuration.",);
-            block_cache_bytes
+    let db_cache = match config.shell.db_type {
+        DbType::RocksDb => {
+            let block_cache_size_bytes = match config.shell.block_cache_bytes {
+                Some(block_cache_bytes) => {
+                    tracing::info!("Block cache set from the configuration.",);
+                    block_cache_bytes
+                }
+                None => {
+                    tracing::info!(
+                        "Block cache size not configured, using 1/3 of \
+                         available memory."
+                    );
+                    *available_memory_bytes / 3
+                }
+            };
+            tracing::info!(
+                "RocksDB block cache size: {}",
+                Byte::from_bytes(block_cache_size_bytes as u128)
+                    .get_appropriate_unit(true)
+            );
+            Some(
+                rocksdb::Cache::new_lru_cache(block_cache_size_bytes as usize)
+                    .unwrap(),
+            )
         }
-        None => {
+        DbType::InMemory => {
             tracing::info!(
-                "Block cache size not configured, using 1/3 of available \
-                 memory."
+                "Using an in-memory DB, the state will be lost on shut down."
             );
-            *available_memory_bytes / 3
+            None
         }
     };
-    tracing::info!(
-        "RocksDB block cache size: {}",
-        Byte::from_bytes(block_cache_size_bytes as u128)
-            .get_appropriate_unit(true)
-    );
-    let db_cache =
-        rocksdb::Cache::new_lru_cache(block_cache_size_bytes as usize).unwrap();
 
     let tendermint_dir = config.tendermint_dir();
     let ledger_address = config.shell.ledger_address.to_string();
@@ -270,6 +315,11 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         .try_into()
         .expect("expected RFC3339 genesis_time");
     let tendermint_config = config.tendermint.clone();
+    let health_check_interval = *config.tendermint.health_check_interval;
+    let health_check_failure_threshold =
+        config.tendermint.health_check_failure_threshold;
+    let health_check_startup_grace =
+        *config.tendermint.health_check_startup_grace;
 
     // Channel for signalling shut down from the shell or from Tendermint
     let (abort_send, abort_recv) =
@@ -308,6 +358,28 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         res
     });
 
+    // Start the health check of the Tendermint RPC
+    let (hc_abort_send, hc_abort_recv) = tokio::sync::oneshot::channel::<()>();
+    let abort_send_for_health_check = abort_send.clone();
+    let health_check_rpc_address = rpc_address.clone();
+    let health_check = tokio::spawn(async move {
+        let health_check = HealthCheck::new(
+            &health_check_rpc_address,
+            health_check_interval,
+            health_check_failure_threshold,
+            health_check_startup_grace,
+        );
+        // On panic or exit, the `Drop` of `AbortSender` will send abort message
+        let aborter = Aborter {
+            sender: abort_send_for_health_check,
+            who: "Tendermint health check",
+        };
+        health_check.run(hc_abort_recv).await;
+        tracing::info!("Tendermint health check is no longer running.");
+
+        drop(aborter);
+    });
+
     let broadcaster = if matches!(
         config.tendermint.tendermint_mode,
         TendermintMode::Validator
@@ -340,16 +412,26 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         None
     };
 
-    // Construct our ABCI application.
+    // Construct our ABCI application and run the shell in its own thread
     let ledger_address = config.shell.ledger_address;
-    let (shell, abci_service) = AbcippShim::new(
-        config,
-        wasm_dir,
-        broadcaster_sender,
-        &db_cache,
-        vp_wasm_compilation_cache,
-        tx_wasm_compilation_cache,
-    );
+    let (abci_service, shell_handler) = match config.shell.db_type {
+        DbType::RocksDb => start_shell::<storage::PersistentDB>(
+            config,
+            wasm_dir,
+            broadcaster_sender,
+            db_cache.as_ref(),
+            vp_wasm_compilation_cache,
+            tx_wasm_compilation_cache,
+        ),
+        DbType::InMemory => start_shell::<MockDB>(
+            config,
+            wasm_dir,
+            broadcaster_sender,
+            None,
+            vp_wasm_compilation_cache,
+            tx_wasm_compilation_cache,
+        ),
+    };
 
     // Start the ABCI server
     let abci = tokio::spawn(async move {
@@ -366,16 +448,6 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         res
     });
 
-    // Run the shell in the main thread
-    let thread_builder =
-        std::thread::Builder::new().name("ledger-shell".into());
-    let shell_handler = thread_builder
-        .spawn(move || {
-            tracing::info!("Anoma ledger node started.");
-            shell.run()
-        })
-        .expect("Must be able to start a thread for the shell");
-
     // Wait for interrupt signal or abort message
     let aborted = wait_for_abort(abort_recv).await;
 
@@ -400,6 +472,15 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         }
     }
 
+    // Request the health check shutdown. Ignore error, which can happen if
+    // the health check has already finished.
+    let _ = hc_abort_send.send(());
+    if let Err(err) = health_check.await {
+        if !err.is_cancelled() {
+            tracing::error!("Tendermint health check error: {}", err);
+        }
+    }
+
     let res = match broadcaster {
         Some((broadcaster, bc_abort_send)) => {
             // request the broadcaster shutdown
@@ -440,6 +521,40 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
     }
 }
 
+/// Construct the shell with the given DB backend and run it in a new thread.
+/// Returns the ABCI service that passes messages to and from the shell and
+/// the handle of the shell's thread.
+fn start_shell<D>(
+    config: config::Ledger,
+    wasm_dir: PathBuf,
+    broadcaster_sender: tokio::sync::mpsc::UnboundedSender<Vec<u8>>,
+    db_cache: Option<&D::Cache>,
+    vp_wasm_compilation_cache: u64,
+    tx_wasm_compilation_cache: u64,
+) -> (AbciService, std::thread::JoinHandle<()>)
+where
+    D: DB + for<'iter> DBIter<'iter> + Send + Sync + 'static,
+{
+    let (shell, abci_service) = AbcippShim::<D>::new(
+        config,
+        wasm_dir,
+        broadcaster_sender,
+        db_cache,
+        vp_wasm_compilation_cache,
+        tx_wasm_compilation_cache,
+    );
+
+    let thread_builder =
+        std::thread::Builder::new().name("ledger-shell".into());
+    let shell_handler = thread_builder
+        .spawn(move || {
+            tracing::info!("Anoma ledger node started.");
+            shell.run()
+        })
+        .expect("Must be able to start a thread for the shell");
+    (abci_service, shell_handler)
+}
+
 /// Runs the an asynchronous ABCI server with four sub-components for consensus,
 /// mempool, snapshot, and info.
 async fn run_abci(
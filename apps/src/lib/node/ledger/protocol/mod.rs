@@ -7,15 +7,20 @@ use anoma::ledger::ibc::vp::{Ibc, IbcToken};
 use anoma::ledger::native_vp::{self, NativeVp};
 use anoma::ledger::parameters::{self, ParametersVp};
 use anoma::ledger::pos::{self, PosVP};
-use anoma::ledger::storage::write_log::WriteLog;
+use anoma::ledger::storage::write_log::{self, StorageModification, WriteLog};
 use anoma::ledger::storage::{DBIter, Storage, StorageHasher, DB};
 use anoma::proto::{self, Tx};
 use anoma::types::address::{Address, InternalAddress};
 use anoma::types::storage::Key;
-use anoma::types::transaction::{DecryptedTx, TxResult, TxType, VpsResult};
+use anoma::types::token::{self, Amount};
+use anoma::types::transaction::{
+    DecryptedTx, TxAbort, TxResult, TxType, VpsResult, WasmTimings, WrapperTx,
+};
 use anoma::vm::wasm::{TxCache, VpCache};
 use anoma::vm::{self, wasm, WasmCacheAccess};
+use borsh::{BorshDeserialize, BorshSerialize};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::ThreadPool;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -46,17 +51,51 @@ pub enum Error {
     IbcTokenNativeVpError(anoma::ledger::ibc::vp::IbcTokenError),
     #[error("Access to an internal address {0} is forbidden")]
     AccessForbidden(InternalAddress),
+    #[error(
+        "The fee payer {payer} has insufficient balance of token {token} to \
+         pay the fee {fee}, available: {balance}"
+    )]
+    InsufficientBalanceForFee {
+        payer: Address,
+        token: Address,
+        fee: Amount,
+        balance: Amount,
+    },
+    #[error("Error decoding the balance of the fee payer: {0}")]
+    FeeBalanceDecodingError(std::io::Error),
+    #[error("The balance of the fee payer at {0} is not a valid amount")]
+    InvalidFeeBalance(Key),
+    #[error("Error writing the balance of the fee payer: {0}")]
+    FeeWriteError(write_log::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The environment variable that, when set to `true`, includes the time spent
+/// on compiling and executing the wasm code in the results of the applied txs
+pub const ENV_VAR_WASM_TIMINGS: &str = "ANOMA_WASM_TIMINGS";
+
+/// Check if the wasm timings should be included in the txs results
+fn wasm_timings_enabled() -> bool {
+    std::env::var(ENV_VAR_WASM_TIMINGS)
+        .map(|var| var.eq_ignore_ascii_case("true"))
+        .unwrap_or_default()
+}
+
 /// Apply a given transaction
 ///
 /// The only Tx Types that should be input here are `Decrypted` and `Wrapper`
 ///
 /// If the given tx is a successfully decrypted payload apply the necessary
-/// vps. Otherwise, we include the tx on chain with the gas charge added
-/// but no further validations.
+/// vps, unless the tx aborts itself, in which case its error code and message
+/// are returned in the result. If it is a wrapper tx, its fee is deducted from
+/// the balance of the fee payer, failing if the payer cannot cover it.
+/// Otherwise, we include the tx on chain with the gas charge added but no
+/// further validations.
+///
+/// The VPs are validated concurrently on the given thread pool, if any, or
+/// otherwise on the global Rayon thread pool.
+#[allow(clippy::too_many_arguments)]
 pub fn apply_tx<D, H, CA>(
     tx: TxType,
     tx_length: usize,
@@ -65,6 +104,7 @@ pub fn apply_tx<D, H, CA>(
     storage: &Storage<D, H>,
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
+    vp_thread_pool: Option<&ThreadPool>,
 ) -> Result<TxResult>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -78,14 +118,32 @@ where
     match tx {
         TxType::Raw(_) => Err(Error::TxTypeError),
         TxType::Decrypted(DecryptedTx::Decrypted(tx)) => {
-            let verifiers = execute_tx(
+            let mut tx_timings = WasmTimings::default();
+            let verifiers = match execute_tx(
                 &tx,
                 storage,
                 block_gas_meter,
                 write_log,
                 vp_wasm_cache,
                 tx_wasm_cache,
-            )?;
+                &mut tx_timings,
+            ) {
+                Ok(verifiers) => verifiers,
+                Err(Error::TxRunnerError(wasm::run::Error::TxAborted {
+                    code,
+                    message,
+                })) => {
+                    let gas_used = block_gas_meter
+                        .finalize_transaction()
+                        .map_err(Error::GasError)?;
+                    return Ok(TxResult {
+                        gas_used,
+                        abort: Some(TxAbort { code, message }),
+                        ..Default::default()
+                    });
+                }
+                Err(err) => return Err(err),
+            };
 
             let vps_result = check_vps(
                 &tx,
@@ -94,6 +152,7 @@ where
                 write_log,
                 &verifiers,
                 vp_wasm_cache,
+                vp_thread_pool,
             )?;
 
             let gas_used = block_gas_meter
@@ -102,6 +161,12 @@ where
             let initialized_accounts = write_log.get_initialized_accounts();
             let changed_keys = write_log.get_keys();
             let ibc_event = write_log.take_ibc_event();
+            let wasm_timings = if wasm_timings_enabled() {
+                tx_timings += vps_result.wasm_timings;
+                Some(tx_timings)
+            } else {
+                None
+            };
 
             Ok(TxResult {
                 gas_used,
@@ -109,6 +174,21 @@ where
                 vps_result,
                 initialized_accounts,
                 ibc_event,
+                wasm_timings,
+                abort: None,
+            })
+        }
+        TxType::Wrapper(wrapper) => {
+            charge_fee(&wrapper, storage, write_log)?;
+
+            let gas_used = block_gas_meter
+                .finalize_transaction()
+                .map_err(Error::GasError)?;
+            let changed_keys = write_log.get_keys();
+            Ok(TxResult {
+                gas_used,
+                changed_keys,
+                ..Default::default()
             })
         }
         _ => {
@@ -123,7 +203,84 @@ where
     }
 }
 
+/// Deduct the fee of a wrapper tx from the balance of its fee payer in the
+/// write log. The fee is charged independently of the gas used by the tx and
+/// it is burnt, as it is not yet distributed to anyone.
+fn charge_fee<D, H>(
+    wrapper: &WrapperTx,
+    storage: &Storage<D, H>,
+    write_log: &mut WriteLog,
+) -> Result<()>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let balance = check_fee_balance(wrapper, storage, write_log)?;
+    // A zero fee leaves the balance untouched
+    if wrapper.fee.amount == Amount::default() {
+        return Ok(());
+    }
+    let balance_key =
+        token::balance_key(&wrapper.fee.token, &wrapper.fee_payer());
+    let new_balance = balance - wrapper.fee.amount;
+    write_log
+        .write(
+            &balance_key,
+            new_balance
+                .try_to_vec()
+                .expect("Serialization of an amount shouldn't fail"),
+        )
+        .map_err(Error::FeeWriteError)?;
+    Ok(())
+}
+
+/// Check that the fee payer of a wrapper tx can pay its fee with its balance
+/// in the write log or, if not modified, in the storage. Returns the payer's
+/// balance.
+pub fn check_fee_balance<D, H>(
+    wrapper: &WrapperTx,
+    storage: &Storage<D, H>,
+    write_log: &WriteLog,
+) -> Result<Amount>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    let payer = wrapper.fee_payer();
+    let token = &wrapper.fee.token;
+    let balance_key = token::balance_key(token, &payer);
+    let balance_bytes = match write_log.read(&balance_key).0 {
+        Some(StorageModification::Write { value }) => Some(value.clone()),
+        Some(StorageModification::Delete) => None,
+        // An earlier tx in the block may have written a temporary value or
+        // initialized an account under the balance key, which cannot pay
+        // the fee
+        Some(
+            StorageModification::InitAccount { .. }
+            | StorageModification::Temp { .. },
+        ) => {
+            return Err(Error::InvalidFeeBalance(balance_key));
+        }
+        None => storage.read(&balance_key).map_err(Error::StorageError)?.0,
+    };
+    let balance = match balance_bytes {
+        Some(bytes) => Amount::try_from_slice(&bytes[..])
+            .map_err(Error::FeeBalanceDecodingError)?,
+        None => Amount::default(),
+    };
+    if balance < wrapper.fee.amount {
+        return Err(Error::InsufficientBalanceForFee {
+            payer,
+            token: token.clone(),
+            fee: wrapper.fee.amount,
+            balance,
+        });
+    }
+    Ok(balance)
+}
+
 /// Execute a transaction code. Returns verifiers requested by the transaction.
+/// The time spent on the tx's wasm is added to the given timings.
 fn execute_tx<D, H, CA>(
     tx: &Tx,
     storage: &Storage<D, H>,
@@ -131,6 +288,7 @@ fn execute_tx<D, H, CA>(
     write_log: &mut WriteLog,
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
+    timings: &mut WasmTimings,
 ) -> Result<BTreeSet<Address>>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -142,7 +300,7 @@ where
         .map_err(Error::GasError)?;
     let empty = vec![];
     let tx_data = tx.data.as_ref().unwrap_or(&empty);
-    wasm::run::tx(
+    wasm::run::timed_tx(
         storage,
         write_log,
         gas_meter,
@@ -150,6 +308,7 @@ where
         tx_data,
         vp_wasm_cache,
         tx_wasm_cache,
+        timings,
     )
     .map_err(Error::TxRunnerError)
 }
@@ -168,6 +327,7 @@ fn check_vps<D, H, CA>(
     write_log: &WriteLog,
     verifiers_from_tx: &BTreeSet<Address>,
     vp_wasm_cache: &mut VpCache<CA>,
+    vp_thread_pool: Option<&ThreadPool>,
 ) -> Result<VpsResult>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -205,14 +365,26 @@ where
 
     let initial_gas = gas_meter.get_current_transaction_gas();
 
-    let vps_result = execute_vps(
-        verifiers,
-        tx,
-        storage,
-        write_log,
-        initial_gas,
-        vp_wasm_cache,
-    )?;
+    let vps_result = match vp_thread_pool {
+        Some(pool) => pool.install(|| {
+            execute_vps(
+                verifiers,
+                tx,
+                storage,
+                write_log,
+                initial_gas,
+                vp_wasm_cache,
+            )
+        }),
+        None => execute_vps(
+            verifiers,
+            tx,
+            storage,
+            write_log,
+            initial_gas,
+            vp_wasm_cache,
+        ),
+    }?;
     tracing::debug!("Total VPs gas cost {:?}", vps_result.gas_used);
 
     gas_meter
@@ -222,7 +394,10 @@ where
     Ok(vps_result)
 }
 
-/// Execute verifiers' validity predicates
+/// Execute verifiers' validity predicates in parallel on the current Rayon
+/// thread pool. Every VP run gets its own result, so that only the maximum
+/// used gas is changed when the results are merged, regardless of how the
+/// runs are split between the threads.
 fn execute_vps<D, H, CA>(
     verifiers: Vec<(Address, BTreeSet<Key>, Vp)>,
     tx: &Tx,
@@ -244,10 +419,11 @@ where
 
     verifiers
         .par_iter()
-        .try_fold(VpsResult::default, |mut result, (addr, keys, vp)| {
+        .map(|(addr, keys, vp)| {
+            let mut result = VpsResult::default();
             let mut gas_meter = VpGasMeter::new(initial_gas);
             let accept = match &vp {
-                Vp::Wasm(vp) => wasm::run::vp(
+                Vp::Wasm(vp) => wasm::run::timed_vp(
                     vp,
                     tx,
                     addr,
@@ -257,6 +433,7 @@ where
                     keys,
                     &verifiers_addr,
                     vp_wasm_cache.clone(),
+                    &mut result.wasm_timings,
                 )
                 .map_err(Error::VpRunnerError),
                 Vp::Native(internal_addr) => {
@@ -384,6 +561,8 @@ fn merge_vp_results(
     let mut errors = a.errors;
     errors.append(&mut b.errors);
     let mut gas_used = a.gas_used;
+    let mut wasm_timings = a.wasm_timings;
+    wasm_timings += b.wasm_timings;
 
     // Returning error from here will short-circuit the VP parallel execution.
     // It's important that we only short-circuit gas errors to get deterministic
@@ -398,5 +577,154 @@ fn merge_vp_results(
         rejected_vps,
         gas_used,
         errors,
+        wasm_timings,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use anoma::ledger::storage::testing::TestStorage;
+    use anoma::types::address;
+    use anoma::types::key::testing::keypair_1;
+    use anoma::types::storage::Epoch;
+    use anoma::types::transaction::Fee;
+    use anoma::vm::wasm::compilation_cache::common::testing::cache;
+
+    use super::*;
+
+    const VP_ALWAYS_TRUE: &str = "../wasm_for_tests/vp_always_true.wasm";
+    const VP_ALWAYS_FALSE: &str = "../wasm_for_tests/vp_always_false.wasm";
+
+    /// Test that a temporary value that an earlier tx in the block wrote
+    /// under the fee payer's balance key is rejected as the fee balance
+    #[test]
+    fn test_fee_balance_temp_value_in_block() {
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: 1.into(),
+                token: address::xan(),
+            },
+            &keypair_1(),
+            Epoch(0),
+            0.into(),
+            Tx::new(vec![], None),
+            Default::default(),
+        );
+        let balance_key =
+            token::balance_key(&wrapper.fee.token, &wrapper.fee_payer());
+        write_log
+            .write_temp(&balance_key, Amount::whole(100).try_to_vec().unwrap())
+            .unwrap();
+        write_log.commit_tx();
+
+        let result = check_fee_balance(&wrapper, &storage, &write_log);
+        match result {
+            Err(Error::InvalidFeeBalance(key)) => assert_eq!(key, balance_key),
+            _ => panic!("Unexpected fee balance check result {:?}", result),
+        }
+        let result = charge_fee(&wrapper, &storage, &mut write_log);
+        assert!(matches!(result, Err(Error::InvalidFeeBalance(_))));
+    }
+
+    /// Test that the results of the VPs of a transaction touching multiple
+    /// accounts are the same when they're validated sequentially and in
+    /// parallel
+    #[test]
+    fn test_vp_parallelism() {
+        let vp_always_true = std::fs::read(VP_ALWAYS_TRUE).unwrap();
+        let vp_always_false = std::fs::read(VP_ALWAYS_FALSE).unwrap();
+        let mut storage = TestStorage::default();
+        let mut verifiers = BTreeSet::new();
+        for i in 0..8 {
+            let addr = address::testing::gen_established_address();
+            let vp = if i % 3 == 0 {
+                &vp_always_false
+            } else {
+                &vp_always_true
+            };
+            storage.write(&Key::validity_predicate(&addr), vp).unwrap();
+            verifiers.insert(addr);
+        }
+        let write_log = WriteLog::default();
+        let tx = Tx::new(vec![], None);
+
+        let check_vps_with_threads = |num_threads| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            let (mut vp_wasm_cache, _cache_dir) = cache();
+            let mut gas_meter = BlockGasMeter::default();
+            let result = check_vps(
+                &tx,
+                &storage,
+                &mut gas_meter,
+                &write_log,
+                &verifiers,
+                &mut vp_wasm_cache,
+                Some(&pool),
+            )
+            .unwrap();
+            let mut errors = result.errors;
+            errors.sort();
+            (
+                result.accepted_vps,
+                result.rejected_vps,
+                errors,
+                gas_meter.get_current_transaction_gas(),
+            )
+        };
+
+        let sequential = check_vps_with_threads(1);
+        assert_eq!(sequential.0.len(), 5);
+        assert_eq!(sequential.1.len(), 3);
+        assert_eq!(sequential, check_vps_with_threads(4));
+    }
+
+    /// Test that the wasm timings of the VPs are populated and that a VP
+    /// fetched from the compilation cache reports a negligible compile time
+    #[test]
+    fn test_vp_wasm_timings() {
+        let vp_always_true = std::fs::read(VP_ALWAYS_TRUE).unwrap();
+        let mut storage = TestStorage::default();
+        let addr = address::testing::gen_established_address();
+        storage
+            .write(&Key::validity_predicate(&addr), &vp_always_true)
+            .unwrap();
+        let verifiers = BTreeSet::from_iter([addr]);
+        let write_log = WriteLog::default();
+        let tx = Tx::new(vec![], None);
+        let (mut vp_wasm_cache, _cache_dir) = cache();
+
+        let check_vps_timings = |vp_wasm_cache: &mut VpCache<_>| {
+            let mut gas_meter = BlockGasMeter::default();
+            let result = check_vps(
+                &tx,
+                &storage,
+                &mut gas_meter,
+                &write_log,
+                &verifiers,
+                vp_wasm_cache,
+                None,
+            )
+            .unwrap();
+            assert_eq!(result.accepted_vps.len(), 1);
+            result.wasm_timings
+        };
+
+        // The first run compiles the VP
+        let compiled = check_vps_timings(&mut vp_wasm_cache);
+        assert!(compiled.compile_micros > 0);
+        assert!(compiled.execute_micros > 0);
+        assert_eq!(vp_wasm_cache.compilations(), 1);
+        assert_eq!(vp_wasm_cache.hits(), 0);
+
+        // The second run is a cache hit
+        let cached = check_vps_timings(&mut vp_wasm_cache);
+        assert!(cached.execute_micros > 0);
+        assert_eq!(vp_wasm_cache.compilations(), 1);
+        assert_eq!(vp_wasm_cache.hits(), 1);
+    }
+}
@@ -1,10 +1,14 @@
 //! RPC endpoint is used for ledger state queries
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::str::FromStr;
 
-use anoma::types::address::Address;
+use anoma::ledger::storage::write_log::StorageModification;
+use anoma::types::address::{self, Address};
 use anoma::types::storage;
+use anoma::types::transaction::VpsResult;
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(not(feature = "ABCI"))]
 use tendermint::abci::Path as AbciPath;
 #[cfg(feature = "ABCI")]
@@ -16,6 +20,9 @@ use thiserror::Error;
 pub enum Path {
     /// Dry run a transaction
     DryRunTx,
+    /// Simulate a transaction and get the storage modifications it would
+    /// make as a [`TxSimulation`]
+    SimulateTx,
     /// Epoch of the last committed block
     Epoch,
     /// Read a storage value with exact storage key
@@ -24,6 +31,34 @@ pub enum Path {
     Prefix(storage::Key),
     /// Check if the given storage key exists
     HasKey(storage::Key),
+    /// Read the balance of the owner of the given token
+    Balance { token: Address, owner: Address },
+    /// Read the PoS bonds and unbonds of the owner to the given validator
+    Bonds { owner: Address, validator: Address },
+}
+
+impl Path {
+    /// Check if the query can be answered at a past block height. The other
+    /// paths are only answered from the last committed state.
+    pub fn is_historical(&self) -> bool {
+        matches!(
+            self,
+            Path::Value(_) | Path::Balance { .. } | Path::Bonds { .. }
+        )
+    }
+}
+
+/// The result of a transaction simulated with the [`Path::SimulateTx`] query,
+/// encoded with [`BorshSerialize`] in the query response
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct TxSimulation {
+    /// Total gas used by the transaction (includes the gas used by VPs)
+    pub gas_used: u64,
+    /// The results of all the triggered validity predicates
+    pub vps_result: VpsResult,
+    /// The writes, deletes and initialized accounts of the transaction.
+    /// Temporary values are not included as they never reach the storage.
+    pub modifications: BTreeMap<storage::Key, StorageModification>,
 }
 
 #[derive(Debug, Clone)]
@@ -35,15 +70,21 @@ pub struct BalanceQuery {
 }
 
 const DRY_RUN_TX_PATH: &str = "dry_run_tx";
+const SIMULATE_TX_PATH: &str = "simulate_tx";
 const EPOCH_PATH: &str = "epoch";
 const VALUE_PREFIX: &str = "value";
 const PREFIX_PREFIX: &str = "prefix";
 const HAS_KEY_PREFIX: &str = "has_key";
+const TOKEN_PREFIX: &str = "token";
+const BALANCE_SEGMENT: &str = "balance";
+const POS_PREFIX: &str = "pos";
+const BONDS_SEGMENT: &str = "bonds";
 
 impl Display for Path {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Path::DryRunTx => write!(f, "{}", DRY_RUN_TX_PATH),
+            Path::SimulateTx => write!(f, "{}", SIMULATE_TX_PATH),
             Path::Epoch => write!(f, "{}", EPOCH_PATH),
             Path::Value(storage_key) => {
                 write!(f, "{}/{}", VALUE_PREFIX, storage_key)
@@ -54,6 +95,20 @@ impl Display for Path {
             Path::HasKey(storage_key) => {
                 write!(f, "{}/{}", HAS_KEY_PREFIX, storage_key)
             }
+            Path::Balance { token, owner } => {
+                write!(
+                    f,
+                    "{}/{}/{}/{}",
+                    TOKEN_PREFIX, token, BALANCE_SEGMENT, owner
+                )
+            }
+            Path::Bonds { owner, validator } => {
+                write!(
+                    f,
+                    "{}/{}/{}/{}",
+                    POS_PREFIX, BONDS_SEGMENT, owner, validator
+                )
+            }
         }
     }
 }
@@ -64,6 +119,7 @@ impl FromStr for Path {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             DRY_RUN_TX_PATH => Ok(Self::DryRunTx),
+            SIMULATE_TX_PATH => Ok(Self::SimulateTx),
             EPOCH_PATH => Ok(Self::Epoch),
             _ => match s.split_once('/') {
                 Some((VALUE_PREFIX, storage_key)) => {
@@ -81,6 +137,30 @@ impl FromStr for Path {
                         .map_err(PathParseError::InvalidStorageKey)?;
                     Ok(Self::HasKey(key))
                 }
+                Some((TOKEN_PREFIX, rest)) => {
+                    match rest.split('/').collect::<Vec<_>>()[..] {
+                        [token, BALANCE_SEGMENT, owner] => {
+                            let token = Address::decode(token)
+                                .map_err(PathParseError::InvalidAddress)?;
+                            let owner = Address::decode(owner)
+                                .map_err(PathParseError::InvalidAddress)?;
+                            Ok(Self::Balance { token, owner })
+                        }
+                        _ => Err(PathParseError::InvalidPath(s.to_string())),
+                    }
+                }
+                Some((POS_PREFIX, rest)) => {
+                    match rest.split('/').collect::<Vec<_>>()[..] {
+                        [BONDS_SEGMENT, owner, validator] => {
+                            let owner = Address::decode(owner)
+                                .map_err(PathParseError::InvalidAddress)?;
+                            let validator = Address::decode(validator)
+                                .map_err(PathParseError::InvalidAddress)?;
+                            Ok(Self::Bonds { owner, validator })
+                        }
+                        _ => Err(PathParseError::InvalidPath(s.to_string())),
+                    }
+                }
                 _ => Err(PathParseError::InvalidPath(s.to_string())),
             },
         }
@@ -103,4 +183,6 @@ pub enum PathParseError {
     InvalidPath(String),
     #[error("Invalid storage key: {0}")]
     InvalidStorageKey(storage::Error),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(address::Error),
 }
@@ -31,8 +31,10 @@ where
     /// transactions are not applied and must be included in the next
     /// [`prepare_proposal`] call.
     ///
-    /// Incoming wrapper txs need no further validation. They
-    /// are added to the block.
+    /// Incoming wrapper txs need no further validation. Their fee is
+    /// deducted from the fee payer's balance and they are added to the
+    /// block. If the fee cannot be paid, the wrapper is rejected and its
+    /// inner tx will not be decrypted.
     ///
     /// Error codes:
     ///   0: Ok
@@ -41,6 +43,7 @@ where
     ///   3: Wasm runtime error
     ///   4: Invalid order of decrypted txs
     ///   5. More decrypted txs than expected
+    ///   8: The fee payer cannot pay the fee
     pub fn finalize_block(
         &mut self,
         req: shim::request::FinalizeBlock,
@@ -128,10 +131,13 @@ where
                 continue;
             }
 
+            // A wrapper tx is only enqueued for decryption once its fee has
+            // been paid
+            let mut wrapper_to_enqueue = None;
             let mut tx_result = match &tx_type {
-                TxType::Wrapper(_wrapper) => {
+                TxType::Wrapper(wrapper) => {
                     if !cfg!(feature = "ABCI") {
-                        self.storage.tx_queue.push(_wrapper.clone());
+                        wrapper_to_enqueue = Some(wrapper.clone());
                     }
                     Event::new_tx_event(&tx_type, height.0)
                 }
@@ -181,6 +187,7 @@ where
                 &self.storage,
                 &mut self.vp_wasm_cache,
                 &mut self.tx_wasm_cache,
+                self.vp_thread_pool.as_ref(),
             )
             .map_err(Error::TxApply)
             {
@@ -192,6 +199,9 @@ where
                             result
                         );
                         self.write_log.commit_tx();
+                        if let Some(wrapper) = wrapper_to_enqueue {
+                            self.storage.tx_queue.push(wrapper);
+                        }
                         if !tx_result.contains_key("code") {
                             tx_result["code"] = ErrorCodes::Ok.into();
                         }
@@ -215,12 +225,35 @@ where
                                 );
                             }
                         }
+                    } else if let Some(abort) = &result.abort {
+                        tracing::info!(
+                            "apply_tx aborted with code {}: {}",
+                            abort.code,
+                            abort.message
+                        );
+                        self.write_log.drop_tx();
+                        tx_result["abort_code"] = abort.code.to_string();
+                        tx_result["abort_message"] = abort.message.clone();
+                        tx_result["code"] = ErrorCodes::TxAborted.into();
                     } else {
                         tracing::info!(
                             "some VPs rejected apply_tx storage modification \
                              {:#?}",
                             result.vps_result.rejected_vps
                         );
+                        match serde_json::to_string(
+                            &result.vps_result.sorted_rejected_vps(),
+                        ) {
+                            Ok(rejected_vps) => {
+                                tx_result["rejected_vps"] = rejected_vps;
+                            }
+                            Err(err) => {
+                                tracing::error!(
+                                    "Failed to serialize the rejected VPs: {}",
+                                    err
+                                );
+                            }
+                        }
                         self.write_log.drop_tx();
                         tx_result["code"] = ErrorCodes::InvalidTx.into();
                     }
@@ -235,15 +268,26 @@ where
                         .get_current_transaction_gas()
                         .to_string();
                     tx_result["info"] = msg.to_string();
-                    tx_result["code"] = ErrorCodes::WasmRuntimeError.into();
+                    tx_result["code"] = match msg {
+                        Error::TxApply(
+                            protocol::Error::InsufficientBalanceForFee {
+                                ..
+                            },
+                        ) => ErrorCodes::InsufficientFeeBalance.into(),
+                        _ => ErrorCodes::WasmRuntimeError.into(),
+                    };
                 }
             }
             response.events.push(tx_result.into());
         }
         self.reset_tx_queue_iter();
+        #[cfg(not(feature = "ABCI"))]
+        {
+            self.wrapper_in_proposal = false;
+        }
 
         if new_epoch {
-            self.update_epoch(&mut response);
+            self.update_epoch(height, &mut response);
         }
 
         response.gas_used = self
@@ -298,10 +342,19 @@ where
     }
 
     /// If a new epoch begins, we update the response to include
-    /// changes to the validator sets and consensus parameters
-    fn update_epoch(&self, response: &mut shim::response::FinalizeBlock) {
-        // Apply validator set update
+    /// changes to the validator sets and consensus parameters and an event
+    /// marking the beginning of the new epoch
+    fn update_epoch(
+        &self,
+        height: BlockHeight,
+        response: &mut shim::response::FinalizeBlock,
+    ) {
         let (current_epoch, _gas) = self.storage.get_current_epoch();
+        response
+            .events
+            .push(Event::new_epoch_event(current_epoch, height).into());
+
+        // Apply validator set update
         // TODO ABCI validator updates on block H affects the validator set
         // on block H+2, do we need to update a block earlier?
         self.storage.validator_set_update(current_epoch, |update| {
@@ -350,8 +403,14 @@ where
 /// are covered by the e2e tests.
 #[cfg(test)]
 mod test_finalize_block {
-    use anoma::types::address::xan;
-    use anoma::types::storage::Epoch;
+    use anoma::ledger::pos::anoma_proof_of_stake::epoched::DynEpochOffset;
+    use anoma::ledger::pos::anoma_proof_of_stake::types::{
+        SlashType, VotingPower, WeightedValidator,
+    };
+    use anoma::ledger::pos::ValidatorConsensusKeys;
+    use anoma::types::address::{self, xan, Address};
+    use anoma::types::storage::{Epoch, Key};
+    use anoma::types::token::Amount;
     use anoma::types::transaction::{EncryptionKey, Fee};
 
     use super::*;
@@ -360,6 +419,246 @@ mod test_finalize_block {
         FinalizeBlock, ProcessedTx,
     };
 
+    /// Write the given XAN balance of the owner directly into the storage
+    fn credit_xan(shell: &mut TestShell, owner: &Address, amount: Amount) {
+        shell
+            .shell
+            .storage
+            .write(
+                &token::balance_key(&xan(), owner),
+                amount.try_to_vec().expect("Test failed"),
+            )
+            .expect("Test failed");
+    }
+
+    /// Finalize a block with a wrapper tx signed by the keypair that pays
+    /// the given XAN fee. Returns the code of the wrapper's event.
+    #[cfg(not(feature = "ABCI"))]
+    fn finalize_wrapper(
+        shell: &mut TestShell,
+        keypair: &common::SecretKey,
+        fee: Amount,
+    ) -> String {
+        let raw_tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: fee,
+                token: xan(),
+            },
+            keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx,
+            Default::default(),
+        );
+        let processed_tx = ProcessedTx {
+            tx: wrapper.sign(keypair).expect("Test failed").to_bytes(),
+            result: TxResult {
+                code: ErrorCodes::Ok.into(),
+                info: "".into(),
+            },
+        };
+
+        let events = shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![processed_tx],
+                reject_all_decrypted: false,
+                ..Default::default()
+            })
+            .expect("Test failed");
+        events[0]
+            .attributes
+            .iter()
+            .find(|attr| attr.key.as_str() == "code")
+            .expect("Test failed")
+            .value
+            .clone()
+    }
+
+    /// Read the XAN balance of the owner from the write log
+    fn written_xan_balance(shell: &TestShell, owner: &Address) -> Amount {
+        match shell
+            .shell
+            .write_log
+            .read(&token::balance_key(&xan(), owner))
+            .0
+        {
+            Some(StorageModification::Write { value }) => {
+                Amount::try_from_slice(&value[..]).expect("Test failed")
+            }
+            modification => {
+                panic!("Unexpected balance modification {:?}", modification)
+            }
+        }
+    }
+
+    /// Check that when the blocks are advanced past the epoch's minimum
+    /// number of blocks, a new epoch begins and the new epoch event is
+    /// emitted exactly once, at the epoch boundary
+    #[test]
+    fn test_new_epoch_event() {
+        let (mut shell, _) = setup();
+        let (genesis_epoch, _gas) = shell.shell.storage.get_current_epoch();
+        let boundary = shell.shell.storage.next_epoch_min_start_height;
+
+        let mut new_epoch_heights = vec![];
+        for height in 1..=boundary.0 + 5 {
+            let mut req = FinalizeBlock::default();
+            req.header.height = height.try_into().expect("Test failed");
+            let events = shell.finalize_block(req).expect("Test failed");
+            let num_new_epoch_events = events
+                .iter()
+                .filter(|event| event.r#type == "new_epoch")
+                .count();
+            assert!(num_new_epoch_events <= 1);
+            if num_new_epoch_events == 1 {
+                new_epoch_heights.push(height);
+            }
+        }
+
+        assert_eq!(new_epoch_heights, vec![boundary.0]);
+        let (current_epoch, _gas) = shell.shell.storage.get_current_epoch();
+        assert_eq!(current_epoch, genesis_epoch.next());
+    }
+
+    /// Check that when a new validator is added to the active validator set at
+    /// the pipeline offset (as it would be by a bond), its voting power
+    /// update is sent to Tendermint once the pipeline epoch begins and in no
+    /// other block
+    #[test]
+    fn test_new_validator_update() {
+        let (mut shell, _) = setup();
+        let storage = &mut shell.shell.storage;
+        let params = storage.read_pos_params();
+        let (current_epoch, _gas) = storage.get_current_epoch();
+        let pipeline_epoch = current_epoch + params.pipeline_len;
+
+        // Add a new validator with some voting power at the pipeline offset
+        let validator = address::testing::established_address_3();
+        let consensus_key = gen_keypair().ref_to();
+        let voting_power = VotingPower::from(1000);
+        storage.write_validator_consensus_key(
+            &validator,
+            &ValidatorConsensusKeys::init(
+                consensus_key.clone(),
+                current_epoch,
+                &params,
+            ),
+        );
+        let mut validator_set = storage.read_validator_set();
+        validator_set.update_from_offset(
+            |set, _epoch| {
+                set.active.insert(WeightedValidator {
+                    voting_power,
+                    address: validator.clone(),
+                });
+            },
+            current_epoch,
+            DynEpochOffset::PipelineLen,
+            &params,
+        );
+        storage.write_validator_set(&validator_set);
+
+        let expected_update = ValidatorUpdate {
+            pub_key: Some(TendermintPublicKey {
+                sum: Some(key_to_tendermint(&consensus_key).unwrap()),
+            }),
+            power: u64::from(voting_power) as i64,
+        };
+        let mut update_heights = vec![];
+        let mut pipeline_epoch_height = None;
+        for height in 1..=50_u64 {
+            // Don't wait for the epoch's minimum duration, so that the epochs
+            // only depend on the number of blocks
+            shell.shell.storage.next_epoch_min_start_time = DateTimeUtc::now();
+            let mut req = FinalizeBlock::default();
+            req.header.height = height.try_into().expect("Test failed");
+            let resp = shell.shell.finalize_block(req).expect("Test failed");
+            if resp.validator_updates.contains(&expected_update) {
+                update_heights.push(height);
+            }
+            let (epoch, _gas) = shell.shell.storage.get_current_epoch();
+            if epoch == pipeline_epoch && pipeline_epoch_height.is_none() {
+                pipeline_epoch_height = Some(height);
+            }
+        }
+
+        let pipeline_epoch_height =
+            pipeline_epoch_height.expect("The pipeline epoch should begin");
+        assert_eq!(update_heights, vec![pipeline_epoch_height]);
+    }
+
+    /// Check that a duplicate vote evidence slashes the validator's bonded
+    /// stake by the configured rate and that the same evidence is only
+    /// applied once
+    #[test]
+    fn test_duplicate_vote_slash() {
+        let (mut shell, _) = setup();
+        let storage = &shell.shell.storage;
+        let params = storage.read_pos_params();
+        let (current_epoch, _gas) = storage.get_current_epoch();
+        let pipeline_epoch = current_epoch + params.pipeline_len;
+        let validator = storage
+            .read_validator_set()
+            .get(current_epoch)
+            .expect("Test failed")
+            .active
+            .iter()
+            .next()
+            .expect("Test failed")
+            .address
+            .clone();
+        let total_deltas = storage
+            .read_validator_total_deltas(&validator)
+            .expect("Test failed");
+        let current_stake: i128 =
+            total_deltas.get(current_epoch).expect("Test failed").into();
+        let stake_before: i128 = total_deltas
+            .get(pipeline_epoch)
+            .expect("Test failed")
+            .into();
+
+        let evidence = Evidence {
+            r#type: abci::EvidenceType::DuplicateVote as i32,
+            validator: Some(abci::Validator {
+                address: validator
+                    .raw_hash()
+                    .expect("Test failed")
+                    .as_bytes()
+                    .to_vec(),
+                power: 0,
+            }),
+            height: 0,
+            ..Default::default()
+        };
+        // Submit the same evidence in two consecutive blocks
+        for height in 1..=2_u64 {
+            let mut req = FinalizeBlock {
+                byzantine_validators: vec![evidence.clone()],
+                ..Default::default()
+            };
+            req.header.height = height.try_into().expect("Test failed");
+            shell.finalize_block(req).expect("Test failed");
+        }
+
+        let storage = &shell.shell.storage;
+        let stake_after: i128 = storage
+            .read_validator_total_deltas(&validator)
+            .expect("Test failed")
+            .get(pipeline_epoch)
+            .expect("Test failed")
+            .into();
+        let slashed = params.duplicate_vote_slash_rate * current_stake;
+        assert!(slashed > 0);
+        assert_eq!(stake_after, stake_before - slashed);
+        let slashes = storage.read_validator_slashes(&validator);
+        assert_eq!(slashes.len(), 1);
+        assert_eq!(slashes[0].r#type, SlashType::DuplicateVote);
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// Check that if a wrapper tx was rejected by [`process_proposal`],
     /// check that the correct event is returned. Check that it does
@@ -368,6 +667,7 @@ mod test_finalize_block {
     fn test_process_proposal_rejected_wrapper_tx() {
         let (mut shell, _) = setup();
         let keypair = gen_keypair();
+        credit_xan(&mut shell, &Address::from(&keypair.ref_to()), 10.into());
         let mut processed_txs = vec![];
         let mut valid_wrappers = vec![];
         // create some wrapper txs
@@ -442,6 +742,62 @@ mod test_finalize_block {
         assert_eq!(counter, 3);
     }
 
+    #[cfg(not(feature = "ABCI"))]
+    /// Check that the fee of an applied wrapper tx is deducted from the
+    /// balance of the fee payer
+    #[test]
+    fn test_wrapper_fee_is_deducted() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        credit_xan(&mut shell, &payer, Amount::whole(10));
+        let fee = Amount::whole(3);
+        let code = finalize_wrapper(&mut shell, &keypair, fee);
+        assert_eq!(code, String::from(ErrorCodes::Ok));
+        let mut expected = Amount::whole(10);
+        expected.spend(&fee);
+        assert_eq!(written_xan_balance(&shell, &payer), expected);
+    }
+
+    #[cfg(not(feature = "ABCI"))]
+    /// Check that a wrapper tx with a zero fee is applied without writing the
+    /// balance of its fee payer
+    #[test]
+    fn test_wrapper_zero_fee_not_written() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        let code = finalize_wrapper(&mut shell, &keypair, Amount::default());
+        assert_eq!(code, String::from(ErrorCodes::Ok));
+        assert!(shell
+            .shell
+            .write_log
+            .read(&token::balance_key(&xan(), &payer))
+            .0
+            .is_none());
+    }
+
+    #[cfg(not(feature = "ABCI"))]
+    /// Check that a wrapper tx whose fee payer cannot cover the fee is
+    /// rejected and that it does not appear in the queue of txs to be
+    /// decrypted
+    #[test]
+    fn test_wrapper_with_insufficient_fee_balance() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let payer = Address::from(&keypair.ref_to());
+        credit_xan(&mut shell, &payer, Amount::whole(1));
+        let code = finalize_wrapper(&mut shell, &keypair, Amount::whole(2));
+        assert_eq!(code, String::from(ErrorCodes::InsufficientFeeBalance));
+        assert!(shell
+            .shell
+            .write_log
+            .read(&token::balance_key(&xan(), &payer))
+            .0
+            .is_none());
+        assert!(shell.next_wrapper().is_none());
+    }
+
     #[cfg(feature = "ABCI")]
     /// Check that if a wrapper tx was rejected by [`process_proposal`],
     /// check that the correct event is returned.
@@ -449,6 +805,7 @@ mod test_finalize_block {
     fn test_process_proposal_rejected_wrapper_tx() {
         let (mut shell, _) = setup();
         let keypair = gen_keypair();
+        credit_xan(&mut shell, &Address::from(&keypair.ref_to()), 10.into());
         let mut processed_txs = vec![];
         // create some wrapper txs
         for i in 1..5 {
@@ -922,6 +1279,109 @@ mod test_finalize_block {
         }
     }
 
+    /// Test that when a decrypted tx aborts itself, its error code and
+    /// message are surfaced in the tx's event and the storage writes it
+    /// made before aborting are dropped
+    #[test]
+    fn test_aborted_tx_drops_writes() {
+        let (mut shell, _) = setup();
+        let keypair = gen_keypair();
+        let key = Key::parse("test/key").expect("Test failed");
+        let message = "insufficient funds";
+        // A tx that writes the value `value` under the `key` and then aborts
+        // with code `42` and the `message`
+        let tx_code = wat::parse_str(format!(
+            r#"
+            (module
+                (type (;0;) (func (param i64 i64)))
+                (type (;1;) (func (param i64 i64 i64 i64)))
+                (type (;2;) (func (param i64 i64 i64)))
+                (import "env" "anoma_tx_write" (func $write (type 1)))
+                (import "env" "anoma_tx_abort" (func $abort (type 2)))
+
+                (func $_apply_tx (type 0) (param i64 i64)
+                (call $write
+                    (i64.const 0) (i64.const {key_len})
+                    (i64.const {key_len}) (i64.const 5))
+                (call $abort
+                    (i64.const 42)
+                    (i64.const {msg_ptr}) (i64.const {msg_len})))
+
+                (memory (;0;) 16)
+                (data (i32.const 0) "{key}value{message}")
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#,
+            key = key,
+            key_len = key.to_string().len(),
+            msg_ptr = key.to_string().len() + 5,
+            msg_len = message.len(),
+            message = message,
+        ))
+        .expect("Test failed");
+
+        let raw_tx = Tx::new(tx_code, None);
+        let wrapper_tx = WrapperTx::new(
+            Fee {
+                amount: 0.into(),
+                token: xan(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            raw_tx.clone(),
+            Default::default(),
+        );
+        shell.enqueue_tx(wrapper_tx);
+        let processed_tx = ProcessedTx {
+            tx: Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(raw_tx)))
+                .to_bytes(),
+            result: TxResult {
+                code: ErrorCodes::Ok.into(),
+                info: "".into(),
+            },
+        };
+
+        let event = &shell
+            .finalize_block(FinalizeBlock {
+                txs: vec![processed_tx],
+                reject_all_decrypted: false,
+                ..Default::default()
+            })
+            .expect("Test failed")[0];
+        assert_eq!(event.r#type, "applied");
+        let expected = [
+            ("code", String::from(ErrorCodes::TxAborted)),
+            ("abort_code", "42".to_string()),
+            ("abort_message", message.to_string()),
+        ];
+        for (attr_key, attr_value) in expected {
+            #[cfg(not(feature = "ABCI"))]
+            {
+                let attr = event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key.as_str() == attr_key)
+                    .expect("Test failed");
+                assert_eq!(attr.value, attr_value);
+            }
+            #[cfg(feature = "ABCI")]
+            {
+                let attr = event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == attr_key.as_bytes())
+                    .expect("Test failed");
+                assert_eq!(
+                    String::from_utf8(attr.value.clone()).expect("Test failed"),
+                    attr_value
+                );
+            }
+        }
+        // the write made before the tx aborted must have been dropped
+        assert!(shell.shell.write_log.read(&key).0.is_none());
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// Tests that if the decrypted txs are submitted out of
     /// order then
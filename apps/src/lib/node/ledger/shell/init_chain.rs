@@ -71,6 +71,7 @@ where
             &mut self.storage,
             &genesis.parameters,
         );
+        self.set_wasm_limits(&genesis.parameters.wasm_limits);
         // Depends on parameters being initialized
         self.storage
             .init_genesis_epoch(
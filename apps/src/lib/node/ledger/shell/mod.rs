@@ -13,6 +13,8 @@ mod process_proposal;
 mod queries;
 
 use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
 use std::mem;
 use std::path::{Path, PathBuf};
 #[allow(unused_imports)]
@@ -24,19 +26,20 @@ use anoma::ledger::pos::anoma_proof_of_stake::types::{
     ActiveValidator, ValidatorSetUpdate,
 };
 use anoma::ledger::pos::anoma_proof_of_stake::PosBase;
-use anoma::ledger::storage::write_log::WriteLog;
-use anoma::ledger::storage::{DBIter, Storage, StorageHasher, DB};
+use anoma::ledger::storage::write_log::{StorageModification, WriteLog};
+use anoma::ledger::storage::{DBIter, MerkleRoot, Storage, StorageHasher, DB};
 use anoma::ledger::{ibc, parameters, pos};
 use anoma::proto::{self, Tx};
 use anoma::types::chain::ChainId;
 use anoma::types::key::*;
-use anoma::types::storage::{BlockHeight, Key};
+use anoma::types::storage::{BlockHash, BlockHeight, Key};
 use anoma::types::time::{DateTimeUtc, TimeZone, Utc};
 use anoma::types::transaction::{
     hash_tx, process_tx, verify_decrypted_correctly, AffineCurve, DecryptedTx,
     EllipticCurve, PairingEngine, TxType, WrapperTx,
 };
 use anoma::types::{address, token};
+use anoma::vm::wasm::run::WasmLimits;
 use anoma::vm::wasm::{TxCache, VpCache};
 use anoma::vm::WasmCacheRwAccess;
 #[cfg(not(feature = "ABCI"))]
@@ -44,6 +47,8 @@ use borsh::BorshDeserialize;
 use borsh::BorshSerialize;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::abci::{
     self, Evidence, RequestPrepareProposal, ValidatorUpdate,
@@ -66,6 +71,7 @@ use tower_abci::{request, response};
 use tower_abci_old::{request, response};
 
 use super::rpc;
+use crate::client::storage_value;
 use crate::config::{genesis, TendermintMode};
 use crate::node::ledger::events::Event;
 use crate::node::ledger::shims::abcipp_shim_types::shim;
@@ -86,6 +92,35 @@ fn key_to_tendermint<PK: PublicKey>(
 pub enum Error {
     #[error("Error removing the DB data: {0}")]
     RemoveDB(std::io::Error),
+    #[error("Error creating the DB directory: {0}")]
+    CreateDB(std::io::Error),
+    #[error("The DB directory {0} doesn't exist")]
+    MissingDB(PathBuf),
+    #[error("Error reading the DB size: {0}")]
+    ReadDBSize(std::io::Error),
+    #[error(
+        "Error compacting the DB, make sure that the ledger node is not \
+         running: {0}"
+    )]
+    CompactDB(anoma::ledger::storage::Error),
+    #[error(
+        "Error opening the DB, make sure that the ledger node is not running: \
+         {0}"
+    )]
+    OpenDB(anoma::ledger::storage::Error),
+    #[error("Error writing the state dump: {0}")]
+    WriteStateDump(std::io::Error),
+    #[error("Error reading the state dump: {0}")]
+    ReadStateDump(std::io::Error),
+    #[error("Invalid state dump: {0}")]
+    InvalidStateDump(String),
+    #[error(
+        "The DB directory {0} already contains a state, use --force to \
+         overwrite it"
+    )]
+    NonEmptyDB(PathBuf),
+    #[error("Error writing the imported state to the DB: {0}")]
+    ImportState(anoma::ledger::storage::Error),
     #[error("chain ID mismatch: {0}")]
     ChainId(String),
     #[error("Error decoding a transaction from bytes: {0}")]
@@ -114,6 +149,8 @@ pub enum ErrorCodes {
     InvalidOrder = 4,
     ExtraTxs = 5,
     Undecryptable = 6,
+    TxAborted = 7,
+    InsufficientFeeBalance = 8,
 }
 
 impl From<ErrorCodes> for u32 {
@@ -143,6 +180,220 @@ pub fn reset(config: config::Ledger) -> Result<()> {
     Ok(())
 }
 
+/// Compact the DB of a ledger node that is not running. Returns the size of
+/// the DB directory in bytes before and after the compaction.
+pub fn compact(config: config::Ledger) -> Result<(u64, u64)> {
+    let db_path = config.db_dir();
+    if !db_path.is_dir() {
+        return Err(Error::MissingDB(db_path));
+    }
+    let size_before = dir_size(&db_path).map_err(Error::ReadDBSize)?;
+    storage::compact_db(&db_path).map_err(Error::CompactDB)?;
+    let size_after = dir_size(&db_path).map_err(Error::ReadDBSize)?;
+    Ok((size_before, size_after))
+}
+
+/// A storage value in a state dump
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedValue {
+    /// The value decoded by the type registered for its key, if any, or else
+    /// hex-encoded
+    value: String,
+    /// The hex-encoded bytes of the value, from which the state is imported
+    bytes: String,
+}
+
+/// Dump the last committed state of a ledger node that is not running to the
+/// given file as a JSON object of storage keys and their values. Every value
+/// is written both decoded by the type registered for its key, if any, and as
+/// hex-encoded bytes. The entries are written as they are read from the DB,
+/// so the state doesn't have to fit in memory. Returns the number of dumped
+/// keys.
+pub fn dump_state(config: config::Ledger, output: &Path) -> Result<u64> {
+    let db_path = config.db_dir();
+    if !db_path.is_dir() {
+        return Err(Error::MissingDB(db_path));
+    }
+    let db = storage::open_db(&db_path, None).map_err(Error::OpenDB)?;
+    let file = File::create(output).map_err(Error::WriteStateDump)?;
+    let mut writer = BufWriter::new(file);
+    let count =
+        write_state_json(&db, &mut writer).map_err(Error::WriteStateDump)?;
+    writer.flush().map_err(Error::WriteStateDump)?;
+    Ok(count)
+}
+
+/// Write all the key-vals of the accounts' sub-spaces in the DB as a JSON
+/// object, one entry per line
+fn write_state_json(
+    db: &storage::PersistentDB,
+    writer: &mut impl Write,
+) -> std::io::Result<u64> {
+    write!(writer, "{{")?;
+    let mut count = 0;
+    for (key, value, _gas) in db.iter_prefix(&Key::default()) {
+        let value = DumpedValue {
+            value: match Key::parse(&key) {
+                Ok(key) => storage_value::format_value(&key, &value),
+                Err(_) => hex::encode(&value),
+            },
+            bytes: hex::encode(&value),
+        };
+        let separator = if count == 0 { "" } else { "," };
+        write!(
+            writer,
+            "{}\n  {}: {}",
+            separator,
+            serde_json::to_string(&key)?,
+            serde_json::to_string(&value)?
+        )?;
+        count += 1;
+    }
+    writeln!(writer, "\n}}")?;
+    Ok(count)
+}
+
+/// Import a JSON state dump written by [`dump_state`] into the DB of a ledger
+/// node that is not running. The storage keys and values are committed in the
+/// first block, which rebuilds the Merkle tree. A DB that already contains a
+/// committed state is only overwritten if `force` is set. Returns the Merkle
+/// root of the imported state and the number of imported keys.
+///
+/// Like the dump, the import is streamed: the file is read once to validate
+/// it before the DB is touched and once more to write its entries, so only
+/// the Merkle tree of the imported state has to fit in memory.
+pub fn import_state(
+    config: config::Ledger,
+    input: &Path,
+    force: bool,
+) -> Result<(MerkleRoot, u64)> {
+    read_state_json(input, |_key, _value| Ok(()))?;
+
+    let db_path = config.db_dir();
+    if db_path.is_dir() {
+        // make sure that the DB is not in use before opening the storage
+        let db = storage::open_db(&db_path, None).map_err(Error::OpenDB)?;
+        let has_state = db.read_last_block().map_err(Error::OpenDB)?.is_some();
+        drop(db);
+        if has_state {
+            if !force {
+                return Err(Error::NonEmptyDB(db_path));
+            }
+            std::fs::remove_dir_all(&db_path).map_err(Error::RemoveDB)?;
+        }
+    }
+    std::fs::create_dir_all(&db_path).map_err(Error::CreateDB)?;
+
+    let mut storage = storage::PersistentStorage::open(
+        &db_path,
+        config.chain_id.clone(),
+        None,
+    );
+    storage.check_chain_id(false).map_err(Error::ImportState)?;
+    storage
+        .begin_block(BlockHash::default(), BlockHeight(1))
+        .map_err(Error::ImportState)?;
+    let count = read_state_json(input, |key, value| {
+        storage.write(&key, value).map_err(Error::ImportState)?;
+        Ok(())
+    })?;
+    storage.commit().map_err(Error::ImportState)?;
+    Ok((storage.merkle_root(), count))
+}
+
+/// Read the entries of a JSON state dump one at a time and pass their parsed
+/// keys and decoded values to `apply`. Returns the number of read entries.
+fn read_state_json(
+    input: &Path,
+    apply: impl FnMut(Key, Vec<u8>) -> Result<()>,
+) -> Result<u64> {
+    let file = File::open(input).map_err(Error::ReadStateDump)?;
+    let mut deserializer =
+        serde_json::Deserializer::from_reader(BufReader::new(file));
+    let mut visitor = StateDumpVisitor { apply, error: None };
+    let res = deserializer
+        .deserialize_map(&mut visitor)
+        .and_then(|count| deserializer.end().map(|()| count));
+    match (res, visitor.error) {
+        (_, Some(err)) => Err(err),
+        (res, None) => {
+            res.map_err(|err| Error::InvalidStateDump(err.to_string()))
+        }
+    }
+}
+
+/// Visits the entries of a JSON state dump, see [`read_state_json`]
+struct StateDumpVisitor<F> {
+    apply: F,
+    /// The error returned by `apply` or from parsing an entry, which is kept
+    /// here because the visitor can only fail with a JSON error
+    error: Option<Error>,
+}
+
+impl<'de, F> Visitor<'de> for &mut StateDumpVisitor<F>
+where
+    F: FnMut(Key, Vec<u8>) -> Result<()>,
+{
+    type Value = u64;
+
+    fn expecting(
+        &self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        formatter.write_str("a map of storage keys to dumped values")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> std::result::Result<u64, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut count = 0;
+        while let Some((key, DumpedValue { bytes, .. })) =
+            map.next_entry::<String, DumpedValue>()?
+        {
+            let res = Key::parse(&key)
+                .map_err(|err| {
+                    Error::InvalidStateDump(format!(
+                        "invalid key {}: {}",
+                        key, err
+                    ))
+                })
+                .and_then(|parsed_key| {
+                    let value = hex::decode(&bytes).map_err(|err| {
+                        Error::InvalidStateDump(format!(
+                            "invalid bytes of key {}: {}",
+                            key, err
+                        ))
+                    })?;
+                    (self.apply)(parsed_key, value)
+                });
+            if let Err(err) = res {
+                let msg = err.to_string();
+                self.error = Some(err);
+                return Err(de::Error::custom(msg));
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Get the total size in bytes of the files in the given directory and its
+/// sub-directories
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
 #[derive(Debug)]
 #[allow(dead_code, clippy::large_enum_variant)]
 pub(super) enum ShellMode {
@@ -206,6 +457,17 @@ pub struct Shell<
     vp_wasm_cache: VpCache<WasmCacheRwAccess>,
     /// Tx WASM compilation cache
     tx_wasm_cache: TxCache<WasmCacheRwAccess>,
+    /// A dedicated thread pool for validating the VPs of a tx, if their
+    /// parallelism is configured. Otherwise, the global Rayon thread pool is
+    /// used.
+    vp_thread_pool: Option<rayon::ThreadPool>,
+    /// Set once a wrapper tx of the block proposal being processed has been
+    /// seen. The decrypted txs must precede all the wrapper txs in a
+    /// proposal.
+    #[cfg(not(feature = "ABCI"))]
+    wrapper_in_proposal: bool,
+    /// Re-derive the Merkle root from the DB after every commit
+    verify_merkle_root: bool,
 }
 
 impl<D, H> Shell<D, H>
@@ -225,6 +487,16 @@ where
     ) -> Self {
         let chain_id = config.chain_id;
         let db_path = config.shell.db_dir(&chain_id);
+        let retained_heights = config.shell.storage_retained_heights;
+        let force_chain_id = config.shell.force_chain_id;
+        let verify_merkle_root = config.shell.verify_merkle_root;
+        let vp_thread_pool = config.shell.vp_parallelism.map(|num_threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .thread_name(|i| format!("ledger-vp-worker-{}", i))
+                .build()
+                .expect("Creating the VP thread pool should not fail")
+        });
         let base_dir = config.shell.base_dir;
         let mode = config.tendermint.tendermint_mode;
         if !Path::new(&base_dir).is_dir() {
@@ -233,12 +505,22 @@ where
         }
         // load last state from storage
         let mut storage = Storage::open(db_path, chain_id.clone(), db_cache);
+        storage.retained_heights = retained_heights;
         storage
             .load_last_state()
             .map_err(|e| {
                 tracing::error!("Cannot load the last state from the DB {}", e);
             })
             .expect("PersistentStorage cannot be initialized");
+        // refuse to start on a DB of another chain, unless forced
+        if let Err(err) = storage.check_chain_id(force_chain_id) {
+            tracing::error!("{}", err);
+            panic!(
+                "Refusing to start the ledger: {}. To start anyway, run the \
+                 ledger with the `--force` flag.",
+                err
+            );
+        }
 
         let vp_wasm_cache_dir =
             base_dir.join(chain_id.as_str()).join("vp_wasm_cache");
@@ -294,7 +576,7 @@ where
             TendermintMode::Seed => ShellMode::Seed,
         };
 
-        Self {
+        let mut shell = Self {
             chain_id,
             storage,
             gas_meter: BlockGasMeter::default(),
@@ -311,7 +593,28 @@ where
                 tx_wasm_cache_dir,
                 tx_wasm_compilation_cache as usize,
             ),
+            vp_thread_pool,
+            #[cfg(not(feature = "ABCI"))]
+            wrapper_in_proposal: false,
+            verify_merkle_root,
+        };
+        // The wasm limits are only missing on a chain that has not been
+        // initialized from genesis yet, in which case they're set in
+        // `init_chain`
+        if let Ok((wasm_limits, _gas)) =
+            parameters::read_wasm_limits(&shell.storage)
+        {
+            shell.set_wasm_limits(&wasm_limits);
         }
+        shell
+    }
+
+    /// Apply the wasm limits of the protocol parameters to the wasm caches
+    fn set_wasm_limits(&mut self, wasm_limits: &parameters::WasmLimitsParams) {
+        self.vp_wasm_cache
+            .set_limits(WasmLimits::vp_from_params(wasm_limits));
+        self.tx_wasm_cache
+            .set_limits(WasmLimits::tx_from_params(wasm_limits));
     }
 
     /// Iterate lazily over the wrapper txs in order
@@ -506,6 +809,12 @@ where
                 e
             )
         });
+        if self.verify_merkle_root {
+            if let Err(err) = self.storage.verify_merkle_root() {
+                tracing::error!("The Merkle root consistency check failed");
+                panic!("The Merkle root consistency check failed: {}", err);
+            }
+        }
 
         let root = self.storage.merkle_root();
         tracing::info!(
@@ -527,7 +836,22 @@ where
     ) -> response::CheckTx {
         let mut response = response::CheckTx::default();
         match Tx::try_from(tx_bytes).map_err(Error::TxDecoding) {
-            Ok(_) => response.log = String::from("Mempool validation passed"),
+            Ok(tx) => {
+                // The fee payer of a wrapper tx must be able to pay its fee
+                if let Ok(TxType::Wrapper(wrapper)) = process_tx(tx) {
+                    if let Err(err) = protocol::check_fee_balance(
+                        &wrapper,
+                        &self.storage,
+                        &self.write_log,
+                    ) {
+                        response.code =
+                            ErrorCodes::InsufficientFeeBalance.into();
+                        response.log = err.to_string();
+                        return response;
+                    }
+                }
+                response.log = String::from("Mempool validation passed")
+            }
             Err(msg) => {
                 response.code = 1;
                 response.log = msg.to_string();
@@ -554,6 +878,7 @@ where
                     &self.storage,
                     &mut vp_wasm_cache,
                     &mut tx_wasm_cache,
+                    self.vp_thread_pool.as_ref(),
                 )
                 .map_err(Error::TxApply)
                 {
@@ -573,6 +898,70 @@ where
         }
     }
 
+    /// Simulate the application of a transaction against a clone of the write
+    /// log over the current storage, without committing anything. The value
+    /// of a successful response is an [`rpc::TxSimulation`] with the storage
+    /// modifications of the transaction, encoded with [`BorshSerialize`].
+    fn simulate_tx(&self, tx_bytes: &[u8]) -> response::Query {
+        let mut gas_meter = BlockGasMeter::default();
+        let mut write_log = self.write_log.clone();
+        let mut vp_wasm_cache = self.vp_wasm_cache.read_only();
+        let mut tx_wasm_cache = self.tx_wasm_cache.read_only();
+        let tx = match Tx::try_from(tx_bytes) {
+            Ok(tx) => TxType::Decrypted(DecryptedTx::Decrypted(tx)),
+            Err(err) => {
+                return response::Query {
+                    code: 1,
+                    log: format!("{}", Error::TxDecoding(err)),
+                    ..Default::default()
+                };
+            }
+        };
+        match protocol::apply_tx(
+            tx,
+            tx_bytes.len(),
+            &mut gas_meter,
+            &mut write_log,
+            &self.storage,
+            &mut vp_wasm_cache,
+            &mut tx_wasm_cache,
+            self.vp_thread_pool.as_ref(),
+        )
+        .map_err(Error::TxApply)
+        {
+            Ok(result) => {
+                let modifications = write_log
+                    .get_tx_modifications()
+                    .iter()
+                    .filter(|(_key, modification)| {
+                        !matches!(
+                            modification,
+                            StorageModification::Temp { .. }
+                        )
+                    })
+                    .map(|(key, modification)| {
+                        (key.clone(), modification.clone())
+                    })
+                    .collect();
+                let simulation = rpc::TxSimulation {
+                    gas_used: result.gas_used,
+                    vps_result: result.vps_result.clone(),
+                    modifications,
+                };
+                response::Query {
+                    value: simulation.try_to_vec().unwrap(),
+                    info: result.to_string(),
+                    ..Default::default()
+                }
+            }
+            Err(error) => response::Query {
+                code: 1,
+                log: format!("{}", error),
+                ..Default::default()
+            },
+        }
+    }
+
     /// Lookup a validator's keypair for their established account from their
     /// wallet. If the node is not validator, this function returns None
     #[cfg(not(feature = "ABCI"))]
@@ -614,12 +1003,13 @@ where
 /// for the shell
 #[cfg(test)]
 mod test_utils {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
 
     use anoma::ledger::storage::mockdb::MockDB;
     use anoma::ledger::storage::{BlockStateWrite, MerkleTree, Sha256Hasher};
     use anoma::types::address::{xan, EstablishedAddressGen};
-    use anoma::types::chain::ChainId;
+    use anoma::types::chain::{ChainId, ChainIdPrefix};
     use anoma::types::key::*;
     use anoma::types::storage::{BlockHash, Epoch};
     use anoma::types::transaction::Fee;
@@ -903,4 +1293,327 @@ mod test_utils {
         );
         assert!(!shell.storage.tx_queue.is_empty());
     }
+
+    /// Test that the wasm limits set in the protocol parameters are applied to
+    /// the wasm caches when the shell starts
+    #[test]
+    fn test_wasm_limits_from_parameters() {
+        let base_dir = tempdir().unwrap().as_ref().canonicalize().unwrap();
+        let wasm_limits = parameters::WasmLimitsParams {
+            tx_max_memory_pages: 100,
+            vp_max_memory_pages: 50,
+            max_stack_height: 1024,
+        };
+        let mut shell =
+            start_persistent_shell(&base_dir, ChainId::default(), false);
+        // The chain has not been initialized, so the defaults are used
+        assert_eq!(shell.tx_wasm_cache.limits(), WasmLimits::tx_default());
+        assert_eq!(shell.vp_wasm_cache.limits(), WasmLimits::vp_default());
+        shell
+            .storage
+            .begin_block(BlockHash::default(), BlockHeight(1))
+            .unwrap();
+        parameters::update_wasm_limits_parameter(
+            &mut shell.storage,
+            &wasm_limits,
+        )
+        .unwrap();
+        shell.storage.commit().unwrap();
+        drop(shell);
+
+        let shell =
+            start_persistent_shell(&base_dir, ChainId::default(), false);
+        assert_eq!(
+            shell.tx_wasm_cache.limits(),
+            WasmLimits::tx_from_params(&wasm_limits)
+        );
+        assert_eq!(
+            shell.vp_wasm_cache.limits(),
+            WasmLimits::vp_from_params(&wasm_limits)
+        );
+    }
+
+    /// Start a shell backed by RocksDB in the given base directory
+    fn start_persistent_shell(
+        base_dir: &Path,
+        chain_id: ChainId,
+        force_chain_id: bool,
+    ) -> Shell<PersistentDB, PersistentStorageHasher> {
+        let (sender, _) = tokio::sync::mpsc::unbounded_channel();
+        let vp_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
+        let tx_wasm_compilation_cache = 50 * 1024 * 1024; // 50 kiB
+        let mut config =
+            config::Ledger::new(base_dir, chain_id, TendermintMode::Validator);
+        config.shell.force_chain_id = force_chain_id;
+        Shell::<PersistentDB, PersistentStorageHasher>::new(
+            config,
+            top_level_directory().join("wasm"),
+            sender,
+            None,
+            vp_wasm_compilation_cache,
+            tx_wasm_compilation_cache,
+        )
+    }
+
+    /// Start a shell with the first chain ID to persist it in the DB and then
+    /// move its data to be used with the second chain ID.
+    fn move_chain_data(base_dir: &Path) -> (ChainId, ChainId) {
+        let chain_a = ChainId::from_genesis(
+            ChainIdPrefix::from_str("chain-a").unwrap(),
+            "genesis a",
+        );
+        let chain_b = ChainId::from_genesis(
+            ChainIdPrefix::from_str("chain-b").unwrap(),
+            "genesis b",
+        );
+        let shell = start_persistent_shell(base_dir, chain_a.clone(), false);
+        std::mem::drop(shell);
+        std::fs::rename(
+            base_dir.join(chain_a.as_str()),
+            base_dir.join(chain_b.as_str()),
+        )
+        .unwrap();
+        (chain_a, chain_b)
+    }
+
+    /// Test that the shell refuses to start with a DB of another chain
+    #[test]
+    #[should_panic(expected = "Refusing to start the ledger")]
+    fn test_chain_id_mismatch_refuses_to_start() {
+        let base_dir = tempdir().unwrap().as_ref().canonicalize().unwrap();
+        let (_chain_a, chain_b) = move_chain_data(&base_dir);
+        start_persistent_shell(&base_dir, chain_b, false);
+    }
+
+    /// Test that the shell starts with a DB of another chain when forced and
+    /// that it overwrites the stored chain ID
+    #[test]
+    fn test_chain_id_mismatch_forced() {
+        let base_dir = tempdir().unwrap().as_ref().canonicalize().unwrap();
+        let (_chain_a, chain_b) = move_chain_data(&base_dir);
+        let shell = start_persistent_shell(&base_dir, chain_b.clone(), true);
+        assert_eq!(
+            shell.storage.db.read_chain_id().unwrap(),
+            Some(chain_b.clone())
+        );
+        std::mem::drop(shell);
+
+        // The overwritten chain ID is accepted without forcing it again
+        start_persistent_shell(&base_dir, chain_b, false);
+    }
+
+    /// Test that committing blocks with the Merkle root consistency check
+    /// enabled doesn't report any mismatch for valid operations
+    #[test]
+    fn test_commit_with_merkle_root_check() {
+        let base_dir = tempdir().unwrap().as_ref().canonicalize().unwrap();
+        let mut shell =
+            start_persistent_shell(&base_dir, Default::default(), false);
+        shell.verify_merkle_root = true;
+        for height in 1..=3_u64 {
+            shell
+                .storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            let key = Key::parse(format!("key{}", height)).unwrap();
+            shell
+                .write_log
+                .write(&key, height.try_to_vec().unwrap())
+                .unwrap();
+            if height == 3 {
+                shell
+                    .write_log
+                    .delete(&Key::parse("key1").unwrap())
+                    .unwrap();
+            }
+            shell.write_log.commit_tx();
+            // panics on a mismatch
+            shell.commit();
+        }
+    }
+
+    /// Test that the mempool rejects a wrapper tx whose fee payer cannot pay
+    /// its fee and accepts it once the payer has the balance
+    #[test]
+    fn test_mempool_checks_fee_balance() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let payer = address::Address::from(&keypair.ref_to());
+        let tx = Tx::new(
+            "wasm_code".as_bytes().to_owned(),
+            Some("transaction data".as_bytes().to_owned()),
+        );
+        let wrapper = WrapperTx::new(
+            Fee {
+                amount: token::Amount::whole(2),
+                token: xan(),
+            },
+            &keypair,
+            Epoch(0),
+            0.into(),
+            tx,
+            Default::default(),
+        )
+        .sign(&keypair)
+        .expect("Test failed")
+        .to_bytes();
+
+        let response = shell
+            .shell
+            .mempool_validate(&wrapper, MempoolTxType::NewTransaction);
+        assert_eq!(
+            response.code,
+            u32::from(ErrorCodes::InsufficientFeeBalance)
+        );
+
+        shell
+            .shell
+            .storage
+            .write(
+                &token::balance_key(&xan(), &payer),
+                token::Amount::whole(2).try_to_vec().expect("Test failed"),
+            )
+            .expect("Test failed");
+        let response = shell
+            .shell
+            .mempool_validate(&wrapper, MempoolTxType::NewTransaction);
+        assert_eq!(response.code, u32::from(ErrorCodes::Ok));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::ledger::storage::types;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::node::ledger::storage::PersistentStorage;
+
+    /// Commit a balance and a raw value in the storage of the given node
+    fn commit_test_state(
+        config: &config::Ledger,
+    ) -> (PersistentStorage, Key, token::Amount, Key) {
+        std::fs::create_dir_all(config.db_dir())
+            .expect("Unable to create the DB directory");
+        let mut storage =
+            PersistentStorage::open(config.db_dir(), ChainId::default(), None);
+        storage
+            .begin_block(BlockHash::default(), BlockHeight(100))
+            .expect("begin_block failed");
+        let balance_key = token::balance_key(
+            &address::xan(),
+            &address::testing::established_address_1(),
+        );
+        let amount = token::Amount::from(100);
+        storage
+            .write(&balance_key, types::encode(&amount))
+            .expect("write failed");
+        let raw_key = Key::parse("raw").expect("cannot parse the key string");
+        storage
+            .write(&raw_key, vec![0xde, 0xad])
+            .expect("write failed");
+        storage.commit().expect("commit failed");
+        (storage, balance_key, amount, raw_key)
+    }
+
+    /// Test that the state dump contains the committed keys with their
+    /// decoded or hex-encoded values and that it's refused while the DB is in
+    /// use
+    #[test]
+    fn test_dump_state() {
+        let base_dir =
+            TempDir::new().expect("Unable to create a temporary directory");
+        let config = config::Ledger::new(
+            base_dir.path(),
+            ChainId::default(),
+            TendermintMode::Full,
+        );
+        let (storage, balance_key, amount, raw_key) =
+            commit_test_state(&config);
+
+        let output = base_dir.path().join("state.json");
+        // the DB is locked while it's in use
+        assert!(dump_state(config.clone(), &output).is_err());
+
+        drop(storage);
+        let count = dump_state(config, &output).expect("dump failed");
+        assert_eq!(count, 2);
+
+        let file = File::open(&output).expect("cannot open the dump");
+        let dump: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_reader(file).expect("invalid JSON");
+        assert_eq!(dump.len(), 2);
+        let balance = &dump[&balance_key.to_string()];
+        assert_eq!(balance["value"], amount.to_string());
+        assert_eq!(balance["bytes"], hex::encode(types::encode(&amount)));
+        let raw = &dump[&raw_key.to_string()];
+        assert_eq!(raw["value"], "dead");
+        assert_eq!(raw["bytes"], "dead");
+    }
+
+    /// Test that a dumped state imported into an empty node has the same
+    /// Merkle root and values and that a non-empty DB is only overwritten
+    /// when forced
+    #[test]
+    fn test_import_state() {
+        let base_dir =
+            TempDir::new().expect("Unable to create a temporary directory");
+        let config = config::Ledger::new(
+            base_dir.path().join("source"),
+            ChainId::default(),
+            TendermintMode::Full,
+        );
+        let (storage, balance_key, amount, raw_key) =
+            commit_test_state(&config);
+        let root = storage.merkle_root();
+        drop(storage);
+        let dump_path = base_dir.path().join("state.json");
+        dump_state(config, &dump_path).expect("dump failed");
+
+        let config = config::Ledger::new(
+            base_dir.path().join("target"),
+            ChainId::default(),
+            TendermintMode::Full,
+        );
+        let (imported_root, count) =
+            import_state(config.clone(), &dump_path, false)
+                .expect("import failed");
+        assert_eq!(imported_root.0, root.0);
+        assert_eq!(count, 2);
+
+        // the DB now contains a state that is not overwritten unless forced
+        match import_state(config.clone(), &dump_path, false) {
+            Err(Error::NonEmptyDB(_)) => {}
+            res => panic!("expected a non-empty DB error, got {:?}", res),
+        }
+        let (imported_root, _) = import_state(config.clone(), &dump_path, true)
+            .expect("forced import failed");
+        assert_eq!(imported_root.0, root.0);
+
+        // an invalid dump is refused before the existing state is removed
+        let invalid_path = base_dir.path().join("invalid.json");
+        std::fs::write(
+            &invalid_path,
+            format!(
+                "{{\"{}\": {{\"value\": \"\", \"bytes\": \"xyz\"}}}}",
+                raw_key
+            ),
+        )
+        .expect("cannot write the invalid dump");
+        match import_state(config.clone(), &invalid_path, true) {
+            Err(Error::InvalidStateDump(_)) => {}
+            res => panic!("expected an invalid dump error, got {:?}", res),
+        }
+
+        let mut storage =
+            PersistentStorage::open(config.db_dir(), ChainId::default(), None);
+        storage
+            .load_last_state()
+            .expect("loading the last state failed");
+        assert_eq!(storage.merkle_root().0, root.0);
+        let (val, _) = storage.read(&balance_key).expect("read failed");
+        assert_eq!(val.expect("no value"), types::encode(&amount));
+        let (val, _) = storage.read(&raw_key).expect("read failed");
+        assert_eq!(val.expect("no value"), vec![0xde, 0xad]);
+    }
 }
@@ -13,9 +13,11 @@ mod prepare_block {
     {
         /// Begin a new block.
         ///
-        /// We include half of the new wrapper txs given to us from the mempool
-        /// by tendermint. The rest of the block is filled with decryptions
-        /// of the wrapper txs from the previously committed block.
+        /// The block begins with the decryptions of the wrapper txs from the
+        /// previously committed block, in their order. We then include half
+        /// of the new wrapper txs given to us from the mempool by tendermint.
+        /// All the decrypted txs must precede the wrapper txs, otherwise the
+        /// proposal is rejected in [`Shell::process_proposal`].
         ///
         /// INVARIANT: Any changes applied in this method must be reverted if
         /// the proposal is rejected (unless we can simply overwrite
@@ -33,10 +35,24 @@ mod prepare_block {
                 let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
 
                 // TODO: Craft the Ethereum state update tx
+                // decrypt the wrapper txs included in the previous block
+                let mut txs: Vec<TxBytes> = self
+                    .storage
+                    .tx_queue
+                    .iter()
+                    .map(|tx| {
+                        Tx::from(match tx.decrypt(privkey) {
+                            Ok(tx) => DecryptedTx::Decrypted(tx),
+                            _ => DecryptedTx::Undecryptable(tx.clone()),
+                        })
+                        .to_bytes()
+                    })
+                    .collect();
+
                 // filter in half of the new txs from Tendermint, only keeping
                 // wrappers
                 let number_of_new_txs = 1 + req.block_data.len() / 2;
-                let mut txs: Vec<TxBytes> = req
+                let mut wrapper_txs: Vec<TxBytes> = req
                     .block_data
                     .into_iter()
                     .take(number_of_new_txs)
@@ -49,21 +65,7 @@ mod prepare_block {
                     })
                     .collect();
 
-                // decrypt the wrapper txs included in the previous block
-                let mut decrypted_txs = self
-                    .storage
-                    .tx_queue
-                    .iter()
-                    .map(|tx| {
-                        Tx::from(match tx.decrypt(privkey) {
-                            Ok(tx) => DecryptedTx::Decrypted(tx),
-                            _ => DecryptedTx::Undecryptable(tx.clone()),
-                        })
-                        .to_bytes()
-                    })
-                    .collect();
-
-                txs.append(&mut decrypted_txs);
+                txs.append(&mut wrapper_txs);
                 txs
             } else {
                 vec![]
@@ -80,6 +82,7 @@ mod prepare_block {
 
         use super::*;
         use crate::node::ledger::shell::test_utils::{gen_keypair, TestShell};
+        use crate::node::ledger::shims::abcipp_shim_types::shim::request::ProcessProposal;
 
         /// Test that if a tx from the mempool is not a
         /// WrapperTx type, it is not included in the
@@ -138,7 +141,8 @@ mod prepare_block {
 
         /// Test that the decrypted txs are included
         /// in the proposal in the same order as their
-        /// corresponding wrappers
+        /// corresponding wrappers and before the new
+        /// wrapper txs
         #[test]
         fn test_decrypted_txs_in_correct_order() {
             let (mut shell, _) = TestShell::new();
@@ -182,8 +186,8 @@ mod prepare_block {
             // we extract the inner data from the txs for testing
             // equality since otherwise changes in timestamps would
             // fail the test
-            expected_wrapper.append(&mut expected_decrypted);
-            let expected_txs: Vec<Vec<u8>> = expected_wrapper
+            expected_decrypted.append(&mut expected_wrapper);
+            let expected_txs: Vec<Vec<u8>> = expected_decrypted
                 .iter()
                 .map(|tx| tx.data.clone().expect("Test failed"))
                 .collect();
@@ -202,6 +206,60 @@ mod prepare_block {
             // check that the order of the txs is correct
             assert_eq!(received, expected_txs);
         }
+
+        /// Test that a block proposal from [`Shell::prepare_proposal`] is
+        /// accepted by [`Shell::process_proposal`]
+        #[test]
+        fn test_prepared_proposal_is_processed() {
+            let (mut shell, _) = TestShell::new();
+            let keypair = gen_keypair();
+            let mut req = RequestPrepareProposal {
+                block_data: vec![],
+                block_data_size: 0,
+            };
+            // two wrappers from the previous block to be decrypted and two
+            // new wrappers from mempool
+            for i in 0..4 {
+                let tx = Tx::new(
+                    "wasm_code".as_bytes().to_owned(),
+                    Some(
+                        format!("transaction data: {}", i)
+                            .as_bytes()
+                            .to_owned(),
+                    ),
+                );
+                let wrapper_tx = WrapperTx::new(
+                    Fee {
+                        amount: 0.into(),
+                        token: xan(),
+                    },
+                    &keypair,
+                    Epoch(0),
+                    0.into(),
+                    tx,
+                    Default::default(),
+                );
+                if i < 2 {
+                    shell.enqueue_tx(wrapper_tx);
+                } else {
+                    let wrapper =
+                        wrapper_tx.sign(&keypair).expect("Test failed");
+                    req.block_data.push(wrapper.to_bytes());
+                }
+            }
+
+            let block_data = shell.prepare_proposal(req).block_data;
+            assert_eq!(block_data.len(), 4);
+            for tx in block_data {
+                let response = shell.process_proposal(ProcessProposal { tx });
+                assert_eq!(
+                    response.result.code,
+                    u32::from(ErrorCodes::Ok),
+                    "{}",
+                    response.result.info
+                );
+            }
+        }
     }
 }
 
@@ -24,7 +24,8 @@ where
     ///
     /// Checks validity of a decrypted tx or that a tx marked un-decryptable
     /// is in fact so. Also checks that decrypted txs were submitted in
-    /// correct order.
+    /// correct order and that none of them follows a wrapper tx in the
+    /// proposal.
     ///
     /// Error codes:
     ///   0: Ok
@@ -33,6 +34,7 @@ where
     ///   3: Wasm runtime error
     ///   4: Invalid order of decrypted txs
     ///   5. More decrypted txs than expected
+    ///   8: The fee payer cannot pay the fee
     ///
     /// INVARIANT: Any changes applied in this method must be reverted if the
     /// proposal is rejected (unless we can simply overwrite them in the
@@ -75,6 +77,15 @@ where
                            is coming soon to a blockchain near you. Patience."
                         .into(),
                 },
+                // All the decrypted txs must be included before the wrapper
+                // txs of the proposal
+                #[cfg(not(feature = "ABCI"))]
+                TxType::Decrypted(_) if self.wrapper_in_proposal => TxResult {
+                    code: ErrorCodes::InvalidOrder.into(),
+                    info: "Process proposal rejected a decrypted transaction \
+                           that was included after a wrapper transaction"
+                        .into(),
+                },
                 TxType::Decrypted(tx) => match self.next_wrapper() {
                     Some(wrapper) => {
                         if wrapper.tx_hash != tx.hash_commitment() {
@@ -108,6 +119,10 @@ where
                     },
                 },
                 TxType::Wrapper(tx) => {
+                    #[cfg(not(feature = "ABCI"))]
+                    {
+                        self.wrapper_in_proposal = true;
+                    }
                     // validate the ciphertext via Ferveo
                     if !tx.validate_ciphertext() {
                         TxResult {
@@ -120,11 +135,14 @@ where
                         }
                     } else {
                         // check that the fee payer has sufficient balance
-                        let balance = self
-                            .get_balance(&tx.fee.token, &tx.fee_payer())
-                            .unwrap_or_default();
-
-                        if tx.fee.amount <= balance {
+                        let has_balance = protocol::check_fee_balance(
+                            &tx,
+                            &self.storage,
+                            &self.write_log,
+                        )
+                        .is_ok();
+
+                        if has_balance {
                             shim::response::TxResult {
                                 code: ErrorCodes::Ok.into(),
                                 info: "Process proposal accepted this \
@@ -133,7 +151,7 @@ where
                             }
                         } else {
                             shim::response::TxResult {
-                                code: ErrorCodes::InvalidTx.into(),
+                                code: ErrorCodes::InsufficientFeeBalance.into(),
                                 info: "The address given does not have \
                                        sufficient balance to pay fee"
                                     .into(),
@@ -235,6 +253,7 @@ where
         &mut self,
         _req: shim::request::RevertProposal,
     ) -> shim::response::RevertProposal {
+        self.wrapper_in_proposal = false;
         Default::default()
     }
 }
@@ -413,7 +432,10 @@ mod test_process_proposal {
             tx: wrapper.to_bytes(),
         };
         let response = shell.process_proposal(request);
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(
+            response.result.code,
+            u32::from(ErrorCodes::InsufficientFeeBalance)
+        );
         assert_eq!(
             response.result.info,
             "The address given does not have sufficient balance to pay fee"
@@ -465,7 +487,10 @@ mod test_process_proposal {
         };
 
         let response = shell.process_proposal(request);
-        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidTx));
+        assert_eq!(
+            response.result.code,
+            u32::from(ErrorCodes::InsufficientFeeBalance)
+        );
         assert_eq!(
             response.result.info,
             String::from(
@@ -527,6 +552,58 @@ mod test_process_proposal {
         );
     }
 
+    #[cfg(not(feature = "ABCI"))]
+    /// Test that if a decrypted tx is included after a wrapper tx in a block
+    /// proposal, [`process_proposal`] rejects it
+    #[test]
+    fn test_decrypted_tx_after_wrapper() {
+        let (mut shell, _) = TestShell::new();
+        let keypair = gen_keypair();
+        let mut wrappers = vec![];
+        let mut txs = vec![];
+        for i in 0..2 {
+            let tx = Tx::new(
+                "wasm_code".as_bytes().to_owned(),
+                Some(format!("transaction data: {}", i).as_bytes().to_owned()),
+            );
+            let wrapper = WrapperTx::new(
+                Fee {
+                    amount: 0.into(),
+                    token: xan(),
+                },
+                &keypair,
+                Epoch(0),
+                0.into(),
+                tx.clone(),
+                Default::default(),
+            );
+            wrappers.push(wrapper);
+            txs.push(tx);
+        }
+        // The first wrapper was included in the previous block
+        shell.enqueue_tx(wrappers[0].clone());
+        let decrypted =
+            Tx::from(TxType::Decrypted(DecryptedTx::Decrypted(txs[0].clone())));
+        let wrapper = wrappers[1].sign(&keypair).expect("Test failed");
+
+        let response = shell.process_proposal(ProcessProposal {
+            tx: wrapper.to_bytes(),
+        });
+        assert_eq!(response.result.code, u32::from(ErrorCodes::Ok));
+
+        let response = shell.process_proposal(ProcessProposal {
+            tx: decrypted.to_bytes(),
+        });
+        assert_eq!(response.result.code, u32::from(ErrorCodes::InvalidOrder));
+        assert_eq!(
+            response.result.info,
+            String::from(
+                "Process proposal rejected a decrypted transaction that was \
+                 included after a wrapper transaction"
+            ),
+        );
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// Test that a tx incorrectly labelled as undecryptable
     /// is rejected by [`process_proposal`]
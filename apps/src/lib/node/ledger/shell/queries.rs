@@ -2,7 +2,7 @@
 use std::cmp::max;
 
 use anoma::ledger::parameters::EpochDuration;
-use anoma::ledger::pos::PosParams;
+use anoma::ledger::pos::{BondId, Bonds, PosParams, Unbonds};
 use anoma::types::address::Address;
 use anoma::types::key;
 use anoma::types::key::dkg_session_keys::DkgPublicKey;
@@ -37,9 +37,34 @@ where
     /// INVARIANT: This method must be stateless.
     pub fn query(&self, query: request::Query) -> response::Query {
         use rpc::Path;
+        let height = match self.get_query_height(query.height) {
+            Ok(height) => height,
+            Err(info) => {
+                return response::Query {
+                    code: 1,
+                    info,
+                    ..Default::default()
+                };
+            }
+        };
         match Path::from_str(&query.path) {
+            Ok(path)
+                if !path.is_historical()
+                    && height != self.storage.last_height =>
+            {
+                response::Query {
+                    code: 1,
+                    info: format!(
+                        "The query path {} can only be queried at the last \
+                         committed height {}, not at {}",
+                        path, self.storage.last_height, height
+                    ),
+                    ..Default::default()
+                }
+            }
             Ok(path) => match path {
                 Path::DryRunTx => self.dry_run_tx(&query.data),
+                Path::SimulateTx => self.simulate_tx(&query.data),
                 Path::Epoch => {
                     let (epoch, _gas) = self.storage.get_last_epoch();
                     let value = anoma::ledger::storage::types::encode(&epoch);
@@ -49,12 +74,18 @@ where
                     }
                 }
                 Path::Value(storage_key) => {
-                    self.read_storage_value(&storage_key, query.prove)
+                    self.read_storage_value(&storage_key, height, query.prove)
                 }
                 Path::Prefix(storage_key) => {
                     self.read_storage_prefix(&storage_key, query.prove)
                 }
                 Path::HasKey(storage_key) => self.has_storage_key(&storage_key),
+                Path::Balance { token, owner } => {
+                    self.read_balance(&token, &owner, height)
+                }
+                Path::Bonds { owner, validator } => {
+                    self.read_bonds(owner, validator, height)
+                }
             },
             Err(err) => response::Query {
                 code: 1,
@@ -71,8 +102,11 @@ where
         token: &Address,
         owner: &Address,
     ) -> std::result::Result<Amount, String> {
-        let query_resp =
-            self.read_storage_value(&token::balance_key(token, owner), false);
+        let query_resp = self.read_storage_value(
+            &token::balance_key(token, owner),
+            self.storage.last_height,
+            false,
+        );
         if query_resp.code != 0 {
             Err(format!(
                 "Unable to read token {} balance of the given address {}",
@@ -88,56 +122,153 @@ where
         }
     }
 
-    /// Query to read a value from storage
+    /// Get the block height at which a query should be answered. Tendermint
+    /// sends `0` for the latest committed height.
+    fn get_query_height(
+        &self,
+        height: i64,
+    ) -> std::result::Result<BlockHeight, String> {
+        let height = u64::try_from(height)
+            .map_err(|_| format!("Invalid query block height {}", height))?;
+        if height == 0 {
+            Ok(self.storage.last_height)
+        } else if height > self.storage.last_height.0 {
+            Err(format!(
+                "The block height {} has not been committed yet, the last \
+                 committed height is {}",
+                height, self.storage.last_height
+            ))
+        } else {
+            Ok(BlockHeight(height))
+        }
+    }
+
+    /// Query to read the balance of the owner of the given token at the given
+    /// block height. The value in a successful response is an [`Amount`]
+    /// encoded with [`BorshSerialize`], which is zero if the owner has no
+    /// balance.
+    fn read_balance(
+        &self,
+        token: &Address,
+        owner: &Address,
+        height: BlockHeight,
+    ) -> response::Query {
+        match self
+            .storage
+            .read_with_height(&token::balance_key(token, owner), height)
+        {
+            Ok((Some(value), _gas)) => response::Query {
+                value,
+                ..Default::default()
+            },
+            Ok((None, _gas)) => response::Query {
+                value: Amount::default().try_to_vec().unwrap(),
+                ..Default::default()
+            },
+            Err(err) => response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Read the PoS bonds and unbonds of the given owner to the given
+    /// validator. The response value is the borsh encoded pair of optional
+    /// [`Bonds`] and [`Unbonds`], which are `None` if there are no bonds or
+    /// unbonds.
+    fn read_bonds(
+        &self,
+        owner: Address,
+        validator: Address,
+        height: BlockHeight,
+    ) -> response::Query {
+        let bond_id = BondId {
+            source: owner,
+            validator,
+        };
+        let read = |key: &Key| match self.storage.read_with_height(key, height)
+        {
+            Ok((value, _gas)) => Ok(value),
+            Err(err) => Err(response::Query {
+                code: 2,
+                info: format!("Storage error: {}", err),
+                ..Default::default()
+            }),
+        };
+        let decode_error = |key: Key, err: std::io::Error| response::Query {
+            code: 2,
+            info: format!("Error decoding the value of {}: {}", key, err),
+            ..Default::default()
+        };
+        let bond_key = pos::bond_key(&bond_id);
+        let bonds = match read(&bond_key) {
+            Ok(value) => match value
+                .map(|value| Bonds::try_from_slice(&value[..]))
+                .transpose()
+            {
+                Ok(bonds) => bonds,
+                Err(err) => return decode_error(bond_key, err),
+            },
+            Err(response) => return response,
+        };
+        let unbond_key = pos::unbond_key(&bond_id);
+        let unbonds = match read(&unbond_key) {
+            Ok(value) => match value
+                .map(|value| Unbonds::try_from_slice(&value[..]))
+                .transpose()
+            {
+                Ok(unbonds) => unbonds,
+                Err(err) => return decode_error(unbond_key, err),
+            },
+            Err(response) => return response,
+        };
+        response::Query {
+            value: (bonds, unbonds).try_to_vec().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Query to read a value from storage at the given block height. Merkle
+    /// proofs can only be requested for the last committed height.
     pub fn read_storage_value(
         &self,
         key: &Key,
+        height: BlockHeight,
         is_proven: bool,
     ) -> response::Query {
-        match self.storage.read(key) {
-            Ok((Some(value), _gas)) => {
-                let proof_ops = if is_proven {
-                    match self.storage.get_existence_proof(key, value.clone()) {
-                        Ok(proof) => Some(proof.into()),
-                        Err(err) => {
-                            return response::Query {
-                                code: 2,
-                                info: format!("Storage error: {}", err),
-                                ..Default::default()
-                            };
-                        }
-                    }
-                } else {
-                    None
-                };
-                response::Query {
-                    value,
-                    proof_ops,
-                    ..Default::default()
-                }
-            }
-            Ok((None, _gas)) => {
-                let proof_ops = if is_proven {
-                    match self.storage.get_non_existence_proof(key) {
-                        Ok(proof) => Some(proof.into()),
-                        Err(err) => {
-                            return response::Query {
-                                code: 2,
-                                info: format!("Storage error: {}", err),
-                                ..Default::default()
-                            };
-                        }
-                    }
-                } else {
-                    None
-                };
-                response::Query {
-                    code: 1,
-                    info: format!("No value found for key: {}", key),
-                    proof_ops,
-                    ..Default::default()
-                }
-            }
+        if is_proven && height != self.storage.last_height {
+            return response::Query {
+                code: 1,
+                info: format!(
+                    "Proofs are only available at the last committed height \
+                     {}",
+                    self.storage.last_height
+                ),
+                ..Default::default()
+            };
+        }
+        let read = if is_proven {
+            self.storage
+                .read_with_proof(key)
+                .map(|(value, proof)| (value, Some(ProofOps::from(proof))))
+        } else {
+            self.storage
+                .read_with_height(key, height)
+                .map(|(value, _gas)| (value, None))
+        };
+        match read {
+            Ok((Some(value), proof_ops)) => response::Query {
+                value,
+                proof_ops,
+                ..Default::default()
+            },
+            Ok((None, proof_ops)) => response::Query {
+                code: 1,
+                info: format!("No value found for key: {}", key),
+                proof_ops,
+                ..Default::default()
+            },
             Err(err) => response::Query {
                 code: 2,
                 info: format!("Storage error: {}", err),
@@ -308,3 +439,204 @@ where
             })
     }
 }
+
+#[cfg(test)]
+mod test_queries {
+    use anoma::ledger::pos::types::Epoch as PosEpoch;
+    use anoma::types::address::xan;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::{setup, top_level_directory};
+    use crate::{wallet, wasm_loader};
+
+    /// Test that the balance query returns the balance funded in genesis and
+    /// a zero amount for an owner without any balance
+    #[test]
+    fn test_balance_query() {
+        let (shell, _) = setup();
+        let query = |owner: Address| {
+            let path = rpc::Path::Balance {
+                token: xan(),
+                owner,
+            };
+            let response = shell.shell.query(request::Query {
+                path: path.to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            Amount::try_from_slice(&response.value[..]).unwrap()
+        };
+
+        assert_eq!(
+            query(wallet::defaults::albert_address()),
+            token::Amount::whole(1_000_000)
+        );
+        assert_eq!(
+            query(address::testing::established_address_1()),
+            Amount::default()
+        );
+    }
+
+    /// Test that the bonds query returns the genesis validator's self-bond
+    /// and empty bonds and unbonds for an owner without any bonds
+    #[test]
+    fn test_bonds_query() {
+        let (shell, _) = setup();
+        let query = |owner: Address, validator: Address| {
+            let path = rpc::Path::Bonds { owner, validator };
+            let response = shell.shell.query(request::Query {
+                path: path.to_string(),
+                ..Default::default()
+            });
+            assert_eq!(response.code, 0, "{}", response.info);
+            <(Option<Bonds>, Option<Unbonds>)>::try_from_slice(
+                &response.value[..],
+            )
+            .unwrap()
+        };
+
+        let (current_epoch, _gas) = shell.shell.storage.get_current_epoch();
+        let validator = shell
+            .shell
+            .storage
+            .read_validator_set()
+            .get(current_epoch)
+            .unwrap()
+            .active
+            .iter()
+            .next()
+            .unwrap()
+            .address
+            .clone();
+        let (bonds, unbonds) = query(validator.clone(), validator.clone());
+        // The genesis self-bond is active from the genesis epoch
+        let bond = bonds.unwrap().get(current_epoch).unwrap();
+        let genesis_stake =
+            bond.deltas.get(&PosEpoch::from(current_epoch)).unwrap();
+        assert_ne!(*genesis_stake, Amount::default());
+        assert_eq!(bond.deltas.len(), 1);
+        assert!(unbonds.is_none());
+
+        let (bonds, unbonds) =
+            query(address::testing::established_address_1(), validator.clone());
+        assert!(bonds.is_none());
+        assert!(unbonds.is_none());
+
+        // Bonds that cannot be decoded are reported in the response
+        let owner = address::testing::established_address_2();
+        let bond_id = BondId {
+            source: owner.clone(),
+            validator: validator.clone(),
+        };
+        shell
+            .shell
+            .storage
+            .write(&pos::bond_key(&bond_id), vec![0xff; 3])
+            .unwrap();
+        let path = rpc::Path::Bonds { owner, validator };
+        let response = shell.shell.query(request::Query {
+            path: path.to_string(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 2);
+        assert!(
+            response.info.contains("Error decoding"),
+            "{}",
+            response.info
+        );
+    }
+
+    /// Test that the paths that can only be answered from the last committed
+    /// state reject a query at a past height, while the historical paths
+    /// accept it
+    #[test]
+    fn test_past_height_query() {
+        let (mut shell, _) = setup();
+        shell.shell.storage.last_height = BlockHeight(2);
+        let key =
+            token::balance_key(&xan(), &wallet::defaults::albert_address());
+        let query = |path: rpc::Path, height: i64| {
+            shell.shell.query(request::Query {
+                path: path.to_string(),
+                height,
+                ..Default::default()
+            })
+        };
+
+        for path in [
+            rpc::Path::Epoch,
+            rpc::Path::Prefix(key.clone()),
+            rpc::Path::HasKey(key.clone()),
+            rpc::Path::DryRunTx,
+            rpc::Path::SimulateTx,
+        ] {
+            let response = query(path.clone(), 1);
+            assert_eq!(response.code, 1, "{}", path);
+            assert!(response.info.contains("last committed height"));
+        }
+        assert_eq!(query(rpc::Path::Epoch, 2).code, 0);
+        assert_eq!(query(rpc::Path::Epoch, 0).code, 0);
+
+        let response = query(rpc::Path::Value(key), 1);
+        assert_eq!(response.code, 0, "{}", response.info);
+    }
+
+    /// Test that simulating a transfer returns the writes of the source and
+    /// target balances without modifying the storage
+    #[test]
+    fn test_simulate_tx_query() {
+        let (shell, _) = setup();
+        let source = wallet::defaults::albert_address();
+        let target = wallet::defaults::bertha_address();
+        let source_balance = shell.shell.get_balance(&xan(), &source).unwrap();
+        let target_balance = shell.shell.get_balance(&xan(), &target).unwrap();
+        let amount = Amount::whole(10);
+
+        let tx_code = wasm_loader::read_wasm(
+            top_level_directory().join("wasm"),
+            "tx_transfer.wasm",
+        );
+        let transfer = token::Transfer {
+            source: source.clone(),
+            target: target.clone(),
+            token: xan(),
+            amount,
+        };
+        let tx = Tx::new(tx_code, Some(transfer.try_to_vec().unwrap()))
+            .sign(&wallet::defaults::albert_keypair());
+        let response = shell.shell.query(request::Query {
+            path: rpc::Path::SimulateTx.to_string(),
+            data: tx.to_bytes(),
+            ..Default::default()
+        });
+        assert_eq!(response.code, 0, "{}", response.log);
+        let simulation =
+            rpc::TxSimulation::try_from_slice(&response.value[..]).unwrap();
+        assert!(simulation.vps_result.rejected_vps.is_empty());
+        assert!(simulation.gas_used > 0);
+
+        let written_amount = |owner: &Address| {
+            let key = token::balance_key(&xan(), owner);
+            match simulation.modifications.get(&key) {
+                Some(StorageModification::Write { value }) => {
+                    Amount::try_from_slice(&value[..]).unwrap()
+                }
+                modification => {
+                    panic!("Unexpected balance modification {:?}", modification)
+                }
+            }
+        };
+        let mut expected_source = source_balance;
+        expected_source.spend(&amount);
+        let mut expected_target = target_balance;
+        expected_target.receive(&amount);
+        assert_eq!(written_amount(&source), expected_source);
+        assert_eq!(written_amount(&target), expected_target);
+
+        // Nothing has been applied
+        assert_eq!(
+            shell.shell.get_balance(&xan(), &source).unwrap(),
+            source_balance
+        );
+    }
+}
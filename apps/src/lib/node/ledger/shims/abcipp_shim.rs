@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use anoma::ledger::storage::{DBIter, DB};
 use anoma::types::storage::BlockHeight;
 use futures::future::FutureExt;
 use tokio::sync::mpsc::UnboundedSender;
@@ -19,13 +20,17 @@ use crate::config;
 use crate::node::ledger::shims::abcipp_shim_types::shim::request::{
     BeginBlock, ProcessedTx,
 };
+use crate::node::ledger::storage;
 
 /// The shim wraps the shell, which implements ABCI++.
 /// The shim makes a crude translation between the ABCI interface currently used
 /// by tendermint and the shell's interface.
 #[derive(Debug)]
-pub struct AbcippShim {
-    service: Shell,
+pub struct AbcippShim<D = storage::PersistentDB>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+{
+    service: Shell<D>,
     begin_block_request: Option<BeginBlock>,
     block_txs: Vec<ProcessedTx>,
     shell_recv: std::sync::mpsc::Receiver<(
@@ -34,14 +39,17 @@ pub struct AbcippShim {
     )>,
 }
 
-impl AbcippShim {
+impl<D> AbcippShim<D>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+{
     /// Create a shell with a ABCI service that passes messages to and from the
     /// shell.
     pub fn new(
         config: config::Ledger,
         wasm_dir: PathBuf,
         broadcast_sender: UnboundedSender<Vec<u8>>,
-        db_cache: &rocksdb::Cache,
+        db_cache: Option<&D::Cache>,
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
     ) -> (Self, AbciService) {
@@ -54,7 +62,7 @@ impl AbcippShim {
                     config,
                     wasm_dir,
                     broadcast_sender,
-                    Some(db_cache),
+                    db_cache,
                     vp_wasm_compilation_cache,
                     tx_wasm_compilation_cache,
                 ),
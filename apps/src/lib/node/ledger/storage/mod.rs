@@ -18,6 +18,8 @@ pub type PersistentDB = rocksdb::RocksDB;
 
 pub type PersistentStorage = Storage<PersistentDB, PersistentStorageHasher>;
 
+pub use self::rocksdb::{compact as compact_db, open as open_db};
+
 impl Hasher for PersistentStorageHasher {
     fn write_h256(&mut self, h: &H256) {
         self.0.write_h256(h)
@@ -141,6 +143,47 @@ mod tests {
         assert_eq!(val.expect("no value"), value_bytes);
     }
 
+    /// Test that a compacted DB keeps its state and that the compaction is
+    /// refused while the DB is in use
+    #[test]
+    fn test_compact_db() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut storage =
+            PersistentStorage::open(db_path.path(), ChainId::default(), None);
+        storage
+            .begin_block(BlockHash::default(), BlockHeight(100))
+            .expect("begin_block failed");
+        let key = Key::parse("key").expect("cannot parse the key string");
+        // overwrite the same key to leave some stale values in the DB
+        for value in 0..100_u64 {
+            storage
+                .write(&key, types::encode(&value))
+                .expect("write failed");
+        }
+        storage.commit().expect("commit failed");
+        let root = storage.merkle_root().0;
+
+        // the DB is locked while it's in use
+        assert!(compact_db(db_path.path()).is_err());
+
+        drop(storage);
+        compact_db(db_path.path()).expect("compaction failed");
+
+        // load the last state
+        let mut storage =
+            PersistentStorage::open(db_path.path(), ChainId::default(), None);
+        storage
+            .load_last_state()
+            .expect("loading the last state failed");
+        let (loaded_root, height) =
+            storage.get_state().expect("no block exists");
+        assert_eq!(loaded_root.0, root);
+        assert_eq!(height, 100);
+        let (val, _) = storage.read(&key).expect("read failed");
+        assert_eq!(val.expect("no value"), types::encode(&99_u64));
+    }
+
     #[test]
     fn test_iter() {
         let db_path =
@@ -35,6 +35,7 @@ use anoma::ledger::storage::{
     types, BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, Error,
     MerkleTreeStoresRead, Result, StoreType, DB,
 };
+use anoma::types::chain::ChainId;
 use anoma::types::storage::{
     BlockHeight, Key, KeySeg, TxQueue, KEY_SEGMENT_SEPARATOR,
 };
@@ -116,6 +117,16 @@ pub fn open(
         .map_err(|e| Error::DBError(e.into_string()))
 }
 
+/// Open the DB at the given path, flush it and compact its whole key range, so
+/// that the overwritten and deleted values are dropped from its SST files. It
+/// fails if the DB is locked by a running ledger.
+pub fn compact(path: impl AsRef<Path>) -> Result<()> {
+    let db = open(path, None)?;
+    db.flush(true)?;
+    db.0.compact_range::<&[u8], &[u8]>(None, None);
+    Ok(())
+}
+
 /// A custom key comparator is used to sort keys by the height. In
 /// lexicographical order, the height aren't ordered. For example, "11" is
 /// before "2".
@@ -521,6 +532,48 @@ impl DB for RocksDB {
         self.flush(false)
     }
 
+    fn read_chain_id(&self) -> Result<Option<ChainId>> {
+        match self
+            .0
+            .get("chain_id")
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_chain_id(&mut self, chain_id: &ChainId) -> Result<()> {
+        self.0
+            .put("chain_id", types::encode(chain_id))
+            .map_err(|e| Error::DBError(e.into_string()))?;
+        // Wait for the chain ID to be flushed, it's only written on startup
+        self.flush(true)
+    }
+
+    fn prune_height(&mut self, height: BlockHeight) -> Result<()> {
+        let prefix = format!("{}/", height.raw());
+
+        let mut read_opts = ReadOptions::default();
+        // don't use the prefix bloom filter
+        read_opts.set_total_order_seek(true);
+        let iter = self.0.iterator_opt(
+            IteratorMode::From(prefix.as_bytes(), Direction::Forward),
+            read_opts,
+        );
+
+        let mut batch = WriteBatch::default();
+        for (key, _value) in iter {
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            batch.delete(key);
+        }
+        self.exec_batch(batch)
+    }
+
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
         let subspace_key =
             Key::parse("subspace").map_err(Error::KeyError)?.join(key);
@@ -529,6 +582,51 @@ impl DB for RocksDB {
             .map_err(|e| Error::DBError(e.into_string()))
     }
 
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        // Find the first block after the given height in which the key has
+        // been changed
+        let mut h = height.next_height();
+        while h <= last_height {
+            let key_prefix = Key::from(h.to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?;
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            if let Some(old_value) = self
+                .0
+                .get(old_val_key)
+                .map_err(|e| Error::DBError(e.into_string()))?
+            {
+                return Ok(Some(old_value));
+            }
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            if self
+                .0
+                .get(new_val_key)
+                .map_err(|e| Error::DBError(e.into_string()))?
+                .is_some()
+            {
+                // The key has been created after the given height
+                return Ok(None);
+            }
+            h = h.next_height();
+        }
+        // The key hasn't been changed after the given height
+        self.read_subspace_val(key)
+    }
+
     fn write_subspace_val(
         &mut self,
         height: BlockHeight,
@@ -578,7 +676,7 @@ impl DB for RocksDB {
         // Check the length of previous value, if any
         let prev_len = match self
             .0
-            .get(key.to_string())
+            .get(subspace_key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(prev_value) => {
@@ -662,7 +760,7 @@ impl DB for RocksDB {
         // Check the length of previous value, if any
         let prev_len = match self
             .0
-            .get(key.to_string())
+            .get(subspace_key.to_string())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
             Some(prev_value) => {
@@ -331,9 +331,13 @@ async fn update_tendermint_config(
     config.p2p.pex = tendermint_config.p2p_pex;
     config.p2p.allow_duplicate_ip = tendermint_config.p2p_allow_duplicate_ip;
 
-    // In "dev", only produce blocks when there are txs or when the AppHash
-    // changes
-    config.consensus.create_empty_blocks = true; // !cfg!(feature = "dev");
+    config.consensus.create_empty_blocks =
+        tendermint_config.consensus_create_empty_blocks;
+    if let Some(interval) =
+        tendermint_config.consensus_create_empty_blocks_interval
+    {
+        config.consensus.create_empty_blocks_interval = interval;
+    }
     config.consensus.timeout_commit =
         tendermint_config.consensus_timeout_commit;
 
@@ -1,16 +1,24 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use anoma::proto::Tx;
+use anoma::proto::{IntentId, Signed, Tx};
 use anoma::types::address::{self, Address};
 use anoma::types::dylib;
+use anoma::types::hash::Hash;
 use anoma::types::intent::{IntentTransfers, MatchedExchanges};
 use anoma::types::key::*;
-use anoma::types::matchmaker::AddIntentResult;
+use anoma::types::matchmaker::{
+    AddIntentResult, MatchNotice, ENV_VAR_LOGS_DIR, ENV_VAR_SNAPSHOTS_DIR,
+};
 use anoma::types::transaction::{hash_tx, Fee, WrapperTx};
+use anoma::vm::{self, WasmValidationError};
 use borsh::{BorshDeserialize, BorshSerialize};
 use libc::c_void;
 use libloading::Library;
@@ -22,6 +30,11 @@ use tendermint_config::net::Address as TendermintAddress;
 use tendermint_config_abci::net;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+use thiserror::Error;
 
 use super::gossip::rpc::matchmakers::{
     ClientDialer, ClientListener, MsgFromClient, MsgFromServer,
@@ -31,12 +44,67 @@ use crate::client::rpc;
 use crate::client::tx::{broadcast_tx, TxBroadcastData};
 use crate::{cli, config, wasm_loader};
 
+/// The maximum time to wait for results that are still being processed when
+/// the matchmaker is shutting down.
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// The maximum number of matchmaker results that may be waiting to be
+/// processed by the [`ResultHandler`].
+const RESULT_CHANNEL_CAPACITY: usize = 100;
+/// The maximum time to wait for the [`ResultHandler`] to make room for a new
+/// result when its channel is full.
+const SEND_RESULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The interval of retrying to send a result when the channel is full.
+const SEND_RESULT_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+/// The window within which a match identical to an already injected one is
+/// not injected again.
+const INJECTED_MATCH_WINDOW: Duration = Duration::from_secs(60);
+/// The interval of querying the height of the last committed block for the
+/// matchmaker implementation.
+const BLOCK_HEIGHT_QUERY_INTERVAL: Duration = Duration::from_secs(5);
+/// The maximum time to wait for the ledger to respond to a block height query.
+const BLOCK_HEIGHT_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// The sub-directory of the working directory for the matchmaker's snapshots
+const SNAPSHOTS_DIR: &str = "snapshots";
+/// The sub-directory of the working directory for the matchmaker's logs
+const LOGS_DIR: &str = "logs";
+/// The magic number at the start of every wasm module
+const WASM_MAGIC_NUMBER: &[u8] = b"\0asm";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error(
+        "Permission denied to create the matchmaker directory {0}. Please \
+         choose a working directory that is writable by the matchmaker."
+    )]
+    PermissionDenied(PathBuf),
+    #[error("Failed to create the matchmaker directory {0}: {1}")]
+    CreateDir(PathBuf, std::io::Error),
+    #[error("The matchmaker library {0} doesn't exist.")]
+    MatchmakerNotFound(PathBuf),
+    #[error(
+        "The matchmaker library couldn't be found. Did you build it? \
+         Attempted to find it in directories \"{0}\", \"{1}\" and \"{2}\"."
+    )]
+    MatchmakerNotFoundInDirs(PathBuf, PathBuf, PathBuf),
+    #[error("Failed to read the matchmaker library {0}: {1}")]
+    ReadMatchmaker(PathBuf, std::io::Error),
+    #[error("Failed to read the matchmaker's transaction code {0}: {1}")]
+    ReadTxCode(PathBuf, std::io::Error),
+    #[error("The matchmaker's transaction code {0} is not a wasm module.")]
+    TxCodeNotWasm(PathBuf),
+    #[error("The matchmaker's transaction code {0} is not valid: {1}")]
+    InvalidTxCode(PathBuf, WasmValidationError),
+}
+
 /// Run a matchmaker
 #[tokio::main]
 pub async fn run(
     config::Matchmaker {
         matchmaker_path,
         tx_code_path,
+        tick_interval,
+        working_dir,
+        dry_run,
     }: config::Matchmaker,
     intent_gossiper_addr: SocketAddr,
     ledger_addr: TendermintAddress,
@@ -53,14 +121,40 @@ pub async fn run(
         cli::safe_exit(1);
     });
 
+    // Check the matchmaker's library and transaction code before relying on
+    // them
+    let matchmaker_path = find_matchmaker_dylib(&matchmaker_path)
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            cli::safe_exit(1);
+        });
+    let tx_code =
+        load_tx_code(&wasm_dir, &tx_code_path).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            cli::safe_exit(1);
+        });
+
+    // Prepare the working directory and let the matchmaker implementation
+    // know about its layout
+    if let Some(working_dir) = working_dir {
+        let working_dir = WorkingDir::new(working_dir);
+        if let Err(err) = working_dir.create() {
+            eprintln!("{}", err);
+            cli::safe_exit(1);
+        }
+        env::set_var(ENV_VAR_SNAPSHOTS_DIR, &working_dir.snapshots);
+        env::set_var(ENV_VAR_LOGS_DIR, &working_dir.logs);
+    }
+
     let (runner, result_handler) = Runner::new_pair(
         intent_gossiper_addr,
         matchmaker_path,
-        tx_code_path,
+        tx_code,
+        tick_interval.map(|interval| *interval),
+        dry_run,
         ledger_addr,
         tx_signing_key,
         tx_source_address,
-        wasm_dir,
     );
 
     // Instantiate and run the matchmaker implementation in a dedicated thread
@@ -77,16 +171,74 @@ pub async fn run(
     }
 }
 
+/// The layout of the matchmaker's working directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingDir {
+    /// The directory for the matchmaker's snapshots
+    pub snapshots: PathBuf,
+    /// The directory for the matchmaker's logs
+    pub logs: PathBuf,
+}
+
+impl WorkingDir {
+    /// The layout of a working directory at the given path
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            snapshots: path.as_ref().join(SNAPSHOTS_DIR),
+            logs: path.as_ref().join(LOGS_DIR),
+        }
+    }
+
+    /// Create the directories of the layout, if they don't exist yet
+    pub fn create(&self) -> Result<(), Error> {
+        for dir in [&self.snapshots, &self.logs] {
+            std::fs::create_dir_all(dir).map_err(|err| {
+                if err.kind() == std::io::ErrorKind::PermissionDenied {
+                    Error::PermissionDenied(dir.clone())
+                } else {
+                    Error::CreateDir(dir.clone(), err)
+                }
+            })?;
+        }
+        Ok(())
+    }
+}
+
 /// A matchmaker receive intents and tries to find a match with previously
 /// received intent.
 #[derive(Debug)]
 pub struct Runner {
     matchmaker_path: PathBuf,
+    /// In the batched mode, the interval of the ticks on which the intents
+    /// accumulated since the last tick are matched together
+    tick_interval: Option<Duration>,
+    /// In the dry-run mode, the matches are only logged and never sent to
+    /// the [`ResultHandler`]
+    dry_run: bool,
     /// The client listener. This is consumed once the listener is started with
     /// [`Runner::listen`].
     listener: Option<ClientListener>,
+    /// A dialer of the client listener, used to stop the listener when the
+    /// [`ResultHandler`] is no longer running.
+    listener_dialer: ClientDialer,
     /// Sender of results of matched intents to the [`ResultHandler`].
-    result_send: tokio::sync::mpsc::UnboundedSender<AddIntentResult>,
+    result_send: tokio::sync::mpsc::Sender<AddIntentResult>,
+    /// The height of the last committed block as last queried by the
+    /// [`ResultHandler`]. It's zero until the first successful query.
+    block_height: Arc<AtomicU64>,
+}
+
+/// The status of sending a matchmaker result to the [`ResultHandler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendResultStatus {
+    /// The result has been sent
+    Sent,
+    /// The result handler couldn't keep up with the results and the result
+    /// has been dropped
+    Backpressure,
+    /// The result handler is no longer running and the result has been
+    /// dropped. The runner's listener is stopped when this happens.
+    Closed,
 }
 
 /// Result handler processes the results sent from the matchmaker [`Runner`].
@@ -95,7 +247,7 @@ pub struct ResultHandler {
     /// A dialer can send messages to the connected intent gossip node
     dialer: ClientDialer,
     /// A receiver of matched intents results from the [`Runner`].
-    result_recv: tokio::sync::mpsc::UnboundedReceiver<AddIntentResult>,
+    result_recv: tokio::sync::mpsc::Receiver<AddIntentResult>,
     /// The ledger address to send any crafted transaction to
     ledger_address: net::Address,
     /// The code of the transaction that is going to be send to a ledger.
@@ -104,6 +256,23 @@ pub struct ResultHandler {
     tx_source_address: Address,
     /// A keypair that will be used to sign transactions.
     tx_signing_key: Rc<common::SecretKey>,
+    /// The recently injected matches, used to suppress duplicate txs.
+    injected_matches: InjectedMatches,
+    /// The height of the last committed block, shared with the [`Runner`].
+    block_height: Arc<AtomicU64>,
+    /// When set, the data of the crafted txs is collected here instead of
+    /// being broadcasted to the ledger.
+    #[cfg(test)]
+    submitted_txs: Option<Vec<Vec<u8>>>,
+}
+
+/// The hashes of the recently injected matches' tx data with the time of
+/// their injection.
+#[derive(Debug)]
+struct InjectedMatches {
+    /// The window within which an identical match is not injected again
+    window: Duration,
+    injected: HashMap<Hash, Instant>,
 }
 
 /// The loaded implementation's dylib and its state
@@ -129,26 +298,31 @@ impl Runner {
     pub fn new_pair(
         intent_gossiper_addr: SocketAddr,
         matchmaker_path: PathBuf,
-        tx_code_path: PathBuf,
+        tx_code: Vec<u8>,
+        tick_interval: Option<Duration>,
+        dry_run: bool,
         ledger_address: TendermintAddress,
         tx_signing_key: Rc<common::SecretKey>,
         tx_source_address: Address,
-        wasm_dir: impl AsRef<Path>,
     ) -> (Self, ResultHandler) {
         // Setup a channel for sending matchmaker results from `Self` to the
         // `ResultHandler`
-        let (result_send, result_recv) = tokio::sync::mpsc::unbounded_channel();
+        let (result_send, result_recv) =
+            tokio::sync::mpsc::channel(RESULT_CHANNEL_CAPACITY);
 
         // Prepare a client for intent gossiper node connection
         let (listener, dialer) = ClientListener::new_pair(intent_gossiper_addr);
-
-        let tx_code = wasm_loader::read_wasm(&wasm_dir, tx_code_path);
+        let block_height = Arc::new(AtomicU64::new(0));
 
         (
             Self {
                 matchmaker_path,
+                tick_interval,
+                dry_run,
                 listener: Some(listener),
+                listener_dialer: dialer.clone(),
                 result_send,
+                block_height: block_height.clone(),
             },
             ResultHandler {
                 dialer,
@@ -157,6 +331,10 @@ impl Runner {
                 tx_code,
                 tx_source_address,
                 tx_signing_key,
+                injected_matches: InjectedMatches::new(INJECTED_MATCH_WINDOW),
+                block_height,
+                #[cfg(test)]
+                submitted_txs: None,
             },
         )
     }
@@ -165,80 +343,21 @@ impl Runner {
         // Load the implementation's dylib and instantiate it. We have to do
         // that here instead of `Self::new_pair`, because we cannot send
         // it across threads and the listener is launched in a dedicated thread.
-
-        // Check or add a filename extension to matchmaker path
-        let matchmaker_filename =
-            if let Some(ext) = self.matchmaker_path.extension() {
-                if ext != dylib::FILE_EXT {
-                    tracing::warn!(
-                        "Unexpected matchmaker file extension. Expected {}, \
-                         got {}.",
-                        dylib::FILE_EXT,
-                        ext.to_string_lossy(),
-                    );
-                }
-                self.matchmaker_path.clone()
-            } else {
-                let mut filename = self.matchmaker_path.clone();
-                filename.set_extension(dylib::FILE_EXT);
-                filename
-            };
-
-        let matchmaker_dylib = if matchmaker_filename.is_absolute() {
-            // If the path is absolute, use it as is
-            matchmaker_filename
-        } else {
-            // The dylib should be built in the same directory as where Anoma
-            // binaries are, even when ran via `cargo run`. Anoma's pre-built
-            // binaries are distributed with the dylib(s) in the same directory.
-            let dylib_dir_with_bins = || {
-                let anoma_path = env::current_exe().unwrap();
-                anoma_path
-                    .parent()
-                    .map(|path| path.to_owned())
-                    .unwrap()
-                    .join(&matchmaker_filename)
-            };
-            // Anoma built from source (`make install`) will install the
-            // dylib(s) to `~/.cargo/lib`.
-            let dylib_dir_installed = || {
-                directories::BaseDirs::new()
-                    .expect("Couldn't determine the $HOME directory")
-                    .home_dir()
-                    .join(".cargo")
-                    .join("lib")
-                    .join(&matchmaker_filename)
-            };
-            // Argument with file path relative to the current dir.
-            let dylib_dir_in_cwd = || {
-                let anoma_path = env::current_dir().unwrap();
-                anoma_path.join(&matchmaker_filename)
-            };
-
-            // Try to find the matchmaker lib in either directory (computed
-            // lazily)
-            let matchmaker_dylib: Option<PathBuf> =
-                check_file_exists(dylib_dir_with_bins)
-                    .or_else(|| check_file_exists(dylib_dir_installed))
-                    .or_else(|| check_file_exists(dylib_dir_in_cwd));
-            matchmaker_dylib.unwrap_or_else(|| {
-                panic!(
-                    "The matchmaker library couldn't not be found. Did you \
-                     build it? Attempted to find it in directories \"{}\", \
-                     \"{}\" and \"{}\".",
-                    dylib_dir_with_bins().to_string_lossy(),
-                    dylib_dir_installed().to_string_lossy(),
-                    dylib_dir_in_cwd().to_string_lossy(),
-                );
-            })
-        };
         tracing::info!(
             "Running matchmaker from {}",
-            matchmaker_dylib.to_string_lossy()
+            self.matchmaker_path.to_string_lossy()
         );
 
         let matchmaker_code =
-            unsafe { Library::new(matchmaker_dylib).unwrap() };
+            unsafe { Library::new(&self.matchmaker_path).unwrap() };
+
+        // Forward our log dispatcher to the matchmaker
+        let set_tracing_dispatcher: libloading::Symbol<
+            unsafe extern "C" fn(&tracing::Dispatch),
+        > = unsafe { matchmaker_code.get(b"_set_tracing_dispatcher").unwrap() };
+        tracing::dispatcher::get_default(|dispatch| unsafe {
+            set_tracing_dispatcher(dispatch)
+        });
 
         // Instantiate the matchmaker
         let new_matchmaker: libloading::Symbol<
@@ -252,12 +371,139 @@ impl Runner {
             library: matchmaker_code,
         };
 
+        // In the batched mode, the intents are accumulated until the next tick
+        let tick_interval = self.tick_interval;
+        let pending_intents: RefCell<Vec<(Vec<u8>, Vec<u8>)>> =
+            RefCell::default();
+
         // Run the listener for messages from the connected intent gossiper node
-        self.listener.take().unwrap().listen(|msg| match msg {
-            MsgFromServer::AddIntent { id, data } => {
-                self.try_match_intent(&r#impl, id, data);
+        self.listener.take().unwrap().listen_with_ticks(
+            tick_interval,
+            |msg| match msg {
+                MsgFromServer::AddIntent { id, data } => {
+                    if tick_interval.is_none() {
+                        self.try_match_intent(&r#impl, id, data);
+                    } else if self.filter_intent(&r#impl, &id, &data) {
+                        pending_intents.borrow_mut().push((id, data));
+                    }
+                }
+                MsgFromServer::RemoveIntent { id } => {
+                    pending_intents
+                        .borrow_mut()
+                        .retain(|(pending_id, _)| *pending_id != id);
+                    self.remove_intent(&r#impl, id);
+                }
+            },
+            || self.try_match_intents(&r#impl, pending_intents.take()),
+        );
+
+        // The listener has stopped, match the intents that are still pending
+        // and give the matchmaker a chance to flush its state and send any
+        // resolved match before it's dropped
+        self.try_match_intents(&r#impl, pending_intents.take());
+        self.shutdown(&r#impl);
+    }
+
+    /// Shut down the matchmaker implementation and forward the matches that
+    /// it resolved, but didn't send yet to the [`ResultHandler`].
+    fn shutdown(&self, r#impl: &MatchmakerImpl) {
+        let shutdown_matchmaker: libloading::Symbol<
+            unsafe extern "C" fn(*mut c_void) -> Vec<AddIntentResult>,
+        > = unsafe { r#impl.library.get(b"_shutdown_matchmaker").unwrap() };
+
+        let results = unsafe { shutdown_matchmaker(*r#impl.state.0) };
+
+        for result in results {
+            if result.tx.is_some() || result.matched_intents.is_some() {
+                tracing::info!(
+                    "Sending a match resolved on matchmaker shutdown"
+                );
+                self.handle_match(result);
             }
-        })
+        }
+    }
+
+    /// Send a result to the [`ResultHandler`] or in the dry-run mode, only
+    /// log the match.
+    fn handle_match(&self, result: AddIntentResult) {
+        if self.dry_run {
+            log_dry_run_match(&result);
+        } else {
+            self.send_match(result);
+        }
+    }
+
+    /// Check if the [`ResultHandler`] has capacity to receive a new result
+    /// without waiting.
+    pub fn can_send_match(&self) -> bool {
+        self.result_send.capacity() > 0
+    }
+
+    /// Send a result to the [`ResultHandler`]. When its channel is full, this
+    /// waits for it to make room for at most [`SEND_RESULT_TIMEOUT`], after
+    /// which the result is dropped. When its channel is closed, the node is
+    /// shutting down and so the listener is stopped to let the matchmaker
+    /// shut down cleanly too. This never panics.
+    fn send_match(&self, result: AddIntentResult) -> SendResultStatus {
+        self.send_match_with_timeout(result, SEND_RESULT_TIMEOUT)
+    }
+
+    fn send_match_with_timeout(
+        &self,
+        mut result: AddIntentResult,
+        timeout: Duration,
+    ) -> SendResultStatus {
+        use tokio::sync::mpsc::error::TrySendError;
+
+        let start = std::time::Instant::now();
+        loop {
+            match self.result_send.try_send(result) {
+                Ok(()) => return SendResultStatus::Sent,
+                Err(TrySendError::Full(unsent)) => {
+                    if start.elapsed() >= timeout {
+                        tracing::error!(
+                            "The matchmaker result handler couldn't keep up \
+                             for {:?}, a matchmaker result has been dropped",
+                            timeout
+                        );
+                        return SendResultStatus::Backpressure;
+                    }
+                    result = unsent;
+                    std::thread::sleep(SEND_RESULT_RETRY_INTERVAL);
+                }
+                Err(TrySendError::Closed(_)) => {
+                    tracing::error!(
+                        "The matchmaker result handler is no longer running, \
+                         a matchmaker result has been dropped"
+                    );
+                    self.listener_dialer.shutdown();
+                    return SendResultStatus::Closed;
+                }
+            }
+        }
+    }
+
+    /// Check if the intent is accepted by the matchmaker's filter
+    #[allow(clippy::ptr_arg)]
+    fn filter_intent(
+        &self,
+        r#impl: &MatchmakerImpl,
+        intent_id: &Vec<u8>,
+        intent_data: &Vec<u8>,
+    ) -> bool {
+        let filter_intent: libloading::Symbol<
+            unsafe extern "C" fn(*mut c_void, &Vec<u8>, &Vec<u8>) -> bool,
+        > = unsafe { r#impl.library.get(b"_filter_intent").unwrap() };
+
+        let is_accepted =
+            unsafe { filter_intent(*r#impl.state.0, intent_id, intent_data) };
+        if !is_accepted {
+            tracing::info!(
+                "Intent ID {} rejected by the matchmaker's filter",
+                IntentId(intent_id.clone())
+            );
+        }
+        is_accepted
     }
 
     /// add the intent to the matchmaker mempool and tries to find a match for
@@ -268,18 +514,128 @@ impl Runner {
         intent_id: Vec<u8>,
         intent_data: Vec<u8>,
     ) {
+        if !self.filter_intent(r#impl, &intent_id, &intent_data) {
+            return;
+        }
+
         let add_intent: libloading::Symbol<
             unsafe extern "C" fn(
                 *mut c_void,
                 &Vec<u8>,
                 &Vec<u8>,
-            ) -> AddIntentResult,
+            ) -> Vec<AddIntentResult>,
         > = unsafe { r#impl.library.get(b"_add_intent").unwrap() };
 
-        let result =
+        self.set_can_send_match(r#impl);
+        self.set_block_height(r#impl);
+        let results =
             unsafe { add_intent(*r#impl.state.0, &intent_id, &intent_data) };
 
-        self.result_send.send(result).unwrap();
+        for result in results {
+            self.handle_match(result);
+        }
+    }
+
+    /// Add the intents accumulated over a tick to the matchmaker mempool and
+    /// try to match them together
+    fn try_match_intents(
+        &self,
+        r#impl: &MatchmakerImpl,
+        intents: Vec<(Vec<u8>, Vec<u8>)>,
+    ) {
+        if intents.is_empty() {
+            return;
+        }
+        let add_intents: libloading::Symbol<
+            unsafe extern "C" fn(
+                *mut c_void,
+                &Vec<(Vec<u8>, Vec<u8>)>,
+            ) -> Vec<AddIntentResult>,
+        > = unsafe { r#impl.library.get(b"_add_intents").unwrap() };
+
+        tracing::debug!("Matching a batch of {} intents", intents.len());
+        self.set_can_send_match(r#impl);
+        self.set_block_height(r#impl);
+        let results = unsafe { add_intents(*r#impl.state.0, &intents) };
+
+        for result in results {
+            if result.tx.is_some() || result.matched_intents.is_some() {
+                self.handle_match(result);
+            }
+        }
+    }
+
+    /// Tell the matchmaker implementation whether the [`ResultHandler`] can
+    /// receive a new result without waiting
+    fn set_can_send_match(&self, r#impl: &MatchmakerImpl) {
+        let set_can_send_match: libloading::Symbol<
+            unsafe extern "C" fn(*mut c_void, bool),
+        > = unsafe { r#impl.library.get(b"_set_can_send_match").unwrap() };
+
+        let can_send_match = self.can_send_match();
+        if !can_send_match {
+            tracing::warn!(
+                "The matchmaker result handler is busy, the result of the new \
+                 intents may have to wait"
+            );
+        }
+        unsafe { set_can_send_match(*r#impl.state.0, can_send_match) };
+    }
+
+    /// Tell the matchmaker implementation the height of the last committed
+    /// block, once it's known
+    fn set_block_height(&self, r#impl: &MatchmakerImpl) {
+        let height = self.block_height.load(Ordering::SeqCst);
+        if height == 0 {
+            return;
+        }
+        let set_block_height: libloading::Symbol<
+            unsafe extern "C" fn(*mut c_void, u64),
+        > = unsafe { r#impl.library.get(b"_set_block_height").unwrap() };
+
+        unsafe { set_block_height(*r#impl.state.0, height) };
+    }
+
+    /// remove a cancelled intent from the matchmaker mempool
+    fn remove_intent(&self, r#impl: &MatchmakerImpl, intent_id: Vec<u8>) {
+        let remove_intent: libloading::Symbol<
+            unsafe extern "C" fn(*mut c_void, &Vec<u8>),
+        > = unsafe { r#impl.library.get(b"_remove_intent").unwrap() };
+
+        tracing::info!(
+            "Removing cancelled intent ID {} from the matchmaker",
+            IntentId(intent_id.clone())
+        );
+        unsafe { remove_intent(*r#impl.state.0, &intent_id) };
+    }
+}
+
+/// Log a match found in the dry-run mode with its transfers
+fn log_dry_run_match(result: &AddIntentResult) {
+    let tx_data = match result.tx.as_ref() {
+        Some(tx_data) => tx_data,
+        None => return,
+    };
+    let intent_ids = result
+        .matched_intents
+        .iter()
+        .flatten()
+        .map(|intent_id| IntentId(intent_id.clone()).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    match MatchedExchanges::try_from_slice(&tx_data[..]) {
+        Ok(matches) => tracing::info!(
+            "Dry run: found a match of intents [{}] with transfers {:#?}, no \
+             transaction is injected",
+            intent_ids,
+            matches.transfers
+        ),
+        Err(err) => tracing::warn!(
+            "Dry run: found a match of intents [{}] with invalid transfers: \
+             {}",
+            intent_ids,
+            err
+        ),
     }
 }
 
@@ -295,17 +651,145 @@ impl Drop for MatchmakerImpl {
 
 impl ResultHandler {
     async fn run(mut self) {
-        while let Some(result) = self.result_recv.recv().await {
-            if let Some(tx) = result.tx {
-                self.submit_tx(tx).await
+        let mut query_block_height =
+            tokio::time::interval(BLOCK_HEIGHT_QUERY_INTERVAL);
+        loop {
+            tokio::select! {
+                result = self.result_recv.recv() => match result {
+                    Some(result) => self.handle_result(result).await,
+                    // The runner has stopped
+                    None => return,
+                },
+                _ = query_block_height.tick() => {
+                    self.query_block_height().await
+                },
+                signal = tokio::signal::ctrl_c() => {
+                    match signal {
+                        Ok(()) => tracing::info!(
+                            "Received interrupt signal, shutting down the \
+                             matchmaker..."
+                        ),
+                        Err(err) => tracing::error!(
+                            "Failed to listen for CTRL+C signal: {}",
+                            err
+                        ),
+                    }
+                    break;
+                },
             }
-            if let Some(intent_ids) = result.matched_intents {
-                self.dialer.send(MsgFromClient::Matched { intent_ids })
+        }
+
+        // Stop the client listener, which lets the runner shut down the
+        // matchmaker implementation, and then drain any results that are still
+        // in flight with a bounded timeout.
+        self.dialer.shutdown();
+        let drain = async {
+            while let Some(result) = self.result_recv.recv().await {
+                self.handle_result(result).await;
             }
+        };
+        if tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, drain)
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                "Timed out after {:?} waiting for the matchmaker to drain its \
+                 results",
+                SHUTDOWN_DRAIN_TIMEOUT
+            );
         }
     }
 
-    async fn submit_tx(&self, tx_data: Vec<u8>) {
+    async fn handle_result(&mut self, result: AddIntentResult) {
+        let tx_hash = match result.tx {
+            Some(tx) => {
+                let match_hash = hash_tx(&tx);
+                let now = Instant::now();
+                if self.injected_matches.contains(&match_hash, now) {
+                    tracing::info!(
+                        "Suppressing the re-injection of an identical match \
+                         {} within {:?}",
+                        match_hash,
+                        self.injected_matches.window
+                    );
+                    None
+                } else {
+                    let tx_hash = self.submit_tx(tx).await;
+                    if tx_hash.is_some() {
+                        self.injected_matches.insert(match_hash, now);
+                    }
+                    tx_hash
+                }
+            }
+            None => None,
+        };
+        if let Some(intent_ids) = result.matched_intents {
+            // Let the submitters of the matched intents know about the tx
+            if let Some(tx_hash) = tx_hash {
+                let notices = self.match_notices(&intent_ids, tx_hash);
+                self.dialer.send(MsgFromClient::MatchNotices { notices })
+            }
+            self.dialer.send(MsgFromClient::Matched { intent_ids })
+        }
+    }
+
+    /// Query the height of the last committed block and share it with the
+    /// [`Runner`]. On failure, the last known height is kept.
+    async fn query_block_height(&self) {
+        let client = match HttpClient::new(self.ledger_address.clone()) {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::warn!("Failed to create a ledger client: {}", err);
+                return;
+            }
+        };
+        match tokio::time::timeout(BLOCK_HEIGHT_QUERY_TIMEOUT, client.status())
+            .await
+        {
+            Ok(Ok(status)) => self.block_height.store(
+                status.sync_info.latest_block_height.value(),
+                Ordering::SeqCst,
+            ),
+            Ok(Err(err)) => tracing::warn!(
+                "Failed to query the height of the last committed block: {}",
+                err
+            ),
+            Err(_) => tracing::warn!(
+                "Timed out querying the height of the last committed block"
+            ),
+        }
+    }
+
+    /// Sign a notice for each of the intents matched into the tx with the
+    /// given hash
+    fn match_notices(
+        &self,
+        intent_ids: &HashSet<Vec<u8>>,
+        tx_hash: Hash,
+    ) -> Vec<Signed<MatchNotice>> {
+        intent_ids
+            .iter()
+            .map(|intent_id| {
+                Signed::new(
+                    &self.tx_signing_key,
+                    MatchNotice {
+                        intent_id: intent_id.clone(),
+                        tx_hash: tx_hash.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Craft a tx from the matched intents and broadcast it to the ledger.
+    /// Returns the hash of the tx, if it has been broadcasted.
+    async fn submit_tx(&mut self, tx_data: Vec<u8>) -> Option<Hash> {
+        #[cfg(test)]
+        if let Some(submitted_txs) = self.submitted_txs.as_mut() {
+            let tx_hash = hash_tx(&tx_data);
+            submitted_txs.push(tx_data);
+            return Some(tx_hash);
+        }
         let tx_code = self.tx_code.clone();
         let matches = MatchedExchanges::try_from_slice(&tx_data[..]).unwrap();
         let intent_transfers = IntentTransfers {
@@ -313,7 +797,7 @@ impl ResultHandler {
             source: self.tx_source_address.clone(),
         };
         let tx_data = intent_transfers.try_to_vec().unwrap();
-        let to_broadcast = {
+        let (tx_hash, to_broadcast) = {
             let epoch = rpc::query_epoch(args::Query {
                 ledger_address: self.ledger_address.clone(),
             })
@@ -341,13 +825,15 @@ impl ResultHandler {
             } else {
                 None
             };
-            TxBroadcastData::Wrapper {
+            let tx_hash = tx.tx_hash.clone();
+            let to_broadcast = TxBroadcastData::Wrapper {
                 tx: tx
                     .sign(&self.tx_signing_key)
                     .expect("Wrapper tx signing keypair should be correct"),
                 wrapper_hash,
                 decrypted_hash,
-            }
+            };
+            (tx_hash, to_broadcast)
         };
 
         let response =
@@ -358,6 +844,7 @@ impl ResultHandler {
                     "Injected transaction from matchmaker with result: {:#?}",
                     tx_response
                 );
+                Some(tx_hash)
             }
             Err(err) => {
                 tracing::error!(
@@ -365,13 +852,369 @@ impl ResultHandler {
                      ledger: {}",
                     err
                 );
+                None
             }
         }
     }
 }
 
+impl InjectedMatches {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            injected: HashMap::new(),
+        }
+    }
+
+    /// Check if a match with the given hash has been injected within the
+    /// window. The matches injected before the window are forgotten.
+    fn contains(&mut self, match_hash: &Hash, now: Instant) -> bool {
+        let window = self.window;
+        self.injected
+            .retain(|_, injected_at| now.duration_since(*injected_at) < window);
+        self.injected.contains_key(match_hash)
+    }
+
+    /// Record an injected match
+    fn insert(&mut self, match_hash: Hash, now: Instant) {
+        self.injected.insert(match_hash, now);
+    }
+}
+
+/// Find the matchmaker's dylib at startup, so that a missing library fails
+/// fast rather than when the matchmaker is launched.
+fn find_matchmaker_dylib(matchmaker_path: &Path) -> Result<PathBuf, Error> {
+    // Check or add a filename extension to matchmaker path
+    let matchmaker_filename = if let Some(ext) = matchmaker_path.extension() {
+        if ext != dylib::FILE_EXT {
+            tracing::warn!(
+                "Unexpected matchmaker file extension. Expected {}, \
+                     got {}.",
+                dylib::FILE_EXT,
+                ext.to_string_lossy(),
+            );
+        }
+        matchmaker_path.to_owned()
+    } else {
+        let mut filename = matchmaker_path.to_owned();
+        filename.set_extension(dylib::FILE_EXT);
+        filename
+    };
+
+    let matchmaker_dylib = if matchmaker_filename.is_absolute() {
+        // If the path is absolute, use it as is
+        if !matchmaker_filename.exists() {
+            return Err(Error::MatchmakerNotFound(matchmaker_filename));
+        }
+        matchmaker_filename
+    } else {
+        // The dylib should be built in the same directory as where Anoma
+        // binaries are, even when ran via `cargo run`. Anoma's pre-built
+        // binaries are distributed with the dylib(s) in the same directory.
+        let dylib_dir_with_bins = || {
+            let anoma_path = env::current_exe().unwrap();
+            anoma_path
+                .parent()
+                .map(|path| path.to_owned())
+                .unwrap()
+                .join(&matchmaker_filename)
+        };
+        // Anoma built from source (`make install`) will install the
+        // dylib(s) to `~/.cargo/lib`.
+        let dylib_dir_installed = || {
+            directories::BaseDirs::new()
+                .expect("Couldn't determine the $HOME directory")
+                .home_dir()
+                .join(".cargo")
+                .join("lib")
+                .join(&matchmaker_filename)
+        };
+        // Argument with file path relative to the current dir.
+        let dylib_dir_in_cwd = || {
+            let anoma_path = env::current_dir().unwrap();
+            anoma_path.join(&matchmaker_filename)
+        };
+
+        // Try to find the matchmaker lib in either directory (computed
+        // lazily)
+        let matchmaker_dylib: Option<PathBuf> =
+            check_file_exists(dylib_dir_with_bins)
+                .or_else(|| check_file_exists(dylib_dir_installed))
+                .or_else(|| check_file_exists(dylib_dir_in_cwd));
+        matchmaker_dylib.ok_or_else(|| {
+            Error::MatchmakerNotFoundInDirs(
+                dylib_dir_with_bins(),
+                dylib_dir_installed(),
+                dylib_dir_in_cwd(),
+            )
+        })?
+    };
+    // Check that the library is readable
+    std::fs::File::open(&matchmaker_dylib)
+        .map_err(|err| Error::ReadMatchmaker(matchmaker_dylib.clone(), err))?;
+    Ok(matchmaker_dylib)
+}
+
+/// Load the matchmaker's transaction code, which may be relative to the wasm
+/// directory, and check that it's a valid wasm module.
+fn load_tx_code(
+    wasm_dir: impl AsRef<Path>,
+    tx_code_path: impl AsRef<Path>,
+) -> Result<Vec<u8>, Error> {
+    let path = wasm_loader::wasm_file_path(wasm_dir, tx_code_path);
+    let tx_code = std::fs::read(&path)
+        .map_err(|err| Error::ReadTxCode(path.clone(), err))?;
+    if !tx_code.starts_with(WASM_MAGIC_NUMBER) {
+        return Err(Error::TxCodeNotWasm(path));
+    }
+    vm::validate_untrusted_wasm(&tx_code)
+        .map_err(|err| Error::InvalidTxCode(path, err))?;
+    Ok(tx_code)
+}
+
 /// Return the path of the file returned by `lazy_path` argument, if it exists.
 fn check_file_exists(lazy_path: impl Fn() -> PathBuf) -> Option<PathBuf> {
     let path = lazy_path();
     if path.exists() { Some(path) } else { None }
 }
+
+#[cfg(test)]
+mod test {
+    use anoma::testing::LogCapture;
+
+    use super::*;
+    use crate::node::gossip::rpc::matchmakers::{ServerDialer, ServerListener};
+
+    /// Test that the layout of the working directory is created
+    #[test]
+    fn test_working_dir_created() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let path = base_dir.path().join("matchmaker");
+        let working_dir = WorkingDir::new(&path);
+        working_dir.create().unwrap();
+        assert!(path.join("snapshots").is_dir());
+        assert!(path.join("logs").is_dir());
+
+        // Creating the existing directories again is fine
+        working_dir.create().unwrap();
+    }
+
+    /// Test that the startup fails with the path of a transaction code that
+    /// doesn't exist or isn't a valid wasm
+    #[test]
+    fn test_load_tx_code() {
+        let wasm_dir = tempfile::tempdir().unwrap();
+
+        let missing = wasm_dir.path().join("tx_missing.wasm");
+        match load_tx_code(wasm_dir.path(), &missing) {
+            Err(err @ Error::ReadTxCode(_, _)) => {
+                assert!(err.to_string().contains(&*missing.to_string_lossy()))
+            }
+            other => panic!("Expected a read error, got {:?}", other),
+        }
+
+        std::fs::write(wasm_dir.path().join("tx_text.wasm"), "not wasm")
+            .unwrap();
+        match load_tx_code(wasm_dir.path(), "tx_text.wasm") {
+            Err(Error::TxCodeNotWasm(path)) => {
+                assert_eq!(path, wasm_dir.path().join("tx_text.wasm"))
+            }
+            other => panic!("Expected a not wasm error, got {:?}", other),
+        }
+
+        // The magic number followed by an unsupported version
+        std::fs::write(
+            wasm_dir.path().join("tx_invalid.wasm"),
+            b"\0asm\x02\0\0\0",
+        )
+        .unwrap();
+        assert!(matches!(
+            load_tx_code(wasm_dir.path(), "tx_invalid.wasm"),
+            Err(Error::InvalidTxCode(_, _))
+        ));
+
+        // An empty module is valid
+        std::fs::write(
+            wasm_dir.path().join("tx_empty.wasm"),
+            b"\0asm\x01\0\0\0",
+        )
+        .unwrap();
+        assert!(load_tx_code(wasm_dir.path(), "tx_empty.wasm").is_ok());
+    }
+
+    /// Test that the startup fails with the path of a matchmaker library that
+    /// doesn't exist
+    #[test]
+    fn test_find_missing_matchmaker_dylib() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir
+            .path()
+            .join("mm_missing")
+            .with_extension(dylib::FILE_EXT);
+        match find_matchmaker_dylib(&path) {
+            Err(Error::MatchmakerNotFound(not_found)) => {
+                assert_eq!(not_found, path)
+            }
+            other => panic!("Expected a not found error, got {:?}", other),
+        }
+    }
+
+    /// Make a runner with the given sender of results, whose listener is
+    /// connected to a matchmakers server. The server is returned to keep it
+    /// alive for the duration of a test.
+    fn test_runner(
+        result_send: tokio::sync::mpsc::Sender<AddIntentResult>,
+    ) -> (Runner, ServerListener, ServerDialer) {
+        let (server, server_dialer) = ServerListener::new_pair("127.0.0.1:0");
+        let (listener, listener_dialer) =
+            ClientListener::new_pair(server.address);
+        let runner = Runner {
+            matchmaker_path: PathBuf::default(),
+            tick_interval: None,
+            dry_run: false,
+            listener: Some(listener),
+            listener_dialer,
+            result_send,
+            block_height: Arc::default(),
+        };
+        (runner, server, server_dialer)
+    }
+
+    #[test]
+    fn test_send_match_backpressure() {
+        let (result_send, mut result_recv) = tokio::sync::mpsc::channel(1);
+        let (runner, _server, _server_dialer) = test_runner(result_send);
+
+        assert!(runner.can_send_match());
+        let status = runner.send_match_with_timeout(
+            AddIntentResult::default(),
+            Duration::ZERO,
+        );
+        assert_eq!(status, SendResultStatus::Sent);
+
+        // The channel is full now
+        assert!(!runner.can_send_match());
+        let status = runner.send_match_with_timeout(
+            AddIntentResult::default(),
+            Duration::ZERO,
+        );
+        assert_eq!(status, SendResultStatus::Backpressure);
+
+        // Once the result handler receives a result, there's room for another
+        assert!(result_recv.try_recv().is_ok());
+        assert!(runner.can_send_match());
+        let status = runner.send_match_with_timeout(
+            AddIntentResult::default(),
+            Duration::ZERO,
+        );
+        assert_eq!(status, SendResultStatus::Sent);
+
+        // The result handler has stopped
+        drop(result_recv);
+        let status = runner.send_match_with_timeout(
+            AddIntentResult::default(),
+            Duration::ZERO,
+        );
+        assert_eq!(status, SendResultStatus::Closed);
+    }
+
+    /// Test that when the result handler is no longer running, sending a
+    /// match stops the runner's listener instead of panicking
+    #[test]
+    fn test_send_match_closed_stops_listener() {
+        let (result_send, result_recv) = tokio::sync::mpsc::channel(1);
+        let (mut runner, _server, _server_dialer) = test_runner(result_send);
+
+        // The result handler has stopped
+        drop(result_recv);
+        let status = runner.send_match(AddIntentResult::default());
+        assert_eq!(status, SendResultStatus::Closed);
+
+        // The listener has been stopped, so it returns right away
+        let (stopped_send, stopped_recv) = std::sync::mpsc::channel();
+        let listener = runner.listener.take().unwrap();
+        std::thread::spawn(move || {
+            listener.listen(|_msg| {});
+            stopped_send.send(()).unwrap();
+        });
+        assert!(stopped_recv.recv_timeout(Duration::from_secs(10)).is_ok());
+    }
+
+    /// Test that when the same match is resolved twice in quick succession,
+    /// the result handler injects only one tx, while a different match or the
+    /// same match after the window is injected
+    #[tokio::test]
+    async fn test_injected_match_suppressed() {
+        let (server, _server_dialer) = ServerListener::new_pair("127.0.0.1:0");
+        let (_listener, dialer) = ClientListener::new_pair(server.address);
+        let (_result_send, result_recv) = tokio::sync::mpsc::channel(1);
+        let window = Duration::from_millis(500);
+        let mut handler = ResultHandler {
+            dialer,
+            result_recv,
+            ledger_address: "tcp://127.0.0.1:26657".parse().unwrap(),
+            tx_code: vec![],
+            tx_source_address: address::testing::established_address_1(),
+            tx_signing_key: Rc::new(anoma::types::key::testing::keypair_1()),
+            injected_matches: InjectedMatches::new(window),
+            block_height: Arc::default(),
+            submitted_txs: Some(vec![]),
+        };
+        let result = |tx_data: &[u8]| AddIntentResult {
+            tx: Some(tx_data.to_vec()),
+            matched_intents: None,
+        };
+
+        handler.handle_result(result(b"match")).await;
+        handler.handle_result(result(b"match")).await;
+        handler.handle_result(result(b"another match")).await;
+        assert_eq!(
+            handler.submitted_txs.as_deref(),
+            Some(&[b"match".to_vec(), b"another match".to_vec()][..])
+        );
+
+        tokio::time::sleep(window).await;
+        handler.handle_result(result(b"match")).await;
+        assert_eq!(handler.submitted_txs.map(|txs| txs.len()), Some(3));
+    }
+
+    /// Test that in the dry-run mode, a match is logged with its transfers,
+    /// but it's not sent to the result handler, so no tx is injected
+    #[test]
+    fn test_dry_run_match_not_injected() {
+        let (result_send, mut result_recv) = tokio::sync::mpsc::channel(1);
+        let (mut runner, _server, _server_dialer) = test_runner(result_send);
+        runner.dry_run = true;
+
+        let mut matches = MatchedExchanges::empty();
+        matches.transfers.insert(anoma::types::token::Transfer {
+            source: address::testing::established_address_1(),
+            target: address::testing::established_address_2(),
+            token: address::xan(),
+            amount: 100.into(),
+        });
+        let result = AddIntentResult {
+            tx: Some(matches.try_to_vec().unwrap()),
+            matched_intents: Some(HashSet::from_iter([vec![1_u8; 32]])),
+        };
+
+        let capture = LogCapture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            runner.handle_match(result)
+        });
+
+        assert!(result_recv.try_recv().is_err());
+        let logs = capture.logs();
+        assert!(
+            logs.contains("Dry run: found a match")
+                && logs.contains("Transfer"),
+            "Expected a dry-run record of the match, got logs:\n{}",
+            logs
+        );
+    }
+}
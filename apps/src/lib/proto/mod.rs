@@ -2,4 +2,7 @@ mod generated;
 mod types;
 
 pub use generated::services;
-pub use types::{IntentMessage, RpcMessage, SubscribeTopicMessage};
+pub use types::{
+    IntentCancellationMessage, IntentMessage, ListIntentsMessage, RpcMessage,
+    SubscribeTopicMessage,
+};
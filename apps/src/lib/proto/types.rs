@@ -1,6 +1,8 @@
 use std::convert::{TryFrom, TryInto};
 
-use anoma::proto::{Dkg, Error, Intent};
+use anoma::proto::{Dkg, Error, Intent, IntentCancellation, Signed};
+use anoma::types::matchmaker::MatchNotice;
+use borsh::BorshSerialize;
 
 use super::generated::services;
 
@@ -10,6 +12,8 @@ pub enum RpcMessage {
     IntentMessage(IntentMessage),
     SubscribeTopicMessage(SubscribeTopicMessage),
     Dkg(Dkg),
+    IntentCancellationMessage(IntentCancellationMessage),
+    ListIntentsMessage(ListIntentsMessage),
 }
 
 impl From<RpcMessage> for services::RpcMessage {
@@ -22,6 +26,12 @@ impl From<RpcMessage> for services::RpcMessage {
                 services::rpc_message::Message::Topic(m.into())
             }
             RpcMessage::Dkg(d) => services::rpc_message::Message::Dkg(d.into()),
+            RpcMessage::IntentCancellationMessage(m) => {
+                services::rpc_message::Message::Cancellation(m.into())
+            }
+            RpcMessage::ListIntentsMessage(m) => {
+                services::rpc_message::Message::ListIntents(m.into())
+            }
         };
         services::RpcMessage {
             message: Some(message),
@@ -41,6 +51,22 @@ impl RpcMessage {
     pub fn new_dkg(dkg: Dkg) -> Self {
         RpcMessage::Dkg(dkg)
     }
+
+    pub fn new_cancellation(
+        cancellation: IntentCancellation,
+        topic: String,
+    ) -> Self {
+        RpcMessage::IntentCancellationMessage(IntentCancellationMessage::new(
+            cancellation,
+            topic,
+        ))
+    }
+
+    pub fn new_list_intents(topic: String, offset: u64, limit: u64) -> Self {
+        RpcMessage::ListIntentsMessage(ListIntentsMessage::new(
+            topic, offset, limit,
+        ))
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -78,6 +104,46 @@ impl IntentMessage {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct IntentCancellationMessage {
+    pub cancellation: IntentCancellation,
+    pub topic: String,
+}
+
+impl TryFrom<services::IntentCancellationMessage>
+    for IntentCancellationMessage
+{
+    type Error = Error;
+
+    fn try_from(message: services::IntentCancellationMessage) -> Result<Self> {
+        match message.cancellation {
+            Some(cancellation) => Ok(IntentCancellationMessage {
+                cancellation: cancellation.try_into()?,
+                topic: message.topic,
+            }),
+            None => Err(Error::NoIntentError),
+        }
+    }
+}
+
+impl From<IntentCancellationMessage> for services::IntentCancellationMessage {
+    fn from(message: IntentCancellationMessage) -> Self {
+        services::IntentCancellationMessage {
+            cancellation: Some(message.cancellation.into()),
+            topic: message.topic,
+        }
+    }
+}
+
+impl IntentCancellationMessage {
+    pub fn new(cancellation: IntentCancellation, topic: String) -> Self {
+        IntentCancellationMessage {
+            cancellation,
+            topic,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct SubscribeTopicMessage {
     pub topic: String,
@@ -105,6 +171,57 @@ impl SubscribeTopicMessage {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct ListIntentsMessage {
+    pub topic: String,
+    /// The number of intents to skip
+    pub offset: u64,
+    /// The maximum number of intents to list
+    pub limit: u64,
+}
+
+impl From<services::ListIntentsMessage> for ListIntentsMessage {
+    fn from(message: services::ListIntentsMessage) -> Self {
+        ListIntentsMessage {
+            topic: message.topic,
+            offset: message.offset,
+            limit: message.limit,
+        }
+    }
+}
+
+impl From<ListIntentsMessage> for services::ListIntentsMessage {
+    fn from(message: ListIntentsMessage) -> Self {
+        services::ListIntentsMessage {
+            topic: message.topic,
+            offset: message.offset,
+            limit: message.limit,
+        }
+    }
+}
+
+impl ListIntentsMessage {
+    pub fn new(topic: String, offset: u64, limit: u64) -> Self {
+        ListIntentsMessage {
+            topic,
+            offset,
+            limit,
+        }
+    }
+}
+
+impl From<Signed<MatchNotice>> for services::MatchNotification {
+    fn from(notice: Signed<MatchNotice>) -> Self {
+        services::MatchNotification {
+            intent_id: notice.data.intent_id.clone(),
+            tx_hash: notice.data.tx_hash.to_string(),
+            signed_notice: notice
+                .try_to_vec()
+                .expect("Encoding a match notice shouldn't fail"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -16,7 +16,7 @@ const ENCRYPTED_KEY_PREFIX: &str = "encrypted:";
 const UNENCRYPTED_KEY_PREFIX: &str = "unencrypted:";
 
 /// A keypair stored in a wallet
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum StoredKeypair {
     /// An encrypted keypair
     Encrypted(EncryptedKeypair),
@@ -103,7 +103,7 @@ pub enum DeserializeStoredKeypairError {
 }
 
 /// An encrypted keypair stored in a wallet
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct EncryptedKeypair(Vec<u8>);
 
 impl Display for EncryptedKeypair {
@@ -187,19 +187,11 @@ impl StoredKeypair {
 impl EncryptedKeypair {
     /// Encrypt a keypair and store it with its salt.
     pub fn new(keypair: &common::SecretKey, password: String) -> Self {
-        let salt = encryption_salt();
-        let encryption_key = encryption_key(&salt, password);
-
         let data = keypair
             .try_to_vec()
             .expect("Serializing keypair shouldn't fail");
 
-        let encrypted_keypair = aead::seal(&encryption_key, &data)
-            .expect("Encryption of data shouldn't fail");
-
-        let encrypted_data = [salt.as_ref(), &encrypted_keypair].concat();
-
-        Self(encrypted_data)
+        Self(encrypt(&data, password))
     }
 
     /// Decrypt an encrypted keypair
@@ -207,20 +199,43 @@ impl EncryptedKeypair {
         &self,
         password: String,
     ) -> Result<common::SecretKey, DecryptionError> {
-        let salt_len = encryption_salt().len();
-        let (raw_salt, cipher) = self.0.split_at(salt_len);
+        let decrypted_data = decrypt(&self.0, password)?;
+
+        common::SecretKey::try_from_slice(&decrypted_data)
+            .map_err(|_| DecryptionError::DeserializingError)
+    }
+}
 
-        let salt = kdf::Salt::from_slice(raw_salt)
-            .map_err(|_| DecryptionError::BadSalt)?;
+/// Encrypt the data with a key derived from the password. The returned bytes
+/// are prefixed with the encryption salt.
+pub(super) fn encrypt(data: &[u8], password: String) -> Vec<u8> {
+    let salt = encryption_salt();
+    let encryption_key = encryption_key(&salt, password);
 
-        let encryption_key = encryption_key(&salt, password);
+    let encrypted_data = aead::seal(&encryption_key, data)
+        .expect("Encryption of data shouldn't fail");
 
-        let decrypted_data = aead::open(&encryption_key, cipher)
-            .map_err(|_| DecryptionError::DecryptionError)?;
+    [salt.as_ref(), &encrypted_data].concat()
+}
 
-        common::SecretKey::try_from_slice(&decrypted_data)
-            .map_err(|_| DecryptionError::DeserializingError)
+/// Decrypt the data encrypted with [`encrypt`].
+pub(super) fn decrypt(
+    data: &[u8],
+    password: String,
+) -> Result<Vec<u8>, DecryptionError> {
+    let salt_len = encryption_salt().len();
+    if data.len() < salt_len {
+        return Err(DecryptionError::BadSalt);
     }
+    let (raw_salt, cipher) = data.split_at(salt_len);
+
+    let salt = kdf::Salt::from_slice(raw_salt)
+        .map_err(|_| DecryptionError::BadSalt)?;
+
+    let encryption_key = encryption_key(&salt, password);
+
+    aead::open(&encryption_key, cipher)
+        .map_err(|_| DecryptionError::DecryptionError)
 }
 
 /// Keypair encryption salt
@@ -16,7 +16,7 @@ use thiserror::Error;
 use self::alias::Alias;
 pub use self::keys::{DecryptionError, StoredKeypair};
 use self::store::Store;
-pub use self::store::{ValidatorData, ValidatorKeys};
+pub use self::store::{ImportStoreError, ValidatorData, ValidatorKeys};
 use crate::cli;
 use crate::config::genesis::genesis_config::GenesisConfig;
 
@@ -94,27 +94,38 @@ impl Wallet {
             println!("Warning: The keypair will NOT be encrypted.");
             None
         } else {
-            Some(read_password("Enter your encryption password: "))
+            Some(read_and_confirm_password())
         };
-        // Bis repetita for confirmation.
-        let pwd = if unsafe_dont_encrypt {
-            None
-        } else {
-            Some(read_password(
-                "To confirm, please enter the same encryption password once \
-                 more: ",
-            ))
-        };
-        if pwd != password {
-            eprintln!("Your two inputs do not match!");
-            cli::safe_exit(1)
-        }
         let (alias, key) = self.store.gen_key(alias, password);
         // Cache the newly added key
         self.decrypted_key_cache.insert(alias.clone(), key.clone());
         (alias.into(), key)
     }
 
+    /// Export the keys and addresses into a backup encrypted with a password
+    /// read from the file/env/stdin. The validator data is not exported.
+    pub fn export(&self) -> Vec<u8> {
+        let password = read_and_confirm_password();
+        self.store.export(password)
+    }
+
+    /// Import the keys and addresses from a backup created with
+    /// [`Wallet::export`], decrypted with a password read from the
+    /// file/env/stdin. Unless `force` is set, the import is refused when any
+    /// of the backup aliases is already used in the wallet. Returns the number
+    /// of the imported keys and addresses.
+    pub fn import(
+        &mut self,
+        data: &[u8],
+        force: bool,
+    ) -> Result<(usize, usize), ImportStoreError> {
+        let password = read_password("Enter the backup decryption password: ");
+        let imported = self.store.import(data, password, force)?;
+        // The replaced keys may have been cached
+        self.decrypted_key_cache.clear();
+        Ok(imported)
+    }
+
     /// Generate keypair
     /// for signing protocol txs and for the DKG (which will also be stored)
     /// A protocol keypair may be optionally provided, indicating that
@@ -329,6 +340,21 @@ impl Wallet {
     }
 }
 
+/// Read the password for encryption from the file/env/stdin twice for
+/// confirmation. Exits if the two inputs do not match.
+fn read_and_confirm_password() -> String {
+    let password = read_password("Enter your encryption password: ");
+    // Bis repetita for confirmation.
+    let pwd = read_password(
+        "To confirm, please enter the same encryption password once more: ",
+    );
+    if pwd != password {
+        eprintln!("Your two inputs do not match!");
+        cli::safe_exit(1)
+    }
+    password
+}
+
 /// Read the password for encryption/decryption from the file/env/stdin. Panics
 /// if all options are empty/invalid.
 fn read_password(prompt_msg: &str) -> String {
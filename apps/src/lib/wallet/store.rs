@@ -17,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::alias::Alias;
-use super::keys::StoredKeypair;
+use super::keys::{self, DecryptionError, StoredKeypair};
 use crate::cli;
 use crate::config::genesis::genesis_config::GenesisConfig;
 
@@ -70,6 +70,19 @@ pub enum LoadStoreError {
     StoreNewWallet(String),
 }
 
+#[derive(Error, Debug)]
+pub enum ImportStoreError {
+    #[error("Failed decrypting the wallet backup: {0}")]
+    Decryption(DecryptionError),
+    #[error("Failed decoding the wallet backup: {0}")]
+    Decode(toml::de::Error),
+    #[error(
+        "The wallet backup contains aliases that already exist in the \
+         wallet: {0}. Use the force flag to overwrite them."
+    )]
+    AliasConflict(String),
+}
+
 impl Store {
     #[cfg(not(feature = "dev"))]
     fn new(genesis: GenesisConfig) -> Self {
@@ -396,6 +409,64 @@ impl Store {
         Some(alias)
     }
 
+    /// Export the keys and addresses, without the validator data, encrypted
+    /// with the given password. The keys that are encrypted in the store stay
+    /// encrypted with their own password in the backup.
+    pub fn export(&self, password: String) -> Vec<u8> {
+        let backup = Self {
+            keys: self.keys.clone(),
+            addresses: self.addresses.clone(),
+            pkhs: self.pkhs.clone(),
+            validator_data: None,
+        };
+        keys::encrypt(&backup.encode(), password)
+    }
+
+    /// Import the keys and addresses from a backup created with
+    /// [`Store::export`]. Unless `force` is set, the import is refused when
+    /// any of the backup aliases is already used in the store. Returns the
+    /// number of the imported keys and addresses.
+    pub fn import(
+        &mut self,
+        data: &[u8],
+        password: String,
+        force: bool,
+    ) -> Result<(usize, usize), ImportStoreError> {
+        let data = keys::decrypt(data, password)
+            .map_err(ImportStoreError::Decryption)?;
+        let backup = Self::decode(data).map_err(ImportStoreError::Decode)?;
+        if !force {
+            let mut conflicts: Vec<&Alias> = backup
+                .keys
+                .keys()
+                .filter(|alias| self.keys.contains_key(alias))
+                .chain(
+                    backup
+                        .addresses
+                        .keys()
+                        .filter(|alias| self.addresses.contains_key(alias)),
+                )
+                .collect();
+            if !conflicts.is_empty() {
+                conflicts.sort();
+                conflicts.dedup();
+                let conflicts: Vec<String> =
+                    conflicts.into_iter().map(Alias::normalize).collect();
+                return Err(ImportStoreError::AliasConflict(
+                    conflicts.join(", "),
+                ));
+            }
+        }
+        // Drop the public key hashes of the keys that are being replaced
+        self.pkhs
+            .retain(|_pkh, alias| !backup.keys.contains_key(alias));
+        let imported = (backup.keys.len(), backup.addresses.len());
+        self.keys.extend(backup.keys);
+        self.addresses.extend(backup.addresses);
+        self.pkhs.extend(backup.pkhs);
+        Ok(imported)
+    }
+
     fn decode(data: Vec<u8>) -> Result<Self, toml::de::Error> {
         toml::from_slice(&data)
     }
@@ -482,3 +553,67 @@ mod test_wallet {
         let _ = Store::decode(data).expect("Test failed");
     }
 }
+
+#[cfg(test)]
+mod test_backup {
+    use super::*;
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut store = Store::default();
+        let (alias_1, key_1) = store.gen_key(Some("Key-1".into()), None);
+        let (alias_2, key_2) =
+            store.gen_key(Some("key-2".into()), Some("key pwd".into()));
+        let password = "backup pwd".to_owned();
+        let backup = store.export(password.clone());
+
+        // A wrong password cannot decrypt the backup
+        let mut imported = Store::default();
+        assert!(matches!(
+            imported.import(&backup, "wrong pwd".into(), false),
+            Err(ImportStoreError::Decryption(_))
+        ));
+
+        let (keys, addresses) = imported
+            .import(&backup, password.clone(), false)
+            .expect("Import should succeed");
+        assert_eq!(keys, 2);
+        assert_eq!(addresses, 2);
+
+        let found_1 = imported
+            .find_key(alias_1.normalize())
+            .expect("Key 1 missing");
+        assert!(!found_1.is_encrypted());
+        assert_eq!(found_1.get(false).unwrap().ref_to(), key_1.ref_to());
+        let found_2 = imported
+            .find_key(alias_2.normalize())
+            .expect("Key 2 missing");
+        match found_2 {
+            StoredKeypair::Encrypted(encrypted) => {
+                let decrypted = encrypted.decrypt("key pwd".into()).unwrap();
+                assert_eq!(decrypted.ref_to(), key_2.ref_to());
+            }
+            StoredKeypair::Raw(_) => panic!("Key 2 should stay encrypted"),
+        }
+        let pk_1 = key_1.ref_to();
+        assert!(imported.find_key_by_pk(&pk_1).is_some());
+        for alias in [&alias_1, &alias_2] {
+            let address = imported.find_address(alias.normalize());
+            assert!(address.is_some());
+            assert_eq!(address, store.find_address(alias.normalize()));
+        }
+
+        // Importing again must not clobber the existing aliases
+        match imported.import(&backup, password.clone(), false) {
+            Err(ImportStoreError::AliasConflict(aliases)) => {
+                assert_eq!(aliases, "key-1, key-2")
+            }
+            _ => panic!("Expected an alias conflict"),
+        }
+        // Unless forced
+        imported
+            .import(&backup, password, true)
+            .expect("Forced import should succeed");
+        assert!(imported.find_key_by_pk(&pk_1).is_some());
+    }
+}
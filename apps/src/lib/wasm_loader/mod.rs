@@ -2,7 +2,7 @@
 use core::borrow::Borrow;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use futures::future::join_all;
 use hex;
@@ -322,6 +322,31 @@ pub fn read_wasm(
     safe_exit(1);
 }
 
+/// Find the path of a WASM file as [`read_wasm`] would read it, without
+/// reading it. A file name listed in the checksums of the WASM directory is
+/// mapped to its file with the hash in the name, while other relative paths
+/// are relative to the WASM directory.
+pub fn wasm_file_path(
+    wasm_directory: impl AsRef<Path>,
+    file_path: impl AsRef<Path>,
+) -> PathBuf {
+    let wasm_directory = wasm_directory.as_ref();
+    let file_path = file_path.as_ref();
+    let checksums: Option<Checksums> =
+        fs::read(wasm_directory.join(DEFAULT_WASM_CHECKSUMS_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    let wasm_filename = file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| checksums.as_ref()?.0.get(name));
+    match wasm_filename {
+        Some(wasm_filename) => wasm_directory.join(wasm_filename),
+        None if file_path.is_relative() => wasm_directory.join(file_path),
+        None => file_path.to_owned(),
+    }
+}
+
 async fn download_wasm(url: String) -> Result<Vec<u8>, Error> {
     tracing::info!("Downloading WASM {}...", url);
     let response = reqwest::get(&url).await;
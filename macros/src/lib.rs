@@ -131,6 +131,10 @@ pub fn validity_predicate(
 /// `anoma::types::matchmaker::AddIntent` to implement a custom matchmaker
 /// algorithm.
 ///
+/// The matchmaker crate must also depend on `tracing`. The host forwards its
+/// `tracing` dispatcher to the matchmaker, so that the matchmaker's log
+/// records are filtered and printed together with the host's.
+///
 /// # Examples
 ///
 /// ```compiler_fail
@@ -165,6 +169,17 @@ pub fn matchmaker(input: TokenStream) -> TokenStream {
             state_ptr
         }
 
+        /// Set the host's `tracing` dispatcher as the matchmaker's global
+        /// default. A dylib has its own copy of `tracing`, whose dispatcher
+        /// would otherwise be unset and drop all the matchmaker's records.
+        #[no_mangle]
+        #[automatically_derived]
+        fn _set_tracing_dispatcher(dispatch: &tracing::Dispatch) {
+            // This only fails if a dispatcher has already been set, in which
+            // case we keep using it
+            let _ = tracing::dispatcher::set_global_default(dispatch.clone());
+        }
+
         /// Drop the matchmaker's state to reclaim its memory
         #[no_mangle]
         #[automatically_derived]
@@ -181,12 +196,92 @@ pub fn matchmaker(input: TokenStream) -> TokenStream {
             state_ptr: *mut std::ffi::c_void,
             intent_id: &Vec<u8>,
             intent_data: &Vec<u8>,
-        ) -> anoma::types::matchmaker::AddIntentResult {
+        ) -> Vec<anoma::types::matchmaker::AddIntentResult> {
+            let state_ptr = state_ptr as *mut #ident;
+            let mut state: #ident = unsafe { std::ptr::read(state_ptr) };
+            let results = state.add_intent(intent_id, intent_data);
+            unsafe { std::ptr::write(state_ptr, state) };
+            results
+        }
+
+        /// Ask the matchmaker to process a batch of new intents
+        #[allow(clippy::ptr_arg)]
+        #[no_mangle]
+        #[automatically_derived]
+        fn _add_intents(
+            state_ptr: *mut std::ffi::c_void,
+            intents: &Vec<(Vec<u8>, Vec<u8>)>,
+        ) -> Vec<anoma::types::matchmaker::AddIntentResult> {
+            let state_ptr = state_ptr as *mut #ident;
+            let mut state: #ident = unsafe { std::ptr::read(state_ptr) };
+            let results = state.add_intents(intents);
+            unsafe { std::ptr::write(state_ptr, state) };
+            results
+        }
+
+        /// Ask the matchmaker whether a new intent should be accepted
+        #[allow(clippy::ptr_arg)]
+        #[no_mangle]
+        #[automatically_derived]
+        fn _filter_intent(
+            state_ptr: *mut std::ffi::c_void,
+            intent_id: &Vec<u8>,
+            intent_data: &Vec<u8>,
+        ) -> bool {
+            let state = unsafe { &*(state_ptr as *const #ident) };
+            state.filter_intent(intent_id, intent_data)
+        }
+
+        /// Tell the matchmaker whether the host can receive a new match
+        /// without waiting
+        #[no_mangle]
+        #[automatically_derived]
+        fn _set_can_send_match(
+            state_ptr: *mut std::ffi::c_void,
+            can_send_match: bool,
+        ) {
+            let state_ptr = state_ptr as *mut #ident;
+            let mut state: #ident = unsafe { std::ptr::read(state_ptr) };
+            state.set_can_send_match(can_send_match);
+            unsafe { std::ptr::write(state_ptr, state) };
+        }
+
+        /// Tell the matchmaker the height of the last committed block
+        #[no_mangle]
+        #[automatically_derived]
+        fn _set_block_height(state_ptr: *mut std::ffi::c_void, height: u64) {
+            let state_ptr = state_ptr as *mut #ident;
+            let mut state: #ident = unsafe { std::ptr::read(state_ptr) };
+            state.set_block_height(height);
+            unsafe { std::ptr::write(state_ptr, state) };
+        }
+
+        /// Ask the matchmaker to remove a cancelled intent from its state
+        #[allow(clippy::ptr_arg)]
+        #[no_mangle]
+        #[automatically_derived]
+        fn _remove_intent(
+            state_ptr: *mut std::ffi::c_void,
+            intent_id: &Vec<u8>,
+        ) {
+            let state_ptr = state_ptr as *mut #ident;
+            let mut state: #ident = unsafe { std::ptr::read(state_ptr) };
+            state.remove_intent(intent_id);
+            unsafe { std::ptr::write(state_ptr, state) };
+        }
+
+        /// Ask the matchmaker to flush its state and return the resolved, but
+        /// unsent matches before it's dropped
+        #[no_mangle]
+        #[automatically_derived]
+        fn _shutdown_matchmaker(
+            state_ptr: *mut std::ffi::c_void,
+        ) -> Vec<anoma::types::matchmaker::AddIntentResult> {
             let state_ptr = state_ptr as *mut #ident;
             let mut state: #ident = unsafe { std::ptr::read(state_ptr) };
-            let result = state.add_intent(intent_id, intent_data);
+            let results = state.shutdown();
             unsafe { std::ptr::write(state_ptr, state) };
-            result
+            results
         }
     };
     TokenStream::from(gen)
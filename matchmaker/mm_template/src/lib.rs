@@ -1,8 +1,13 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
 
+use anoma::proto::Signed;
 use anoma::types::address::Address;
 use anoma::types::intent::{Auction, AuctionIntent, CreateAuction, Exchange, FungibleTokenIntent, MatchedExchanges, PlaceBid};
-use anoma::types::matchmaker::{AddIntent, AddIntentResult};
+use anoma::types::matchmaker::{
+    AddIntent, AddIntentResult, ENV_VAR_SNAPSHOTS_DIR,
+};
 use anoma::types::token;
 use anoma_macros::Matchmaker;
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -14,15 +19,226 @@ use petgraph::graph::{node_index, DiGraph, NodeIndex};
 use petgraph::visit::{depth_first_search, Control, DfsEvent, EdgeRef};
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
-use sha2::{Sha256};
-use sha2::Digest;
 
 // use anoma::ledger::vp_env::get_block_height;
 // use anoma_vp_prelude::*;
 
-#[derive(Default, Matchmaker)]
+/// The environment variable with a path to the file in which the auction
+/// snapshot is persisted
+const ENV_VAR_SNAPSHOT_PATH: &str = "ANOMA_MM_AUCTION_SNAPSHOT";
+
+/// The file name of the auction snapshot in the matchmaker's snapshots
+/// directory, used when the snapshot path is not set explicitly
+const SNAPSHOT_FILE: &str = "auction-snapshot.json";
+
+/// The environment variable with a path to the file in which the open auctions
+/// are listed for bidders
+const ENV_VAR_LISTING_PATH: &str = "ANOMA_MM_AUCTION_LISTING";
+
+#[derive(Matchmaker)]
 struct AuctionMaker {
     auctions_map: HashMap<String, AuctionEntry>,
+    /// Matches of resolved auctions that haven't been sent yet
+    unsent_matches: VecDeque<AddIntentResult>,
+    /// Whether the host can receive a new match without waiting. When it
+    /// can't, the resolved matches are kept until it can.
+    can_send_match: bool,
+    /// The persisted state of the auctions
+    snapshot: AuctionSnapshot,
+    /// Where to persist the snapshot, if anywhere
+    snapshot_path: Option<PathBuf>,
+    /// Where to list the open auctions, if anywhere
+    listing_path: Option<PathBuf>,
+    /// The height of the last committed block, if known. The auctions that
+    /// end at or before it are resolved.
+    block_height: Option<u64>,
+}
+
+/// The auction state that is persisted across matchmaker restarts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct AuctionSnapshot {
+    /// The IDs of auctions that have already been resolved. A resolved auction
+    /// must not be matched again.
+    resolved_auctions: HashSet<String>,
+}
+
+/// An open auction as listed for bidders. The identities of the auction's
+/// creator and of the bidders are omitted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct AuctionListing {
+    /// The ID by which bids refer to the auction
+    id: String,
+    create_auction: CreateAuction,
+    /// The highest bid so far, if any
+    highest_bid: Option<token::Amount>,
+    /// The end of the auction, after which no more bids are accepted
+    deadline: u64,
+}
+
+impl Default for AuctionMaker {
+    fn default() -> Self {
+        let snapshot_path = std::env::var(ENV_VAR_SNAPSHOT_PATH)
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var(ENV_VAR_SNAPSHOTS_DIR)
+                    .ok()
+                    .map(|dir| PathBuf::from(dir).join(SNAPSHOT_FILE))
+            });
+        let listing_path = std::env::var(ENV_VAR_LISTING_PATH)
+            .ok()
+            .map(PathBuf::from);
+        Self {
+            listing_path,
+            ..Self::new(snapshot_path)
+        }
+    }
+}
+
+impl AuctionMaker {
+    /// Instantiate the matchmaker and load its snapshot from the given path,
+    /// if it exists
+    fn new(snapshot_path: Option<PathBuf>) -> Self {
+        let snapshot = snapshot_path
+            .as_ref()
+            .and_then(|path| {
+                let bytes = fs::read(path).ok()?;
+                match serde_json::from_slice(&bytes) {
+                    Ok(snapshot) => Some(snapshot),
+                    Err(err) => {
+                        tracing::error!(
+                            "Ignoring the invalid auction snapshot {}: {}",
+                            path.to_string_lossy(),
+                            err
+                        );
+                        None
+                    }
+                }
+            })
+            .unwrap_or_default();
+        Self {
+            auctions_map: HashMap::default(),
+            unsent_matches: VecDeque::default(),
+            can_send_match: true,
+            snapshot,
+            snapshot_path,
+            listing_path: None,
+            block_height: None,
+        }
+    }
+
+    /// Resolve the auction with the given ID, if it exists and hasn't been
+    /// resolved before. The match is queued until it's sent.
+    fn resolve_auction(&mut self, auction_id: &str) {
+        if self.snapshot.resolved_auctions.contains(auction_id) {
+            tracing::info!("Auction {} has already been resolved", auction_id);
+            return;
+        }
+        if let Some(entry) = self.auctions_map.remove(auction_id) {
+            // Only the winning bid is matched together with the auction, the
+            // losing bids are not
+            let mut matched_intents = HashSet::from([entry.id.clone()]);
+            let tx =
+                try_resolve_auction(&entry).map(|(tx_data, winning_bid)| {
+                    matched_intents.insert(winning_bid.id.clone());
+                    tx_data.try_to_vec().unwrap()
+                });
+            self.snapshot
+                .resolved_auctions
+                .insert(auction_id.to_string());
+            self.unsent_matches.push_back(AddIntentResult {
+                tx,
+                matched_intents: Some(matched_intents),
+            });
+        }
+    }
+
+    /// Resolve the auctions that end at or before the height of the last
+    /// committed block, if it's known
+    fn resolve_ended_auctions(&mut self) {
+        if let Some(height) = self.block_height {
+            let mut ended: Vec<String> = self
+                .auctions_map
+                .iter()
+                .filter(|(_, entry)| entry.create_auction.auction_end <= height)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ended.sort();
+            for auction_id in ended {
+                tracing::info!(
+                    "Resolving the auction {} that ended at height {}",
+                    auction_id,
+                    height
+                );
+                self.resolve_auction(&auction_id);
+            }
+        }
+    }
+
+    /// Take the next resolved match that hasn't been sent yet
+    fn next_unsent_match(&mut self) -> Option<AddIntentResult> {
+        self.unsent_matches.pop_front()
+    }
+
+    /// Take all the resolved matches that haven't been sent yet, if the host
+    /// can receive them without waiting
+    fn matches_to_send(&mut self) -> Vec<AddIntentResult> {
+        if self.can_send_match {
+            self.unsent_matches.drain(..).collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// List the open auctions, sorted by their IDs
+    fn listing(&self) -> Vec<AuctionListing> {
+        let mut listing: Vec<AuctionListing> = self
+            .auctions_map
+            .iter()
+            .map(|(id, entry)| AuctionListing {
+                id: id.clone(),
+                create_auction: entry.create_auction.clone(),
+                highest_bid: entry
+                    .bids
+                    .iter()
+                    .map(|bid| bid.place_bid.amount)
+                    .max(),
+                deadline: entry.create_auction.auction_end,
+            })
+            .collect();
+        listing.sort_by(|a, b| a.id.cmp(&b.id));
+        listing
+    }
+
+    /// Write the listing of the open auctions to its file, if any
+    fn write_listing(&self) {
+        if let Some(path) = &self.listing_path {
+            let bytes = serde_json::to_vec_pretty(&self.listing())
+                .expect("Auction listing serialization shouldn't fail");
+            if let Err(err) = fs::write(path, bytes) {
+                tracing::warn!(
+                    "Failed to write the auction listing to {}: {}",
+                    path.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Write the snapshot to its file, if any
+    fn persist_snapshot(&self) {
+        if let Some(path) = &self.snapshot_path {
+            let bytes = serde_json::to_vec(&self.snapshot)
+                .expect("Auction snapshot serialization shouldn't fail");
+            if let Err(err) = fs::write(path, bytes) {
+                tracing::warn!(
+                    "Failed to persist the auction snapshot to {}: {}",
+                    path.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
 }
 
 impl AddIntent for AuctionMaker {
@@ -30,51 +246,50 @@ impl AddIntent for AuctionMaker {
         &mut self,
         intent_id: &Vec<u8>,
         intent_data: &Vec<u8>,
-    ) -> AddIntentResult {
+    ) -> Vec<AddIntentResult> {
         let intent = decode_intent_data(&intent_data[..]);
         let auctions = intent.data.auctions.clone();
 
-        println!("intent_id: {:?}", intent_id);
+        tracing::debug!("intent_id: {:?}", intent_id);
 
-        //TODO: check if intent is defined for an existing auction, and resolve it, if
-        // time is over
         for x in &auctions {
-            println!("data: {:?}", x.data);
-            println!("signature: {:?}", x.sig);
+            tracing::debug!("data: {:?}", x.data);
+            tracing::debug!("signature: {:?}", x.sig);
 
             // println!("auction_end: {:?}", x.data.auction_end);
-            println!("create_auction: {:?}", x.data.create_auction);
-            println!("place_bid: {:?}", x.data.place_bid);
-            // println!("current height: {:?}", get_block_height());
-            //TODO: get current height
-
-            let result = try_resolve_auction(
-                &mut self.auctions_map,
-                intent_id.to_vec(),
-                auction,
-                intent.clone(),
-            );
-
-            if result.is_some() {
-                return result.unwrap();
-            }
+            tracing::debug!("create_auction: {:?}", x.data.create_auction);
+            tracing::debug!("place_bid: {:?}", x.data.place_bid);
         }
 
+        // The auctions that are over are resolved before the new bids are
+        // added, which are too late for them
+        self.resolve_ended_auctions();
+
         //TODO: add new auctions if intent is AuctionIntent
-        println!("trying to add create_auction intents");
-        auctions.into_iter().for_each(|auction| {
-            if auction.data.create_auction.is_some() {
-                add_auction_entry(
-                    &mut self.auctions_map,
-                    intent_id.to_vec(),
-                    auction,
-                    intent.clone(),
-                )
+        tracing::debug!("trying to add create_auction intents");
+        let resolved_auctions = &self.snapshot.resolved_auctions;
+        auctions.iter().cloned().for_each(|auction| {
+            if let Some(create_auction) = &auction.data.create_auction {
+                // A resolved auction must not be opened again
+                let auction_id = create_auction.id();
+                if resolved_auctions.contains(&auction_id) {
+                    tracing::warn!(
+                        "The auction {} has already been resolved",
+                        auction_id
+                    );
+                } else {
+                    add_auction_entry(
+                        &mut self.auctions_map,
+                        intent_id.to_vec(),
+                        auction,
+                        intent.clone(),
+                    )
+                }
             }
         });
 
         //TODO: add new bid if intent is BidIntent
-        println!("trying to add place_bid intents");
+        tracing::debug!("trying to add place_bid intents");
         auctions.into_iter().for_each(|auction| {
             if auction.data.place_bid.is_some() {
                 add_bid_entry(
@@ -86,16 +301,39 @@ impl AddIntent for AuctionMaker {
             }
         });
 
-        AddIntentResult {
-            tx: None,
-            matched_intents: None,
+        self.write_listing();
+        self.matches_to_send()
+    }
+
+    fn set_can_send_match(&mut self, can_send_match: bool) {
+        self.can_send_match = can_send_match;
+    }
+
+    fn set_block_height(&mut self, height: u64) {
+        self.block_height = Some(height);
+    }
+
+    fn remove_intent(&mut self, intent_id: &Vec<u8>) {
+        // A cancelled auction is closed together with its bids and a
+        // cancelled bid is withdrawn from its auction
+        self.auctions_map.retain(|_, entry| entry.id != *intent_id);
+        for entry in self.auctions_map.values_mut() {
+            entry.bids.retain(|bid| bid.id != *intent_id);
         }
+        self.write_listing();
+    }
+
+    fn shutdown(&mut self) -> Vec<AddIntentResult> {
+        self.persist_snapshot();
+        self.unsent_matches.drain(..).collect()
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct BidEntry {
     id: Vec<u8>,
+    /// The bidder
+    addr: Address,
     place_bid: PlaceBid,
     intent: anoma::proto::Signed<AuctionIntent>,
 }
@@ -103,6 +341,8 @@ struct BidEntry {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AuctionEntry {
     id: Vec<u8>,
+    /// The creator of the auction
+    addr: Address,
     create_auction: CreateAuction,
     intent: anoma::proto::Signed<AuctionIntent>,
     bids: Vec<BidEntry>,
@@ -124,24 +364,21 @@ fn add_auction_entry(
 ) {
     let new_entry = AuctionEntry {
         id,
+        addr: auction.data.addr.clone(),
         create_auction: auction.data.create_auction.unwrap().clone(),
         intent,
         bids: vec![],
     };
 
-    // create a Sha256 object
-    let mut hasher = Sha256::new();
-    // write input message
-    hasher.update(new_entry.create_auction);
-    // read hash digest and consume hasher
-    let key = hasher.finalize();
+    // The auction is keyed by the same ID that is shown to bidders
+    let key = new_entry.create_auction.id();
 
-    if auctions_map.contains_key(&*key[..].encode_hex::<String>()) {
-        println!("Hashmap already contains entry with key: {:?}.", key[..]);
+    if auctions_map.contains_key(&key) {
+        tracing::warn!("Hashmap already contains entry with key: {}.", key);
         return;
     }
 
-    auctions_map.insert(key[..].encode_hex::<String>(), new_entry.clone());
+    auctions_map.insert(key, new_entry);
 }
 
 /// Add a new node to the graph for the intent
@@ -153,41 +390,126 @@ fn add_bid_entry(
 ) {
     let new_entry = BidEntry {
         id,
+        addr: auction.data.addr.clone(),
         place_bid: auction.data.place_bid.unwrap().clone(),
         intent,
     };
 
-    if auctions_map.contains_key(&new_entry.place_bid.auction_id) {
-        // println!("Hashmap already contains entry with key: {:?}.", key[..]);
-        // TODO:
-        return;
-    } else {
-        println!("No such auction exist with id: {:?}.", new_entry.place_bid.auction_id);
+    match auctions_map.get_mut(&new_entry.place_bid.auction_id) {
+        Some(auction_entry)
+            if new_entry.place_bid.token
+                != auction_entry.create_auction.token_buy =>
+        {
+            tracing::warn!(
+                "Rejecting the bid in {} for the auction {}, which is paid in \
+                 {}.",
+                new_entry.place_bid.token,
+                new_entry.place_bid.auction_id,
+                auction_entry.create_auction.token_buy
+            );
+        }
+        Some(auction_entry) => {
+            // The bid must exceed the highest bid by the auction's increment
+            let highest_bid = auction_entry
+                .bids
+                .iter()
+                .map(|bid| bid.place_bid.amount)
+                .max();
+            if auction_entry
+                .create_auction
+                .accepts_bid(highest_bid, new_entry.place_bid.amount)
+            {
+                auction_entry.bids.push(new_entry);
+            } else {
+                tracing::warn!(
+                    "Rejecting the bid of {} for the auction {}, it doesn't \
+                     exceed the highest bid by the minimum bid increment {}.",
+                    new_entry.place_bid.amount,
+                    new_entry.place_bid.auction_id,
+                    auction_entry.create_auction.min_bid_increment
+                );
+            }
+        }
+        None => {
+            tracing::warn!(
+                "No such auction exist with id: {:?}.",
+                new_entry.place_bid.auction_id
+            );
+        }
     }
 }
 
-/// Add a new node to the graph for the intent
+/// Craft the transfers of a resolved auction together with the exchanges
+/// that its creator and its highest bidder committed to. The highest bid is
+/// paid in the auction's payment token to its creator and the auctioned
+/// tokens are delivered to the highest bidder. Returns `None` for an auction
+/// without bids or when the exchanges are missing from the intents.
 fn try_resolve_auction(
-    auctions_map: &mut HashMap<String, AuctionEntry>,
-    id: Vec<u8>,
-    auction: anoma::proto::Signed<Auction>,
-    intent: anoma::proto::Signed<AuctionIntent>,
-) -> Option<AddIntentResult> {
-    let new_entry = BidEntry {
-        id,
-        place_bid: auction.data.place_bid.unwrap().clone(),
-        intent,
-    };
-
-    return if auctions_map.contains_key(&new_entry.place_bid.auction_id) {
-        // TODO:
-        Some(AddIntentResult {
-            tx: None,
-            matched_intents: None,
-        })
-    } else {
-        None
+    entry: &AuctionEntry,
+) -> Option<(MatchedExchanges, &BidEntry)> {
+    let winning_bid =
+        entry.bids.iter().max_by_key(|bid| bid.place_bid.amount)?;
+    let create_auction = &entry.create_auction;
+    let creator_exchange = find_exchange(
+        &entry.intent,
+        &create_auction.creator_exchange(entry.addr.clone()),
+    );
+    let bidder_exchange = find_exchange(
+        &winning_bid.intent,
+        &create_auction.bidder_exchange(
+            winning_bid.addr.clone(),
+            winning_bid.place_bid.amount,
+        ),
+    );
+    let (creator_exchange, bidder_exchange) =
+        match (creator_exchange, bidder_exchange) {
+            (Some(creator), Some(bidder)) => (creator, bidder),
+            _ => {
+                tracing::warn!(
+                    "The auction {} cannot be settled, the exchange of its \
+                     creator or of its highest bidder is missing",
+                    create_auction.id()
+                );
+                return None;
+            }
+        };
+    let mut tx_data = MatchedExchanges::empty();
+    for (addr, (exchange, intent)) in [
+        (&entry.addr, creator_exchange),
+        (&winning_bid.addr, bidder_exchange),
+    ] {
+        tx_data.exchanges.insert(addr.clone(), exchange);
+        tx_data.intents.insert(addr.clone(), intent);
     }
+    tx_data.transfers.insert(token::Transfer {
+        source: winning_bid.addr.clone(),
+        target: entry.addr.clone(),
+        token: entry.create_auction.token_buy.clone(),
+        amount: winning_bid.place_bid.amount,
+    });
+    tx_data.transfers.insert(token::Transfer {
+        source: entry.addr.clone(),
+        target: winning_bid.addr.clone(),
+        token: entry.create_auction.token_sell.clone(),
+        amount: entry.create_auction.amount,
+    });
+    tracing::debug!("tx data: {:?}", tx_data.transfers);
+    Some((tx_data, winning_bid))
+}
+
+/// Find the expected exchange in the exchange intent of its address in an
+/// auction intent. Returns the signed exchange with its signed intent.
+fn find_exchange(
+    intent: &Signed<AuctionIntent>,
+    expected: &Exchange,
+) -> Option<(Signed<Exchange>, Signed<FungibleTokenIntent>)> {
+    let exchange_intent = intent.data.exchange_intents.get(&expected.addr)?;
+    let exchange = exchange_intent
+        .data
+        .exchange
+        .iter()
+        .find(|exchange| exchange.data == *expected)?;
+    Some((exchange.clone(), exchange_intent.clone()))
 }
 
 // /// Find the nodes that are matching the intent on sell side and buy side.
@@ -474,3 +796,592 @@ fn decode_intent_data(
 ) -> anoma::proto::Signed<AuctionIntent> {
     anoma::proto::Signed::<AuctionIntent>::try_from_slice(bytes).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address;
+    use anoma::types::key::common;
+
+    use super::*;
+
+    /// Sign an auction into an intent together with the exchange intent of
+    /// the auction's address, which commits to the exchange of the auction's
+    /// creator or of its bidder for the given auction
+    fn signed_auction(
+        keypair: &common::SecretKey,
+        auction: Auction,
+        create_auction: &CreateAuction,
+    ) -> (Signed<Auction>, Signed<AuctionIntent>) {
+        let addr = auction.addr.clone();
+        let exchange = match &auction.place_bid {
+            Some(place_bid) => {
+                create_auction.bidder_exchange(addr.clone(), place_bid.amount)
+            }
+            None => create_auction.creator_exchange(addr.clone()),
+        };
+        let exchange_intent = Signed::new(
+            keypair,
+            FungibleTokenIntent {
+                exchange: HashSet::from([Signed::new(keypair, exchange)]),
+                nonce: 0,
+            },
+        );
+        let auction = Signed::new(keypair, auction);
+        let intent = Signed::new(
+            keypair,
+            AuctionIntent {
+                auctions: HashSet::from([auction.clone()]),
+                exchange_intents: HashMap::from([(addr, exchange_intent)]),
+            },
+        );
+        (auction, intent)
+    }
+
+    /// Test that the matches resolved before shutdown are each returned on
+    /// shutdown with their own tx and that the resolutions are persisted so
+    /// that they're not sent again on restart
+    #[test]
+    fn test_shutdown_flushes_resolved_auction() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot_path = dir.path().join("snapshot.json");
+        let mut matchmaker = AuctionMaker::new(Some(snapshot_path.clone()));
+
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = CreateAuction {
+            token_sell: anoma::types::address::xan(),
+            token_buy: anoma::types::address::btc(),
+            amount: token::Amount::from(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let creator = address::testing::established_address_1();
+        let bidder = address::testing::established_address_2();
+        let place_bid = |auction_id: &str| PlaceBid {
+            token: anoma::types::address::btc(),
+            amount: token::Amount::from(10),
+            auction_id: auction_id.to_string(),
+        };
+        let auctions = [
+            ("auction_1".to_string(), vec![1_u8], vec![2_u8]),
+            ("auction_2".to_string(), vec![3_u8], vec![4_u8]),
+        ];
+        for (auction_id, intent_id, bid_id) in &auctions {
+            let (_, creator_intent) = signed_auction(
+                &keypair,
+                Auction {
+                    addr: creator.clone(),
+                    create_auction: Some(create_auction.clone()),
+                    place_bid: None,
+                },
+                &create_auction,
+            );
+            let (_, bidder_intent) = signed_auction(
+                &keypair,
+                Auction {
+                    addr: bidder.clone(),
+                    create_auction: None,
+                    place_bid: Some(place_bid(auction_id)),
+                },
+                &create_auction,
+            );
+            matchmaker.auctions_map.insert(
+                auction_id.clone(),
+                AuctionEntry {
+                    id: intent_id.clone(),
+                    addr: creator.clone(),
+                    create_auction: create_auction.clone(),
+                    intent: creator_intent,
+                    bids: vec![BidEntry {
+                        id: bid_id.clone(),
+                        addr: bidder.clone(),
+                        place_bid: place_bid(auction_id),
+                        intent: bidder_intent,
+                    }],
+                },
+            );
+            matchmaker.resolve_auction(auction_id);
+        }
+
+        // Shut down before the matches are consumed
+        let results = matchmaker.shutdown();
+        assert_eq!(results.len(), auctions.len());
+        for (result, (_, intent_id, bid_id)) in results.iter().zip(&auctions) {
+            assert!(result.tx.is_some());
+            let expected: HashSet<Vec<u8>> =
+                HashSet::from([intent_id.clone(), bid_id.clone()]);
+            assert_eq!(result.matched_intents, Some(expected));
+        }
+
+        // The snapshot must record the resolutions
+        let mut restarted = AuctionMaker::new(Some(snapshot_path));
+        for (auction_id, _, _) in &auctions {
+            assert!(restarted.snapshot.resolved_auctions.contains(auction_id));
+            restarted.resolve_auction(auction_id);
+        }
+        assert!(restarted.next_unsent_match().is_none());
+    }
+
+    /// Test that the resolved matches are held back while the host can't
+    /// receive them and are all sent once it can
+    #[test]
+    fn test_match_held_back_while_host_busy() {
+        let mut matchmaker = AuctionMaker::new(None);
+        for tx in [vec![1], vec![2]] {
+            matchmaker.unsent_matches.push_back(AddIntentResult {
+                tx: Some(tx),
+                matched_intents: None,
+            });
+        }
+
+        matchmaker.set_can_send_match(false);
+        assert!(matchmaker.matches_to_send().is_empty());
+        assert_eq!(matchmaker.unsent_matches.len(), 2);
+
+        matchmaker.set_can_send_match(true);
+        let txs: Vec<_> = matchmaker
+            .matches_to_send()
+            .into_iter()
+            .map(|result| result.tx)
+            .collect();
+        assert_eq!(txs, vec![Some(vec![1]), Some(vec![2])]);
+        assert!(matchmaker.unsent_matches.is_empty());
+    }
+
+    /// Test that an invalid snapshot is ignored instead of crashing the
+    /// matchmaker
+    #[test]
+    fn test_invalid_snapshot_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot_path = dir.path().join("snapshot.json");
+        fs::write(&snapshot_path, "not a snapshot").unwrap();
+        let matchmaker = AuctionMaker::new(Some(snapshot_path));
+        assert_eq!(matchmaker.snapshot, AuctionSnapshot::default());
+    }
+
+    /// Test that a new auction is keyed by the ID that the client prints for
+    /// it
+    #[test]
+    fn test_auction_entry_keyed_by_id() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = CreateAuction {
+            token_sell: anoma::types::address::xan(),
+            token_buy: anoma::types::address::btc(),
+            amount: token::Amount::from(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let auction = anoma::proto::Signed::new(
+            &keypair,
+            Auction {
+                addr: anoma::types::address::testing::established_address_1(),
+                create_auction: Some(create_auction.clone()),
+                place_bid: None,
+            },
+        );
+        let intent = anoma::proto::Signed::new(
+            &keypair,
+            AuctionIntent {
+                auctions: HashSet::from([auction.clone()]),
+                exchange_intents: HashMap::default(),
+            },
+        );
+
+        let mut auctions_map = HashMap::default();
+        add_auction_entry(&mut auctions_map, vec![1_u8], auction, intent);
+        let keys: Vec<&String> = auctions_map.keys().collect();
+        assert_eq!(keys, vec![&create_auction.id()]);
+    }
+
+    /// Test that the open auctions are listed with their parameters
+    #[test]
+    fn test_auction_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let listing_path = dir.path().join("listing.json");
+        let mut matchmaker = AuctionMaker {
+            listing_path: Some(listing_path.clone()),
+            ..AuctionMaker::new(None)
+        };
+
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = |amount, auction_end| CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::from(amount),
+            auction_start: 0,
+            auction_end,
+            min_bid_increment: token::Amount::default(),
+        };
+        let auctions = [create_auction(100, 10), create_auction(200, 20)];
+        for create_auction in &auctions {
+            let auction = anoma::proto::Signed::new(
+                &keypair,
+                Auction {
+                    addr: address::testing::established_address_1(),
+                    create_auction: Some(create_auction.clone()),
+                    place_bid: None,
+                },
+            );
+            let intent = anoma::proto::Signed::new(
+                &keypair,
+                AuctionIntent {
+                    auctions: HashSet::from([auction.clone()]),
+                    exchange_intents: HashMap::default(),
+                },
+            );
+            add_auction_entry(
+                &mut matchmaker.auctions_map,
+                vec![1_u8],
+                auction,
+                intent,
+            );
+        }
+        matchmaker.write_listing();
+
+        let mut expected: Vec<AuctionListing> = auctions
+            .iter()
+            .map(|create_auction| AuctionListing {
+                id: create_auction.id(),
+                create_auction: create_auction.clone(),
+                highest_bid: None,
+                deadline: create_auction.auction_end,
+            })
+            .collect();
+        expected.sort_by(|a, b| a.id.cmp(&b.id));
+        let listing: Vec<AuctionListing> =
+            serde_json::from_slice(&fs::read(listing_path).unwrap()).unwrap();
+        assert_eq!(listing, expected);
+    }
+
+    /// Test that a bid that doesn't exceed the highest bid by the auction's
+    /// minimum bid increment is not added
+    #[test]
+    fn test_bid_increment_enforced() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::whole(5),
+        };
+        let signed =
+            |auction| signed_auction(&keypair, auction, &create_auction);
+        let bid = |amount| Auction {
+            addr: address::testing::established_address_2(),
+            create_auction: None,
+            place_bid: Some(PlaceBid {
+                amount: token::Amount::whole(amount),
+                token: address::btc(),
+                auction_id: create_auction.id(),
+            }),
+        };
+
+        let mut auctions_map = HashMap::default();
+        let (auction, intent) = signed(Auction {
+            addr: address::testing::established_address_1(),
+            create_auction: Some(create_auction.clone()),
+            place_bid: None,
+        });
+        add_auction_entry(&mut auctions_map, vec![1_u8], auction, intent);
+        for (id, amount) in [(2_u8, 20), (3, 24), (4, 25)] {
+            let (auction, intent) = signed(bid(amount));
+            add_bid_entry(&mut auctions_map, vec![id], auction, intent);
+        }
+
+        let bids: Vec<token::Amount> = auctions_map[&create_auction.id()]
+            .bids
+            .iter()
+            .map(|bid| bid.place_bid.amount)
+            .collect();
+        assert_eq!(
+            bids,
+            vec![token::Amount::whole(20), token::Amount::whole(25)]
+        );
+    }
+
+    /// Test that a cross-token auction is resolved into a payment of the
+    /// highest bid in the payment token and a delivery of the auctioned token,
+    /// and that a bid in another token is rejected
+    #[test]
+    fn test_resolve_cross_token_auction() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let creator = address::testing::established_address_1();
+        let bidder = address::testing::established_address_2();
+        let create_auction = CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let signed =
+            |auction| signed_auction(&keypair, auction, &create_auction);
+        let bid = |addr: &Address, bid_token, amount| Auction {
+            addr: addr.clone(),
+            create_auction: None,
+            place_bid: Some(PlaceBid {
+                amount: token::Amount::whole(amount),
+                token: bid_token,
+                auction_id: create_auction.id(),
+            }),
+        };
+
+        let mut matchmaker = AuctionMaker::new(None);
+        let (auction, intent) = signed(Auction {
+            addr: creator.clone(),
+            create_auction: Some(create_auction.clone()),
+            place_bid: None,
+        });
+        add_auction_entry(
+            &mut matchmaker.auctions_map,
+            vec![1_u8],
+            auction,
+            intent,
+        );
+        let other_bidder = address::testing::established_address_3();
+        let bids = [
+            (2_u8, bid(&bidder, address::btc(), 20)),
+            // Bids in a token other than the payment token are rejected
+            (3, bid(&other_bidder, address::eth(), 50)),
+        ];
+        for (id, bid) in bids {
+            let (auction, intent) = signed(bid);
+            add_bid_entry(
+                &mut matchmaker.auctions_map,
+                vec![id],
+                auction,
+                intent,
+            );
+        }
+        matchmaker.resolve_auction(&create_auction.id());
+
+        let result = matchmaker.next_unsent_match().unwrap();
+        let tx_data =
+            MatchedExchanges::try_from_slice(&result.tx.unwrap()).unwrap();
+        let expected: HashSet<token::Transfer> = HashSet::from([
+            token::Transfer {
+                source: bidder.clone(),
+                target: creator.clone(),
+                token: address::btc(),
+                amount: token::Amount::whole(20),
+            },
+            token::Transfer {
+                source: creator.clone(),
+                target: bidder.clone(),
+                token: address::xan(),
+                amount: token::Amount::whole(100),
+            },
+        ]);
+        assert_eq!(tx_data.transfers, expected);
+        // The exchanges of the creator and of the highest bidder are included
+        // for their validity predicates
+        let mut exchange_addrs: Vec<&Address> =
+            tx_data.exchanges.keys().collect();
+        exchange_addrs.sort();
+        let mut intent_addrs: Vec<&Address> = tx_data.intents.keys().collect();
+        intent_addrs.sort();
+        let mut expected_addrs = vec![&creator, &bidder];
+        expected_addrs.sort();
+        assert_eq!(exchange_addrs, expected_addrs);
+        assert_eq!(intent_addrs, expected_addrs);
+        let expected_ids: HashSet<Vec<u8>> = HashSet::from([vec![1], vec![2]]);
+        assert_eq!(result.matched_intents, Some(expected_ids));
+    }
+
+    /// Test that the auctions that are over are resolved when new intents are
+    /// added, that only the winning bid is matched and that a resolved auction
+    /// is not opened again
+    #[test]
+    fn test_ended_auction_resolved_on_add_intent() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let signed = |auction| {
+            let (_, intent) =
+                signed_auction(&keypair, auction, &create_auction);
+            intent.try_to_vec().unwrap()
+        };
+        let bid = |amount| Auction {
+            addr: address::testing::established_address_2(),
+            create_auction: None,
+            place_bid: Some(PlaceBid {
+                amount: token::Amount::whole(amount),
+                token: address::btc(),
+                auction_id: create_auction.id(),
+            }),
+        };
+        let create = signed(Auction {
+            addr: address::testing::established_address_1(),
+            create_auction: Some(create_auction.clone()),
+            place_bid: None,
+        });
+
+        let mut matchmaker = AuctionMaker::new(None);
+        matchmaker.set_block_height(5);
+        let intents = [
+            (1_u8, create.clone()),
+            (2, signed(bid(20))),
+            (3, signed(bid(30))),
+        ];
+        for (id, intent) in intents {
+            let results = matchmaker.add_intent(&vec![id], &intent);
+            assert!(results.is_empty());
+        }
+
+        // A bid that comes after the auction has ended is too late
+        matchmaker.set_block_height(10);
+        let results = matchmaker.add_intent(&vec![4], &signed(bid(40)));
+        assert_eq!(results.len(), 1);
+        assert!(results[0].tx.is_some());
+        let expected_ids: HashSet<Vec<u8>> = HashSet::from([vec![1], vec![3]]);
+        assert_eq!(results[0].matched_intents, Some(expected_ids));
+        assert!(matchmaker.auctions_map.is_empty());
+
+        let results = matchmaker.add_intent(&vec![5], &create);
+        assert!(results.is_empty());
+        assert!(matchmaker.auctions_map.is_empty());
+    }
+
+    /// Test that the auctions that end at the same time are all resolved and
+    /// returned from the same call
+    #[test]
+    fn test_ended_auctions_resolved_together() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = |amount| CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(amount),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let create = |create_auction: &CreateAuction| {
+            let auction = Auction {
+                addr: address::testing::established_address_1(),
+                create_auction: Some(create_auction.clone()),
+                place_bid: None,
+            };
+            let (_, intent) = signed_auction(&keypair, auction, create_auction);
+            intent.try_to_vec().unwrap()
+        };
+        let bid = |create_auction: &CreateAuction| {
+            let auction = Auction {
+                addr: address::testing::established_address_2(),
+                create_auction: None,
+                place_bid: Some(PlaceBid {
+                    amount: token::Amount::whole(20),
+                    token: address::btc(),
+                    auction_id: create_auction.id(),
+                }),
+            };
+            let (_, intent) = signed_auction(&keypair, auction, create_auction);
+            intent.try_to_vec().unwrap()
+        };
+        let first_auction = create_auction(100);
+        let second_auction = create_auction(200);
+
+        let mut matchmaker = AuctionMaker::new(None);
+        matchmaker.set_block_height(5);
+        let intents = [
+            (1_u8, create(&first_auction)),
+            (2, bid(&first_auction)),
+            (3, create(&second_auction)),
+            (4, bid(&second_auction)),
+        ];
+        for (id, intent) in intents {
+            let results = matchmaker.add_intent(&vec![id], &intent);
+            assert!(results.is_empty());
+        }
+
+        // An unrelated intent added after both auctions have ended
+        matchmaker.set_block_height(10);
+        let results =
+            matchmaker.add_intent(&vec![5], &create(&create_auction(300)));
+        let mut matched_intents: Vec<HashSet<Vec<u8>>> = results
+            .into_iter()
+            .map(|result| {
+                assert!(result.tx.is_some());
+                result.matched_intents.unwrap()
+            })
+            .collect();
+        matched_intents.sort_by_key(|ids| ids.iter().min().cloned());
+        assert_eq!(
+            matched_intents,
+            vec![
+                HashSet::from([vec![1], vec![2]]),
+                HashSet::from([vec![3], vec![4]])
+            ]
+        );
+        assert!(matchmaker.unsent_matches.is_empty());
+    }
+
+    /// Test that a cancelled bid is withdrawn from its auction and that a
+    /// cancelled auction is closed
+    #[test]
+    fn test_remove_intent() {
+        let keypair = anoma::types::key::testing::keypair_1();
+        let create_auction = CreateAuction {
+            token_sell: address::xan(),
+            token_buy: address::btc(),
+            amount: token::Amount::whole(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let signed =
+            |auction| signed_auction(&keypair, auction, &create_auction);
+        let bid = |amount| Auction {
+            addr: address::testing::established_address_2(),
+            create_auction: None,
+            place_bid: Some(PlaceBid {
+                amount: token::Amount::whole(amount),
+                token: address::btc(),
+                auction_id: create_auction.id(),
+            }),
+        };
+
+        let mut matchmaker = AuctionMaker::new(None);
+        let (auction, intent) = signed(Auction {
+            addr: address::testing::established_address_1(),
+            create_auction: Some(create_auction.clone()),
+            place_bid: None,
+        });
+        add_auction_entry(
+            &mut matchmaker.auctions_map,
+            vec![1_u8],
+            auction,
+            intent,
+        );
+        for (id, amount) in [(2_u8, 20), (3, 30)] {
+            let (auction, intent) = signed(bid(amount));
+            add_bid_entry(
+                &mut matchmaker.auctions_map,
+                vec![id],
+                auction,
+                intent,
+            );
+        }
+
+        matchmaker.remove_intent(&vec![3]);
+        let bid_ids: Vec<&Vec<u8>> = matchmaker.auctions_map
+            [&create_auction.id()]
+            .bids
+            .iter()
+            .map(|bid| &bid.id)
+            .collect();
+        assert_eq!(bid_ids, vec![&vec![2]]);
+
+        matchmaker.remove_intent(&vec![1]);
+        assert!(matchmaker.auctions_map.is_empty());
+    }
+}
@@ -1,35 +1,350 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anoma::types::address::Address;
 use anoma::types::intent::{Exchange, FungibleTokenIntent, MatchedExchanges};
-use anoma::types::matchmaker::{AddIntent, AddIntentResult};
+use anoma::types::matchmaker::{
+    AddIntent, AddIntentResult, ENV_VAR_SNAPSHOTS_DIR,
+};
 use anoma::types::token;
 use anoma_macros::Matchmaker;
 use borsh::{BorshDeserialize, BorshSerialize};
 use good_lp::{
     constraint, default_solver, variable, variables, Expression,
-    ResolutionError, SolverModel, Variable, VariableDefinition,
+    ResolutionError, Solution, SolverModel, Variable, VariableDefinition,
 };
 use petgraph::graph::{node_index, DiGraph, NodeIndex};
 use petgraph::visit::{depth_first_search, Control, DfsEvent, EdgeRef};
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// The maximum size of an intent's data accepted by the matchmaker. Larger
+/// intents are rejected by the filter.
+const MAX_INTENT_DATA_BYTES: usize = 64 * 1024;
+
+/// The environment variable with the maximum number of intents accepted from
+/// a single address within the rate limit window
+const ENV_VAR_RATE_LIMIT: &str = "ANOMA_MM_RATE_LIMIT";
+/// The environment variable with the length of the rate limit window in
+/// seconds
+const ENV_VAR_RATE_LIMIT_WINDOW: &str = "ANOMA_MM_RATE_LIMIT_WINDOW";
+/// The default maximum number of intents accepted from a single address within
+/// the rate limit window
+const DEFAULT_RATE_LIMIT: usize = 100;
+/// The default length of the rate limit window
+const DEFAULT_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+/// The environment variable with the maximum number of intents in a matched
+/// cycle
+const ENV_VAR_MAX_CYCLE_LEN: &str = "ANOMA_MM_MAX_CYCLE_LEN";
+/// The default maximum number of intents in a matched cycle
+const DEFAULT_MAX_CYCLE_LEN: usize = 8;
+/// The largest difference in micro units of a solver's amount from a whole
+/// number of micro units that is treated as a floating point error rather
+/// than a fraction beyond the token's precision
+const SOLVER_AMOUNT_TOLERANCE: f64 = 1e-3;
+/// The environment variable with a path to the file in which the nonces of
+/// the matched intents are persisted
+const ENV_VAR_NONCES_SNAPSHOT_PATH: &str = "ANOMA_MM_NONCES_SNAPSHOT";
+/// The file name of the nonces snapshot in the matchmaker's snapshots
+/// directory, used when the snapshot path is not set explicitly
+const NONCES_SNAPSHOT_FILE: &str = "token-exchange-nonces.json";
+
 #[derive(Default, Matchmaker)]
 struct TokenExchange {
     graph: DiGraph<ExchangeNode, Address>,
+    rate_limiter: RateLimiter,
+    max_cycle_len: MaxCycleLen,
+    matched_nonces: MatchedNonces,
+}
+
+/// The highest nonce of the matched intents of every address. An intent with
+/// a nonce that is not greater is a replay. The nonces are persisted, so that
+/// the matched intents cannot be replayed after a matchmaker restart.
+#[derive(Debug)]
+struct MatchedNonces {
+    nonces: HashMap<Address, u64>,
+    /// Where to persist the nonces, if anywhere
+    snapshot_path: Option<PathBuf>,
+}
+
+/// Limits the number of intents accepted from every source address within a
+/// sliding window
+#[derive(Debug)]
+struct RateLimiter {
+    /// The maximum number of intents from a single address within the window
+    max_intents: usize,
+    window: Duration,
+    /// The times at which the recent intents of every address were accepted
+    recent_intents: HashMap<Address, VecDeque<Instant>>,
+}
+
+/// The maximum number of intents in a matched cycle. The longer cycles are
+/// skipped, so that the cost of solving and settling a match stays bounded.
+#[derive(Debug, Clone, Copy)]
+struct MaxCycleLen(usize);
+
+impl Default for MaxCycleLen {
+    fn default() -> Self {
+        let max_cycle_len = std::env::var(ENV_VAR_MAX_CYCLE_LEN)
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(DEFAULT_MAX_CYCLE_LEN);
+        Self(max_cycle_len)
+    }
+}
+
+impl Default for MatchedNonces {
+    fn default() -> Self {
+        let snapshot_path = std::env::var(ENV_VAR_NONCES_SNAPSHOT_PATH)
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var(ENV_VAR_SNAPSHOTS_DIR)
+                    .ok()
+                    .map(|dir| PathBuf::from(dir).join(NONCES_SNAPSHOT_FILE))
+            });
+        Self::new(snapshot_path)
+    }
+}
+
+impl MatchedNonces {
+    /// Load the nonces from the given snapshot path, if it exists
+    fn new(snapshot_path: Option<PathBuf>) -> Self {
+        let nonces = snapshot_path
+            .as_ref()
+            .and_then(|path| {
+                let bytes = fs::read(path).ok()?;
+                match serde_json::from_slice(&bytes) {
+                    Ok(nonces) => Some(nonces),
+                    Err(err) => {
+                        tracing::error!(
+                            "Ignoring the invalid nonces snapshot {}: {}",
+                            path.to_string_lossy(),
+                            err
+                        );
+                        None
+                    }
+                }
+            })
+            .unwrap_or_default();
+        Self {
+            nonces,
+            snapshot_path,
+        }
+    }
+
+    /// Check if the given nonce of an intent from the given address is not
+    /// greater than the nonce of its last matched intent
+    fn is_replay(&self, addr: &Address, nonce: u64) -> bool {
+        self.nonces
+            .get(addr)
+            .map(|matched_nonce| nonce <= *matched_nonce)
+            .unwrap_or_default()
+    }
+
+    /// Record the nonces of the matched intents and persist them
+    fn record<'a>(
+        &mut self,
+        matched: impl IntoIterator<Item = (&'a Address, u64)>,
+    ) {
+        for (addr, nonce) in matched {
+            let matched_nonce = self.nonces.entry(addr.clone()).or_default();
+            *matched_nonce = (*matched_nonce).max(nonce);
+        }
+        self.persist_snapshot();
+    }
+
+    /// Write the nonces to the snapshot file, if any
+    fn persist_snapshot(&self) {
+        if let Some(path) = &self.snapshot_path {
+            let bytes = serde_json::to_vec(&self.nonces)
+                .expect("Nonces snapshot serialization shouldn't fail");
+            if let Err(err) = write_atomically(path, &bytes) {
+                tracing::warn!(
+                    "Failed to persist the nonces snapshot to {}: {}",
+                    path.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Write the contents to a temporary file next to the given path, which is
+/// then renamed to the path, so that a crash mid-write never leaves a
+/// truncated snapshot behind
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    let tmp_path = path.with_file_name(file_name);
+    let result = File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        let max_intents = std::env::var(ENV_VAR_RATE_LIMIT)
+            .ok()
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(DEFAULT_RATE_LIMIT);
+        let window = std::env::var(ENV_VAR_RATE_LIMIT_WINDOW)
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_RATE_LIMIT_WINDOW);
+        Self::new(max_intents, window)
+    }
+}
+
+impl RateLimiter {
+    fn new(max_intents: usize, window: Duration) -> Self {
+        Self {
+            max_intents,
+            window,
+            recent_intents: HashMap::default(),
+        }
+    }
+
+    /// Check that none of the given source addresses of an intent exceeded
+    /// its rate at the given time. If so, the intent is counted for all of
+    /// them and `true` is returned.
+    fn try_accept<'a>(
+        &mut self,
+        addresses: impl IntoIterator<Item = &'a Address> + Clone,
+        now: Instant,
+    ) -> bool {
+        let window = self.window;
+        // Forget the intents that are out of the window
+        self.recent_intents.retain(|_, times| {
+            while let Some(time) = times.front() {
+                if now.duration_since(*time) < window {
+                    break;
+                }
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+        let exceeded = addresses.clone().into_iter().find(|addr| {
+            self.recent_intents
+                .get(addr)
+                .map(|times| times.len() >= self.max_intents)
+                .unwrap_or_default()
+        });
+        if let Some(addr) = exceeded {
+            tracing::warn!(
+                "Dropping an intent from {}, which exceeded the rate of {} \
+                 intents per {:?}",
+                addr,
+                self.max_intents,
+                self.window
+            );
+            return false;
+        }
+        for addr in addresses {
+            self.recent_intents
+                .entry(addr.clone())
+                .or_default()
+                .push_back(now);
+        }
+        true
+    }
 }
 
 impl AddIntent for TokenExchange {
+    fn filter_intent(
+        &self,
+        _intent_id: &Vec<u8>,
+        intent_data: &Vec<u8>,
+    ) -> bool {
+        intent_data.len() <= MAX_INTENT_DATA_BYTES
+    }
+
     fn add_intent(
         &mut self,
         intent_id: &Vec<u8>,
         intent_data: &Vec<u8>,
-    ) -> AddIntentResult {
-        let intent = decode_intent_data(&intent_data[..]);
-        let exchanges = intent.data.exchange.clone();
+    ) -> Vec<AddIntentResult> {
+        tracing::debug!("trying to match new intent");
+        if !self.add_intent_nodes(intent_id, intent_data) {
+            return vec![];
+        }
+        self.try_match()
+            .map(|(tx, matched_intents)| AddIntentResult {
+                tx: Some(tx),
+                matched_intents: Some(matched_intents),
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn add_intents(
+        &mut self,
+        intents: &Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Vec<AddIntentResult> {
+        tracing::debug!("trying to match a batch of {} intents", intents.len());
+        for (intent_id, intent_data) in intents {
+            self.add_intent_nodes(intent_id, intent_data);
+        }
+        // Match all the intents in the graph, one cycle per tx
+        let mut results = Vec::new();
+        while let Some((tx, matched_intents)) = self.try_match() {
+            results.push(AddIntentResult {
+                tx: Some(tx),
+                matched_intents: Some(matched_intents),
+            });
+        }
+        results
+    }
+
+    fn remove_intent(&mut self, intent_id: &Vec<u8>) {
+        self.graph
+            .retain_nodes(|graph, index| graph[index].id != *intent_id);
+    }
+}
 
-        println!("trying to match new intent");
+impl TokenExchange {
+    /// Add the nodes of the intent's exchanges to the graph, unless the
+    /// intent's source addresses exceeded their rate. Returns `true` if the
+    /// intent has been added.
+    fn add_intent_nodes(
+        &mut self,
+        intent_id: &[u8],
+        intent_data: &[u8],
+    ) -> bool {
+        let intent = decode_intent_data(intent_data);
+        let addresses: HashSet<&Address> = intent
+            .data
+            .exchange
+            .iter()
+            .map(|exchange| &exchange.data.addr)
+            .collect();
+        if let Some(addr) = addresses
+            .iter()
+            .find(|addr| self.matched_nonces.is_replay(addr, intent.data.nonce))
+        {
+            tracing::warn!(
+                "Rejecting a replayed intent from {} with nonce {}, which is \
+                 not greater than the nonce of its last matched intent",
+                addr,
+                intent.data.nonce
+            );
+            return false;
+        }
+        if !self.rate_limiter.try_accept(addresses, Instant::now()) {
+            return false;
+        }
+        let exchanges = intent.data.exchange.clone();
         exchanges.into_iter().for_each(|exchange| {
             add_intent_node(
                 &mut self.graph,
@@ -38,14 +353,23 @@ impl AddIntent for TokenExchange {
                 intent.clone(),
             )
         });
-        let (tx, matched_intents) = match try_match(&mut self.graph) {
-            Some((tx, matched_intents)) => (Some(tx), Some(matched_intents)),
-            None => (None, None),
-        };
-        AddIntentResult {
-            tx,
-            matched_intents,
-        }
+        true
+    }
+
+    /// Try to find matching intents in the graph and record the nonces of the
+    /// matched intents. If found, returns the tx bytes and a hash set of the
+    /// matched intent IDs.
+    fn try_match(&mut self) -> Option<(Vec<u8>, HashSet<Vec<u8>>)> {
+        let (tx_data, matched_nodes) =
+            try_match(&mut self.graph, self.max_cycle_len)?;
+        self.matched_nonces.record(
+            matched_nodes
+                .iter()
+                .map(|node| (&node.exchange.data.addr, node.intent.data.nonce)),
+        );
+        let matched_intents =
+            matched_nodes.into_iter().map(|node| node.id).collect();
+        Some((tx_data, matched_intents))
     }
 }
 
@@ -91,34 +415,39 @@ fn find_nodes_to_update(
     graph: &DiGraph<ExchangeNode, Address>,
     new_node: &ExchangeNode,
 ) -> (Vec<NodeIndex>, Vec<NodeIndex>) {
-    let start = node_index(0);
     let mut connect_sell = Vec::new();
     let mut connect_buy = Vec::new();
-    depth_first_search(graph, Some(start), |event| {
-        if let DfsEvent::Discover(index, _time) = event {
-            let current_node = &graph[index];
-            if new_node.exchange.data.token_sell
-                == current_node.exchange.data.token_buy
-            // && new_node.exchange.data.max_sell
-            //     >= current_node.exchange.data.min_buy
-            {
-                connect_sell.push(index);
-            }
-            if new_node.exchange.data.token_buy
-                == current_node.exchange.data.token_sell
-            // && current_node.exchange.data.max_sell
-            //     >= new_node.exchange.data.min_buy
-            {
-                connect_buy.push(index);
-            }
+    // All the nodes are visited, including the ones that are not connected
+    // to the rest of the graph
+    for index in graph.node_indices() {
+        let current_node = &graph[index];
+        // Skip the intent itself and the intents of the same address, which
+        // would be a self-trade
+        if current_node.id == new_node.id
+            || current_node.exchange.data.addr == new_node.exchange.data.addr
+        {
+            continue;
         }
-        Control::<()>::Continue
-    });
+        if new_node.exchange.data.token_sell
+            == current_node.exchange.data.token_buy
+        // && new_node.exchange.data.max_sell
+        //     >= current_node.exchange.data.min_buy
+        {
+            connect_sell.push(index);
+        }
+        if new_node.exchange.data.token_buy
+            == current_node.exchange.data.token_sell
+        // && current_node.exchange.data.max_sell
+        //     >= new_node.exchange.data.min_buy
+        {
+            connect_buy.push(index);
+        }
+    }
     (connect_sell, connect_buy)
 }
 
-// The cycle returned by tarjan_scc only contains the node_index in an arbitrary
-// order without edges. we must reorder them to craft the transfer
+// The cycle only contains the node_index without edges. We must order them by
+// the edges to craft the transfer
 fn sort_intents(
     graph: &DiGraph<ExchangeNode, Address>,
     matched_intents_indices: &[NodeIndex],
@@ -141,47 +470,139 @@ fn sort_intents(
 }
 
 /// Try to find matching intents in the graph. If found, returns the tx bytes
-/// and a hash set of the matched intent IDs.
+/// and the matched nodes, which are removed from the graph. When there are
+/// several cycles, the cycle with the largest total matched volume is chosen
+/// and ties are broken by the lexicographically smallest sorted intent IDs,
+/// so that the choice doesn't depend on the order in which the intents were
+/// added. Only the cycles of at most `max_cycle_len` intents are considered.
 fn try_match(
     graph: &mut DiGraph<ExchangeNode, Address>,
-) -> Option<(Vec<u8>, HashSet<Vec<u8>>)> {
-    // We only use the first found cycle, because an intent cannot be matched
-    // into more than one tx
-    if let Some(mut matchned_intents_indices) =
-        petgraph::algo::tarjan_scc(&*graph).into_iter().next()
-    {
+    max_cycle_len: MaxCycleLen,
+) -> Option<(Vec<u8>, Vec<ExchangeNode>)> {
+    // We only use a single cycle, because an intent cannot be matched into
+    // more than one tx
+    let best_cycle = petgraph::algo::tarjan_scc(&*graph)
+        .into_iter()
         // a node is a cycle with itself
-        if matchned_intents_indices.len() > 1 {
-            println!("found a match: {:?}", matchned_intents_indices);
+        .filter(|component| component.len() > 1)
+        // A component larger than the maximum cycle length may still contain
+        // shorter cycles
+        .flat_map(|component| simple_cycles(graph, &component, max_cycle_len.0))
+        .filter_map(|cycle| {
+            let volume = cycle_volume(graph, &cycle)?;
+            // The IDs in the order of the cycle starting from the smallest
+            // one, to tell apart the cycles through the same intents
+            let mut cycle_ids: Vec<Vec<u8>> =
+                cycle.iter().map(|index| graph[*index].id.clone()).collect();
+            let smallest = (0..cycle_ids.len())
+                .min_by_key(|i| &cycle_ids[*i])
+                .unwrap_or_default();
+            cycle_ids.rotate_left(smallest);
+            let mut intent_ids = cycle_ids.clone();
+            intent_ids.sort();
+            Some((cycle, volume, intent_ids, cycle_ids))
+        })
+        .min_by(
+            |(_, volume_a, ids_a, cycle_a), (_, volume_b, ids_b, cycle_b)| {
+                volume_b
+                    .cmp(volume_a)
+                    .then_with(|| ids_a.cmp(ids_b))
+                    .then_with(|| cycle_a.cmp(cycle_b))
+            },
+        );
+    if let Some((matched_intents_indices, _, _, _)) = best_cycle {
+        tracing::info!("found a match: {:?}", matched_intents_indices);
+        if let Some(tx_data) = prepare_tx_data(graph, &matched_intents_indices)
+        {
             // Must be sorted in reverse order because it removes the node by
             // index otherwise it would not remove the correct node
-            matchned_intents_indices.sort_by(|a, b| b.cmp(a));
-            if let Some(tx_data) =
-                prepare_tx_data(graph, &matchned_intents_indices)
-            {
-                let removed_intent_ids = matchned_intents_indices
-                    .into_iter()
-                    .filter_map(|i| {
-                        if let Some(removed) = graph.remove_node(i) {
-                            Some(removed.id)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                return Some((tx_data, removed_intent_ids));
-            }
+            let mut removed_indices = matched_intents_indices;
+            removed_indices.sort_by(|a, b| b.cmp(a));
+            let removed_nodes = removed_indices
+                .into_iter()
+                .filter_map(|i| graph.remove_node(i))
+                .collect();
+            return Some((tx_data, removed_nodes));
         }
     }
     None
 }
 
+/// Find the simple cycles of at most `max_len` nodes in a strongly connected
+/// component of the graph. Every cycle is found once, starting from its
+/// smallest node index, with its nodes in the order of its edges.
+fn simple_cycles(
+    graph: &DiGraph<ExchangeNode, Address>,
+    component: &[NodeIndex],
+    max_len: usize,
+) -> Vec<Vec<NodeIndex>> {
+    let component: HashSet<NodeIndex> = component.iter().copied().collect();
+    let mut cycles = Vec::new();
+    for &start in &component {
+        let mut path = vec![start];
+        extend_path(graph, &component, max_len, &mut path, &mut cycles);
+    }
+    cycles
+}
+
+/// Extend the path with the component's nodes whose index is larger than the
+/// path's start and collect the paths that lead back to the start as cycles
+fn extend_path(
+    graph: &DiGraph<ExchangeNode, Address>,
+    component: &HashSet<NodeIndex>,
+    max_len: usize,
+    path: &mut Vec<NodeIndex>,
+    cycles: &mut Vec<Vec<NodeIndex>>,
+) {
+    let start = path[0];
+    let last = path[path.len() - 1];
+    for next in graph.neighbors(last) {
+        if next == start {
+            if path.len() > 1 {
+                cycles.push(path.clone());
+            }
+        } else if next > start
+            && path.len() < max_len
+            && component.contains(&next)
+            && !path.contains(&next)
+        {
+            path.push(next);
+            extend_path(graph, component, max_len, path, cycles);
+            path.pop();
+        }
+    }
+}
+
+/// Compute the total volume that would be matched by the cycle, i.e. the sum
+/// of the exchanged amounts. Returns `None` if the cycle cannot be matched.
+fn cycle_volume(
+    graph: &DiGraph<ExchangeNode, Address>,
+    cycle: &[NodeIndex],
+) -> Option<token::Change> {
+    let matched_intents = sort_intents(graph, cycle);
+    match compute_amounts(graph, &matched_intents) {
+        Ok(amounts) => Some(amounts.values().map(token::Amount::change).sum()),
+        Err(err @ AmountsError::InvalidAmount(_)) => {
+            tracing::error!(
+                "Abandoning the match of cycle {:?}: {}",
+                cycle,
+                err
+            );
+            None
+        }
+        Err(err) => {
+            tracing::debug!("Cycle {:?} cannot be matched: {}", cycle, err);
+            None
+        }
+    }
+}
+
 /// Prepare the transaction's data from the matched intents
 fn prepare_tx_data(
     graph: &DiGraph<ExchangeNode, Address>,
     matched_intent_indices: &[NodeIndex],
 ) -> Option<Vec<u8>> {
-    println!(
+    tracing::info!(
         "found match; creating tx with {:?} nodes",
         matched_intent_indices.len()
     );
@@ -190,7 +611,7 @@ fn prepare_tx_data(
 
     match amounts {
         Ok(res) => {
-            println!(
+            tracing::debug!(
                 "amounts: {}",
                 res.values()
                     .map(|x| x.to_string())
@@ -206,7 +627,7 @@ fn prepare_tx_data(
                     let node = &graph[intent_index];
                     let exchanged_amount =
                         *res.get(&node.exchange.data).unwrap();
-                    println!(
+                    tracing::info!(
                         "crafting transfer: {}, {}, {}",
                         node.exchange.data.addr.clone(),
                         prev_node.exchange.data.addr.clone(),
@@ -229,7 +650,7 @@ fn prepare_tx_data(
                     node
                 });
             let last_amount = *res.get(&first_node.exchange.data).unwrap();
-            println!(
+            tracing::info!(
                 "crafting transfer: {}, {}, {}",
                 first_node.exchange.data.addr.clone(),
                 last_node.exchange.data.addr.clone(),
@@ -248,24 +669,120 @@ fn prepare_tx_data(
                 first_node.exchange.data.addr.clone(),
                 first_node.intent.clone(),
             );
-            println!("tx data: {:?}", tx_data.transfers);
+            tracing::debug!("tx data: {:?}", tx_data.transfers);
             Some(tx_data.try_to_vec().unwrap())
         }
         Err(err) => {
-            println!("Invalid exchange: {}.", err);
+            tracing::warn!("Invalid exchange: {}.", err);
             None
         }
     }
 }
 
+/// An error of the computation of the exchanged amounts of a cycle
+#[derive(Debug)]
+enum AmountsError {
+    /// The solver couldn't find a solution
+    Solver(ResolutionError),
+    /// The solution found by the solver doesn't satisfy an exchange's minimum
+    /// rate, once converted back to exact amounts
+    RateViolation {
+        bought: token::Amount,
+        sold: token::Amount,
+        rate_min: Decimal,
+    },
+    /// The solver's amount cannot be represented as a token amount, because
+    /// it's out of range or more precise than the token
+    InvalidAmount(f64),
+}
+
+impl std::fmt::Display for AmountsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AmountsError::Solver(err) => write!(f, "{}", err),
+            AmountsError::RateViolation {
+                bought,
+                sold,
+                rate_min,
+            } => write!(
+                f,
+                "The amount bought {} is less than the amount sold {} at the \
+                 minimum rate {}",
+                bought, sold, rate_min
+            ),
+            AmountsError::InvalidAmount(amount) => write!(
+                f,
+                "The solver's amount {} is not a valid token amount",
+                amount
+            ),
+        }
+    }
+}
+
+/// Convert an exchange rate to the solver's floating point representation.
+/// The conversion rounds up, so that the solver's constraint is never looser
+/// than the exact decimal rate.
+fn rate_to_solver(rate: Decimal) -> f64 {
+    let approx = rate.to_f64().unwrap();
+    match Decimal::from_f64_retain(approx) {
+        Some(exact) if exact < rate => f64::from_bits(approx.to_bits() + 1),
+        _ => approx,
+    }
+}
+
+/// Convert an amount found by the solver to a token amount. Fails instead of
+/// truncating or saturating when the amount is negative, out of range or
+/// has a fraction smaller than the token's precision.
+fn amount_from_solver(amount: f64) -> Result<token::Amount, AmountsError> {
+    let micro = amount * token::SCALE as f64;
+    let whole = micro.round();
+    // `u64::MAX as f64` is rounded up to 2^64, which is itself out of range
+    if !micro.is_finite()
+        || whole < 0.0
+        || whole >= u64::MAX as f64
+        || (micro - whole).abs() > SOLVER_AMOUNT_TOLERANCE
+    {
+        return Err(AmountsError::InvalidAmount(amount));
+    }
+    Ok(token::Amount::from(whole as u64))
+}
+
+/// Convert a token amount to an exact decimal value
+fn amount_to_decimal(amount: token::Amount) -> Decimal {
+    Decimal::from_i128_with_scale(
+        u64::from(amount).into(),
+        token::MAX_DECIMAL_PLACES,
+    )
+}
+
+/// Check that the amount bought satisfies the minimum rate of the amount sold
+/// exactly
+fn check_rate(
+    bought: token::Amount,
+    sold: token::Amount,
+    rate_min: Decimal,
+) -> Result<(), AmountsError> {
+    if amount_to_decimal(bought) >= amount_to_decimal(sold) * rate_min {
+        Ok(())
+    } else {
+        Err(AmountsError::RateViolation {
+            bought,
+            sold,
+            rate_min,
+        })
+    }
+}
+
 fn compute_amounts(
     graph: &DiGraph<ExchangeNode, Address>,
     cycle_intents: &[NodeIndex],
-) -> Result<HashMap<Exchange, token::Amount>, ResolutionError> {
-    let nodes = graph
-        .raw_nodes()
-        .iter()
-        .map(|x| x.weight.exchange.data.clone())
+) -> Result<HashMap<Exchange, token::Amount>, AmountsError> {
+    // The variables are added in the order of the cycle's node indices
+    let mut cycle_indices = cycle_intents.to_vec();
+    cycle_indices.sort();
+    let nodes = cycle_indices
+        .into_iter()
+        .map(|index| graph[index].exchange.data.clone())
         .collect::<Vec<Exchange>>();
     let mut vars = variables!();
 
@@ -314,41 +831,54 @@ fn compute_amounts(
     let start = node_index(0);
     depth_first_search(&intent_graph, Some(start), |event| {
         if let DfsEvent::Discover(index, _time) = event {
-            let edges = graph.edges(index);
+            // The filtered graph's indices don't match the original graph's
+            // indices when there are nodes outside of the cycle, so its own
+            // (inverted) edges are used in their original direction
+            let edges = intent_graph.edges(index);
 
             edges.for_each(|edge| {
-                let source = intent_graph[edge.source()];
-                let target = intent_graph[edge.target()];
+                let source = intent_graph[edge.target()];
+                let target = intent_graph[edge.source()];
 
                 constrains.push((
                     source.0,
                     target.0,
-                    target.1.exchange.data.rate_min.0.to_f64().unwrap(),
+                    target.1.exchange.data.rate_min.0,
                 ));
             });
         }
         Control::<()>::Continue
     });
 
-    for constrain in constrains.iter() {
-        let constrain = constraint!(constrain.0 >= constrain.1 * constrain.2);
+    // The rates are only converted to floats for the solver, the solution is
+    // validated against the exact decimal rates below
+    for (bought, sold, rate_min) in constrains.iter() {
+        let constrain =
+            constraint!(*bought >= *sold * rate_to_solver(*rate_min));
         model = model.with(constrain);
     }
 
     match model.solve() {
         Ok(solution) => {
+            for (bought, sold, rate_min) in constrains.iter() {
+                check_rate(
+                    amount_from_solver(solution.value(*bought))?,
+                    amount_from_solver(solution.value(*sold))?,
+                    *rate_min,
+                )?;
+            }
             let mut amount_map = HashMap::new();
             let amounts = solution
                 .into_inner()
                 .iter()
-                .map(|(_, amount)| token::Amount::from(*amount))
-                .collect::<Vec<_>>();
+                .map(|(_, amount)| amount_from_solver(*amount))
+                .collect::<Result<Vec<_>, _>>()?;
             nodes.iter().enumerate().for_each(|(index, exchange)| {
                 amount_map.insert(exchange.clone(), amounts[index]);
             });
             Ok(amount_map)
         }
-        Err(error) => Err(error),
+        Err(error) => Err(AmountsError::Solver(error)),
     }
 }
 
@@ -370,3 +900,612 @@ fn decode_intent_data(
 ) -> anoma::proto::Signed<FungibleTokenIntent> {
     anoma::proto::Signed::<FungibleTokenIntent>::try_from_slice(bytes).unwrap()
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use anoma::proto::Signed;
+    use anoma::testing::LogCapture;
+    use anoma::types::address::{apfel, btc, dot, eth, testing, xan};
+    use anoma::types::intent::DecimalWrapper;
+    use anoma::types::key;
+
+    use super::*;
+
+    /// Encode an intent with a single exchange signed by the given key
+    fn intent_data(
+        keypair: &key::common::SecretKey,
+        exchange: Exchange,
+    ) -> Vec<u8> {
+        intent_data_with_nonce(keypair, exchange, 0)
+    }
+
+    /// Encode an intent with a single exchange and the given nonce signed by
+    /// the given key
+    fn intent_data_with_nonce(
+        keypair: &key::common::SecretKey,
+        exchange: Exchange,
+        nonce: u64,
+    ) -> Vec<u8> {
+        let exchange = Signed::new(keypair, exchange);
+        let intent = Signed::new(
+            keypair,
+            FungibleTokenIntent {
+                exchange: HashSet::from_iter([exchange]),
+                nonce,
+            },
+        );
+        intent.try_to_vec().unwrap()
+    }
+
+    /// An exchange of the given tokens with the default rate and amounts
+    fn exchange(
+        addr: Address,
+        token_sell: Address,
+        token_buy: Address,
+    ) -> Exchange {
+        Exchange {
+            addr,
+            token_sell,
+            rate_min: DecimalWrapper::from_str("0.5").unwrap(),
+            max_sell: token::Amount::from(100),
+            token_buy,
+            min_buy: token::Amount::from(10),
+            vp: None,
+        }
+    }
+
+    /// An intent with the given ID and a single exchange of the given tokens,
+    /// signed by one of the two testing keys depending on the ID's parity
+    fn intent(
+        id: u8,
+        addr: Address,
+        token_sell: Address,
+        token_buy: Address,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let keypair = if id % 2 == 1 {
+            key::testing::keypair_1()
+        } else {
+            key::testing::keypair_2()
+        };
+        (
+            vec![id; 32],
+            intent_data(&keypair, exchange(addr, token_sell, token_buy)),
+        )
+    }
+
+    #[test]
+    fn test_filter_intent_by_size() {
+        let matchmaker = TokenExchange::default();
+        let intent_id = vec![1_u8; 32];
+
+        let small_intent = vec![0_u8; 1024];
+        assert!(matchmaker.filter_intent(&intent_id, &small_intent));
+
+        let max_intent = vec![0_u8; MAX_INTENT_DATA_BYTES];
+        assert!(matchmaker.filter_intent(&intent_id, &max_intent));
+
+        let large_intent = vec![0_u8; MAX_INTENT_DATA_BYTES + 1];
+        assert!(!matchmaker.filter_intent(&intent_id, &large_intent));
+    }
+
+    /// Test that a match is logged at the info level, while the per-intent
+    /// traces are only logged at the debug level.
+    #[test]
+    fn test_match_is_logged_at_info() {
+        let bertha_keypair = key::testing::keypair_1();
+        let albert_keypair = key::testing::keypair_2();
+        let bertha_intent = intent_data(
+            &bertha_keypair,
+            Exchange {
+                addr: testing::established_address_1(),
+                token_sell: btc(),
+                rate_min: DecimalWrapper::from_str("0.5").unwrap(),
+                max_sell: token::Amount::from(100),
+                token_buy: xan(),
+                min_buy: token::Amount::from(10),
+                vp: None,
+            },
+        );
+        let albert_intent = intent_data(
+            &albert_keypair,
+            Exchange {
+                addr: testing::established_address_2(),
+                token_sell: xan(),
+                rate_min: DecimalWrapper::from_str("0.5").unwrap(),
+                max_sell: token::Amount::from(100),
+                token_buy: btc(),
+                min_buy: token::Amount::from(10),
+                vp: None,
+            },
+        );
+
+        let capture = LogCapture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let mut matchmaker = TokenExchange::default();
+            matchmaker.add_intent(&vec![1_u8; 32], &bertha_intent);
+            matchmaker.add_intent(&vec![2_u8; 32], &albert_intent);
+        });
+
+        let logs = capture.logs();
+        assert!(
+            logs.lines()
+                .any(|line| line.contains("INFO")
+                    && line.contains("found a match")),
+            "Expected an info record of the match, got logs:\n{}",
+            logs
+        );
+        assert!(!logs.contains("trying to match new intent"));
+    }
+
+    /// Test that the intents added within one tick of the batched mode are
+    /// matched together into a single tx
+    #[test]
+    fn test_add_intents_batch() {
+        // A cycle of three exchanges btc -> xan -> eth -> btc
+        let intents = vec![
+            intent(1, testing::established_address_1(), btc(), xan()),
+            intent(2, testing::established_address_2(), xan(), eth()),
+            intent(3, testing::established_address_3(), eth(), btc()),
+        ];
+
+        let mut matchmaker = TokenExchange::default();
+        let results = matchmaker.add_intents(&intents);
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        let tx_data =
+            MatchedExchanges::try_from_slice(result.tx.as_ref().unwrap())
+                .unwrap();
+        assert_eq!(tx_data.transfers.len(), 3);
+        let expected: HashSet<Vec<u8>> =
+            intents.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(result.matched_intents, Some(expected));
+        assert_eq!(matchmaker.graph.node_count(), 0);
+    }
+
+    /// Test that when there are several cycles, the same cycle is chosen
+    /// regardless of the order in which the intents are added
+    #[test]
+    fn test_deterministic_cycle_selection() {
+        let intent = |id: u8, token_sell, token_buy, max_sell| {
+            // The two sides of each cycle are of different addresses
+            let addr = if id % 2 == 1 {
+                testing::established_address_1()
+            } else {
+                testing::established_address_2()
+            };
+            let exchange = Exchange {
+                addr,
+                token_sell,
+                rate_min: DecimalWrapper::from_str("0.5").unwrap(),
+                max_sell: token::Amount::from(max_sell),
+                token_buy,
+                min_buy: token::Amount::from(10),
+                vp: None,
+            };
+            (
+                vec![id; 32],
+                intent_data(&key::testing::keypair_1(), exchange),
+            )
+        };
+        let match_intents = |intents: &[(Vec<u8>, Vec<u8>)]| {
+            let mut matchmaker = TokenExchange::default();
+            for (id, data) in intents {
+                matchmaker.add_intent_nodes(id, data);
+            }
+            matchmaker.try_match().unwrap().1
+        };
+
+        // Two cycles btc <-> xan and eth <-> dot, the latter with a larger
+        // volume
+        let small_cycle =
+            [intent(1, btc(), xan(), 100), intent(2, xan(), btc(), 100)];
+        let large_cycle =
+            [intent(3, eth(), dot(), 200), intent(4, dot(), eth(), 200)];
+        let expected: HashSet<Vec<u8>> =
+            [vec![3; 32], vec![4; 32]].into_iter().collect();
+        let mut intents: Vec<_> =
+            small_cycle.iter().chain(&large_cycle).cloned().collect();
+        assert_eq!(match_intents(&intents), expected);
+        intents.reverse();
+        assert_eq!(match_intents(&intents), expected);
+
+        // Two cycles with the same volume, the one with the smallest intent
+        // IDs is chosen
+        let other_cycle =
+            [intent(5, eth(), dot(), 100), intent(6, dot(), eth(), 100)];
+        let expected: HashSet<Vec<u8>> =
+            [vec![1; 32], vec![2; 32]].into_iter().collect();
+        let mut intents: Vec<_> =
+            other_cycle.iter().chain(&small_cycle).cloned().collect();
+        assert_eq!(match_intents(&intents), expected);
+        intents.reverse();
+        assert_eq!(match_intents(&intents), expected);
+    }
+
+    /// Test that the exchanges of a single intent and of the same address are
+    /// not matched against each other
+    #[test]
+    fn test_no_self_match() {
+        let keypair = key::testing::keypair_1();
+        let signed_exchange = |addr, token_sell, token_buy| {
+            Signed::new(&keypair, exchange(addr, token_sell, token_buy))
+        };
+        let addr = testing::established_address_1();
+        // A single intent with exchanges that would form a cycle
+        let intent = Signed::new(
+            &keypair,
+            FungibleTokenIntent {
+                exchange: HashSet::from_iter([
+                    signed_exchange(addr.clone(), btc(), xan()),
+                    signed_exchange(addr.clone(), xan(), btc()),
+                ]),
+                nonce: 0,
+            },
+        );
+
+        let mut matchmaker = TokenExchange::default();
+        let results = matchmaker
+            .add_intent(&vec![1_u8; 32], &intent.try_to_vec().unwrap());
+        assert!(results.is_empty());
+
+        // Another intent of the same address
+        let other_intent = intent_data(&keypair, exchange(addr, xan(), btc()));
+        let results = matchmaker.add_intent(&vec![2_u8; 32], &other_intent);
+        assert!(results.is_empty());
+        assert_eq!(matchmaker.graph.edge_count(), 0);
+    }
+
+    /// Test that the intents from an address that exceeds its rate are
+    /// dropped, while another address is unaffected
+    #[test]
+    fn test_rate_limit() {
+        let mut matchmaker = TokenExchange {
+            rate_limiter: RateLimiter::new(3, Duration::from_secs(60)),
+            ..TokenExchange::default()
+        };
+
+        for id in 0..10_u8 {
+            let intent = intent_data(
+                &key::testing::keypair_1(),
+                exchange(testing::established_address_1(), btc(), xan()),
+            );
+            matchmaker.add_intent(&vec![id; 32], &intent);
+        }
+        let intent = intent_data(
+            &key::testing::keypair_2(),
+            exchange(testing::established_address_2(), eth(), dot()),
+        );
+        matchmaker.add_intent(&vec![10; 32], &intent);
+
+        let count = |addr: &Address| {
+            matchmaker
+                .graph
+                .raw_nodes()
+                .iter()
+                .filter(|node| node.weight.exchange.data.addr == *addr)
+                .count()
+        };
+        assert_eq!(count(&testing::established_address_1()), 3);
+        assert_eq!(count(&testing::established_address_2()), 1);
+    }
+
+    /// Test that the intents out of the rate limit window are not counted
+    #[test]
+    fn test_rate_limit_window() {
+        let addr = testing::established_address_1();
+        let window = Duration::from_secs(60);
+        let mut limiter = RateLimiter::new(1, window);
+        let start = Instant::now();
+        assert!(limiter.try_accept([&addr], start));
+        assert!(!limiter.try_accept([&addr], start + window / 2));
+        assert!(limiter.try_accept([&addr], start + window));
+    }
+
+    /// Test that a matched intent that is submitted again is rejected as a
+    /// replay, while a new intent with a greater nonce is accepted
+    #[test]
+    fn test_replayed_intent_rejected() {
+        let bertha_intent = intent_data_with_nonce(
+            &key::testing::keypair_1(),
+            exchange(testing::established_address_1(), btc(), xan()),
+            1,
+        );
+        let albert_intent = intent_data_with_nonce(
+            &key::testing::keypair_2(),
+            exchange(testing::established_address_2(), xan(), btc()),
+            1,
+        );
+
+        let mut matchmaker = TokenExchange::default();
+        matchmaker.add_intent(&vec![1_u8; 32], &bertha_intent);
+        let results = matchmaker.add_intent(&vec![2_u8; 32], &albert_intent);
+        assert_eq!(results.len(), 1);
+        assert_eq!(matchmaker.graph.node_count(), 0);
+
+        // The stale intent is rejected
+        let results = matchmaker.add_intent(&vec![1_u8; 32], &bertha_intent);
+        assert!(results.is_empty());
+        assert_eq!(matchmaker.graph.node_count(), 0);
+
+        // A new intent from the same address is accepted
+        let new_intent = intent_data_with_nonce(
+            &key::testing::keypair_1(),
+            exchange(testing::established_address_1(), btc(), xan()),
+            2,
+        );
+        matchmaker.add_intent(&vec![3_u8; 32], &new_intent);
+        assert_eq!(matchmaker.graph.node_count(), 1);
+    }
+
+    /// Test that the nonces of the matched intents are persisted, so that a
+    /// matched intent is still rejected as a replay after a restart
+    #[test]
+    fn test_matched_nonces_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot_path = dir.path().join("nonces.json");
+        let bertha_intent = intent_data_with_nonce(
+            &key::testing::keypair_1(),
+            exchange(testing::established_address_1(), btc(), xan()),
+            1,
+        );
+        let albert_intent = intent_data_with_nonce(
+            &key::testing::keypair_2(),
+            exchange(testing::established_address_2(), xan(), btc()),
+            1,
+        );
+
+        let mut matchmaker = TokenExchange {
+            matched_nonces: MatchedNonces::new(Some(snapshot_path.clone())),
+            ..TokenExchange::default()
+        };
+        matchmaker.add_intent(&vec![1_u8; 32], &bertha_intent);
+        let results = matchmaker.add_intent(&vec![2_u8; 32], &albert_intent);
+        assert_eq!(results.len(), 1);
+        // The temporary file of the atomic write is renamed to the snapshot
+        assert!(snapshot_path.exists());
+        assert!(!dir.path().join("nonces.json.tmp").exists());
+
+        // The restarted matchmaker still rejects the matched intent
+        let mut restarted = TokenExchange {
+            matched_nonces: MatchedNonces::new(Some(snapshot_path)),
+            ..TokenExchange::default()
+        };
+        assert!(restarted
+            .matched_nonces
+            .is_replay(&testing::established_address_1(), 1));
+        restarted.add_intent(&vec![1_u8; 32], &bertha_intent);
+        assert_eq!(restarted.graph.node_count(), 0);
+    }
+
+    /// Test that a rate which is not exactly representable as a float is
+    /// never loosened by the conversion for the solver
+    #[test]
+    fn test_rate_to_solver_is_conservative() {
+        for rate in ["0.1", "0.3", "0.7", "1.1", "2.675", "10"] {
+            let rate = Decimal::from_str(rate).unwrap();
+            let converted =
+                Decimal::from_f64_retain(rate_to_solver(rate)).unwrap();
+            assert!(converted >= rate, "{} < {}", converted, rate);
+        }
+    }
+
+    /// Test that the amounts are checked against the exact decimal rate
+    #[test]
+    fn test_check_rate() {
+        let rate = Decimal::from_str("0.3").unwrap();
+        // 0.3 * 10 = 3 micro units exactly
+        assert!(check_rate(
+            token::Amount::from(3),
+            token::Amount::from(10),
+            rate
+        )
+        .is_ok());
+        assert!(matches!(
+            check_rate(token::Amount::from(2), token::Amount::from(10), rate),
+            Err(AmountsError::RateViolation { .. })
+        ));
+        // amounts above `i64::MAX` micro units must not wrap around
+        let rate = Decimal::from_str("0.5").unwrap();
+        assert!(matches!(
+            check_rate(
+                token::Amount::from(1),
+                token::Amount::from(u64::MAX),
+                rate
+            ),
+            Err(AmountsError::RateViolation { .. })
+        ));
+    }
+
+    /// Test that the transfers of a match with rates that are lossy as floats
+    /// satisfy the exact decimal rates
+    #[test]
+    fn test_match_with_lossy_rate() {
+        let rate_min = DecimalWrapper::from_str("0.3").unwrap();
+        let lossy_exchange = |addr, token_sell, token_buy| Exchange {
+            rate_min: rate_min.clone(),
+            ..exchange(addr, token_sell, token_buy)
+        };
+        let bertha_exchange =
+            lossy_exchange(testing::established_address_1(), btc(), xan());
+        let albert_exchange =
+            lossy_exchange(testing::established_address_2(), xan(), btc());
+        let intents = vec![
+            (
+                vec![1_u8; 32],
+                intent_data(&key::testing::keypair_1(), bertha_exchange),
+            ),
+            (
+                vec![2_u8; 32],
+                intent_data(&key::testing::keypair_2(), albert_exchange),
+            ),
+        ];
+
+        let mut matchmaker = TokenExchange::default();
+        let results = matchmaker.add_intents(&intents);
+
+        assert_eq!(results.len(), 1);
+        let tx_data =
+            MatchedExchanges::try_from_slice(results[0].tx.as_ref().unwrap())
+                .unwrap();
+        assert_eq!(tx_data.transfers.len(), 2);
+        for addr in tx_data.exchanges.keys() {
+            let bought = tx_data
+                .transfers
+                .iter()
+                .find(|transfer| &transfer.target == addr)
+                .unwrap()
+                .amount;
+            let sold = tx_data
+                .transfers
+                .iter()
+                .find(|transfer| &transfer.source == addr)
+                .unwrap()
+                .amount;
+            assert!(check_rate(bought, sold, rate_min.0).is_ok());
+        }
+    }
+
+    /// Test that the solver's amounts are converted to token amounts only
+    /// when they are in range and within the token's precision
+    #[test]
+    fn test_amount_from_solver() {
+        assert_eq!(
+            amount_from_solver(1.5).unwrap(),
+            token::Amount::from(1_500_000)
+        );
+        // A floating point error is not a fraction of a micro unit
+        assert_eq!(
+            amount_from_solver(0.1 + 0.2).unwrap(),
+            token::Amount::from(300_000)
+        );
+        for amount in
+            [-1.0, f64::NAN, f64::INFINITY, u64::MAX as f64, 0.000_000_5]
+        {
+            assert!(matches!(
+                amount_from_solver(amount),
+                Err(AmountsError::InvalidAmount(_))
+            ));
+        }
+    }
+
+    /// Test that a match whose amounts would overflow `token::Amount` is
+    /// abandoned with a logged error, instead of crafting a tx with
+    /// saturated amounts
+    #[test]
+    fn test_overflowing_match_abandoned() {
+        let large_exchange = |addr, token_sell, token_buy| Exchange {
+            max_sell: token::Amount::from(u64::MAX),
+            ..exchange(addr, token_sell, token_buy)
+        };
+        let intents = vec![
+            (
+                vec![1_u8; 32],
+                intent_data(
+                    &key::testing::keypair_1(),
+                    large_exchange(
+                        testing::established_address_1(),
+                        btc(),
+                        xan(),
+                    ),
+                ),
+            ),
+            (
+                vec![2_u8; 32],
+                intent_data(
+                    &key::testing::keypair_2(),
+                    large_exchange(
+                        testing::established_address_2(),
+                        xan(),
+                        btc(),
+                    ),
+                ),
+            ),
+        ];
+
+        let capture = LogCapture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::ERROR)
+            .with_writer(move || writer.clone())
+            .finish();
+        let mut matchmaker = TokenExchange::default();
+        let results = tracing::subscriber::with_default(subscriber, || {
+            matchmaker.add_intents(&intents)
+        });
+
+        assert!(results.is_empty());
+        // The intents are kept in the graph
+        assert_eq!(matchmaker.graph.node_count(), 2);
+        let logs = capture.logs();
+        assert!(
+            logs.lines().any(|line| line.contains("ERROR")
+                && line.contains("Abandoning the match")),
+            "Expected an error record of the abandoned match, got logs:\n{}",
+            logs
+        );
+    }
+
+    /// Test that a cycle longer than the maximum cycle length is not matched,
+    /// while a shorter cycle is
+    #[test]
+    fn test_max_cycle_len() {
+        let intents = vec![
+            // A long cycle btc -> xan -> eth -> btc
+            intent(1, testing::established_address_1(), btc(), xan()),
+            intent(2, testing::established_address_2(), xan(), eth()),
+            intent(3, testing::established_address_3(), eth(), btc()),
+            // A short cycle dot <-> apfel
+            intent(4, testing::established_address_1(), dot(), apfel()),
+            intent(5, testing::established_address_2(), apfel(), dot()),
+        ];
+
+        let mut matchmaker = TokenExchange {
+            max_cycle_len: MaxCycleLen(2),
+            ..TokenExchange::default()
+        };
+        let results = matchmaker.add_intents(&intents);
+
+        assert_eq!(results.len(), 1);
+        let expected: HashSet<Vec<u8>> =
+            [vec![4; 32], vec![5; 32]].into_iter().collect();
+        assert_eq!(results[0].matched_intents, Some(expected));
+        // The long cycle is left in the graph
+        assert_eq!(matchmaker.graph.node_count(), 3);
+    }
+
+    /// Test that a short cycle is matched even when it's a part of a strongly
+    /// connected component larger than the maximum cycle length
+    #[test]
+    fn test_max_cycle_len_in_larger_component() {
+        let intents = vec![
+            // A short cycle btc <-> xan
+            intent(1, testing::established_address_1(), btc(), xan()),
+            intent(2, testing::established_address_2(), xan(), btc()),
+            // A long cycle btc -> eth -> xan -> btc through the first intent,
+            // which joins both cycles into a single component
+            intent(3, testing::established_address_3(), xan(), eth()),
+            intent(4, testing::established_address_2(), eth(), btc()),
+        ];
+
+        let mut matchmaker = TokenExchange {
+            max_cycle_len: MaxCycleLen(2),
+            ..TokenExchange::default()
+        };
+        let results = matchmaker.add_intents(&intents);
+
+        assert_eq!(results.len(), 1);
+        let expected: HashSet<Vec<u8>> =
+            [vec![1; 32], vec![2; 32]].into_iter().collect();
+        assert_eq!(results[0].matched_intents, Some(expected));
+        // The rest of the long cycle is left in the graph
+        assert_eq!(matchmaker.graph.node_count(), 2);
+    }
+}
@@ -826,7 +826,10 @@ pub trait PosBase {
         active_validators.chain(inactive_validators).for_each(f)
     }
 
-    /// Apply a slash to a byzantine validator for the given evidence.
+    /// Apply a slash to a byzantine validator for the given evidence. The
+    /// slash is only applied once for the same evidence, i.e. if the
+    /// validator has already been slashed for the same type of slashable
+    /// event at the same block height, this is a no-op.
     fn slash(
         &mut self,
         params: &PosParams,
@@ -846,6 +849,16 @@ pub trait PosBase {
             block_height: evidence_block_height.into(),
         };
 
+        let already_slashed =
+            self.read_validator_slashes(validator).iter().any(|slash| {
+                slash.epoch == validator_slash.epoch
+                    && slash.block_height == validator_slash.block_height
+                    && slash.r#type == validator_slash.r#type
+            });
+        if already_slashed {
+            return Ok(());
+        }
+
         let mut total_deltas =
             self.read_validator_total_deltas(validator).ok_or_else(|| {
                 SlashError::ValidatorHasNoTotalDeltas(validator.clone())
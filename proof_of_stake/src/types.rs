@@ -329,7 +329,15 @@ pub struct Slash {
 pub type Slashes = Vec<Slash>;
 
 /// A type of slashsable event.
-#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, BorshSchema)]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    BorshDeserialize,
+    BorshSerialize,
+    BorshSchema,
+)]
 pub enum SlashType {
     /// Duplicate block vote.
     DuplicateVote,
@@ -4,6 +4,7 @@
 use std::convert::TryFrom;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[allow(missing_docs)]
@@ -29,6 +30,11 @@ const TRANSACTION_GAS_LIMIT: u64 = 10_000_000_000;
 /// The minimum gas cost for accessing the storage
 pub const MIN_STORAGE_GAS: u64 = 1;
 
+/// The default gas cost of a transaction signature verification
+const DEFAULT_VERIFY_TX_SIG_GAS: u64 = 1000;
+/// The default gas cost per byte of validating a WASM code
+const DEFAULT_WASM_VALIDATION_GAS_PER_BYTE: u64 = 1;
+
 /// Gas module result for functions that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -58,6 +64,75 @@ pub struct VpsGas {
     rest: Vec<u64>,
 }
 
+/// The gas costs of the operations exposed by the host environment. The
+/// schedule is a part of the protocol parameters, so that each chain can tune
+/// it in its genesis.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct GasSchedule {
+    /// The base cost of a storage read
+    pub storage_read_base: u64,
+    /// The base cost of a storage write or delete
+    pub storage_write_base: u64,
+    /// The cost per byte of the accessed storage keys and values
+    pub storage_per_byte: u64,
+    /// The cost of a transaction signature verification
+    pub verify_tx_sig: u64,
+    /// The cost of an account initialization, on top of its storage writes
+    pub init_account: u64,
+    /// The cost per byte of validating a WASM code
+    pub wasm_validation_per_byte: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self {
+            storage_read_base: 0,
+            storage_write_base: 0,
+            storage_per_byte: 1,
+            verify_tx_sig: DEFAULT_VERIFY_TX_SIG_GAS,
+            init_account: 0,
+            wasm_validation_per_byte: DEFAULT_WASM_VALIDATION_GAS_PER_BYTE,
+        }
+    }
+}
+
+impl GasSchedule {
+    /// The gas cost of a storage read, given the number of accessed bytes, as
+    /// returned by the storage or the write log.
+    pub fn storage_read(&self, bytes_len: u64) -> u64 {
+        self.storage_per_byte
+            .saturating_mul(bytes_len)
+            .saturating_add(self.storage_read_base)
+    }
+
+    /// The gas cost of a storage write or delete, given the number of accessed
+    /// bytes, as returned by the storage or the write log.
+    pub fn storage_write(&self, bytes_len: u64) -> u64 {
+        self.storage_per_byte
+            .saturating_mul(bytes_len)
+            .saturating_add(self.storage_write_base)
+    }
+
+    /// The gas cost of validating a WASM code of the given length
+    pub fn wasm_validation(&self, code_len: usize) -> u64 {
+        self.wasm_validation_per_byte
+            .saturating_mul(code_len as u64)
+    }
+}
+
 impl BlockGasMeter {
     /// Add gas cost for the current transaction. It will return error when the
     /// consumed gas exceeds the transaction gas limit, but the state will still
@@ -304,6 +379,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_gas_schedule_costs() {
+        let default = GasSchedule::default();
+        assert_eq!(default.storage_read(10), 10);
+        assert_eq!(default.storage_write(10), 10);
+        assert_eq!(default.wasm_validation(10), 10);
+
+        let schedule = GasSchedule {
+            storage_read_base: 5,
+            storage_write_base: 7,
+            storage_per_byte: 3,
+            ..GasSchedule::default()
+        };
+        assert_eq!(schedule.storage_read(10), 35);
+        assert_eq!(schedule.storage_write(10), 37);
+        assert_eq!(schedule.storage_read(u64::MAX), u64::MAX);
+    }
+
     /// Test that the function [`as_i64`] cannot fail for transaction and block
     /// gas limit + some "tolerance" for gas exhaustion.
     #[test]
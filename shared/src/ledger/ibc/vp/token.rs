@@ -19,7 +19,7 @@ use crate::types::ibc::data::{
 };
 use crate::types::storage::Key;
 use crate::types::token::{
-    self, is_non_owner_balance_key, Amount, AmountParseError,
+    self, is_non_owner_balance_key, Amount, AmountParseError, Change,
 };
 use crate::vm::WasmCacheAccess;
 
@@ -38,6 +38,8 @@ pub enum Error {
     NoToken,
     #[error("Parsing amount error")]
     Amount(AmountParseError),
+    #[error("Amount overflow error")]
+    AmountOverflow,
     #[error("Decoding error")]
     Decoding(std::io::Error),
     #[error("Decoding PacketData error")]
@@ -154,7 +156,17 @@ where
             let post =
                 try_decode_token_amount(self.ctx.read_post(&target_key)?)?
                     .unwrap_or_default();
-            post.change() - pre.change()
+            let escrowed = checked_change_sub(post.change(), pre.change())?;
+            // the escrowed amount should be taken from the sender
+            let sender_change = self.balance_change(&token, &data.sender)?;
+            if checked_change_sub(0, sender_change)? != escrowed {
+                return Err(Error::TokenTransfer(format!(
+                    "The escrowed amount doesn't match the sender's balance \
+                     change: {}",
+                    data
+                )));
+            }
+            escrowed
         };
 
         if change == amount.change() {
@@ -194,7 +206,18 @@ where
             let post =
                 try_decode_token_amount(self.ctx.read_post(&source_key)?)?
                     .unwrap_or_default();
-            pre.change() - post.change()
+            let unescrowed = checked_change_sub(pre.change(), post.change())?;
+            // the unescrowed amount should be given to the receiver
+            let receiver_change =
+                self.balance_change(&token, &data.receiver)?;
+            if receiver_change != unescrowed {
+                return Err(Error::TokenTransfer(format!(
+                    "The unescrowed amount doesn't match the receiver's \
+                     balance change: {}",
+                    data
+                )));
+            }
+            unescrowed
         } else {
             // the sender is the source
             let source = Address::Internal(InternalAddress::IbcMint);
@@ -203,7 +226,7 @@ where
                 try_decode_token_amount(self.ctx.read_temp(&source_key)?)?
                     .unwrap_or_default();
             // the previous balance of the mint address should be the maximum
-            Amount::max().change() - post.change()
+            checked_change_sub(Amount::max().change(), post.change())?
         };
 
         if change == amount.change() {
@@ -239,7 +262,7 @@ where
                 try_decode_token_amount(self.ctx.read_temp(&source_key)?)?
                     .unwrap_or_default();
             // the previous balance of the mint address should be the maximum
-            Amount::max().change() - post.change()
+            checked_change_sub(Amount::max().change(), post.change())?
         } else {
             // source zone: unescrow the token for the refund
             let source =
@@ -253,7 +276,17 @@ where
             let post =
                 try_decode_token_amount(self.ctx.read_post(&source_key)?)?
                     .unwrap_or_default();
-            pre.change() - post.change()
+            let unescrowed = checked_change_sub(pre.change(), post.change())?;
+            // the unescrowed amount should be given back to the sender
+            let sender_change = self.balance_change(&token, &data.sender)?;
+            if sender_change != unescrowed {
+                return Err(Error::TokenTransfer(format!(
+                    "The unescrowed amount doesn't match the sender's balance \
+                     change: {}",
+                    data
+                )));
+            }
+            unescrowed
         };
 
         if change == amount.change() {
@@ -265,6 +298,17 @@ where
             )))
         }
     }
+    /// Get the balance change (the posterior minus the prior balance) of the
+    /// given owner
+    fn balance_change(&self, token: &Address, owner: &str) -> Result<Change> {
+        let owner = Address::decode(owner).map_err(Error::Address)?;
+        let key = token::balance_key(token, &owner);
+        let pre = try_decode_token_amount(self.ctx.read_pre(&key)?)?
+            .unwrap_or_default();
+        let post = try_decode_token_amount(self.ctx.read_post(&key)?)?
+            .unwrap_or_default();
+        checked_change_sub(post.change(), pre.change())
+    }
 }
 
 impl From<native_vp::Error> for Error {
@@ -273,6 +317,11 @@ impl From<native_vp::Error> for Error {
     }
 }
 
+/// Subtract the token balance changes, failing on overflow instead of wrapping
+fn checked_change_sub(lhs: Change, rhs: Change) -> Result<Change> {
+    lhs.checked_sub(rhs).ok_or(Error::AmountOverflow)
+}
+
 fn try_decode_token_amount(
     bytes: Option<Vec<u8>>,
 ) -> Result<Option<token::Amount>> {
@@ -282,3 +331,30 @@ fn try_decode_token_amount(
     }
     Ok(None)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_change_sub() {
+        let max = Amount::max().change();
+        assert_eq!(checked_change_sub(max, max).unwrap(), 0);
+        assert_eq!(checked_change_sub(max, 0).unwrap(), max);
+        assert_eq!(checked_change_sub(0, max).unwrap(), -max);
+
+        // Changes near the bounds of the type must not wrap around
+        assert!(matches!(
+            checked_change_sub(Change::MIN, 1),
+            Err(Error::AmountOverflow)
+        ));
+        assert!(matches!(
+            checked_change_sub(Change::MAX, -1),
+            Err(Error::AmountOverflow)
+        ));
+        assert!(matches!(
+            checked_change_sub(0, Change::MIN),
+            Err(Error::AmountOverflow)
+        ));
+    }
+}
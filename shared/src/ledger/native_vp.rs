@@ -12,6 +12,7 @@ use crate::ledger::{storage, vp_env};
 use crate::proto::Tx;
 use crate::types::address::{Address, InternalAddress};
 use crate::types::storage::{BlockHash, BlockHeight, Epoch, Key};
+use crate::types::time::Rfc3339String;
 use crate::vm::prefix_iter::PrefixIterators;
 use crate::vm::WasmCacheAccess;
 
@@ -107,7 +108,8 @@ where
     }
 
     /// Storage read prior state (before tx execution). It will try to read from
-    /// the storage.
+    /// the storage. The prior state is anchored to the last committed block
+    /// height, see [`vp_env::read_pre`].
     pub fn read_pre(&self, key: &Key) -> Result<Option<Vec<u8>>> {
         vp_env::read_pre(&mut *self.gas_meter.borrow_mut(), self.storage, key)
             .map_err(Error::ContextError)
@@ -183,6 +185,13 @@ where
             .map_err(Error::ContextError)
     }
 
+    /// Getting the block time. The time is that of the block header to which
+    /// the current transaction is being applied.
+    pub fn get_block_time(&self) -> Result<Option<Rfc3339String>> {
+        vp_env::get_block_time(&mut *self.gas_meter.borrow_mut(), self.storage)
+            .map_err(Error::ContextError)
+    }
+
     /// Getting the block epoch. The epoch is that of the block to which the
     /// current transaction is being applied.
     pub fn get_block_epoch(&self) -> Result<Epoch> {
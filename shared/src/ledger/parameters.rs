@@ -3,9 +3,11 @@
 use std::collections::BTreeSet;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::storage::types::decode;
+use crate::ledger::gas::GasSchedule;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::storage::types::{self, encode};
 use crate::ledger::storage::{self, Storage, StorageHasher};
@@ -19,6 +21,8 @@ const EPOCH_DURATION_KEY: &str = "epoch_duration";
 const VP_WHITELIST_KEY: &str = "vp_whitelist";
 const TX_WHITELIST_KEY: &str = "tx_whitelist";
 const MAX_EXPECTED_TIME_PER_BLOCK_KEY: &str = "max_expected_time_per_block";
+const GAS_SCHEDULE_KEY: &str = "gas_schedule";
+const WASM_LIMITS_KEY: &str = "wasm_limits";
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -63,6 +67,50 @@ pub struct Parameters {
     pub vp_whitelist: Vec<String>,
     /// Whitelisted tx hashes
     pub tx_whitelist: Vec<String>,
+    /// Gas costs of the host environment operations
+    pub gas_schedule: GasSchedule,
+    /// Limits of the resources available to the wasm code
+    pub wasm_limits: WasmLimitsParams,
+}
+
+/// The limits of the resources available to the wasm code of transactions and
+/// validity predicates. Whether the code traps depends on these limits, so
+/// they have to be the same for all the validators.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct WasmLimitsParams {
+    /// The maximum number of memory pages (64 KiB each) of a transaction
+    pub tx_max_memory_pages: u32,
+    /// The maximum number of memory pages (64 KiB each) of a validity
+    /// predicate
+    pub vp_max_memory_pages: u32,
+    /// The maximum height of the stack of transactions and validity
+    /// predicates
+    pub max_stack_height: u32,
+}
+
+impl Default for WasmLimitsParams {
+    fn default() -> Self {
+        Self {
+            // 12.8 MiB
+            tx_max_memory_pages: 200,
+            // 12.8 MiB
+            vp_max_memory_pages: 200,
+            max_stack_height: u16::MAX as u32,
+        }
+    }
 }
 
 /// Epoch duration. A new epoch begins as soon as both the `min_num_of_blocks`
@@ -128,6 +176,21 @@ pub fn init_genesis_storage<DB, H>(
             "Max expected time per block parameters must be initialized in \
              the genesis block",
         );
+
+    // write gas schedule parameter
+    let gas_schedule_key = gas_schedule_storage_key();
+    let gas_schedule_value = encode(&parameters.gas_schedule);
+    storage.write(&gas_schedule_key, gas_schedule_value).expect(
+        "Gas schedule parameter must be initialized in the genesis block",
+    );
+    storage.gas_schedule = parameters.gas_schedule.clone();
+
+    // write wasm limits parameter
+    let wasm_limits_key = wasm_limits_storage_key();
+    let wasm_limits_value = encode(&parameters.wasm_limits);
+    storage.write(&wasm_limits_key, wasm_limits_value).expect(
+        "Wasm limits parameter must be initialized in the genesis block",
+    );
 }
 
 #[allow(missing_docs)]
@@ -180,17 +243,66 @@ where
         decode(value.ok_or(ReadError::ParametersMissing)?)
             .map_err(ReadError::StorageTypeError)?;
 
+    let (gas_schedule, gas_schedule_gas) = read_gas_schedule(storage)?;
+
+    let (wasm_limits, gas_wasm_limits) = read_wasm_limits(storage)?;
+
     Ok((
         Parameters {
             epoch_duration,
             max_expected_time_per_block,
             vp_whitelist,
             tx_whitelist,
+            gas_schedule,
+            wasm_limits,
         },
-        gas_epoch + gas_tx + gas_vp + gas_time,
+        gas_epoch
+            + gas_tx
+            + gas_vp
+            + gas_time
+            + gas_schedule_gas
+            + gas_wasm_limits,
     ))
 }
 
+/// Read the gas schedule parameter from store
+pub fn read_gas_schedule<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(GasSchedule, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let gas_schedule_key = gas_schedule_storage_key();
+    let (value, gas) = storage
+        .read(&gas_schedule_key)
+        .map_err(ReadError::StorageError)?;
+    let gas_schedule: GasSchedule =
+        decode(value.ok_or(ReadError::ParametersMissing)?)
+            .map_err(ReadError::StorageTypeError)?;
+
+    Ok((gas_schedule, gas))
+}
+
+/// Read the wasm limits parameter from store
+pub fn read_wasm_limits<DB, H>(
+    storage: &Storage<DB, H>,
+) -> std::result::Result<(WasmLimitsParams, u64), ReadError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let wasm_limits_key = wasm_limits_storage_key();
+    let (value, gas) = storage
+        .read(&wasm_limits_key)
+        .map_err(ReadError::StorageError)?;
+    let wasm_limits: WasmLimitsParams =
+        decode(value.ok_or(ReadError::ParametersMissing)?)
+            .map_err(ReadError::StorageTypeError)?;
+
+    Ok((wasm_limits, gas))
+}
+
 /// Read the the epoch duration parameter from store
 pub fn read_epoch_parameter<DB, H>(
     storage: &Storage<DB, H>,
@@ -296,6 +408,35 @@ where
     update(storage, value, key)
 }
 
+/// Update the gas schedule parameter in storage and the schedule used to
+/// charge gas from now on. Returns the gas cost.
+pub fn update_gas_schedule_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &GasSchedule,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = gas_schedule_storage_key();
+    let gas = update(storage, value, key)?;
+    storage.gas_schedule = value.clone();
+    Ok(gas)
+}
+
+/// Update the wasm limits parameter in storage. Returns the gas cost.
+pub fn update_wasm_limits_parameter<DB, H>(
+    storage: &mut Storage<DB, H>,
+    value: &WasmLimitsParams,
+) -> std::result::Result<u64, WriteError>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: storage::StorageHasher,
+{
+    let key = wasm_limits_storage_key();
+    update(storage, value, key)
+}
+
 impl<'a, DB, H, CA> NativeVp for ParametersVp<'a, DB, H, CA>
 where
     DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -358,6 +499,26 @@ pub fn max_expected_time_per_block_key() -> Key {
     }
 }
 
+/// Storage key used for gas schedule parameter.
+pub fn gas_schedule_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(GAS_SCHEDULE_KEY.to_string()),
+        ],
+    }
+}
+
+/// Storage key used for wasm limits parameter.
+pub fn wasm_limits_storage_key() -> Key {
+    Key {
+        segments: vec![
+            DbKeySeg::AddressSeg(Address::Internal(ADDR)),
+            DbKeySeg::StringSeg(WASM_LIMITS_KEY.to_string()),
+        ],
+    }
+}
+
 impl From<native_vp::Error> for Error {
     fn from(err: native_vp::Error) -> Self {
         Self::NativeVpError(err)
@@ -37,6 +37,8 @@ pub enum Error {
     Smt(SmtError),
     #[error("Invalid store type: {0}")]
     StoreType(String),
+    #[error("The root of the {0} tree doesn't match its leaves")]
+    InvalidRoot(StoreType),
 }
 
 /// Result for functions that may fail
@@ -227,6 +229,29 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
         (*self.base.root()).into()
     }
 
+    /// Check that the root of each tree can be re-computed from its leaves
+    /// and that the base tree holds the roots of the sub trees.
+    pub fn validate(&self) -> Result<()> {
+        for store_type in StoreType::iter() {
+            let tree = self.tree(store_type);
+            let mut rebuilt =
+                SparseMerkleTree::<H, H256, DefaultStore<H256>>::default();
+            for leaf in tree.store().leaves_map().values() {
+                rebuilt.update(leaf.key, leaf.value)?;
+            }
+            if rebuilt.root() != tree.root() {
+                return Err(Error::InvalidRoot(*store_type));
+            }
+            if *store_type != StoreType::Base {
+                let base_key = H::hash(&store_type.to_string());
+                if self.base.get(&base_key)? != *tree.root() {
+                    return Err(Error::InvalidRoot(StoreType::Base));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Get the stores of the base and sub trees
     pub fn stores(&self) -> MerkleTreeStoresWrite {
         MerkleTreeStoresWrite {
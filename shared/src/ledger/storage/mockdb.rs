@@ -11,6 +11,7 @@ use super::{
     BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, Error, Result, DB,
 };
 use crate::ledger::storage::types::{self, KVBytes, PrefixIterator};
+use crate::types::chain::ChainId;
 #[cfg(feature = "ferveo-tpke")]
 use crate::types::storage::TxQueue;
 use crate::types::storage::{BlockHeight, Key, KeySeg, KEY_SEGMENT_SEPARATOR};
@@ -131,6 +132,9 @@ impl DB for MockDB {
                             types::decode(bytes).map_err(Error::CodingError)?,
                         );
                     }
+                    "diffs" => {
+                        // ignore the diffs
+                    }
                     _ => unknown_key_error(path)?,
                 },
                 None => unknown_key_error(path)?,
@@ -257,42 +261,86 @@ impl DB for MockDB {
         Ok(())
     }
 
+    fn read_chain_id(&self) -> Result<Option<ChainId>> {
+        match self.0.borrow().get("chain_id") {
+            Some(bytes) => {
+                Ok(Some(types::decode(bytes).map_err(Error::CodingError)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_chain_id(&mut self, chain_id: &ChainId) -> Result<()> {
+        self.0
+            .borrow_mut()
+            .insert("chain_id".to_owned(), types::encode(chain_id));
+        Ok(())
+    }
+
+    fn prune_height(&mut self, height: BlockHeight) -> Result<()> {
+        let prefix = format!("{}/", height.raw());
+        self.0
+            .borrow_mut()
+            .retain(|key, _value| !key.starts_with(&prefix));
+        Ok(())
+    }
+
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
         let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
         Ok(self.0.borrow().get(&key.to_string()).cloned())
     }
 
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        // Find the first block after the given height in which the key has
+        // been changed
+        let mut h = height.next_height();
+        while h <= last_height {
+            let key_prefix = Key::from(h.to_db_key())
+                .push(&"diffs".to_owned())
+                .map_err(Error::KeyError)?;
+            let old_val_key = key_prefix
+                .push(&"old".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            if let Some(old_value) = self.0.borrow().get(&old_val_key) {
+                return Ok(Some(old_value.clone()));
+            }
+            let new_val_key = key_prefix
+                .push(&"new".to_owned())
+                .map_err(Error::KeyError)?
+                .join(key)
+                .to_string();
+            if self.0.borrow().contains_key(&new_val_key) {
+                // The key has been created after the given height
+                return Ok(None);
+            }
+            h = h.next_height();
+        }
+        // The key hasn't been changed after the given height
+        self.read_subspace_val(key)
+    }
+
     fn write_subspace_val(
         &mut self,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
-        let value = value.as_ref();
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        let current_len = value.len() as i64;
-        Ok(
-            match self
-                .0
-                .borrow_mut()
-                .insert(key.to_string(), value.to_owned())
-            {
-                Some(prev_value) => current_len - prev_value.len() as i64,
-                None => current_len,
-            },
-        )
+        self.write_subspace_val_and_diff(height, key, value)
     }
 
     fn delete_subspace_val(
         &mut self,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        Ok(match self.0.borrow_mut().remove(&key.to_string()) {
-            Some(value) => value.len() as i64,
-            None => 0,
-        })
+        self.delete_subspace_val_and_diff(height, key)
     }
 
     fn batch() -> Self::WriteBatch {
@@ -308,37 +356,110 @@ impl DB for MockDB {
     fn batch_write_subspace_val(
         &self,
         _batch: &mut Self::WriteBatch,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
         value: impl AsRef<[u8]>,
     ) -> Result<i64> {
-        let value = value.as_ref();
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        let current_len = value.len() as i64;
-        Ok(
-            match self
-                .0
-                .borrow_mut()
-                .insert(key.to_string(), value.to_owned())
-            {
-                Some(prev_value) => current_len - prev_value.len() as i64,
-                None => current_len,
-            },
-        )
+        self.write_subspace_val_and_diff(height, key, value)
     }
 
     fn batch_delete_subspace_val(
         &self,
         _batch: &mut Self::WriteBatch,
-        _height: BlockHeight,
+        height: BlockHeight,
         key: &Key,
     ) -> Result<i64> {
-        let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
-        Ok(match self.0.borrow_mut().remove(&key.to_string()) {
-            Some(value) => value.len() as i64,
+        self.delete_subspace_val_and_diff(height, key)
+    }
+}
+
+impl MockDB {
+    /// Write the value of an account subspace key and persist its diff under
+    /// the height where it was changed. Returns the size difference from
+    /// previous value, if any, or the size of the value otherwise.
+    fn write_subspace_val_and_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+    ) -> Result<i64> {
+        let value = value.as_ref();
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let current_len = value.len() as i64;
+        let prev_value = self
+            .0
+            .borrow_mut()
+            .insert(subspace_key.to_string(), value.to_owned());
+        self.write_subspace_diff(
+            height,
+            key,
+            prev_value.as_deref(),
+            Some(value),
+        )?;
+        Ok(match prev_value {
+            Some(prev_value) => current_len - prev_value.len() as i64,
+            None => current_len,
+        })
+    }
+
+    /// Delete the value of an account subspace key and persist its diff under
+    /// the height where it was changed. Returns the size of the removed value,
+    /// if any, 0 if no previous value was found.
+    fn delete_subspace_val_and_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+    ) -> Result<i64> {
+        let subspace_key =
+            Key::parse("subspace").map_err(Error::KeyError)?.join(key);
+        let prev_value =
+            self.0.borrow_mut().remove(&subspace_key.to_string());
+        Ok(match prev_value {
+            Some(prev_value) => {
+                self.write_subspace_diff(height, key, Some(&prev_value), None)?;
+                prev_value.len() as i64
+            }
             None => 0,
         })
     }
+
+    /// Persist the diff of an account subspace key-val under the height where
+    /// it was changed. Only the first change of a key in a block is recorded
+    /// as its old value, so that it's the value from the predecessor block
+    /// height.
+    fn write_subspace_diff(
+        &self,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+    ) -> Result<()> {
+        let key_prefix = Key::from(height.to_db_key())
+            .push(&"diffs".to_owned())
+            .map_err(Error::KeyError)?;
+        let old_val_key = key_prefix
+            .push(&"old".to_owned())
+            .map_err(Error::KeyError)?
+            .join(key)
+            .to_string();
+        let new_val_key = key_prefix
+            .push(&"new".to_owned())
+            .map_err(Error::KeyError)?
+            .join(key)
+            .to_string();
+
+        let mut db = self.0.borrow_mut();
+        let already_changed =
+            db.contains_key(&old_val_key) || db.contains_key(&new_val_key);
+        if let (Some(old_value), false) = (old_value, already_changed) {
+            db.insert(old_val_key, old_value.to_owned());
+        }
+        if let Some(new_value) = new_value {
+            db.insert(new_val_key, new_value.to_owned());
+        }
+        Ok(())
+    }
 }
 
 impl<'iter> DBIter<'iter> for MockDB {
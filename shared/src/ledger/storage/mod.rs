@@ -19,7 +19,7 @@ use tendermint_stable::merkle::proof::Proof;
 use thiserror::Error;
 
 use super::parameters::Parameters;
-use crate::ledger::gas::MIN_STORAGE_GAS;
+use crate::ledger::gas::{GasSchedule, MIN_STORAGE_GAS};
 use crate::ledger::parameters::{self, EpochDuration};
 use crate::ledger::storage::merkle_tree::{
     Error as MerkleTreeError, MerkleRoot,
@@ -68,6 +68,17 @@ where
     /// Wrapper txs to be decrypted in the next block proposal
     #[cfg(feature = "ferveo-tpke")]
     pub tx_queue: TxQueue,
+    /// The number of the last committed block heights whose state is retained
+    /// for historical reads. Older heights are pruned on commit. When `None`,
+    /// the state of all the heights is kept.
+    pub retained_heights: Option<u64>,
+    /// The lowest block height that may still have to be pruned. This is not
+    /// persisted, so after a restart the pruning starts again from the first
+    /// height, which only costs a DB seek for each height already pruned.
+    next_height_to_prune: BlockHeight,
+    /// The gas costs of the host environment operations, loaded from the
+    /// protocol parameters
+    pub gas_schedule: GasSchedule,
 }
 
 /// The block storage data
@@ -100,6 +111,23 @@ pub enum Error {
     MerkleTreeError(MerkleTreeError),
     #[error("Merkle tree error: {0}")]
     DBError(String),
+    #[error("The state at block height {0} is not available")]
+    UnavailableHeight(BlockHeight),
+    #[error(
+        "The chain ID {stored} stored in the DB doesn't match the configured \
+         chain ID {configured}"
+    )]
+    ChainIdMismatch {
+        stored: ChainId,
+        configured: ChainId,
+    },
+    #[error(
+        "The Merkle root {computed} re-derived from the DB doesn't match the \
+         committed Merkle root {committed}"
+    )]
+    MerkleRootMismatch { committed: String, computed: String },
+    #[error("No committed block found in the DB")]
+    NoCommittedBlock,
 }
 
 /// The block's state as stored in the database.
@@ -170,9 +198,30 @@ pub trait DB: std::fmt::Debug {
     /// Write block's metadata
     fn write_block(&mut self, state: BlockStateWrite) -> Result<()>;
 
+    /// Read the chain ID persisted in the DB, if any
+    fn read_chain_id(&self) -> Result<Option<ChainId>>;
+
+    /// Persist the chain ID in the DB
+    fn write_chain_id(&mut self, chain_id: &ChainId) -> Result<()>;
+
+    /// Prune the data of the block at the given height, including its Merkle
+    /// tree stores and the diffs in account subspaces' key-vals.
+    fn prune_height(&mut self, height: BlockHeight) -> Result<()>;
+
     /// Read the latest value for account subspace key from the DB
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>>;
 
+    /// Read the value for account subspace key at the given height from the
+    /// DB. The value is reconstructed from the diffs written after the given
+    /// height up to the `last_height`, which must be the height of the latest
+    /// written diffs.
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>>;
+
     /// Write the value with the given height and account subspace key to the
     /// DB. Returns the size difference from previous value, if any, or the
     /// size of the value otherwise.
@@ -274,6 +323,9 @@ where
             ),
             #[cfg(feature = "ferveo-tpke")]
             tx_queue: TxQueue::default(),
+            retained_heights: None,
+            next_height_to_prune: BlockHeight::default(),
+            gas_schedule: GasSchedule::default(),
         }
     }
 
@@ -307,6 +359,13 @@ where
             {
                 self.tx_queue = tx_queue;
             }
+            // The gas schedule is only missing on a chain that has not been
+            // initialized from genesis
+            if let Ok((gas_schedule, _gas)) =
+                parameters::read_gas_schedule(self)
+            {
+                self.gas_schedule = gas_schedule;
+            }
             tracing::debug!("Loaded storage from DB");
         } else {
             tracing::info!("No state could be found");
@@ -314,6 +373,30 @@ where
         Ok(())
     }
 
+    /// Check that the chain ID persisted in the DB, if any, matches the
+    /// storage's chain ID. If there is no chain ID in the DB yet, or if
+    /// `force` is set, the storage's chain ID is persisted.
+    pub fn check_chain_id(&mut self, force: bool) -> Result<()> {
+        match self.db.read_chain_id()? {
+            Some(stored) if stored == self.chain_id => return Ok(()),
+            Some(stored) if !force => {
+                return Err(Error::ChainIdMismatch {
+                    stored,
+                    configured: self.chain_id.clone(),
+                });
+            }
+            Some(stored) => {
+                tracing::warn!(
+                    "Overwriting the chain ID {} stored in the DB with {}",
+                    stored,
+                    self.chain_id
+                );
+            }
+            None => {}
+        }
+        self.db.write_chain_id(&self.chain_id)
+    }
+
     /// Returns the Merkle root hash and the height of the committed block. If
     /// no block exists, returns None.
     pub fn get_state(&self) -> Option<(MerkleRoot, u64)> {
@@ -341,6 +424,75 @@ where
         self.db.write_block(state)?;
         self.last_height = self.block.height;
         self.header = None;
+        self.prune_old_heights()?;
+        Ok(())
+    }
+
+    /// Re-derive the Merkle root from all the accounts' sub-space key-vals
+    /// and the epoch data of the last block in the DB and check that it
+    /// matches the root of the committed tree. The last block's Merkle tree
+    /// stores persisted in the DB are also checked to be consistent. This is
+    /// a safety net to catch storage bugs early and should be called after
+    /// [`Storage::commit`].
+    pub fn verify_merkle_root(&mut self) -> Result<()> {
+        let BlockStateRead {
+            merkle_tree_stores,
+            epoch,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            ..
+        } = self.db.read_last_block()?.ok_or(Error::NoCommittedBlock)?;
+        MerkleTree::<H>::new(merkle_tree_stores).validate()?;
+
+        let mut tree = MerkleTree::<H>::default();
+        for (key, value, _gas) in self.db.iter_prefix(&Key::default()) {
+            let key = Key::parse(key).map_err(Error::KeyError)?;
+            tree.update(&key, value)?;
+        }
+        update_epoch_in_tree(
+            &mut tree,
+            epoch,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+        )?;
+        let computed = tree.root();
+        let committed = self.merkle_root();
+        if computed.0 != committed.0 {
+            return Err(Error::MerkleRootMismatch {
+                committed: committed.to_string(),
+                computed: computed.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Get the lowest block height whose state is available for historical
+    /// reads.
+    pub fn get_oldest_height(&self) -> BlockHeight {
+        match self.retained_heights {
+            Some(retained) => {
+                // The last committed height is always retained
+                let retained = retained.max(1);
+                BlockHeight((self.last_height.0 + 1).saturating_sub(retained))
+            }
+            None => BlockHeight(0),
+        }
+    }
+
+    /// Prune all the block heights that have fallen out of the retention
+    /// window and haven't been pruned yet. This catches up with all the older
+    /// heights when the pruning is enabled or the retention is lowered.
+    fn prune_old_heights(&mut self) -> Result<()> {
+        if self.retained_heights.is_none() {
+            return Ok(());
+        }
+        let oldest = self.get_oldest_height();
+        while self.next_height_to_prune < oldest {
+            let height = self.next_height_to_prune;
+            tracing::debug!("Pruning the state at height {}", height);
+            self.db.prune_height(height)?;
+            self.next_height_to_prune = height.next_height();
+        }
         Ok(())
     }
 
@@ -372,6 +524,34 @@ where
         }
     }
 
+    /// Returns a value from the specified subspace at the given block height
+    /// and the gas cost. The height must not be greater than the height of
+    /// the last committed block.
+    pub fn read_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+    ) -> Result<(Option<Vec<u8>>, u64)> {
+        if height > self.last_height || height < self.get_oldest_height() {
+            return Err(Error::UnavailableHeight(height));
+        }
+        if height == self.last_height {
+            return self.read(key);
+        }
+        tracing::debug!("storage read key {} at height {}", key, height);
+        match self.db.read_subspace_val_with_height(
+            key,
+            height,
+            self.block.height,
+        )? {
+            Some(v) => {
+                let gas = key.len() + v.len();
+                Ok((Some(v), gas as _))
+            }
+            None => Ok((None, key.len() as _)),
+        }
+    }
+
     /// Returns a prefix iterator and the gas cost
     pub fn iter_prefix(
         &self,
@@ -476,6 +656,22 @@ where
         Ok(self.block.tree.get_non_existence_proof(key)?)
     }
 
+    /// Read the value with the given key together with a proof against the
+    /// current Merkle root: an existence proof if the key is present or a
+    /// non-existence proof otherwise. Both are taken from the same tree, so
+    /// that a light client can verify the value without a second query.
+    pub fn read_with_proof(
+        &self,
+        key: &Key,
+    ) -> Result<(Option<Vec<u8>>, Proof)> {
+        let (value, _gas) = self.read(key)?;
+        let proof = match &value {
+            Some(value) => self.get_existence_proof(key, value.clone())?,
+            None => self.get_non_existence_proof(key)?,
+        };
+        Ok((value, proof))
+    }
+
     /// Get the current (yet to be committed) block epoch
     pub fn get_current_epoch(&self) -> (Epoch, u64) {
         (self.block.epoch, MIN_STORAGE_GAS)
@@ -545,30 +741,12 @@ where
 
     /// Update the merkle tree with epoch data
     fn update_epoch_in_merkle_tree(&mut self) -> Result<()> {
-        let key_prefix: Key =
-            Address::Internal(InternalAddress::PoS).to_db_key().into();
-
-        let key = key_prefix
-            .push(&"epoch_start_height".to_string())
-            .map_err(Error::KeyError)?;
-        self.block
-            .tree
-            .update(&key, types::encode(&self.next_epoch_min_start_height))?;
-
-        let key = key_prefix
-            .push(&"epoch_start_time".to_string())
-            .map_err(Error::KeyError)?;
-        self.block
-            .tree
-            .update(&key, types::encode(&self.next_epoch_min_start_time))?;
-
-        let key = key_prefix
-            .push(&"current_epoch".to_string())
-            .map_err(Error::KeyError)?;
-        self.block
-            .tree
-            .update(&key, types::encode(&self.block.epoch))?;
-        Ok(())
+        update_epoch_in_tree(
+            &mut self.block.tree,
+            self.block.epoch,
+            self.next_epoch_min_start_height,
+            self.next_epoch_min_start_time,
+        )
     }
 
     /// Start write batch.
@@ -610,6 +788,34 @@ where
     }
 }
 
+/// Update the given Merkle tree with the epoch data, which is not stored in the
+/// accounts' sub-spaces
+fn update_epoch_in_tree<H: StorageHasher>(
+    tree: &mut MerkleTree<H>,
+    epoch: Epoch,
+    next_epoch_min_start_height: BlockHeight,
+    next_epoch_min_start_time: DateTimeUtc,
+) -> Result<()> {
+    let key_prefix: Key =
+        Address::Internal(InternalAddress::PoS).to_db_key().into();
+
+    let key = key_prefix
+        .push(&"epoch_start_height".to_string())
+        .map_err(Error::KeyError)?;
+    tree.update(&key, types::encode(&next_epoch_min_start_height))?;
+
+    let key = key_prefix
+        .push(&"epoch_start_time".to_string())
+        .map_err(Error::KeyError)?;
+    tree.update(&key, types::encode(&next_epoch_min_start_time))?;
+
+    let key = key_prefix
+        .push(&"current_epoch".to_string())
+        .map_err(Error::KeyError)?;
+    tree.update(&key, types::encode(&epoch))?;
+    Ok(())
+}
+
 impl From<MerkleTreeError> for Error {
     fn from(error: MerkleTreeError) -> Self {
         Self::MerkleTreeError(error)
@@ -652,6 +858,9 @@ pub mod testing {
                 ),
                 #[cfg(feature = "ferveo-tpke")]
                 tx_queue: TxQueue::default(),
+                retained_heights: None,
+                next_height_to_prune: BlockHeight::default(),
+                gas_schedule: GasSchedule::default(),
             }
         }
     }
@@ -667,6 +876,248 @@ mod tests {
     use crate::ledger::parameters::Parameters;
     use crate::types::time::{self, Duration};
 
+    /// Test that a value changed in several blocks can be read at each of the
+    /// committed heights
+    #[test]
+    fn test_read_with_height() {
+        let mut storage = TestStorage::default();
+        let key = Key::parse("key").unwrap();
+        for height in 1..=3_u64 {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            let mut batch = TestStorage::batch();
+            storage
+                .batch_write_subspace_val(
+                    &mut batch,
+                    &key,
+                    types::encode(&height),
+                )
+                .unwrap();
+            storage.exec_batch(batch).unwrap();
+            storage.commit().unwrap();
+        }
+
+        for height in 1..=3_u64 {
+            let (value, _gas) =
+                storage.read_with_height(&key, BlockHeight(height)).unwrap();
+            assert_eq!(value, Some(types::encode(&height)));
+        }
+        // The key didn't exist before the first block
+        let (value, _gas) =
+            storage.read_with_height(&key, BlockHeight(0)).unwrap();
+        assert_eq!(value, None);
+        // The state above the last committed height is not available
+        assert!(storage.read_with_height(&key, BlockHeight(4)).is_err());
+    }
+
+    /// Test that the heights older than the retention window are pruned on
+    /// commit, while the current state stays readable
+    #[test]
+    fn test_prune_old_heights() {
+        let mut storage = TestStorage {
+            retained_heights: Some(2),
+            ..Default::default()
+        };
+        let key = Key::parse("key").unwrap();
+        for height in 1..=5_u64 {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            let mut batch = TestStorage::batch();
+            storage
+                .batch_write_subspace_val(
+                    &mut batch,
+                    &key,
+                    types::encode(&height),
+                )
+                .unwrap();
+            storage.exec_batch(batch).unwrap();
+            let root_before_commit = storage.merkle_root();
+            storage.commit().unwrap();
+            // Pruning must not affect the latest Merkle root
+            assert_eq!(storage.merkle_root(), root_before_commit);
+        }
+
+        assert_eq!(storage.get_oldest_height(), BlockHeight(4));
+        for height in 0..=3_u64 {
+            assert!(storage
+                .read_with_height(&key, BlockHeight(height))
+                .is_err());
+        }
+        let (value, _gas) =
+            storage.read_with_height(&key, BlockHeight(4)).unwrap();
+        assert_eq!(value, Some(types::encode(&4_u64)));
+        let (value, _gas) = storage.read(&key).unwrap();
+        assert_eq!(value, Some(types::encode(&5_u64)));
+
+        // The last committed block can still be loaded
+        assert!(storage.db.read_last_block().unwrap().is_some());
+    }
+
+    /// Test that when the pruning is enabled after some heights have been
+    /// committed, all the heights below the retention window are pruned, not
+    /// only the last one that has fallen out of it
+    #[test]
+    fn test_prune_all_old_heights() {
+        let mut storage = TestStorage::default();
+        let key = Key::parse("key").unwrap();
+        let commit_height = |storage: &mut TestStorage, height: u64| {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            let mut batch = TestStorage::batch();
+            storage
+                .batch_write_subspace_val(
+                    &mut batch,
+                    &key,
+                    types::encode(&height),
+                )
+                .unwrap();
+            storage.exec_batch(batch).unwrap();
+            storage.commit().unwrap();
+        };
+        for height in 1..=5_u64 {
+            commit_height(&mut storage, height);
+        }
+        // The value at height 1 is found in the diffs of height 2
+        let value_at_1 = |storage: &TestStorage| {
+            storage
+                .db
+                .read_subspace_val_with_height(
+                    &key,
+                    BlockHeight(1),
+                    storage.last_height,
+                )
+                .unwrap()
+        };
+        assert_eq!(value_at_1(&storage), Some(types::encode(&1_u64)));
+
+        storage.retained_heights = Some(2);
+        commit_height(&mut storage, 6);
+
+        // The diffs of the heights 1 to 4 have been pruned, so the oldest
+        // diff left is the one from height 5, whose old value is from
+        // height 4
+        assert_eq!(storage.get_oldest_height(), BlockHeight(5));
+        assert_eq!(value_at_1(&storage), Some(types::encode(&4_u64)));
+    }
+
+    /// Test that the Merkle root re-derived from the DB matches the committed
+    /// root for valid operations and that a diverging tree is reported
+    #[test]
+    fn test_verify_merkle_root() {
+        let mut storage = TestStorage::default();
+        assert!(matches!(
+            storage.verify_merkle_root(),
+            Err(Error::NoCommittedBlock)
+        ));
+        for height in 1..=3_u64 {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            storage.update_epoch_in_merkle_tree().unwrap();
+            let key = Key::parse(format!("key{}", height)).unwrap();
+            storage.write(&key, types::encode(&height)).unwrap();
+            if height == 3 {
+                storage.delete(&Key::parse("key1").unwrap()).unwrap();
+            }
+            storage.commit().unwrap();
+            storage.verify_merkle_root().unwrap();
+        }
+
+        // A value written to the DB without updating the tree diverges
+        // from the committed root
+        let key = Key::parse("key2").unwrap();
+        storage
+            .db
+            .write_subspace_val(storage.last_height, &key, [0_u8])
+            .unwrap();
+        assert!(matches!(
+            storage.verify_merkle_root(),
+            Err(Error::MerkleRootMismatch { .. })
+        ));
+        let value = types::encode(&2_u64);
+        storage
+            .db
+            .write_subspace_val(storage.last_height, &key, value)
+            .unwrap();
+        storage.verify_merkle_root().unwrap();
+
+        // An update of the tree that is not committed diverges from the DB
+        storage.block.tree.update(&key, [0_u8]).unwrap();
+        assert!(matches!(
+            storage.verify_merkle_root(),
+            Err(Error::MerkleRootMismatch { .. })
+        ));
+    }
+
+    /// Test that a read with proof returns the value together with an
+    /// existence proof that verifies against the Merkle root, and a
+    /// non-existence proof for a missing key
+    #[test]
+    fn test_read_with_proof() {
+        use ics23::commitment_proof::Proof as Ics23Proof;
+        use ics23::CommitmentProof;
+        use prost::Message;
+
+        let mut storage = TestStorage::default();
+        let present = Key::parse("present").unwrap();
+        let absent = Key::parse("absent").unwrap();
+        let val = [1u8; 8].to_vec();
+        storage.write(&present, val.clone()).unwrap();
+        let specs = storage.block.tree.proof_specs();
+        let root = storage.block.tree.root().0;
+
+        let (value, proof) = storage.read_with_proof(&present).unwrap();
+        assert_eq!(value, Some(val.clone()));
+        let paths = [present.to_string(), StoreType::Account.to_string()];
+        let mut sub_root = val.clone();
+        let mut value = val;
+        for ((op, spec), key) in proof.ops.iter().zip(&specs).zip(&paths) {
+            let commitment_proof = CommitmentProof::decode(&*op.data).unwrap();
+            let existence_proof = match commitment_proof.clone().proof.unwrap()
+            {
+                Ics23Proof::Exist(ep) => ep,
+                _ => panic!("Expected an existence proof"),
+            };
+            sub_root =
+                ics23::calculate_existence_root(&existence_proof).unwrap();
+            assert!(ics23::verify_membership(
+                &commitment_proof,
+                spec,
+                &sub_root,
+                key.as_bytes(),
+                &value,
+            ));
+            value = sub_root.clone();
+        }
+        assert_eq!(sub_root, root);
+
+        let (value, proof) = storage.read_with_proof(&absent).unwrap();
+        assert!(value.is_none());
+        let sub_proof = CommitmentProof::decode(&*proof.ops[0].data).unwrap();
+        match sub_proof.proof.unwrap() {
+            Ics23Proof::Nonexist(nep) => {
+                assert_eq!(nep.key, absent.to_string().as_bytes())
+            }
+            _ => panic!("Expected a non-existence proof"),
+        }
+        // The base proof anchors the account sub tree to the Merkle root
+        let account_root = MerkleRoot::from(
+            *storage.block.tree.stores().root(&StoreType::Account),
+        )
+        .0;
+        let base_proof = CommitmentProof::decode(&*proof.ops[1].data).unwrap();
+        assert!(ics23::verify_membership(
+            &base_proof,
+            &specs[1],
+            &root,
+            StoreType::Account.to_string().as_bytes(),
+            &account_root,
+        ));
+    }
+
     prop_compose! {
         /// Setup test input data with arbitrary epoch duration, epoch start
         /// height and time, and a block height and time that are greater than
@@ -731,7 +1182,9 @@ mod tests {
                 epoch_duration: epoch_duration.clone(),
                 max_expected_time_per_block: Duration::seconds(max_expected_time_per_block).into(),
                 vp_whitelist: vec![],
-                tx_whitelist: vec![]
+                tx_whitelist: vec![],
+                gas_schedule: Default::default(),
+                wasm_limits: Default::default(),
             };
             parameters::init_genesis_storage(&mut storage, &parameters);
 
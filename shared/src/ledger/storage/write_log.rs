@@ -1,14 +1,16 @@
 //! Write log is temporary storage for modifications performed by a transaction.
 //! before they are committed to the ledger's storage.
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
+use borsh::{BorshDeserialize, BorshSerialize};
 use thiserror::Error;
 
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::types::address::{Address, EstablishedAddressGen};
 use crate::types::ibc::IbcEvent;
 use crate::types::storage::Key;
+use crate::types::transaction::hash_tx;
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -32,7 +34,7 @@ pub enum Error {
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// A storage modification
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
 pub enum StorageModification {
     /// Write a new value
     Write {
@@ -210,13 +212,41 @@ impl WriteLog {
         &mut self,
         storage_address_gen: &EstablishedAddressGen,
         vp: Vec<u8>,
+    ) -> (Address, u64) {
+        self.init_account_with_rng(
+            storage_address_gen,
+            "TODO more randomness".as_bytes(),
+            vp,
+        )
+    }
+
+    /// Initialize a new account with an address derived from the given parent
+    /// address and the hash of the validity predicate code, and return the gas
+    /// cost. The state of the address generator serves as a nonce, so that
+    /// accounts initialized with the same parent and code get distinct
+    /// addresses.
+    pub fn init_account_derived(
+        &mut self,
+        storage_address_gen: &EstablishedAddressGen,
+        parent: &Address,
+        vp: Vec<u8>,
+    ) -> (Address, u64) {
+        let vp_hash = hash_tx(&vp);
+        let rng_source = [parent.encode().as_bytes(), &vp_hash.0].concat();
+        self.init_account_with_rng(storage_address_gen, rng_source, vp)
+    }
+
+    fn init_account_with_rng(
+        &mut self,
+        storage_address_gen: &EstablishedAddressGen,
+        rng_source: impl AsRef<[u8]>,
+        vp: Vec<u8>,
     ) -> (Address, u64) {
         // If we've previously generated a new account, we use the local copy of
         // the generator. Otherwise, we create a new copy from the storage
         let address_gen =
             self.address_gen.get_or_insert(storage_address_gen.clone());
-        let addr =
-            address_gen.generate_address("TODO more randomness".as_bytes());
+        let addr = address_gen.generate_address(rng_source);
         let key = Key::validity_predicate(&addr);
         let gas = (key.len() + vp.len()) as _;
         self.tx_write_log
@@ -234,6 +264,11 @@ impl WriteLog {
         len as _
     }
 
+    /// Get the storage modifications of the current transaction
+    pub fn get_tx_modifications(&self) -> &HashMap<Key, StorageModification> {
+        &self.tx_write_log
+    }
+
     /// Get the storage keys changed and accounts keys initialized in the
     /// current transaction. The account keys point to the validity predicates
     /// of the newly created accounts.
@@ -241,6 +276,29 @@ impl WriteLog {
         self.tx_write_log.keys().cloned().collect()
     }
 
+    /// Get the modifications of the keys that start with the segments of the
+    /// given prefix from the block's and the current transaction's write log
+    /// and the gas cost of scanning them. The modifications of the current
+    /// transaction take precedence. A prefix matches whole segments only, so
+    /// e.g. the prefix `a/1` matches the key `a/1/b`, but not `a/10`.
+    pub fn prefix_modifications(
+        &self,
+        prefix: &Key,
+    ) -> (BTreeMap<&Key, &StorageModification>, u64) {
+        let mut gas: u64 = 0;
+        let modifications = self
+            .block_write_log
+            .iter()
+            .chain(self.tx_write_log.iter())
+            .filter(|(key, _)| {
+                // every scanned key is charged, whether it matches or not
+                gas += key.len() as u64;
+                key.segments.starts_with(&prefix.segments)
+            })
+            .collect();
+        (modifications, gas)
+    }
+
     /// Get the storage keys changed in the current transaction (left) and
     /// the addresses of accounts initialized in the current transaction
     /// (right). The first vector excludes keys of validity predicates of
@@ -354,15 +412,19 @@ impl WriteLog {
     ///
     /// Note that some storage keys may comprise of multiple addresses, in which
     /// case every address will be the verifier of the key.
+    ///
+    /// The verifiers are ordered by their addresses, so that their VPs are
+    /// dispatched in the same order regardless of the order in which the keys
+    /// have been changed.
     pub fn verifiers_changed_keys(
         &self,
         verifiers_from_tx: &BTreeSet<Address>,
-    ) -> HashMap<Address, BTreeSet<Key>> {
+    ) -> BTreeMap<Address, BTreeSet<Key>> {
         let (changed_keys, initialized_accounts) = self.get_partitioned_keys();
         let mut verifiers =
             verifiers_from_tx
                 .iter()
-                .fold(HashMap::new(), |mut acc, addr| {
+                .fold(BTreeMap::new(), |mut acc, addr| {
                     let changed_keys: BTreeSet<Key> =
                         changed_keys.iter().map(|&key| key.clone()).collect();
                     acc.insert(addr.clone(), changed_keys);
@@ -505,6 +567,35 @@ mod tests {
         assert_eq!(init_accounts.len(), 1);
     }
 
+    /// Test that the verifiers are in the same order regardless of the order
+    /// in which the keys of their addresses have been changed, so that their
+    /// VPs are dispatched in a deterministic order
+    #[test]
+    fn test_verifiers_changed_keys_order() {
+        let addresses = [
+            address::testing::established_address_1(),
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+        ];
+        let verifiers_order = |order: [usize; 3]| {
+            let mut write_log = WriteLog::default();
+            for i in order {
+                let key = Key::validity_predicate(&addresses[i]);
+                write_log.write(&key, vec![i as u8]).unwrap();
+            }
+            write_log
+                .verifiers_changed_keys(&BTreeSet::new())
+                .into_keys()
+                .collect::<Vec<_>>()
+        };
+
+        let mut sorted = addresses.to_vec();
+        sorted.sort();
+        for order in [[0, 1, 2], [2, 1, 0], [1, 2, 0]] {
+            assert_eq!(verifiers_order(order), sorted);
+        }
+    }
+
     #[test]
     fn test_update_initialized_account_should_fail() {
         let mut write_log = WriteLog::default();
@@ -545,6 +636,39 @@ mod tests {
         assert_matches!(result, Error::DeleteVp);
     }
 
+    #[test]
+    fn test_prefix_modifications() {
+        let mut write_log = WriteLog::default();
+        let prefix = Key::parse("a/1").expect("cannot parse the key string");
+        let key = |key: &str| Key::parse(key).expect("cannot parse the key");
+        let val = "val".as_bytes().to_vec();
+
+        write_log.write(&key("a/1/b"), val.clone()).unwrap();
+        write_log.write(&key("a/1/c"), val.clone()).unwrap();
+        write_log.write(&key("a/10/b"), val.clone()).unwrap();
+        write_log.commit_tx();
+        // the current transaction's modification takes precedence
+        write_log.delete(&key("a/1/c")).unwrap();
+        write_log.write(&key("b/1"), val).unwrap();
+
+        let (modifications, gas) = write_log.prefix_modifications(&prefix);
+        assert_eq!(modifications.len(), 2);
+        assert_matches!(
+            modifications.get(&key("a/1/b")),
+            Some(StorageModification::Write { .. })
+        );
+        assert_matches!(
+            modifications.get(&key("a/1/c")),
+            Some(StorageModification::Delete)
+        );
+        // every scanned key is charged
+        let scanned_len: usize = ["a/1/b", "a/1/c", "a/10/b", "a/1/c", "b/1"]
+            .into_iter()
+            .map(|raw| key(raw).len())
+            .sum();
+        assert_eq!(gas, scanned_len as u64);
+    }
+
     #[test]
     fn test_commit() {
         let mut storage =
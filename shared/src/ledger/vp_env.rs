@@ -11,8 +11,10 @@ use crate::ledger::gas::VpGasMeter;
 use crate::ledger::storage::write_log::WriteLog;
 use crate::ledger::storage::{self, write_log, Storage, StorageHasher};
 use crate::proto::Tx;
+use crate::types::address;
 use crate::types::hash::Hash;
 use crate::types::storage::{BlockHash, BlockHeight, Epoch, Key};
+use crate::types::time::Rfc3339String;
 
 /// These runtime errors will abort VP execution immediately
 #[allow(missing_docs)]
@@ -24,6 +26,8 @@ pub enum RuntimeError {
     StorageError(storage::Error),
     #[error("Storage data error: {0}")]
     StorageDataError(crate::types::storage::Error),
+    #[error("Address error: {0}")]
+    AddressError(address::Error),
     #[error("Encoding error: {0}")]
     EncodingError(std::io::Error),
     #[error("Numeric conversion error: {0}")]
@@ -48,8 +52,29 @@ pub fn add_gas(gas_meter: &mut VpGasMeter, used_gas: u64) -> Result<()> {
     result
 }
 
+/// Add a gas cost of a storage read, given the number of the accessed bytes,
+/// as charged by the chain's gas schedule
+fn add_storage_read_gas<DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &Storage<DB, H>,
+    bytes_len: u64,
+) -> Result<()>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    add_gas(gas_meter, storage.gas_schedule.storage_read(bytes_len))
+}
+
 /// Storage read prior state (before tx execution). It will try to read from the
 /// storage.
+///
+/// The prior state is anchored to the storage at the last committed block
+/// height. The writes of the current tx and of any txs applied before it in
+/// the same block are held in the write log until the block is committed, so
+/// they are never visible in the prior state, regardless of the order of the
+/// txs in the block. This includes the temporary values, which are never
+/// written to the storage.
 pub fn read_pre<DB, H>(
     gas_meter: &mut VpGasMeter,
     storage: &Storage<DB, H>,
@@ -60,7 +85,7 @@ where
     H: StorageHasher,
 {
     let (value, gas) = storage.read(key).map_err(RuntimeError::StorageError)?;
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, storage, gas)?;
     Ok(value)
 }
 
@@ -78,7 +103,7 @@ where
 {
     // Try to read from the write log first
     let (log_val, gas) = write_log.read(key);
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, storage, gas)?;
     match log_val {
         Some(&write_log::StorageModification::Write { ref value }) => {
             Ok(Some(value.clone()))
@@ -100,7 +125,7 @@ where
             // When not found in write log, try to read from the storage
             let (value, gas) =
                 storage.read(key).map_err(RuntimeError::StorageError)?;
-            add_gas(gas_meter, gas)?;
+            add_storage_read_gas(gas_meter, storage, gas)?;
             Ok(value)
         }
     }
@@ -125,7 +150,7 @@ pub fn read_temp(
 }
 
 /// Storage `has_key` in prior state (before tx execution). It will try to read
-/// from the storage.
+/// from the storage. The prior state is anchored like in [`read_pre`].
 pub fn has_key_pre<DB, H>(
     gas_meter: &mut VpGasMeter,
     storage: &Storage<DB, H>,
@@ -137,7 +162,7 @@ where
 {
     let (present, gas) =
         storage.has_key(key).map_err(RuntimeError::StorageError)?;
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, storage, gas)?;
     Ok(present)
 }
 
@@ -155,7 +180,7 @@ where
 {
     // Try to read from the write log first
     let (log_val, gas) = write_log.read(key);
-    add_gas(gas_meter, gas)?;
+    add_storage_read_gas(gas_meter, storage, gas)?;
     match log_val {
         Some(&write_log::StorageModification::Write { .. }) => Ok(true),
         Some(&write_log::StorageModification::Delete) => {
@@ -168,7 +193,7 @@ where
             // When not found in write log, try to check the storage
             let (present, gas) =
                 storage.has_key(key).map_err(RuntimeError::StorageError)?;
-            add_gas(gas_meter, gas)?;
+            add_storage_read_gas(gas_meter, storage, gas)?;
             Ok(present)
         }
     }
@@ -218,6 +243,22 @@ where
     Ok(hash)
 }
 
+/// Getting the block time. The time is that of the block header to which the
+/// current transaction is being applied. Returns `None` when the header is not
+/// available.
+pub fn get_block_time<DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &Storage<DB, H>,
+) -> Result<Option<Rfc3339String>>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (header, gas) = storage.get_block_header();
+    add_gas(gas_meter, gas)?;
+    Ok(header.map(|header| Rfc3339String(header.time.to_rfc3339())))
+}
+
 /// Getting the block hash. The height is that of the block to which the
 /// current transaction is being applied.
 pub fn get_tx_code_hash(gas_meter: &mut VpGasMeter, tx: &Tx) -> Result<Hash> {
@@ -256,8 +297,48 @@ where
     Ok(iter)
 }
 
+/// Count the keys with the given prefix in posterior state (after tx
+/// execution). The keys in the storage are counted, unless they have been
+/// deleted in the write log, together with the keys written in the write log.
+/// Unlike [`iter_post_next`], the keys written in the write log that are not
+/// yet in the storage are counted too. Temporary values and the VPs of new
+/// accounts are not counted. The keys are matched by whole segments of the
+/// prefix. The gas is charged for every visited key.
+pub fn prefix_count_post<DB, H>(
+    gas_meter: &mut VpGasMeter,
+    storage: &Storage<DB, H>,
+    write_log: &WriteLog,
+    prefix: &Key,
+) -> Result<u64>
+where
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+{
+    let (modifications, gas) = write_log.prefix_modifications(prefix);
+    add_gas(gas_meter, gas)?;
+    let (iter, gas) = storage.iter_prefix(prefix);
+    add_gas(gas_meter, gas)?;
+    let mut count = 0;
+    for (key, _val, iter_gas) in iter {
+        add_gas(gas_meter, iter_gas)?;
+        let key = Key::parse(key).map_err(RuntimeError::StorageDataError)?;
+        // The storage iterator matches the prefix as a string, so the keys
+        // that only share a part of the last segment are skipped. The
+        // modified keys are counted below.
+        if key.segments.starts_with(&prefix.segments)
+            && !modifications.contains_key(&key)
+        {
+            count += 1;
+        }
+    }
+    let written = modifications.values().filter(|modification| {
+        matches!(modification, write_log::StorageModification::Write { .. })
+    });
+    Ok(count + written.count() as u64)
+}
+
 /// Storage prefix iterator for prior state (before tx execution). It will try
-/// to read from the storage.
+/// to read from the storage. The prior state is anchored like in [`read_pre`].
 pub fn iter_pre_next<DB>(
     gas_meter: &mut VpGasMeter,
     iter: &mut <DB as storage::DBIter<'_>>::PrefixIter,
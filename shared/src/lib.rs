@@ -26,6 +26,8 @@ pub use tendermint_stable as tendermint;
 pub mod bytes;
 pub mod ledger;
 pub mod proto;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod types;
 pub mod vm;
 
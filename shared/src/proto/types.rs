@@ -2,6 +2,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Display;
 use std::hash::{Hash, Hasher};
+use std::path::Path;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use prost::Message;
@@ -9,6 +10,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::generated::types;
+use crate::types::intent::IntentData;
 use crate::types::key::*;
 use crate::types::time::DateTimeUtc;
 use crate::types::transaction::hash_tx;
@@ -17,10 +19,16 @@ use crate::types::transaction::hash_tx;
 pub enum Error {
     #[error("Error decoding a transaction from bytes: {0}")]
     TxDecodingError(prost::DecodeError),
+    #[error("Error decoding a transaction data: {0}")]
+    TxDataDecodingError(std::io::Error),
+    #[error("Transaction has no data")]
+    NoTxDataError,
     #[error("Error decoding an IntentGossipMessage from bytes: {0}")]
     IntentDecodingError(prost::DecodeError),
     #[error("Error decoding an DkgGossipMessage from bytes: {0}")]
     DkgDecodingError(prost::DecodeError),
+    #[error("Error decoding an intent cancellation: {0}")]
+    IntentCancellationDecodingError(std::io::Error),
     #[error("Intent is empty")]
     NoIntentError,
     #[error("Dkg is empty")]
@@ -106,6 +114,34 @@ where
         Self { data, sig }
     }
 
+    /// Initialize a new signed data with a signature of its encoding made by
+    /// the given function, e.g. with a key that is not available locally.
+    pub fn try_new_with<E>(
+        data: T,
+        sign: impl FnOnce(&[u8]) -> std::result::Result<common::Signature, E>,
+    ) -> std::result::Result<Self, E> {
+        let to_sign = data
+            .try_to_vec()
+            .expect("Encoding data for signing shouldn't fail");
+        let sig = sign(&to_sign)?;
+        Ok(Self { data, sig })
+    }
+
+    /// Decode a signed data from bytes that must be its unique Borsh
+    /// encoding. Other encodings of the same data, e.g. with the elements of a
+    /// set in a different order, are rejected, so that the signed data cannot
+    /// be altered without invalidating its signature.
+    pub fn try_from_canonical_slice(bytes: &[u8]) -> std::io::Result<Self> {
+        let signed = Self::try_from_slice(bytes)?;
+        if signed.try_to_vec()? != bytes {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The signed data is not canonically encoded",
+            ));
+        }
+        Ok(signed)
+    }
+
     /// Verify that the data has been signed by the secret key
     /// counterpart of the given public key.
     pub fn verify(
@@ -186,6 +222,22 @@ impl Tx {
     pub fn sign(self, keypair: &common::SecretKey) -> Self {
         let to_sign = self.hash();
         let sig = common::SigScheme::sign(keypair, &to_sign);
+        self.with_sig(sig)
+    }
+
+    /// Sign a transaction using [`SignedTxData`] with a signature of its hash
+    /// made by the given function, e.g. with a key that is not available
+    /// locally.
+    pub fn try_sign_with<E>(
+        self,
+        sign: impl FnOnce(&[u8]) -> std::result::Result<common::Signature, E>,
+    ) -> std::result::Result<Self, E> {
+        let to_sign = self.hash();
+        let sig = sign(&to_sign)?;
+        Ok(self.with_sig(sig))
+    }
+
+    fn with_sig(self, sig: common::Signature) -> Self {
         let signed = SignedTxData {
             data: self.data,
             sig,
@@ -219,24 +271,122 @@ impl Tx {
         let signed_data = tx.hash();
         common::SigScheme::verify_signature_raw(pk, &signed_data, sig)
     }
+
+    /// Decode the Borsh encoded data of a transaction that has been built
+    /// without a signer.
+    pub fn decode_data<T: BorshDeserialize>(&self) -> Result<Option<T>> {
+        self.data
+            .as_ref()
+            .map(|data| T::try_from_slice(data))
+            .transpose()
+            .map_err(Error::TxDataDecodingError)
+    }
+
+    /// Decode the Borsh encoded data of a transaction that has been signed,
+    /// together with its signature.
+    pub fn decode_signed_data<T: BorshDeserialize>(
+        &self,
+    ) -> Result<(Option<T>, common::Signature)> {
+        let data = self.data.as_ref().ok_or(Error::NoTxDataError)?;
+        let signed = SignedTxData::try_from_slice(data)
+            .map_err(Error::TxDataDecodingError)?;
+        let data = signed
+            .data
+            .as_ref()
+            .map(|data| T::try_from_slice(data))
+            .transpose()
+            .map_err(Error::TxDataDecodingError)?;
+        Ok((data, signed.sig))
+    }
+}
+
+/// A builder of transactions that composes the code, the Borsh encoded data
+/// and an optional signature.
+#[derive(Clone, Debug, Default)]
+pub struct TxBuilder {
+    code: Vec<u8>,
+    data: Option<Vec<u8>>,
+    signer: Option<common::SecretKey>,
+}
+
+impl TxBuilder {
+    /// Create a new transaction builder with empty code and no data
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the transaction code
+    pub fn code(mut self, code: impl Into<Vec<u8>>) -> Self {
+        self.code = code.into();
+        self
+    }
+
+    /// Set the transaction code from a file at the given path
+    pub fn code_from_path(
+        self,
+        path: impl AsRef<Path>,
+    ) -> std::io::Result<Self> {
+        let code = std::fs::read(path)?;
+        Ok(self.code(code))
+    }
+
+    /// Set the transaction data from a Borsh encode-able value
+    pub fn data(self, data: &impl BorshSerialize) -> Self {
+        let data = data
+            .try_to_vec()
+            .expect("Encoding transaction data shouldn't fail");
+        self.raw_data(data)
+    }
+
+    /// Set the transaction data from raw bytes
+    pub fn raw_data(mut self, data: Vec<u8>) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Set the key to sign the transaction with
+    pub fn signer(mut self, keypair: &common::SecretKey) -> Self {
+        self.signer = Some(keypair.clone());
+        self
+    }
+
+    /// Build the transaction, signed with the signer, if any
+    pub fn build(self) -> Tx {
+        let tx = Tx::new(self.code, self.data);
+        match &self.signer {
+            Some(keypair) => tx.sign(keypair),
+            None => tx,
+        }
+    }
+
+    /// Build the transaction and encode it to bytes
+    pub fn build_bytes(self) -> Vec<u8> {
+        self.build().to_bytes()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub struct IntentGossipMessage {
-    pub intent: Intent,
+pub enum IntentGossipMessage {
+    /// A new intent
+    Intent(Intent),
+    /// A cancellation of a previously gossiped intent
+    Cancellation(IntentCancellation),
 }
 
 impl TryFrom<&[u8]> for IntentGossipMessage {
     type Error = Error;
 
     fn try_from(intent_bytes: &[u8]) -> Result<Self> {
-        let intent = types::IntentGossipMessage::decode(intent_bytes)
+        let message = types::IntentGossipMessage::decode(intent_bytes)
             .map_err(Error::IntentDecodingError)?;
-        match &intent.msg {
+        match message.msg {
             Some(types::intent_gossip_message::Msg::Intent(intent)) => {
-                Ok(IntentGossipMessage {
-                    intent: intent.clone().try_into()?,
-                })
+                Ok(IntentGossipMessage::Intent(intent.try_into()?))
+            }
+            Some(types::intent_gossip_message::Msg::Cancellation(
+                cancellation,
+            )) => {
+                Ok(IntentGossipMessage::Cancellation(cancellation.try_into()?))
             }
             None => Err(Error::NoIntentError),
         }
@@ -245,17 +395,27 @@ impl TryFrom<&[u8]> for IntentGossipMessage {
 
 impl From<IntentGossipMessage> for types::IntentGossipMessage {
     fn from(message: IntentGossipMessage) -> Self {
-        types::IntentGossipMessage {
-            msg: Some(types::intent_gossip_message::Msg::Intent(
-                message.intent.into(),
-            )),
-        }
+        let msg = match message {
+            IntentGossipMessage::Intent(intent) => {
+                types::intent_gossip_message::Msg::Intent(intent.into())
+            }
+            IntentGossipMessage::Cancellation(cancellation) => {
+                types::intent_gossip_message::Msg::Cancellation(
+                    cancellation.into(),
+                )
+            }
+        };
+        types::IntentGossipMessage { msg: Some(msg) }
     }
 }
 
 impl IntentGossipMessage {
     pub fn new(intent: Intent) -> Self {
-        IntentGossipMessage { intent }
+        IntentGossipMessage::Intent(intent)
+    }
+
+    pub fn new_cancellation(cancellation: IntentCancellation) -> Self {
+        IntentGossipMessage::Cancellation(cancellation)
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -361,6 +521,96 @@ impl Intent {
         self.hash(&mut hasher);
         IntentId::from(hasher.finish().to_string())
     }
+
+    /// Verify that the intent's data is an [`IntentData`] that has been
+    /// signed by the secret key counterpart of the given public key.
+    pub fn verify_signer(
+        &self,
+        pk: &common::PublicKey,
+    ) -> std::result::Result<(), VerifySigError> {
+        match IntentData::try_from_slice(&self.data)
+            .map_err(|_| VerifySigError::MissingData)?
+        {
+            IntentData::FungibleToken(intent) => intent.verify(pk),
+            IntentData::Auction(intent) => intent.verify(pk),
+        }
+    }
+}
+
+/// A request to withdraw a previously gossiped intent, signed by the intent's
+/// signer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntentCancellation {
+    /// The ID of the intent to be cancelled
+    pub intent_id: IntentId,
+    /// The public key of the intent's signer
+    pub pk: common::PublicKey,
+    /// The signature of the intent ID
+    pub sig: common::Signature,
+}
+
+impl TryFrom<types::IntentCancellation> for IntentCancellation {
+    type Error = Error;
+
+    fn try_from(cancellation: types::IntentCancellation) -> Result<Self> {
+        let pk = BorshDeserialize::try_from_slice(&cancellation.public_key)
+            .map_err(Error::IntentCancellationDecodingError)?;
+        let sig = BorshDeserialize::try_from_slice(&cancellation.signature)
+            .map_err(Error::IntentCancellationDecodingError)?;
+        Ok(IntentCancellation {
+            intent_id: IntentId(cancellation.intent_id),
+            pk,
+            sig,
+        })
+    }
+}
+
+impl From<IntentCancellation> for types::IntentCancellation {
+    fn from(cancellation: IntentCancellation) -> Self {
+        types::IntentCancellation {
+            intent_id: cancellation.intent_id.0,
+            public_key: cancellation
+                .pk
+                .try_to_vec()
+                .expect("Encoding public key shouldn't fail"),
+            signature: cancellation
+                .sig
+                .try_to_vec()
+                .expect("Encoding signature shouldn't fail"),
+        }
+    }
+}
+
+impl IntentCancellation {
+    /// Create a cancellation of the intent with the given ID, signed with the
+    /// intent's signer keypair.
+    pub fn new(intent_id: IntentId, keypair: &common::SecretKey) -> Self {
+        let sig = common::SigScheme::sign(keypair, &intent_id.0);
+        Self {
+            intent_id,
+            pk: keypair.ref_to(),
+            sig,
+        }
+    }
+
+    /// Verify the cancellation's signature of the intent ID.
+    pub fn verify_sig(&self) -> std::result::Result<(), VerifySigError> {
+        common::SigScheme::verify_signature_raw(
+            &self.pk,
+            &self.intent_id.0,
+            &self.sig,
+        )
+    }
+
+    /// Verify that the cancellation is validly signed by the signer of the
+    /// given intent.
+    pub fn verify(
+        &self,
+        intent: &Intent,
+    ) -> std::result::Result<(), VerifySigError> {
+        self.verify_sig()?;
+        intent.verify_signer(&self.pk)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -405,7 +655,192 @@ impl Dkg {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::str::FromStr;
+
     use super::*;
+    use crate::types::intent::{
+        Auction, AuctionIntent, CreateAuction, DecimalWrapper, Exchange,
+        FungibleTokenIntent, PlaceBid,
+    };
+    use crate::types::{address, token};
+
+    /// An exchange of the given address signed by the given key
+    fn signed_exchange(
+        addr: address::Address,
+        keypair: common::SecretKey,
+    ) -> Signed<Exchange> {
+        Signed::new(
+            &keypair,
+            Exchange {
+                addr,
+                token_sell: address::xan(),
+                rate_min: DecimalWrapper::from_str("1").unwrap(),
+                max_sell: token::Amount::whole(100),
+                token_buy: address::btc(),
+                min_buy: token::Amount::whole(1),
+                vp: None,
+            },
+        )
+    }
+
+    /// A fungible token intent with exchanges signed by different keys
+    fn signed_intent() -> Signed<FungibleTokenIntent> {
+        Signed::new(
+            &testing::keypair_1(),
+            FungibleTokenIntent {
+                exchange: HashSet::from([
+                    signed_exchange(
+                        address::testing::established_address_1(),
+                        testing::keypair_1(),
+                    ),
+                    signed_exchange(
+                        address::testing::established_address_2(),
+                        testing::keypair_2(),
+                    ),
+                ]),
+                nonce: 1,
+            },
+        )
+    }
+
+    /// An auction intent with an auction and a bid on it, signed by different
+    /// keys, together with the exchange intents of their addresses
+    fn signed_auction_intent() -> Signed<AuctionIntent> {
+        let creator = address::testing::established_address_1();
+        let bidder = address::testing::established_address_2();
+        let auction = Signed::new(
+            &testing::keypair_1(),
+            Auction {
+                addr: creator.clone(),
+                create_auction: Some(CreateAuction {
+                    token_sell: address::xan(),
+                    token_buy: address::btc(),
+                    amount: token::Amount::whole(100),
+                    auction_start: 1,
+                    auction_end: 2,
+                    min_bid_increment: token::Amount::whole(1),
+                }),
+                place_bid: None,
+            },
+        );
+        let bid = Signed::new(
+            &testing::keypair_2(),
+            Auction {
+                addr: bidder.clone(),
+                create_auction: None,
+                place_bid: Some(PlaceBid {
+                    amount: token::Amount::whole(10),
+                    token: address::btc(),
+                    auction_id: "auction".to_string(),
+                }),
+            },
+        );
+        let exchange_intent =
+            |addr: &address::Address, keypair: common::SecretKey| {
+                Signed::new(
+                    &keypair,
+                    FungibleTokenIntent {
+                        exchange: HashSet::from([signed_exchange(
+                            addr.clone(),
+                            keypair.clone(),
+                        )]),
+                        nonce: 1,
+                    },
+                )
+            };
+        Signed::new(
+            &testing::keypair_1(),
+            AuctionIntent {
+                auctions: HashSet::from([auction, bid]),
+                exchange_intents: HashMap::from([
+                    (
+                        creator.clone(),
+                        exchange_intent(&creator, testing::keypair_1()),
+                    ),
+                    (
+                        bidder.clone(),
+                        exchange_intent(&bidder, testing::keypair_2()),
+                    ),
+                ]),
+            },
+        )
+    }
+
+    /// Assert that the encodings of the signed data and of its signed value
+    /// are canonical, i.e. that decoding them and encoding them again gives
+    /// the same bytes, so that the signature, made over the encoding of the
+    /// value, verifies with the value decoded from the signed data's bytes
+    fn assert_canonical<T>(signed: &Signed<T>)
+    where
+        T: BorshSerialize + BorshDeserialize,
+    {
+        let data_bytes = signed.data.try_to_vec().unwrap();
+        let data_reencoded = T::try_from_slice(&data_bytes)
+            .unwrap()
+            .try_to_vec()
+            .unwrap();
+        assert_eq!(data_reencoded, data_bytes);
+
+        let bytes = signed.try_to_vec().unwrap();
+        let decoded = Signed::<T>::try_from_canonical_slice(&bytes)
+            .expect("decoding failed");
+        assert_eq!(decoded.try_to_vec().unwrap(), bytes);
+    }
+
+    /// Test that all the signed types of the intents are canonically encoded
+    #[test]
+    fn test_signed_types_canonical_encoding() {
+        assert_canonical(&signed_exchange(
+            address::testing::established_address_1(),
+            testing::keypair_1(),
+        ));
+        assert_canonical(&signed_intent());
+        let auction_intent = signed_auction_intent();
+        for auction in &auction_intent.data.auctions {
+            assert_canonical(auction);
+        }
+        for exchange_intent in auction_intent.data.exchange_intents.values() {
+            assert_canonical(exchange_intent);
+        }
+        assert_canonical(&auction_intent);
+    }
+
+    /// Test that a decoded signed intent is encoded again to the same bytes
+    /// and that its signature verifies
+    #[test]
+    fn test_signed_intent_canonical_encoding() {
+        let bytes = signed_intent().try_to_vec().unwrap();
+        let decoded =
+            Signed::<FungibleTokenIntent>::try_from_canonical_slice(&bytes)
+                .expect("decoding failed");
+        assert_eq!(decoded.try_to_vec().unwrap(), bytes);
+        assert!(decoded.verify(&testing::keypair_1().ref_to()).is_ok());
+    }
+
+    /// Test that an encoding of a signed intent with its exchanges in a
+    /// different order, with which the signature would still verify, is
+    /// rejected
+    #[test]
+    fn test_signed_intent_non_canonical_encoding() {
+        let intent = signed_intent();
+        let mut exchanges: Vec<_> = intent.data.exchange.iter().collect();
+        exchanges.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        let mut bytes = (exchanges.len() as u32).try_to_vec().unwrap();
+        for exchange in exchanges {
+            bytes.extend(exchange.try_to_vec().unwrap());
+        }
+        bytes.extend(intent.data.nonce.try_to_vec().unwrap());
+        bytes.extend(intent.sig.try_to_vec().unwrap());
+        assert_ne!(bytes, intent.try_to_vec().unwrap());
+
+        let decoded =
+            Signed::<FungibleTokenIntent>::try_from_slice(&bytes).unwrap();
+        assert!(decoded.verify(&testing::keypair_1().ref_to()).is_ok());
+        let result =
+            Signed::<FungibleTokenIntent>::try_from_canonical_slice(&bytes);
+        assert!(result.is_err());
+    }
 
     #[test]
     fn test_tx() {
@@ -431,6 +866,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tx_builder_with_signer() {
+        let keypair = testing::keypair_1();
+        let code = "wasm code".as_bytes().to_owned();
+        let data = "arbitrary data".to_owned();
+        let bytes = TxBuilder::new()
+            .code(code.clone())
+            .data(&data)
+            .signer(&keypair)
+            .build_bytes();
+
+        let tx = Tx::try_from(bytes.as_ref()).expect("decoding failed");
+        assert_eq!(tx.code, code);
+        let (decoded, sig) = tx
+            .decode_signed_data::<String>()
+            .expect("decoding data failed");
+        assert_eq!(decoded, Some(data));
+        tx.verify_sig(&keypair.ref_to(), &sig)
+            .expect("signature verification failed");
+    }
+
+    #[test]
+    fn test_tx_builder_without_signer() {
+        let code = "wasm code".as_bytes().to_owned();
+        let data = 42_u64;
+        let tx = TxBuilder::new().code(code.clone()).data(&data).build();
+
+        assert_eq!(tx.code, code);
+        let decoded = tx.decode_data::<u64>().expect("decoding data failed");
+        assert_eq!(decoded, Some(data));
+        match tx.decode_signed_data::<u64>() {
+            Err(Error::TxDataDecodingError(_)) => {}
+            _ => panic!("unexpected result"),
+        }
+
+        let tx = TxBuilder::new().code(code).build();
+        assert_eq!(tx.data, None);
+        assert_eq!(tx.decode_data::<u64>().expect("decoding failed"), None);
+        match tx.decode_signed_data::<u64>() {
+            Err(Error::NoTxDataError) => {}
+            _ => panic!("unexpected result"),
+        }
+    }
+
     #[test]
     fn test_intent_gossip_message() {
         let data = "arbitrary data".as_bytes().to_owned();
@@ -443,6 +922,44 @@ mod tests {
         assert_eq!(message_from_bytes, message);
     }
 
+    #[test]
+    fn test_intent_cancellation() {
+        let keypair = testing::keypair_1();
+        let data = signed_intent().try_to_vec().unwrap();
+        let intent = Intent::new(data);
+
+        let cancellation = IntentCancellation::new(intent.id(), &keypair);
+        cancellation
+            .verify(&intent)
+            .expect("cancellation by the intent's signer must be valid");
+        let message = IntentGossipMessage::new_cancellation(cancellation);
+        let bytes = message.to_bytes();
+        let message_from_bytes = IntentGossipMessage::try_from(bytes.as_ref())
+            .expect("decoding failed");
+        assert_eq!(message_from_bytes, message);
+
+        let other_keypair = testing::keypair_2();
+        let cancellation = IntentCancellation::new(intent.id(), &other_keypair);
+        cancellation
+            .verify_sig()
+            .expect("the cancellation signature must be valid");
+        assert!(
+            cancellation.verify(&intent).is_err(),
+            "cancellation by a different signer must be invalid"
+        );
+
+        // Signed data that is not an intent data cannot be cancelled
+        let data = Signed::new(&keypair, "arbitrary data".to_owned())
+            .try_to_vec()
+            .unwrap();
+        let intent = Intent::new(data);
+        let cancellation = IntentCancellation::new(intent.id(), &keypair);
+        assert!(matches!(
+            cancellation.verify(&intent),
+            Err(VerifySigError::MissingData)
+        ));
+    }
+
     #[test]
     fn test_dkg_gossip_message() {
         let data = "arbitrary string".to_owned();
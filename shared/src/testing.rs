@@ -0,0 +1,28 @@
+//! Helpers for testing that are not specific to any of the modules.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// A log writer that captures the formatted records in a shared buffer. Use a
+/// clone of it as the writer of a `tracing` subscriber to check the logs
+/// emitted in a test.
+#[derive(Clone, Debug, Default)]
+pub struct LogCapture(Arc<Mutex<Vec<u8>>>);
+
+impl LogCapture {
+    /// Get the logs captured so far
+    pub fn logs(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl io::Write for LogCapture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -8,10 +8,12 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use derivative::Derivative;
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 
 use crate::proto::Signed;
 use crate::types::address::Address;
+use crate::types::key::{common, VerifySigError};
 use crate::types::storage::{BlockHeight, DbKeySeg, Key, KeySeg};
 use crate::types::token;
 
@@ -29,6 +31,11 @@ use crate::types::token;
 pub struct FungibleTokenIntent {
     /// List of exchange definitions
     pub exchange: HashSet<Signed<Exchange>>,
+    /// The nonce of the intent. It must be greater than the nonces of the
+    /// already matched intents of the exchanges' addresses, so that a matched
+    /// intent cannot be replayed. The intent must be signed by the exchanges'
+    /// addresses, so that the nonce cannot be replaced.
+    pub nonce: u64,
 }
 
 
@@ -46,6 +53,11 @@ Eq,
 pub struct AuctionIntent {
     /// List of exchange definitions
     pub auctions: HashSet<Signed<Auction>>,
+    /// The intents of the auctions' creators and bidders to exchange the
+    /// auctioned tokens for the bids, keyed by their addresses. A matchmaker
+    /// includes them in the tx of a resolved auction, so that the validity
+    /// predicates of the accounts can check the transfers.
+    pub exchange_intents: HashMap<Address, Signed<FungibleTokenIntent>>,
 }
 
 #[derive(
@@ -104,7 +116,9 @@ pub struct CreateAuction {
     /// The amount of token to be put on auction
     pub auction_start: u64,
     /// The amount of token to be put on auction
-    pub auction_end: u64
+    pub auction_end: u64,
+    /// The minimum amount by which a bid must exceed the highest bid so far
+    pub min_bid_increment: token::Amount,
 }
 
 #[derive(
@@ -124,6 +138,9 @@ Derivative,
 pub struct PlaceBid {
     /// The bid
     pub amount: token::Amount,
+    /// The token in which the bid is paid, which must be the auction's
+    /// `token_buy`
+    pub token: Address,
     /// The auction id
     pub auction_id: String
 }
@@ -218,6 +235,12 @@ impl From<Decimal> for DecimalWrapper {
 pub enum Error {
     #[error("Error parsing as decimal: {0}.")]
     DecimalParseError(String),
+    #[error("Error decoding the intent data: {0}")]
+    IntentDataDecodingError(std::io::Error),
+    #[error("The public key of {0} is unknown")]
+    UnknownPublicKey(Address),
+    #[error("Invalid signature of {0}: {1}")]
+    InvalidSignature(Address, VerifySigError),
 }
 
 impl TryFrom<token::Amount> for DecimalWrapper {
@@ -287,6 +310,183 @@ impl BorshDeserialize for DecimalWrapper {
     }
 }
 
+impl Exchange {
+    /// Get the intent gossip topic for this exchange's token pair. See
+    /// [`token_pair_topic`].
+    pub fn topic(&self) -> String {
+        token_pair_topic(&self.token_sell, &self.token_buy)
+    }
+}
+
+impl CreateAuction {
+    /// Get the ID of this auction, which is the hex-encoded SHA-256 hash of
+    /// its Borsh encoding. Matchmakers key the auctions by this ID and bids
+    /// refer to an auction with it.
+    pub fn id(&self) -> String {
+        let bytes = self
+            .try_to_vec()
+            .expect("Encoding an auction shouldn't fail");
+        hex::encode(Sha256::digest(&bytes))
+    }
+
+    /// Check if a bid of the given amount is accepted for this auction, given
+    /// its highest bid so far, if any. A bid must exceed the highest bid by at
+    /// least the auction's minimum bid increment.
+    pub fn accepts_bid(
+        &self,
+        highest_bid: Option<token::Amount>,
+        amount: token::Amount,
+    ) -> bool {
+        match highest_bid {
+            None => true,
+            Some(highest_bid) => {
+                amount > highest_bid
+                    && amount.change() - highest_bid.change()
+                        >= self.min_bid_increment.change()
+            }
+        }
+    }
+
+    /// The exchange that the given creator of this auction commits to, in
+    /// which the auctioned tokens are sold for any amount of the payment token
+    pub fn creator_exchange(&self, addr: Address) -> Exchange {
+        Exchange {
+            addr,
+            token_sell: self.token_sell.clone(),
+            rate_min: DecimalWrapper::default(),
+            max_sell: self.amount,
+            token_buy: self.token_buy.clone(),
+            min_buy: token::Amount::default(),
+            vp: None,
+        }
+    }
+
+    /// The exchange that the given bidder commits to with a bid of the given
+    /// amount, in which the bid is paid for all the auctioned tokens
+    pub fn bidder_exchange(
+        &self,
+        addr: Address,
+        bid: token::Amount,
+    ) -> Exchange {
+        Exchange {
+            addr,
+            token_sell: self.token_buy.clone(),
+            rate_min: DecimalWrapper::default(),
+            max_sell: bid,
+            token_buy: self.token_sell.clone(),
+            min_buy: self.amount,
+            vp: None,
+        }
+    }
+}
+
+/// The data of a gossiped intent, signed by its source
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntentData {
+    /// A fungible token exchange intent
+    FungibleToken(Signed<FungibleTokenIntent>),
+    /// An auction intent
+    Auction(Signed<AuctionIntent>),
+}
+
+impl IntentData {
+    /// Decode the data of a gossiped intent. The bytes must be the canonical
+    /// encoding of the signed intent.
+    pub fn try_from_slice(bytes: &[u8]) -> Result<Self, Error> {
+        Signed::<FungibleTokenIntent>::try_from_canonical_slice(bytes)
+            .map(Self::FungibleToken)
+            .or_else(|_| {
+                Signed::<AuctionIntent>::try_from_canonical_slice(bytes)
+                    .map(Self::Auction)
+            })
+            .map_err(Error::IntentDataDecodingError)
+    }
+
+    /// The addresses of the intent's exchanges or auctions, whose
+    /// signatures are included in the intent.
+    pub fn signers(&self) -> HashSet<&Address> {
+        match self {
+            IntentData::FungibleToken(intent) => intent
+                .data
+                .exchange
+                .iter()
+                .map(|exchange| &exchange.data.addr)
+                .collect(),
+            IntentData::Auction(intent) => intent
+                .data
+                .auctions
+                .iter()
+                .map(|auction| &auction.data.addr)
+                .chain(intent.data.exchange_intents.iter().flat_map(
+                    |(addr, exchange_intent)| {
+                        exchange_intent
+                            .data
+                            .exchange
+                            .iter()
+                            .map(|exchange| &exchange.data.addr)
+                            .chain(std::iter::once(addr))
+                    },
+                ))
+                .collect(),
+        }
+    }
+
+    /// Verify the signatures of the intent's exchanges or auctions against
+    /// the public keys of their addresses, which are obtained with `get_pk`.
+    pub fn verify_signatures<'a>(
+        &self,
+        get_pk: impl Fn(&Address) -> Option<&'a common::PublicKey>,
+    ) -> Result<(), Error> {
+        match self {
+            IntentData::FungibleToken(intent) => {
+                verify_exchange_intent(intent, &get_pk)
+            }
+            IntentData::Auction(intent) => {
+                intent.data.auctions.iter().try_for_each(|auction| {
+                    verify_signed_by(auction, &auction.data.addr, &get_pk)
+                })?;
+                intent.data.exchange_intents.iter().try_for_each(
+                    |(addr, exchange_intent)| {
+                        verify_signed_by(exchange_intent, addr, &get_pk)?;
+                        verify_exchange_intent(exchange_intent, &get_pk)
+                    },
+                )
+            }
+        }
+    }
+}
+
+/// Verify the signatures of the intent's exchanges against the public keys of
+/// their addresses. The intent itself must be signed by the exchanges'
+/// addresses too, as its nonce guards against replays. Otherwise, a signed
+/// exchange could be re-wrapped into another intent with a fresh nonce.
+fn verify_exchange_intent<'a>(
+    intent: &Signed<FungibleTokenIntent>,
+    get_pk: impl Fn(&Address) -> Option<&'a common::PublicKey>,
+) -> Result<(), Error> {
+    intent.data.exchange.iter().try_for_each(|exchange| {
+        verify_signed_by(exchange, &exchange.data.addr, &get_pk)?;
+        verify_signed_by(intent, &exchange.data.addr, &get_pk)
+    })
+}
+
+/// Verify that the data has been signed by the given address, whose public
+/// key is obtained with `get_pk`.
+fn verify_signed_by<'a, T>(
+    signed: &Signed<T>,
+    addr: &Address,
+    get_pk: impl Fn(&Address) -> Option<&'a common::PublicKey>,
+) -> Result<(), Error>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    let pk =
+        get_pk(addr).ok_or_else(|| Error::UnknownPublicKey(addr.clone()))?;
+    signed
+        .verify(pk)
+        .map_err(|err| Error::InvalidSignature(addr.clone(), err))
+}
+
 impl MatchedExchanges {
     /// Create an empty [`MatchedExchanges`].
     pub fn empty() -> Self {
@@ -298,6 +498,24 @@ impl MatchedExchanges {
     }
 }
 
+/// The prefix of the intent gossip topics derived from a token pair
+pub const TOKEN_PAIR_TOPIC_PREFIX: &str = "asset_v0";
+
+/// Derive the intent gossip topic for the exchanges of the given pair of
+/// tokens. The topic is `asset_v0/{token}/{token}` with the two bech32m encoded
+/// token addresses sorted in lexicographical order.
+///
+/// The topic doesn't depend on the order of the tokens, i.e. on the direction
+/// of the exchange, because an intent selling token A for token B can only be
+/// matched with intents selling token B for token A, so they must be gossiped
+/// on the same topic.
+pub fn token_pair_topic(token_a: &Address, token_b: &Address) -> String {
+    let mut tokens = [token_a.encode(), token_b.encode()];
+    tokens.sort();
+    let [first, second] = tokens;
+    format!("{}/{}/{}", TOKEN_PAIR_TOPIC_PREFIX, first, second)
+}
+
 const INVALID_INTENT_STORAGE_KEY: &str = "invalid_intent";
 
 /// Obtain a storage key for user's invalid intent set.
@@ -329,7 +547,7 @@ mod tests {
 
     use super::*;
     use crate::ledger::storage::types::{decode, encode};
-    use crate::types::key;
+    use crate::types::key::{self, RefTo};
 
     #[test]
     fn test_encode_decode_intent_transfer_without_vp() {
@@ -380,6 +598,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_one,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -391,6 +610,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_two,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -488,6 +708,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_one,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -499,6 +720,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_two,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -531,6 +753,137 @@ mod tests {
         assert!(decoded_intent_transfer == it);
     }
 
+    #[test]
+    fn test_token_pair_topic() {
+        let xan = Address::from_str(XAN).unwrap();
+        let btc = Address::from_str(BTC).unwrap();
+        let eth = Address::from_str(ETH).unwrap();
+
+        // The topic doesn't depend on the direction of the exchange
+        let topic = token_pair_topic(&xan, &btc);
+        assert_eq!(topic, token_pair_topic(&btc, &xan));
+        assert_eq!(topic, format!("asset_v0/{}/{}", XAN, BTC));
+
+        // Different token pairs have different topics
+        assert_ne!(topic, token_pair_topic(&xan, &eth));
+        assert_ne!(topic, token_pair_topic(&btc, &eth));
+    }
+
+    /// Test that the exchange intents of an auction intent are verified
+    /// against the public keys of their addresses
+    #[test]
+    fn test_verify_auction_exchange_intents() {
+        let albert = Address::from_str(ALBERT).unwrap();
+        let albert_keypair = key::testing::keypair_1();
+        let bertha_keypair = key::testing::keypair_2();
+        let create_auction = CreateAuction {
+            token_sell: Address::from_str(XAN).unwrap(),
+            token_buy: Address::from_str(BTC).unwrap(),
+            amount: token::Amount::from(100),
+            auction_start: 0,
+            auction_end: 10,
+            min_bid_increment: token::Amount::default(),
+        };
+        let intent = |exchange_keypair: &common::SecretKey,
+                      intent_keypair: &common::SecretKey| {
+            let exchange = Signed::new(
+                exchange_keypair,
+                create_auction.creator_exchange(albert.clone()),
+            );
+            let exchange_intent = Signed::new(
+                intent_keypair,
+                FungibleTokenIntent {
+                    exchange: HashSet::from([exchange]),
+                    nonce: 0,
+                },
+            );
+            let auction = Signed::new(
+                &albert_keypair,
+                Auction {
+                    addr: albert.clone(),
+                    create_auction: Some(create_auction.clone()),
+                    place_bid: None,
+                },
+            );
+            IntentData::Auction(Signed::new(
+                &albert_keypair,
+                AuctionIntent {
+                    auctions: HashSet::from([auction]),
+                    exchange_intents: HashMap::from([(
+                        albert.clone(),
+                        exchange_intent,
+                    )]),
+                },
+            ))
+        };
+        let pk = albert_keypair.ref_to();
+        let get_pk = |addr: &Address| (*addr == albert).then(|| &pk);
+
+        let valid = intent(&albert_keypair, &albert_keypair);
+        assert_eq!(valid.signers(), HashSet::from([&albert]));
+        assert!(valid.verify_signatures(get_pk).is_ok());
+
+        // An exchange intent or an exchange signed by another key is rejected
+        let invalid = intent(&albert_keypair, &bertha_keypair);
+        assert!(matches!(
+            invalid.verify_signatures(get_pk),
+            Err(Error::InvalidSignature(_, _))
+        ));
+        let invalid = intent(&bertha_keypair, &albert_keypair);
+        assert!(matches!(
+            invalid.verify_signatures(get_pk),
+            Err(Error::InvalidSignature(_, _))
+        ));
+    }
+
+    /// Test that a signed exchange that is re-wrapped into an intent signed
+    /// by another key, e.g. to replay it with a fresh nonce, is rejected
+    #[test]
+    fn test_verify_rewrapped_exchange() {
+        let albert = Address::from_str(ALBERT).unwrap();
+        let albert_keypair = key::testing::keypair_1();
+        let bertha = Address::from_str(BERTHA).unwrap();
+        let bertha_keypair = key::testing::keypair_2();
+        let exchange = Signed::new(
+            &albert_keypair,
+            Exchange {
+                addr: albert.clone(),
+                token_sell: Address::from_str(BTC).unwrap(),
+                rate_min: DecimalWrapper::from_str("0.1").unwrap(),
+                max_sell: token::Amount::from(100),
+                token_buy: Address::from_str(XAN).unwrap(),
+                min_buy: token::Amount::from(1),
+                vp: None,
+            },
+        );
+        let intent = |keypair: &common::SecretKey, nonce| {
+            IntentData::FungibleToken(Signed::new(
+                keypair,
+                FungibleTokenIntent {
+                    exchange: HashSet::from([exchange.clone()]),
+                    nonce,
+                },
+            ))
+        };
+        let albert_pk = albert_keypair.ref_to();
+        let bertha_pk = bertha_keypair.ref_to();
+        let get_pk = |addr: &Address| {
+            if *addr == albert {
+                Some(&albert_pk)
+            } else if *addr == bertha {
+                Some(&bertha_pk)
+            } else {
+                None
+            }
+        };
+
+        assert!(intent(&albert_keypair, 1).verify_signatures(get_pk).is_ok());
+        assert!(matches!(
+            intent(&bertha_keypair, 2).verify_signatures(get_pk),
+            Err(Error::InvalidSignature(addr, _)) if addr == albert
+        ));
+    }
+
     #[cfg(test)]
     #[allow(dead_code)]
     mod constants {
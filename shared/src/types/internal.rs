@@ -1,5 +1,7 @@
 //! Shared internal types between the host env and guest (wasm).
 
+use crate::types::address::{Address, InternalAddress};
+
 /// A result of a wasm call to host functions that may fail.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HostEnvResult {
@@ -31,3 +33,69 @@ impl From<bool> for HostEnvResult {
         if success { Self::Success } else { Self::Fail }
     }
 }
+
+/// The kind of an address as classified by the host functions that check if
+/// an address is internal. It's passed to wasm as `i64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InternalAddressKind {
+    /// Not an internal address, e.g. a user account
+    NotInternal = 0,
+    /// [`InternalAddress::PoS`]
+    PoS = 1,
+    /// [`InternalAddress::PosSlashPool`]
+    PosSlashPool = 2,
+    /// [`InternalAddress::Ibc`]
+    Ibc = 3,
+    /// [`InternalAddress::Parameters`]
+    Parameters = 4,
+    /// [`InternalAddress::IbcEscrow`]
+    IbcEscrow = 5,
+    /// [`InternalAddress::IbcBurn`]
+    IbcBurn = 6,
+    /// [`InternalAddress::IbcMint`]
+    IbcMint = 7,
+}
+
+impl InternalAddressKind {
+    /// Classify the given address
+    pub fn of(addr: &Address) -> Self {
+        match addr {
+            Address::Internal(internal) => match internal {
+                InternalAddress::PoS => Self::PoS,
+                InternalAddress::PosSlashPool => Self::PosSlashPool,
+                InternalAddress::Ibc => Self::Ibc,
+                InternalAddress::Parameters => Self::Parameters,
+                InternalAddress::IbcEscrow(_) => Self::IbcEscrow,
+                InternalAddress::IbcBurn => Self::IbcBurn,
+                InternalAddress::IbcMint => Self::IbcMint,
+            },
+            Address::Established(_) | Address::Implicit(_) => Self::NotInternal,
+        }
+    }
+
+    /// Convert the kind to `i64`, which can be passed to wasm
+    pub fn to_i64(self) -> i64 {
+        self as _
+    }
+
+    /// Convert the kind from `i64` passed to wasm, if it's valid
+    pub fn from_i64(int: i64) -> Option<Self> {
+        [
+            Self::NotInternal,
+            Self::PoS,
+            Self::PosSlashPool,
+            Self::Ibc,
+            Self::Parameters,
+            Self::IbcEscrow,
+            Self::IbcBurn,
+            Self::IbcMint,
+        ]
+        .into_iter()
+        .find(|kind| kind.to_i64() == int)
+    }
+
+    /// Check if the kind is of an internal address
+    pub fn is_internal(self) -> bool {
+        self != Self::NotInternal
+    }
+}
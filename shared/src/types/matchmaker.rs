@@ -2,6 +2,20 @@
 
 use std::collections::HashSet;
 
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::types::hash::Hash;
+
+/// The environment variable with the path to the directory in which a
+/// matchmaker can persist its snapshots. This is set by the host when the
+/// matchmaker has a working directory.
+pub const ENV_VAR_SNAPSHOTS_DIR: &str = "ANOMA_MM_SNAPSHOTS_DIR";
+
+/// The environment variable with the path to the directory in which a
+/// matchmaker can write its logs. This is set by the host when the matchmaker
+/// has a working directory.
+pub const ENV_VAR_LOGS_DIR: &str = "ANOMA_MM_LOGS_DIR";
+
 /// A matchmaker marker trait. This should not be implemented manually. Instead,
 /// it is added by the derive `Matchmaker` macro, which also adds necessary
 /// binding code for matchmaker dylib runner.
@@ -11,16 +25,79 @@ pub trait Matchmaker: AddIntent {}
 pub trait AddIntent: Default {
     // TODO: For some reason, using `&[u8]` causes the `decode_intent_data` to
     // fail decoding
-    /// Add a new intent to matchmaker's state
+    /// Add a new intent to matchmaker's state and return the matches that are
+    /// ready to be sent, each as its own result
     #[allow(clippy::ptr_arg)]
     fn add_intent(
         &mut self,
         intent_id: &Vec<u8>,
         intent_data: &Vec<u8>,
-    ) -> AddIntentResult;
+    ) -> Vec<AddIntentResult>;
+
+    /// Add a batch of new intents to matchmaker's state and try to match them
+    /// together. This is called instead of [`AddIntent::add_intent`] when the
+    /// matchmaker runs in the batched mode, in which the intents received
+    /// within a tick are accumulated and added together on the tick. An
+    /// implementation may override this to match all the intents in a single
+    /// solve. By default, the intents are added one by one with
+    /// [`AddIntent::add_intent`].
+    #[allow(clippy::ptr_arg)]
+    fn add_intents(
+        &mut self,
+        intents: &Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Vec<AddIntentResult> {
+        intents
+            .iter()
+            .flat_map(|(intent_id, intent_data)| {
+                self.add_intent(intent_id, intent_data)
+            })
+            .collect()
+    }
+
+    /// Decide whether a new intent should be accepted by the matchmaker. This
+    /// is called before [`AddIntent::add_intent`] and a rejected intent is not
+    /// added to the matchmaker's state. An implementation may decode the
+    /// intent data to apply custom predicates. By default, all intents are
+    /// accepted.
+    #[allow(clippy::ptr_arg)]
+    fn filter_intent(
+        &self,
+        _intent_id: &Vec<u8>,
+        _intent_data: &Vec<u8>,
+    ) -> bool {
+        true
+    }
+
+    /// Called before new intents are added with whether the host can receive
+    /// a new match without waiting. When it can't, the host's result handler
+    /// is busy and an implementation may hold back its matches, e.g. by
+    /// keeping them resolved and returning them from a later call, instead of
+    /// having them wait in the host or dropped. By default, this does
+    /// nothing.
+    fn set_can_send_match(&mut self, _can_send_match: bool) {}
+
+    /// Called before new intents are added with the height of the last
+    /// committed block, once the host has queried it from the ledger. An
+    /// implementation may use it to act on deadlines, e.g. to resolve the
+    /// auctions that are over. By default, this does nothing.
+    fn set_block_height(&mut self, _height: u64) {}
+
+    /// Remove a cancelled intent from the matchmaker's state. This is only
+    /// called for intents whose cancellation has been verified against the
+    /// intent's signer. By default, this does nothing.
+    #[allow(clippy::ptr_arg)]
+    fn remove_intent(&mut self, _intent_id: &Vec<u8>) {}
+
+    /// Called once before the matchmaker is dropped when it's being shut
+    /// down. An implementation should persist any state that it needs on
+    /// restart and return the matches that have been resolved, but not yet
+    /// returned from [`AddIntent::add_intent`], each as its own result.
+    fn shutdown(&mut self) -> Vec<AddIntentResult> {
+        vec![]
+    }
 }
 
-/// The result of calling matchmaker's `add_intent` function
+/// A match returned from matchmaker's `add_intent` function
 #[derive(Clone, Debug, Default)]
 pub struct AddIntentResult {
     /// A transaction matched from the intent, if any
@@ -28,3 +105,14 @@ pub struct AddIntentResult {
     /// The intent IDs that were matched into the tx, if any
     pub matched_intents: Option<HashSet<Vec<u8>>>,
 }
+
+/// A notice that an intent has been matched into a transaction. The matchmaker
+/// signs it and sends it via the intent gossiper to the clients subscribed to
+/// the intent's signers.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct MatchNotice {
+    /// The ID of the matched intent
+    pub intent_id: Vec<u8>,
+    /// The hash of the transaction crafted from the matched intents
+    pub tx_hash: Hash,
+}
@@ -26,6 +26,8 @@ pub enum Error {
     ParseAddressFromKey,
     #[error("Reserved prefix or string is specified: {0}")]
     InvalidKeySeg(String),
+    #[error("A storage key segment cannot be empty")]
+    EmptyKeySeg,
 }
 
 /// Result for functions that may fail
@@ -158,7 +160,18 @@ impl core::fmt::Debug for BlockHash {
 }
 
 /// A storage key is made of storage key segments [`DbKeySeg`], separated by
-/// [`KEY_SEGMENT_SEPARATOR`].
+/// [`KEY_SEGMENT_SEPARATOR`]. A key parsed from a string must follow the
+/// grammar:
+///
+/// ```text
+/// key         = segment *( "/" segment )
+/// segment     = address-seg / string-seg
+/// address-seg = "#" encoded-address
+/// string-seg  = 1*( any character except "/" ), not starting with "#"
+/// ```
+///
+/// The segments are validated by [`DbKeySeg::parse`], which is used both to
+/// parse a key and to push segments into it.
 #[derive(
     Clone,
     BorshSerialize,
@@ -330,6 +343,14 @@ impl Display for Key {
     }
 }
 
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Key::parse(s)
+    }
+}
+
 // TODO use std::convert::{TryFrom, Into}?
 /// Represents a segment in a path that may be used as a database key
 pub trait KeySeg {
@@ -370,6 +391,9 @@ pub enum DbKeySeg {
 
 impl KeySeg for DbKeySeg {
     fn parse(mut string: String) -> Result<Self> {
+        if string.is_empty() {
+            return Err(Error::EmptyKeySeg);
+        }
         // a separator should not included
         if string.contains(KEY_SEGMENT_SEPARATOR) {
             return Err(Error::InvalidKeySeg(string));
@@ -690,6 +714,24 @@ mod tests {
         assert_eq!(key.to_string(), target);
     }
 
+    #[test]
+    fn test_key_parse_invalid() {
+        for target in ["", "test//test", "test/", "/test"] {
+            let error = Key::parse(target).expect_err("parsing should fail");
+            assert!(matches!(error, Error::EmptyKeySeg), "{}", target);
+        }
+
+        let target = "#invalid-address/test";
+        let error = Key::parse(target).expect_err("parsing should fail");
+        assert!(matches!(error, Error::ParseAddress(_)));
+
+        let addr = address::testing::established_address_1();
+        let error = Key::from(addr.to_db_key())
+            .push(&"".to_owned())
+            .expect_err("pushing should fail");
+        assert!(matches!(error, Error::EmptyKeySeg));
+    }
+
     #[test]
     fn test_key_push_valid() {
         let addr = address::testing::established_address_1();
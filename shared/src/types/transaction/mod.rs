@@ -15,6 +15,8 @@ pub mod wrapper;
 
 use std::collections::{BTreeSet, HashSet};
 use std::fmt;
+use std::ops::AddAssign;
+use std::time::Duration;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 pub use decrypted::*;
@@ -52,15 +54,32 @@ pub struct TxResult {
     pub initialized_accounts: Vec<Address>,
     /// Optional IBC event emitted by the transaction
     pub ibc_event: Option<IbcEvent>,
+    /// The time spent on compiling and executing the wasm code of the
+    /// transaction and its VPs, only included for debugging
+    pub wasm_timings: Option<WasmTimings>,
+    /// The error code and message, if the transaction has aborted itself
+    pub abort: Option<TxAbort>,
 }
 
 impl TxResult {
-    /// Check if the tx has been accepted by all the VPs
+    /// Check if the tx hasn't aborted and has been accepted by all the VPs
     pub fn is_accepted(&self) -> bool {
-        self.vps_result.rejected_vps.is_empty()
+        self.abort.is_none() && self.vps_result.rejected_vps.is_empty()
     }
 }
 
+/// A custom error code and message with which a transaction's wasm code has
+/// aborted its execution
+#[derive(
+    Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct TxAbort {
+    /// The error code
+    pub code: u64,
+    /// The error message
+    pub message: String,
+}
+
 /// Result of checking a transaction with validity predicates
 // TODO derive BorshSchema after <https://github.com/near/borsh-rs/issues/82>
 #[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize)]
@@ -73,32 +92,100 @@ pub struct VpsResult {
     pub gas_used: VpsGas,
     /// Errors occurred in any of the VPs, if any
     pub errors: Vec<(Address, String)>,
+    /// The total time spent on compiling and executing the wasm VPs
+    pub wasm_timings: WasmTimings,
+}
+
+/// The time spent on compiling and executing wasm code
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize,
+)]
+pub struct WasmTimings {
+    /// The time spent on fetching the compiled modules from the cache or
+    /// compiling them, in microseconds
+    pub compile_micros: u64,
+    /// The time spent on instantiating and executing the modules, in
+    /// microseconds
+    pub execute_micros: u64,
+}
+
+impl WasmTimings {
+    /// Add the time spent on fetching or compiling a module
+    pub fn add_compile(&mut self, time: Duration) {
+        self.compile_micros = self
+            .compile_micros
+            .saturating_add(time.as_micros().try_into().unwrap_or(u64::MAX));
+    }
+
+    /// Add the time spent on instantiating and executing a module
+    pub fn add_execute(&mut self, time: Duration) {
+        self.execute_micros = self
+            .execute_micros
+            .saturating_add(time.as_micros().try_into().unwrap_or(u64::MAX));
+    }
+}
+
+impl AddAssign for WasmTimings {
+    fn add_assign(&mut self, rhs: Self) {
+        self.compile_micros =
+            self.compile_micros.saturating_add(rhs.compile_micros);
+        self.execute_micros =
+            self.execute_micros.saturating_add(rhs.execute_micros);
+    }
 }
 
 impl fmt::Display for TxResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Transaction is {}. Gas used: {};{} VPs result: {}",
+            "Transaction is {}. Gas used: {};{}{} VPs result: {}{}",
             if self.is_accepted() {
                 "valid"
             } else {
                 "invalid"
             },
             self.gas_used,
+            match &self.abort {
+                Some(TxAbort { code, message }) =>
+                    format!(" Aborted with code {}: {};", code, message),
+                None => "".into(),
+            },
             iterable_to_string("Changed keys", self.changed_keys.iter()),
             self.vps_result,
+            match &self.wasm_timings {
+                Some(timings) => format!(
+                    " Wasm compilation: {}us, execution: {}us;",
+                    timings.compile_micros, timings.execute_micros
+                ),
+                None => "".into(),
+            },
         )
     }
 }
 
+impl VpsResult {
+    /// Get the addresses whose VPs rejected the transaction, sorted
+    pub fn sorted_rejected_vps(&self) -> Vec<&Address> {
+        let mut rejected_vps: Vec<&Address> =
+            self.rejected_vps.iter().collect();
+        rejected_vps.sort();
+        rejected_vps
+    }
+}
+
 impl fmt::Display for VpsResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut accepted_vps: Vec<&Address> =
+            self.accepted_vps.iter().collect();
+        accepted_vps.sort();
         write!(
             f,
             "{}{}{}",
-            iterable_to_string("Accepted", self.accepted_vps.iter()),
-            iterable_to_string("Rejected", self.rejected_vps.iter()),
+            iterable_to_string("Accepted", accepted_vps.into_iter()),
+            iterable_to_string(
+                "Rejected",
+                self.sorted_rejected_vps().into_iter()
+            ),
             iterable_to_string(
                 "Errors",
                 self.errors
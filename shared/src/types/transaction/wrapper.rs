@@ -190,11 +190,32 @@ pub mod wrapper_tx {
             gas_limit: GasLimit,
             tx: Tx,
             encryption_key: EncryptionKey,
+        ) -> WrapperTx {
+            Self::new_with_pk(
+                fee,
+                keypair.ref_to(),
+                epoch,
+                gas_limit,
+                tx,
+                encryption_key,
+            )
+        }
+
+        /// Create a new wrapper tx like [`WrapperTx::new`], but for the given
+        /// public key whose keypair need not be available locally, e.g. to
+        /// sign the wrapper with [`WrapperTx::try_sign_with`]
+        pub fn new_with_pk(
+            fee: Fee,
+            pk: common::PublicKey,
+            epoch: Epoch,
+            gas_limit: GasLimit,
+            tx: Tx,
+            encryption_key: EncryptionKey,
         ) -> WrapperTx {
             let inner_tx = EncryptedTx::encrypt(&tx.to_bytes(), encryption_key);
             Self {
                 fee,
-                pk: keypair.ref_to(),
+                pk,
                 epoch,
                 gas_limit,
                 inner_tx,
@@ -244,7 +265,21 @@ pub mod wrapper_tx {
             if self.pk != keypair.ref_to() {
                 return Err(WrapperTxErr::InvalidKeyPair);
             }
-            Ok(Tx::new(
+            Ok(self.to_tx().sign(keypair))
+        }
+
+        /// Sign the wrapper transaction with a signature made by the given
+        /// function and convert to a normal Tx type. The signature must be
+        /// made with the keypair of the wrapper's public key.
+        pub fn try_sign_with<E>(
+            &self,
+            sign: impl FnOnce(&[u8]) -> Result<common::Signature, E>,
+        ) -> Result<Tx, E> {
+            self.to_tx().try_sign_with(sign)
+        }
+
+        fn to_tx(&self) -> Tx {
+            Tx::new(
                 vec![],
                 Some(
                     TxType::Wrapper(self.clone())
@@ -252,7 +287,6 @@ pub mod wrapper_tx {
                         .expect("Could not serialize WrapperTx"),
                 ),
             )
-            .sign(keypair))
         }
 
         /// Validate the signature of a wrapper tx
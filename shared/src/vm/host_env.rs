@@ -12,26 +12,25 @@ use super::wasm::TxCache;
 #[cfg(feature = "wasm-runtime")]
 use super::wasm::VpCache;
 use super::WasmCacheAccess;
-use crate::ledger::gas::{self, BlockGasMeter, VpGasMeter};
+use crate::ledger::gas::{self, BlockGasMeter, VpGasMeter, MIN_STORAGE_GAS};
 use crate::ledger::storage::write_log::{self, WriteLog};
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::ledger::vp_env;
 use crate::proto::Tx;
 use crate::types::address::{self, Address};
+use crate::types::hash::Hash;
 use crate::types::ibc::IbcEvent;
-use crate::types::internal::HostEnvResult;
+use crate::types::internal::{HostEnvResult, InternalAddressKind};
 use crate::types::key::*;
 use crate::types::storage::Key;
-use crate::vm::memory::VmMemory;
+use crate::vm::log_value::LogValue;
+use crate::vm::memory::{self, VmMemory};
 use crate::vm::prefix_iter::{PrefixIteratorId, PrefixIterators};
 use crate::vm::types::KeyVal;
 use crate::vm::{
     validate_untrusted_wasm, HostRef, MutHostRef, WasmValidationError,
 };
 
-const VERIFY_TX_SIG_GAS_COST: u64 = 1000;
-const WASM_VALIDATION_GAS_PER_BYTE: u64 = 1;
-
 /// These runtime errors will abort tx WASM execution immediately
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -49,6 +48,13 @@ pub enum TxRuntimeError {
          WASM {0}"
     )]
     InitAccountInvalidVpWasm(WasmValidationError),
+    #[error("Trying to initialize an account with an unknown parent {0}")]
+    InitAccountUnknownParent(Address),
+    #[error(
+        "Trying to initialize an account with a parent that is not an \
+         established address {0}"
+    )]
+    InitAccountInvalidParent(Address),
     #[error("Storage modification error: {0}")]
     StorageModificationError(write_log::Error),
     #[error("Storage error: {0}")]
@@ -63,6 +69,8 @@ pub enum TxRuntimeError {
     NumConversionError(TryFromIntError),
     #[error("Memory error: {0}")]
     MemoryError(Box<dyn std::error::Error + Sync + Send + 'static>),
+    #[error("Transaction aborted with code {code}: {message}")]
+    Aborted { code: u64, message: String },
 }
 
 type TxResult<T> = std::result::Result<T, TxRuntimeError>;
@@ -100,6 +108,8 @@ where
     pub verifiers: MutHostRef<'a, &'a BTreeSet<Address>>,
     /// Cache for 2-step reads from host environment.
     pub result_buffer: MutHostRef<'a, &'a Option<Vec<u8>>>,
+    /// The SHA-256 hash of the code of the transaction being executed.
+    pub tx_code_hash: HostRef<'a, &'a Hash>,
     /// VP WASM compilation cache (this is available in tx context, because
     /// we're pre-compiling VPs from [`tx_init_account`])
     #[cfg(feature = "wasm-runtime")]
@@ -135,6 +145,7 @@ where
         gas_meter: &mut BlockGasMeter,
         verifiers: &mut BTreeSet<Address>,
         result_buffer: &mut Option<Vec<u8>>,
+        tx_code_hash: &Hash,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
         #[cfg(feature = "wasm-runtime")] tx_wasm_cache: &mut TxCache<CA>,
     ) -> Self {
@@ -144,6 +155,7 @@ where
         let gas_meter = unsafe { MutHostRef::new(gas_meter) };
         let verifiers = unsafe { MutHostRef::new(verifiers) };
         let result_buffer = unsafe { MutHostRef::new(result_buffer) };
+        let tx_code_hash = unsafe { HostRef::new(tx_code_hash) };
         #[cfg(feature = "wasm-runtime")]
         let vp_wasm_cache = unsafe { MutHostRef::new(vp_wasm_cache) };
         #[cfg(feature = "wasm-runtime")]
@@ -155,6 +167,7 @@ where
             gas_meter,
             verifiers,
             result_buffer,
+            tx_code_hash,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache,
             #[cfg(feature = "wasm-runtime")]
@@ -196,6 +209,7 @@ where
             gas_meter: self.gas_meter.clone(),
             verifiers: self.verifiers.clone(),
             result_buffer: self.result_buffer.clone(),
+            tx_code_hash: self.tx_code_hash.clone(),
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache: self.vp_wasm_cache.clone(),
             #[cfg(feature = "wasm-runtime")]
@@ -475,6 +489,38 @@ where
     result
 }
 
+/// Add a gas cost of a storage read, given the number of the accessed bytes,
+/// as charged by the chain's gas schedule
+fn tx_add_storage_read_gas<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    bytes_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let storage = unsafe { env.ctx.storage.get() };
+    tx_add_gas(env, storage.gas_schedule.storage_read(bytes_len))
+}
+
+/// Add a gas cost of a storage write or delete, given the number of the
+/// accessed bytes, as charged by the chain's gas schedule
+fn tx_add_storage_write_gas<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    bytes_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let storage = unsafe { env.ctx.storage.get() };
+    tx_add_gas(env, storage.gas_schedule.storage_write(bytes_len))
+}
+
 /// Called from VP wasm to request to use the given gas amount
 pub fn vp_charge_gas<MEM, DB, H, EVAL, CA>(
     env: &VpEnv<MEM, DB, H, EVAL, CA>,
@@ -522,7 +568,7 @@ where
     // try to read from the write log first
     let write_log = unsafe { env.ctx.write_log.get() };
     let (log_val, gas) = write_log.read(&key);
-    tx_add_gas(env, gas)?;
+    tx_add_storage_read_gas(env, gas)?;
     Ok(match log_val {
         Some(&write_log::StorageModification::Write { .. }) => {
             HostEnvResult::Success.to_i64()
@@ -543,7 +589,7 @@ where
             let (present, gas) = storage
                 .has_key(&key)
                 .map_err(TxRuntimeError::StorageError)?;
-            tx_add_gas(env, gas)?;
+            tx_add_storage_read_gas(env, gas)?;
             HostEnvResult::from(present).to_i64()
         }
     })
@@ -578,7 +624,7 @@ where
     // try to read from the write log first
     let write_log = unsafe { env.ctx.write_log.get() };
     let (log_val, gas) = write_log.read(&key);
-    tx_add_gas(env, gas)?;
+    tx_add_storage_read_gas(env, gas)?;
     Ok(match log_val {
         Some(&write_log::StorageModification::Write { ref value }) => {
             let len: i64 = value
@@ -619,7 +665,7 @@ where
             let storage = unsafe { env.ctx.storage.get() };
             let (value, gas) =
                 storage.read(&key).map_err(TxRuntimeError::StorageError)?;
-            tx_add_gas(env, gas)?;
+            tx_add_storage_read_gas(env, gas)?;
             match value {
                 Some(value) => {
                     let len: i64 = value
@@ -804,7 +850,7 @@ where
         .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
     tx_add_gas(env, gas)?;
 
-    tracing::debug!("tx_update {}, {:?}", key, value);
+    tracing::debug!("tx_update {}, {:?}", key, LogValue::new(&value));
 
     let key = Key::parse(key).map_err(TxRuntimeError::StorageDataError)?;
 
@@ -814,10 +860,96 @@ where
     let (gas, _size_diff) = write_log
         .write(&key, value)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_add_gas(env, gas)
+    tx_add_storage_write_gas(env, gas)
     // TODO: charge the size diff
 }
 
+/// Storage compare-and-set function exposed to the wasm VM Tx environment. The
+/// current value of the given key is read from the write log first and if no
+/// entry found then from the storage. The new value is written to the write
+/// log only if the current value is equal to the expected value.
+///
+/// Returns [`HostEnvResult::Success`] when the value has been written or
+/// [`HostEnvResult::Fail`] when the current value doesn't match the expected
+/// value, including when the key is not present.
+pub fn tx_write_cas<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    key_ptr: u64,
+    key_len: u64,
+    expected_ptr: u64,
+    expected_len: u64,
+    val_ptr: u64,
+    val_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (key, gas) = env
+        .memory
+        .read_string(key_ptr, key_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+    let (expected, gas) = env
+        .memory
+        .read_bytes(expected_ptr, expected_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+    let (value, gas) = env
+        .memory
+        .read_bytes(val_ptr, val_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+
+    tracing::debug!(
+        "tx_write_cas {}, expected {:?}, value {:?}",
+        key,
+        LogValue::new(&expected),
+        LogValue::new(&value)
+    );
+
+    let key = Key::parse(key).map_err(TxRuntimeError::StorageDataError)?;
+
+    check_address_existence(env, &key)?;
+
+    // try to read the current value from the write log first
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let (log_val, gas) = write_log.read(&key);
+    tx_add_storage_read_gas(env, gas)?;
+    let current = match log_val {
+        Some(write_log::StorageModification::Write { value }) => {
+            Some(value.clone())
+        }
+        Some(write_log::StorageModification::Delete) => None,
+        Some(write_log::StorageModification::InitAccount { vp }) => {
+            Some(vp.clone())
+        }
+        Some(write_log::StorageModification::Temp { value }) => {
+            Some(value.clone())
+        }
+        None => {
+            // when not found in write log, try to read from the storage
+            let storage = unsafe { env.ctx.storage.get() };
+            let (value, gas) =
+                storage.read(&key).map_err(TxRuntimeError::StorageError)?;
+            tx_add_storage_read_gas(env, gas)?;
+            value
+        }
+    };
+    if current.as_ref() != Some(&expected) {
+        return Ok(HostEnvResult::Fail.to_i64());
+    }
+
+    let (gas, _size_diff) = write_log
+        .write(&key, value)
+        .map_err(TxRuntimeError::StorageModificationError)?;
+    tx_add_storage_write_gas(env, gas)?;
+    // TODO: charge the size diff
+    Ok(HostEnvResult::Success.to_i64())
+}
+
 /// Temporary storage write function exposed to the wasm VM Tx environment. The
 /// given key/value will be written only to the write log. It will be never
 /// written to the storage.
@@ -845,7 +977,7 @@ where
         .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
     tx_add_gas(env, gas)?;
 
-    tracing::debug!("tx_write_temp {}, {:?}", key, value);
+    tracing::debug!("tx_write_temp {}, {:?}", key, LogValue::new(&value));
 
     let key = Key::parse(key).map_err(TxRuntimeError::StorageDataError)?;
 
@@ -855,7 +987,7 @@ where
     let (gas, _size_diff) = write_log
         .write_temp(&key, value)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_add_gas(env, gas)
+    tx_add_storage_write_gas(env, gas)
     // TODO: charge the size diff
 }
 
@@ -878,14 +1010,14 @@ where
         }
         let vp_key = Key::validity_predicate(&addr);
         let (vp, gas) = write_log.read(&vp_key);
-        tx_add_gas(env, gas)?;
+        tx_add_storage_read_gas(env, gas)?;
         // just check the existence because the write log should not have the
         // delete log of the VP
         if vp.is_none() {
             let (is_present, gas) = storage
                 .has_key(&vp_key)
                 .map_err(TxRuntimeError::StorageError)?;
-            tx_add_gas(env, gas)?;
+            tx_add_storage_read_gas(env, gas)?;
             if !is_present {
                 tracing::info!(
                     "Trying to write into storage with a key containing an \
@@ -931,7 +1063,7 @@ where
     let (gas, _size_diff) = write_log
         .delete(&key)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_add_gas(env, gas)
+    tx_add_storage_write_gas(env, gas)
     // TODO: charge the size diff
 }
 
@@ -993,7 +1125,7 @@ where
         "vp_read_pre addr {}, key {}, value {:?}",
         unsafe { env.ctx.address.get() },
         key,
-        value,
+        LogValue::new_opt(value.as_ref()),
     );
     Ok(match value {
         Some(value) => {
@@ -1101,6 +1233,225 @@ where
     })
 }
 
+/// Storage batch read prior state (before tx execution) function exposed to
+/// the wasm VM VP environment. It takes Borsh encoded `Vec<String>` of storage
+/// keys and reads each of them from the storage the same way as
+/// [`vp_read_pre`]. The gas charged is equal to the sum of the individual
+/// reads.
+///
+/// Returns the length of the Borsh encoded `Vec<Option<Vec<u8>>>` of the read
+/// values, which will be placed in the result buffer.
+pub fn vp_read_batch_pre<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    keys_ptr: u64,
+    keys_len: u64,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let keys = read_batch_keys(env, keys_ptr, keys_len)?;
+    tracing::debug!("vp_read_batch_pre keys {:?}", keys);
+
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    let values = keys
+        .iter()
+        .map(|key| vp_env::read_pre(gas_meter, storage, key))
+        .collect::<vp_env::Result<Vec<_>>>()?;
+    batch_values_to_result_buffer(env, values)
+}
+
+/// Storage batch read posterior state (after tx execution) function exposed
+/// to the wasm VM VP environment. It takes Borsh encoded `Vec<String>` of
+/// storage keys and reads each of them from the write log first and if no
+/// entry found then from the storage, the same way as [`vp_read_post`]. The gas
+/// charged is equal to the sum of the individual reads.
+///
+/// Returns the length of the Borsh encoded `Vec<Option<Vec<u8>>>` of the read
+/// values, which will be placed in the result buffer.
+pub fn vp_read_batch_post<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    keys_ptr: u64,
+    keys_len: u64,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let keys = read_batch_keys(env, keys_ptr, keys_len)?;
+    tracing::debug!("vp_read_batch_post keys {:?}", keys);
+
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let values = keys
+        .iter()
+        .map(|key| vp_env::read_post(gas_meter, storage, write_log, key))
+        .collect::<vp_env::Result<Vec<_>>>()?;
+    batch_values_to_result_buffer(env, values)
+}
+
+/// Read and parse the Borsh encoded storage keys of a batch read. The gas is
+/// charged for each key as if each of them was read from the memory
+/// separately.
+fn read_batch_keys<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    keys_ptr: u64,
+    keys_len: u64,
+) -> vp_env::Result<Vec<Key>>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (keys, _gas) = env
+        .memory
+        .read_bytes(keys_ptr, keys_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    let keys: Vec<String> = BorshDeserialize::try_from_slice(&keys)
+        .map_err(vp_env::RuntimeError::EncodingError)?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    keys.into_iter()
+        .map(|key| {
+            vp_env::add_gas(gas_meter, memory::access_gas(key.len()))?;
+            Key::parse(key).map_err(vp_env::RuntimeError::StorageDataError)
+        })
+        .collect()
+}
+
+/// Place the Borsh encoded values of a batch read in the result buffer and
+/// return their length.
+fn batch_values_to_result_buffer<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    values: Vec<Option<Vec<u8>>>,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let values = values
+        .try_to_vec()
+        .map_err(vp_env::RuntimeError::EncodingError)?;
+    let len: i64 = values
+        .len()
+        .try_into()
+        .map_err(vp_env::RuntimeError::NumConversionError)?;
+    let result_buffer = unsafe { env.ctx.result_buffer.get() };
+    result_buffer.replace(values);
+    Ok(len)
+}
+
+/// Read the validity predicate code of the given address in prior state
+/// (before tx execution) function exposed to the wasm VM VP environment. The
+/// gas is charged by the length of the code.
+///
+/// Returns `-1` when the address has no validity predicate, or the length of
+/// the code when it's present.
+pub fn vp_read_vp_pre<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    addr_ptr: u64,
+    addr_len: u64,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (addr, gas) = env
+        .memory
+        .read_string(addr_ptr, addr_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    vp_env::add_gas(gas_meter, gas)?;
+
+    let addr =
+        Address::decode(addr).map_err(vp_env::RuntimeError::AddressError)?;
+    tracing::debug!("vp_read_vp_pre for addr {}", addr);
+
+    let key = Key::validity_predicate(&addr);
+    let storage = unsafe { env.ctx.storage.get() };
+    let code = vp_env::read_pre(gas_meter, storage, &key)?;
+    vp_code_to_result_buffer(env, code)
+}
+
+/// Read the validity predicate code of the given address in posterior state
+/// (after tx execution) function exposed to the wasm VM VP environment. It
+/// will try to read from the write log first and if no entry found then from
+/// the storage. The gas is charged by the length of the code.
+///
+/// Returns `-1` when the address has no validity predicate, or the length of
+/// the code when it's present.
+pub fn vp_read_vp_post<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    addr_ptr: u64,
+    addr_len: u64,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (addr, gas) = env
+        .memory
+        .read_string(addr_ptr, addr_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    vp_env::add_gas(gas_meter, gas)?;
+
+    let addr =
+        Address::decode(addr).map_err(vp_env::RuntimeError::AddressError)?;
+    tracing::debug!("vp_read_vp_post for addr {}", addr);
+
+    let key = Key::validity_predicate(&addr);
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let code = vp_env::read_post(gas_meter, storage, write_log, &key)?;
+    vp_code_to_result_buffer(env, code)
+}
+
+/// Place the read validity predicate code in the result buffer. The gas for
+/// the length of the code has already been charged by the storage read.
+fn vp_code_to_result_buffer<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    code: Option<Vec<u8>>,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    Ok(match code {
+        Some(code) => {
+            let len: i64 = code
+                .len()
+                .try_into()
+                .map_err(vp_env::RuntimeError::NumConversionError)?;
+            let result_buffer = unsafe { env.ctx.result_buffer.get() };
+            result_buffer.replace(code);
+            len
+        }
+        None => HostEnvResult::Fail.to_i64(),
+    })
+}
+
 /// This function is a helper to handle the first step of reading var-len
 /// values from the host.
 ///
@@ -1224,6 +1575,41 @@ where
     Ok(iterators.insert(iter).id())
 }
 
+/// Storage prefix count function exposed to the wasm VM VP environment. It
+/// returns the number of keys with the given prefix in posterior state (after
+/// tx execution), as counted by [`vp_env::prefix_count_post`].
+pub fn vp_prefix_count<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    prefix_ptr: u64,
+    prefix_len: u64,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (prefix, gas) = env
+        .memory
+        .read_string(prefix_ptr, prefix_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    vp_env::add_gas(gas_meter, gas)?;
+
+    let prefix =
+        Key::parse(prefix).map_err(vp_env::RuntimeError::StorageDataError)?;
+    tracing::debug!("vp_prefix_count {}", prefix);
+
+    let storage = unsafe { env.ctx.storage.get() };
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let count =
+        vp_env::prefix_count_post(gas_meter, storage, write_log, &prefix)?;
+    count
+        .try_into()
+        .map_err(vp_env::RuntimeError::NumConversionError)
+}
+
 /// Storage prefix iterator for prior state (before tx execution) function
 /// exposed to the wasm VM VP environment. It will try to read from the storage.
 ///
@@ -1329,7 +1715,7 @@ where
 
     let verifiers = unsafe { env.ctx.verifiers.get() };
     verifiers.insert(addr);
-    tx_add_gas(env, addr_len)
+    Ok(())
 }
 
 /// Update a validity predicate function exposed to the wasm VM Tx environment
@@ -1362,14 +1748,15 @@ where
         .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
     tx_add_gas(env, gas)?;
 
-    tx_add_gas(env, code.len() as u64 * WASM_VALIDATION_GAS_PER_BYTE)?;
+    let storage = unsafe { env.ctx.storage.get() };
+    tx_add_gas(env, storage.gas_schedule.wasm_validation(code.len()))?;
     validate_untrusted_wasm(&code).map_err(TxRuntimeError::UpdateVpInvalid)?;
 
     let write_log = unsafe { env.ctx.write_log.get() };
     let (gas, _size_diff) = write_log
         .write(&key, code)
         .map_err(TxRuntimeError::StorageModificationError)?;
-    tx_add_gas(env, gas)
+    tx_add_storage_write_gas(env, gas)
     // TODO: charge the size diff
 }
 
@@ -1392,7 +1779,8 @@ where
         .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
     tx_add_gas(env, gas)?;
 
-    tx_add_gas(env, code.len() as u64 * WASM_VALIDATION_GAS_PER_BYTE)?;
+    let storage = unsafe { env.ctx.storage.get() };
+    tx_add_gas(env, storage.gas_schedule.wasm_validation(code.len()))?;
     validate_untrusted_wasm(&code)
         .map_err(TxRuntimeError::InitAccountInvalidVpWasm)?;
     #[cfg(feature = "wasm-runtime")]
@@ -1403,12 +1791,90 @@ where
 
     tracing::debug!("tx_init_account");
 
-    let storage = unsafe { env.ctx.storage.get() };
     let write_log = unsafe { env.ctx.write_log.get() };
     let (addr, gas) = write_log.init_account(&storage.address_gen, code);
     let addr_bytes =
         addr.try_to_vec().map_err(TxRuntimeError::EncodingError)?;
+    tx_add_storage_write_gas(env, gas)?;
+    tx_add_gas(env, storage.gas_schedule.init_account)?;
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, addr_bytes)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)
+}
+
+/// Initialize a new account with an established address derived from the
+/// given parent address, a nonce and the hash of the validity predicate code.
+/// The parent must exist and it's inserted into the verifiers set.
+pub fn tx_init_account_derived<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    parent_ptr: u64,
+    parent_len: u64,
+    code_ptr: u64,
+    code_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (parent, gas) = env
+        .memory
+        .read_string(parent_ptr, parent_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+    let parent =
+        Address::decode(parent).map_err(TxRuntimeError::AddressError)?;
+
+    let (code, gas) = env
+        .memory
+        .read_bytes(code_ptr, code_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
     tx_add_gas(env, gas)?;
+
+    let storage = unsafe { env.ctx.storage.get() };
+    tx_add_gas(env, storage.gas_schedule.wasm_validation(code.len()))?;
+    validate_untrusted_wasm(&code)
+        .map_err(TxRuntimeError::InitAccountInvalidVpWasm)?;
+
+    tracing::debug!("tx_init_account_derived with parent {}", parent);
+
+    // only established accounts can be parents of derived accounts
+    if !matches!(parent, Address::Established(_)) {
+        return Err(TxRuntimeError::InitAccountInvalidParent(parent));
+    }
+
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let parent_vp_key = Key::validity_predicate(&parent);
+    let (parent_vp, gas) = write_log.read(&parent_vp_key);
+    tx_add_storage_read_gas(env, gas)?;
+    if parent_vp.is_none() {
+        let (is_present, gas) = storage
+            .has_key(&parent_vp_key)
+            .map_err(TxRuntimeError::StorageError)?;
+        tx_add_storage_read_gas(env, gas)?;
+        if !is_present {
+            return Err(TxRuntimeError::InitAccountUnknownParent(parent));
+        }
+    }
+    let verifiers = unsafe { env.ctx.verifiers.get() };
+    verifiers.insert(parent.clone());
+
+    #[cfg(feature = "wasm-runtime")]
+    {
+        let vp_wasm_cache = unsafe { env.ctx.vp_wasm_cache.get() };
+        vp_wasm_cache.pre_compile(&code);
+    }
+
+    let (addr, gas) =
+        write_log.init_account_derived(&storage.address_gen, &parent, code);
+    let addr_bytes =
+        addr.try_to_vec().map_err(TxRuntimeError::EncodingError)?;
+    tx_add_storage_write_gas(env, gas)?;
+    tx_add_gas(env, storage.gas_schedule.init_account)?;
     let gas = env
         .memory
         .write_bytes(result_ptr, addr_bytes)
@@ -1477,6 +1943,28 @@ where
     tx_add_gas(env, gas)
 }
 
+/// Getting the transaction code hash function exposed to the wasm VM Tx
+/// environment. The hash is the SHA-256 of the code of the transaction being
+/// executed.
+pub fn tx_get_tx_code_hash<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let hash = unsafe { env.ctx.tx_code_hash.get() };
+    tx_add_gas(env, MIN_STORAGE_GAS)?;
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, hash.0)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)
+}
+
 /// Getting the block epoch function exposed to the wasm VM Tx
 /// environment. The epoch is that of the block to which the current
 /// transaction is being applied.
@@ -1495,6 +1983,31 @@ where
     Ok(epoch.0)
 }
 
+/// Check if the given address is internal function exposed to the wasm VM Tx
+/// environment. Returns the [`InternalAddressKind`] of the address as `i64`.
+pub fn tx_is_internal_address<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    addr_ptr: u64,
+    addr_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (addr, gas) = env
+        .memory
+        .read_string(addr_ptr, addr_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+
+    let addr = Address::decode(&addr).map_err(TxRuntimeError::AddressError)?;
+    tracing::debug!("tx_is_internal_address {}", addr);
+
+    Ok(InternalAddressKind::of(&addr).to_i64())
+}
+
 /// Getting the chain ID function exposed to the wasm VM VP environment.
 pub fn vp_get_chain_id<MEM, DB, H, EVAL, CA>(
     env: &VpEnv<MEM, DB, H, EVAL, CA>,
@@ -1570,6 +2083,40 @@ where
     })
 }
 
+/// Getting the block time function exposed to the wasm VM VP
+/// environment. The time is that of the block header to which the current
+/// transaction is being applied.
+pub fn vp_get_block_time<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    let storage = unsafe { env.ctx.storage.get() };
+    let time = vp_env::get_block_time(gas_meter, storage)?;
+    Ok(match time {
+        Some(time) => {
+            let time = time
+                .0
+                .try_to_vec()
+                .map_err(vp_env::RuntimeError::EncodingError)?;
+            let len: i64 = time
+                .len()
+                .try_into()
+                .map_err(vp_env::RuntimeError::NumConversionError)?;
+            let result_buffer = unsafe { env.ctx.result_buffer.get() };
+            result_buffer.replace(time);
+            len
+        }
+        None => HostEnvResult::Fail.to_i64(),
+    })
+}
+
 /// Getting the block hash function exposed to the wasm VM VP environment. The
 /// hash is that of the block to which the current transaction is being applied.
 pub fn vp_get_block_hash<MEM, DB, H, EVAL, CA>(
@@ -1634,6 +2181,34 @@ where
     Ok(epoch.0)
 }
 
+/// Check if the given address is internal function exposed to the wasm VM VP
+/// environment. Returns the [`InternalAddressKind`] of the address as `i64`.
+pub fn vp_is_internal_address<MEM, DB, H, EVAL, CA>(
+    env: &VpEnv<MEM, DB, H, EVAL, CA>,
+    addr_ptr: u64,
+    addr_len: u64,
+) -> vp_env::Result<i64>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (addr, gas) = env
+        .memory
+        .read_string(addr_ptr, addr_len as _)
+        .map_err(|e| vp_env::RuntimeError::MemoryError(Box::new(e)))?;
+    let gas_meter = unsafe { env.ctx.gas_meter.get() };
+    vp_env::add_gas(gas_meter, gas)?;
+
+    let addr =
+        Address::decode(addr).map_err(vp_env::RuntimeError::AddressError)?;
+    tracing::debug!("vp_is_internal_address {}", addr);
+
+    Ok(InternalAddressKind::of(&addr).to_i64())
+}
+
 /// Verify a transaction signature.
 pub fn vp_verify_tx_signature<MEM, DB, H, EVAL, CA>(
     env: &VpEnv<MEM, DB, H, EVAL, CA>,
@@ -1666,7 +2241,8 @@ where
     let sig: common::Signature = BorshDeserialize::try_from_slice(&sig)
         .map_err(vp_env::RuntimeError::EncodingError)?;
 
-    vp_env::add_gas(gas_meter, VERIFY_TX_SIG_GAS_COST)?;
+    let storage = unsafe { env.ctx.storage.get() };
+    vp_env::add_gas(gas_meter, storage.gas_schedule.verify_tx_sig)?;
     let tx = unsafe { env.ctx.tx.get() };
     Ok(HostEnvResult::from(tx.verify_sig(&pk, &sig).is_ok()).to_i64())
 }
@@ -1693,6 +2269,30 @@ where
     Ok(())
 }
 
+/// Abort the transaction with the given error code and message, exposed to the
+/// wasm VM Tx environment. The abort is always returned as an error, which
+/// stops the execution of the tx wasm.
+pub fn tx_abort<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    code: u64,
+    msg_ptr: u64,
+    msg_len: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (message, gas) = env
+        .memory
+        .read_string(msg_ptr, msg_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+    tracing::debug!("WASM Transaction aborted with code {}: {}", code, message);
+    Err(TxRuntimeError::Aborted { code, message })
+}
+
 /// Evaluate a validity predicate with the given input data.
 pub fn vp_eval<MEM, DB, H, EVAL, CA>(
     env: &VpEnv<'static, MEM, DB, H, EVAL, CA>,
@@ -1768,6 +2368,7 @@ pub mod testing {
         verifiers: &mut BTreeSet<Address>,
         gas_meter: &mut BlockGasMeter,
         result_buffer: &mut Option<Vec<u8>>,
+        tx_code_hash: &Hash,
         #[cfg(feature = "wasm-runtime")] vp_wasm_cache: &mut VpCache<CA>,
         #[cfg(feature = "wasm-runtime")] tx_wasm_cache: &mut TxCache<CA>,
     ) -> TxEnv<'static, NativeMemory, DB, H, CA>
@@ -1784,6 +2385,7 @@ pub mod testing {
             gas_meter,
             verifiers,
             result_buffer,
+            tx_code_hash,
             #[cfg(feature = "wasm-runtime")]
             vp_wasm_cache,
             #[cfg(feature = "wasm-runtime")]
@@ -0,0 +1,100 @@
+//! Storage values as they're written to the VM host environment's logs. By
+//! default, a value is redacted to its length and a prefix of its hash, so
+//! that the logs neither leak the stored data nor grow with large values.
+
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+/// The environment variable that, when set to `true`, disables the redaction
+/// of the logged values, which is useful for debugging.
+pub const ENV_VAR_LOG_FULL_VALUES: &str = "ANOMA_LOG_FULL_VALUES";
+
+/// The number of bytes of a value's hash that are logged in the redacted
+/// summary
+const HASH_PREFIX_LEN: usize = 4;
+
+/// A storage value to be logged. Its [`fmt::Debug`] and [`fmt::Display`]
+/// implementations write only a summary of the value, unless the full
+/// verbosity is enabled.
+pub struct LogValue<'a> {
+    value: &'a [u8],
+    full: bool,
+}
+
+impl<'a> LogValue<'a> {
+    /// Prepare a value to be logged, redacted unless
+    /// [`ENV_VAR_LOG_FULL_VALUES`] is set to `true`.
+    pub fn new(value: &'a [u8]) -> Self {
+        let full = std::env::var(ENV_VAR_LOG_FULL_VALUES)
+            .map(|var| var.eq_ignore_ascii_case("true"))
+            .unwrap_or_default();
+        Self { value, full }
+    }
+
+    /// Prepare an optional value to be logged
+    pub fn new_opt(value: Option<&'a Vec<u8>>) -> Option<Self> {
+        value.map(|value| Self::new(value))
+    }
+}
+
+impl<'a> fmt::Debug for LogValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.full {
+            return write!(f, "{:?}", self.value);
+        }
+        let hash = Sha256::digest(self.value);
+        write!(
+            f,
+            "<{} bytes, hash {}..>",
+            self.value.len(),
+            hex::encode_upper(&hash[..HASH_PREFIX_LEN])
+        )
+    }
+}
+
+impl<'a> fmt::Display for LogValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that with the redaction on, a large value is logged as a summary
+    /// of its length and hash prefix, not in full
+    #[test]
+    fn test_large_value_redacted() {
+        let value = vec![42_u8; 10_000];
+        let logged = format!(
+            "tx_update {}, {:?}",
+            "key",
+            LogValue {
+                value: &value,
+                full: false
+            }
+        );
+        let hash = hex::encode_upper(&Sha256::digest(&value)[..4]);
+        assert_eq!(
+            logged,
+            format!("tx_update key, <10000 bytes, hash {}..>", hash)
+        );
+        assert!(!logged.contains("42, 42"));
+    }
+
+    /// Test that with the full verbosity, the value is logged in full
+    #[test]
+    fn test_full_value_logged() {
+        let value = vec![1_u8, 2, 3];
+        let logged = format!(
+            "{:?}",
+            LogValue {
+                value: &value,
+                full: true
+            }
+        );
+        assert_eq!(logged, "[1, 2, 3]");
+    }
+}
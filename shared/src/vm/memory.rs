@@ -2,33 +2,49 @@
 
 use std::error::Error;
 
+/// The base gas cost of every read from or write to the VM memory.
+pub const MEMORY_ACCESS_GAS_BASE: u64 = 1;
+/// The gas cost of every byte read from or written to the VM memory.
+pub const MEMORY_ACCESS_GAS_PER_BYTE: u64 = 1;
+
+/// The gas cost of reading or writing the given number of bytes from or to the
+/// VM memory. Every memory access is charged [`MEMORY_ACCESS_GAS_BASE`] plus
+/// [`MEMORY_ACCESS_GAS_PER_BYTE`] for each byte copied.
+pub fn access_gas(len: usize) -> u64 {
+    MEMORY_ACCESS_GAS_BASE + len as u64 * MEMORY_ACCESS_GAS_PER_BYTE
+}
+
 /// Abstract representation of virtual machine's memory.
 pub trait VmMemory: Clone + Send + Sync {
     /// Error type for the methods' results.
     type Error: Error + Sync + Send + 'static;
 
-    /// Returns bytes read from memory together with the associated gas cost.
+    /// Returns bytes read from memory together with the associated gas cost,
+    /// which must be equal to [`access_gas`] of the number of bytes read.
     fn read_bytes(
         &self,
         offset: u64,
         len: usize,
     ) -> Result<(Vec<u8>, u64), Self::Error>;
 
-    /// Write bytes to memory. Returns the gas cost.
+    /// Write bytes to memory. Returns the gas cost, which must be equal to
+    /// [`access_gas`] of the number of bytes written.
     fn write_bytes(
         &self,
         offset: u64,
         bytes: impl AsRef<[u8]>,
     ) -> Result<u64, Self::Error>;
 
-    /// Returns string read from memory together with the associated gas cost.
+    /// Returns string read from memory together with the associated gas cost,
+    /// which must be equal to [`access_gas`] of the number of bytes read.
     fn read_string(
         &self,
         offset: u64,
         len: usize,
     ) -> Result<(String, u64), Self::Error>;
 
-    /// Write string to memory. Returns the gas cost.
+    /// Write string to memory. Returns the gas cost, which must be equal to
+    /// [`access_gas`] of the number of bytes written.
     fn write_string(
         &self,
         offset: u64,
@@ -60,7 +76,7 @@ pub mod testing {
             len: usize,
         ) -> Result<(Vec<u8>, u64)> {
             let slice = unsafe { slice::from_raw_parts(offset as _, len as _) };
-            Ok((slice.to_vec(), 0))
+            Ok((slice.to_vec(), access_gas(len)))
         }
 
         fn write_bytes(
@@ -73,7 +89,7 @@ pub mod testing {
             let target =
                 unsafe { slice::from_raw_parts_mut(offset as _, len as _) };
             target.clone_from_slice(bytes);
-            Ok(0)
+            Ok(access_gas(len))
         }
 
         fn read_string(
@@ -85,7 +101,7 @@ pub mod testing {
             let string = std::str::from_utf8(slice)
                 .expect("unable to decode string from memory")
                 .to_string();
-            Ok((string, 0))
+            Ok((string, access_gas(len)))
         }
 
         fn write_string(&self, offset: u64, string: String) -> Result<u64> {
@@ -94,7 +110,7 @@ pub mod testing {
             let target =
                 unsafe { slice::from_raw_parts_mut(offset as _, len as _) };
             target.clone_from_slice(bytes);
-            Ok(0)
+            Ok(access_gas(len))
         }
     }
 }
@@ -8,6 +8,7 @@ use std::slice;
 use wasmparser::{Validator, WasmFeatures};
 
 pub mod host_env;
+pub mod log_value;
 pub mod memory;
 pub mod prefix_iter;
 pub mod types;
@@ -9,6 +9,7 @@ use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use std::thread::sleep;
 use std::time::Duration;
@@ -18,7 +19,7 @@ use clru::{CLruCache, CLruCacheConfig, WeightScale};
 use wasmer::{Module, Store};
 use wasmer_cache::{FileSystemCache, Hash};
 
-use crate::vm::wasm::run::untrusted_wasm_store;
+use crate::vm::wasm::run::{untrusted_wasm_store, WasmLimits};
 use crate::vm::wasm::{self, memory};
 use crate::vm::{WasmCacheAccess, WasmCacheRoAccess};
 
@@ -34,6 +35,10 @@ pub struct Cache<N: CacheName, A: WasmCacheAccess> {
     progress: Arc<RwLock<HashMap<Hash, Compilation>>>,
     /// In-memory LRU cache of compiled modules
     in_memory: Arc<RwLock<MemoryCache>>,
+    /// Counters of the cache's hits and compilations
+    stats: Arc<CacheStats>,
+    /// The limits of the resources available to the cached modules
+    limits: WasmLimits,
     /// The cache's name
     name: PhantomData<N>,
     /// Cache access level
@@ -44,6 +49,19 @@ pub struct Cache<N: CacheName, A: WasmCacheAccess> {
 pub trait CacheName: Clone + std::fmt::Debug {
     /// Get the name of the cache
     fn name() -> &'static str;
+
+    /// Get the default limits of the cached modules
+    fn default_limits() -> WasmLimits;
+}
+
+/// Counters of the modules fetched from the cache and of the modules that had
+/// to be compiled
+#[derive(Debug, Default)]
+struct CacheStats {
+    /// The number of modules fetched from the in-memory or file cache
+    hits: AtomicU64,
+    /// The number of modules compiled
+    compilations: AtomicU64,
 }
 
 /// In-memory LRU cache of compiled modules
@@ -96,6 +114,8 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
             dir,
             progress: Default::default(),
             in_memory,
+            stats: Default::default(),
+            limits: N::default_limits(),
             name: Default::default(),
             access: Default::default(),
         }
@@ -131,7 +151,8 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                 N::name(),
                 hash.to_string()
             );
-            return Ok((module.clone(), store()));
+            self.record_hit();
+            return Ok((module.clone(), store(&self.limits)));
         }
         drop(in_memory);
 
@@ -148,15 +169,18 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                             N::name(),
                             hash.to_string()
                         );
-                        return Ok((module.clone(), store()));
+                        self.record_hit();
+                        return Ok((module.clone(), store(&self.limits)));
                     }
 
-                    let (module, store) = file_load_module(&self.dir, &hash);
+                    let (module, store) =
+                        file_load_module(&self.dir, &hash, &self.limits);
                     tracing::info!(
                         "{} found {} in file cache.",
                         N::name(),
                         hash.to_string()
                     );
+                    self.record_hit();
                     // Put into cache, ignore result if it's full
                     let _ = in_memory.put_with_weight(hash, module.clone());
 
@@ -184,16 +208,21 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                                 N::name(),
                                 hash.to_string()
                             );
-                            file_load_module(&self.dir, &hash)
+                            self.record_hit();
+                            file_load_module(&self.dir, &hash, &self.limits)
                         } else {
                             tracing::info!(
                                 "Compiling {} {}.",
                                 N::name(),
                                 hash.to_string()
                             );
+                            self.record_compilation();
 
-                            match wasm::run::prepare_wasm_code(code) {
-                                Ok(code) => match compile(code) {
+                            match wasm::run::prepare_wasm_code(
+                                code,
+                                self.limits.max_stack_height,
+                            ) {
+                                Ok(code) => match compile(code, &self.limits) {
                                     Ok((module, store)) => {
                                         // Write the file
                                         file_write_module(
@@ -261,7 +290,8 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                 N::name(),
                 hash.to_string()
             );
-            return Ok((module.clone(), store()));
+            self.record_hit();
+            return Ok((module.clone(), store(&self.limits)));
         }
         drop(in_memory);
 
@@ -278,15 +308,18 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                             N::name(),
                             hash.to_string()
                         );
-                        return Ok((module.clone(), store()));
+                        self.record_hit();
+                        return Ok((module.clone(), store(&self.limits)));
                     }
 
-                    let (module, store) = file_load_module(&self.dir, &hash);
+                    let (module, store) =
+                        file_load_module(&self.dir, &hash, &self.limits);
                     tracing::info!(
                         "{} found {} in file cache.",
                         N::name(),
                         hash.to_string()
                     );
+                    self.record_hit();
                     return Ok((module, store));
                 }
                 Some(Compilation::Compiling) => {
@@ -309,15 +342,20 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                             N::name(),
                             hash.to_string()
                         );
-                        Ok(file_load_module(&self.dir, &hash))
+                        self.record_hit();
+                        Ok(file_load_module(&self.dir, &hash, &self.limits))
                     } else {
                         tracing::info!(
                             "Compiling {} {}.",
                             N::name(),
                             hash.to_string()
                         );
-                        let code = wasm::run::prepare_wasm_code(code)?;
-                        compile(code)
+                        self.record_compilation();
+                        let code = wasm::run::prepare_wasm_code(
+                            code,
+                            self.limits.max_stack_height,
+                        )?;
+                        compile(code, &self.limits)
                     };
                 }
             }
@@ -344,12 +382,17 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
                     let progress = self.progress.clone();
                     let code = code.as_ref().to_vec();
                     let dir = self.dir.clone();
+                    let limits = self.limits;
+                    self.record_compilation();
                     std::thread::spawn(move || {
                         tracing::info!("Compiling {}.", hash.to_string());
 
                         let (_module, _store) =
-                            match wasm::run::prepare_wasm_code(code) {
-                                Ok(code) => match compile(code) {
+                            match wasm::run::prepare_wasm_code(
+                                code,
+                                limits.max_stack_height,
+                            ) {
+                                Ok(code) => match compile(code, &limits) {
                                     Ok((module, store)) => {
                                         let mut progress =
                                             progress.write().unwrap();
@@ -391,12 +434,67 @@ impl<N: CacheName, A: WasmCacheAccess> Cache<N, A> {
         }
     }
 
+    /// Set the limits of the resources available to the cached modules. The
+    /// modules compiled with a non-default stack limit are stored in a
+    /// separate sub-directory of the file system cache, so that they're not
+    /// mixed with modules compiled with a different limit.
+    pub fn with_limits(mut self, limits: WasmLimits) -> Self {
+        self.set_limits(limits);
+        self
+    }
+
+    /// Change the limits of the resources available to the cached modules,
+    /// see [`Cache::with_limits`]. This must be done before any modules are
+    /// fetched, because the modules already in memory are not re-compiled.
+    pub fn set_limits(&mut self, limits: WasmLimits) {
+        let default_stack_height = N::default_limits().max_stack_height;
+        if self.limits.max_stack_height != default_stack_height {
+            // Go back from the sub-directory of the previous stack limit
+            self.dir.pop();
+        }
+        if limits.max_stack_height != default_stack_height {
+            self.dir = self
+                .dir
+                .join(format!("stack_height_{}", limits.max_stack_height));
+            fs::create_dir_all(&self.dir)
+                .expect("Couldn't create the wasm cache directory");
+        }
+        self.limits = limits;
+    }
+
+    /// The limits of the resources available to the cached modules
+    pub fn limits(&self) -> WasmLimits {
+        self.limits
+    }
+
+    /// The number of modules that have been fetched from the cache without
+    /// compiling them. This is shared by all the handles of the cache.
+    pub fn hits(&self) -> u64 {
+        self.stats.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of modules that have been compiled. This is shared by all
+    /// the handles of the cache.
+    pub fn compilations(&self) -> u64 {
+        self.stats.compilations.load(Ordering::Relaxed)
+    }
+
+    fn record_hit(&self) {
+        self.stats.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_compilation(&self) {
+        self.stats.compilations.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Get a read-only cache handle.
     pub fn read_only(&self) -> Cache<N, WasmCacheRoAccess> {
         Cache {
             dir: self.dir.clone(),
             progress: self.progress.clone(),
             in_memory: self.in_memory.clone(),
+            stats: self.stats.clone(),
+            limits: self.limits,
             name: Default::default(),
             access: Default::default(),
         }
@@ -417,11 +515,12 @@ fn hash_to_store_dir(hash: &Hash) -> PathBuf {
 
 fn compile(
     code: impl AsRef<[u8]>,
+    limits: &WasmLimits,
 ) -> Result<(Module, Store), wasm::run::Error> {
     // There's an issue with dylib compiler on mac in linker and on linux
     // with the dylib's store loading the dylib from a file, so we're caching a
     // module serialized to bytes instead for now.
-    universal::compile(code).map_err(wasm::run::Error::CompileError)
+    universal::compile(code, limits).map_err(wasm::run::Error::CompileError)
 }
 
 fn file_ext() -> &'static str {
@@ -430,10 +529,10 @@ fn file_ext() -> &'static str {
     universal::FILE_EXT
 }
 
-fn store() -> Store {
+fn store(limits: &WasmLimits) -> Store {
     // This has to be using the store matching the compilation method in the
     // `fn compile`
-    universal::store()
+    universal::store(limits)
 }
 
 fn file_write_module(dir: impl AsRef<Path>, module: &Module, hash: &Hash) {
@@ -442,10 +541,14 @@ fn file_write_module(dir: impl AsRef<Path>, module: &Module, hash: &Hash) {
     fs_cache.store(*hash, module).unwrap();
 }
 
-fn file_load_module(dir: impl AsRef<Path>, hash: &Hash) -> (Module, Store) {
+fn file_load_module(
+    dir: impl AsRef<Path>,
+    hash: &Hash,
+    limits: &WasmLimits,
+) -> (Module, Store) {
     use wasmer_cache::Cache;
     let fs_cache = fs_cache(dir, hash);
-    let store = store();
+    let store = store(limits);
     let module = unsafe { fs_cache.load(&store, *hash) }.unwrap();
     (module, store)
 }
@@ -477,16 +580,17 @@ mod universal {
     #[allow(dead_code)]
     pub fn compile(
         code: impl AsRef<[u8]>,
+        limits: &WasmLimits,
     ) -> Result<(Module, Store), wasmer::CompileError> {
-        let store = store();
+        let store = store(limits);
         let module = Module::new(&store, code.as_ref())?;
         Ok((module, store))
     }
 
     /// Universal WASM store
     #[allow(dead_code)]
-    pub fn store() -> Store {
-        untrusted_wasm_store(memory::vp_limit())
+    pub fn store(limits: &WasmLimits) -> Store {
+        untrusted_wasm_store(memory::limit(limits.max_memory_pages))
     }
 }
 
@@ -508,18 +612,22 @@ mod dylib {
     #[allow(dead_code)]
     pub fn compile(
         code: impl AsRef<[u8]>,
+        limits: &WasmLimits,
     ) -> Result<(Module, Store), wasmer::CompileError> {
-        let store = store();
+        let store = store(limits);
         let module = Module::new(&store, code.as_ref())?;
         Ok((module, store))
     }
 
     /// Dylib WASM store
     #[allow(dead_code)]
-    pub fn store() -> Store {
+    pub fn store(limits: &WasmLimits) -> Store {
         let compiler = wasmer_compiler_singlepass::Singlepass::default();
         let engine = wasmer_engine_dylib::Dylib::new(compiler).engine();
-        Store::new_with_tunables(&engine, memory::vp_limit())
+        Store::new_with_tunables(
+            &engine,
+            memory::limit(limits.max_memory_pages),
+        )
     }
 }
 
@@ -922,6 +1030,30 @@ mod test {
         }
     }
 
+    /// Test that the second fetch of the same code, also from a read-only
+    /// handle, is a cache hit that skips the compilation
+    #[test]
+    fn test_fetch_or_compile_cache_hit() {
+        let vp_always_true = load_wasm(VP_ALWAYS_TRUE);
+        let (mut cache, _tmp_dir) = cache(50 * 1024 * 1024);
+
+        let (_module, _store) =
+            cache.fetch_or_compile(&vp_always_true.code).unwrap();
+        assert_eq!(cache.compilations(), 1);
+        assert_eq!(cache.hits(), 0);
+
+        let (_module, _store) =
+            cache.fetch_or_compile(&vp_always_true.code).unwrap();
+        assert_eq!(cache.compilations(), 1);
+        assert_eq!(cache.hits(), 1);
+
+        let mut read_only = cache.read_only();
+        let (_module, _store) =
+            read_only.fetch_or_compile(&vp_always_true.code).unwrap();
+        assert_eq!(cache.compilations(), 1);
+        assert_eq!(cache.hits(), 2);
+    }
+
     /// Get the WASM code bytes, its hash and find the compiled module's size
     fn load_wasm(file: impl AsRef<str>) -> WasmWithMeta {
         // When `WeightScale` calls `loupe::size_of_val` in the cache, for some
@@ -965,6 +1097,10 @@ mod test {
         fn name() -> &'static str {
             "test"
         }
+
+        fn default_limits() -> WasmLimits {
+            WasmLimits::vp_default()
+        }
     }
 
     /// A cache with a temp dir for unit tests
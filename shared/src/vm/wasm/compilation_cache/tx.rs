@@ -1,6 +1,7 @@
 //! Transaction WASM compilation cache
 
 use super::common::{Cache, CacheName};
+use crate::vm::wasm::run::WasmLimits;
 
 /// Tx WASM compilation cache handle. Thread-safe.
 pub type TxCache<A> = Cache<Name, A>;
@@ -13,4 +14,8 @@ impl CacheName for Name {
     fn name() -> &'static str {
         "Tx"
     }
+
+    fn default_limits() -> WasmLimits {
+        WasmLimits::tx_default()
+    }
 }
@@ -1,6 +1,7 @@
 //! VP WASM compilation cache
 
 use super::common::{Cache, CacheName};
+use crate::vm::wasm::run::WasmLimits;
 
 /// VP WASM compilation cache handle. Thread-safe.
 pub type VpCache<A> = Cache<Name, A>;
@@ -13,4 +14,8 @@ impl CacheName for Name {
     fn name() -> &'static str {
         "VP"
     }
+
+    fn default_limits() -> WasmLimits {
+        WasmLimits::vp_default()
+    }
 }
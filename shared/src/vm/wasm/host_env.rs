@@ -65,19 +65,24 @@ where
             "anoma_tx_has_key" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_has_key),
             "anoma_tx_write" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_write),
             "anoma_tx_write_temp" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_write_temp),
+            "anoma_tx_write_cas" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_write_cas),
             "anoma_tx_delete" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_delete),
             "anoma_tx_iter_prefix" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_iter_prefix),
             "anoma_tx_iter_next" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_iter_next),
             "anoma_tx_insert_verifier" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_insert_verifier),
             "anoma_tx_update_validity_predicate" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_update_validity_predicate),
             "anoma_tx_init_account" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_init_account),
+            "anoma_tx_init_account_derived" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_init_account_derived),
             "anoma_tx_emit_ibc_event" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_emit_ibc_event),
             "anoma_tx_get_chain_id" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_chain_id),
             "anoma_tx_get_block_height" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_height),
             "anoma_tx_get_block_time" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_time),
             "anoma_tx_get_block_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_hash),
+            "anoma_tx_get_tx_code_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_tx_code_hash),
             "anoma_tx_get_block_epoch" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_get_block_epoch),
+            "anoma_tx_is_internal_address" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_is_internal_address),
             "anoma_tx_log_string" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_log_string),
+            "anoma_tx_abort" => Function::new_native_with_env(wasm_store, env.clone(), host_env::tx_abort),
         },
     }
 }
@@ -103,17 +108,24 @@ where
             "anoma_vp_read_pre" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_pre),
             "anoma_vp_read_post" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_post),
             "anoma_vp_read_temp" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_temp),
+            "anoma_vp_read_batch_pre" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_batch_pre),
+            "anoma_vp_read_batch_post" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_batch_post),
+            "anoma_vp_read_vp_pre" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_vp_pre),
+            "anoma_vp_read_vp_post" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_read_vp_post),
             "anoma_vp_result_buffer" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_result_buffer),
             "anoma_vp_has_key_pre" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_has_key_pre),
             "anoma_vp_has_key_post" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_has_key_post),
             "anoma_vp_iter_prefix" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_iter_prefix),
+            "anoma_vp_prefix_count" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_prefix_count),
             "anoma_vp_iter_pre_next" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_iter_pre_next),
             "anoma_vp_iter_post_next" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_iter_post_next),
             "anoma_vp_get_chain_id" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_chain_id),
             "anoma_vp_get_block_height" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_height),
+            "anoma_vp_get_block_time" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_time),
             "anoma_vp_get_block_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_hash),
             "anoma_vp_get_tx_code_hash" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_tx_code_hash),
             "anoma_vp_get_block_epoch" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_get_block_epoch),
+            "anoma_vp_is_internal_address" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_is_internal_address),
             "anoma_vp_verify_tx_signature" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_verify_tx_signature),
             "anoma_vp_eval" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_eval),
             "anoma_vp_log_string" => Function::new_native_with_env(wasm_store, env.clone(), host_env::vp_log_string),
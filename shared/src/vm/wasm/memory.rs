@@ -15,7 +15,7 @@ use wasmer_vm::{
     MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition,
 };
 
-use crate::vm::memory::VmMemory;
+use crate::vm::memory::{access_gas, VmMemory};
 use crate::vm::types::VpInput;
 
 #[allow(missing_docs)]
@@ -48,21 +48,29 @@ pub const VP_MEMORY_INIT_PAGES: u32 = 100; // 6.4 MiB
 /// Mamixmum pages in VP memory
 pub const VP_MEMORY_MAX_PAGES: u32 = 200; // 12.8 MiB
 
-/// Prepare memory for instantiating a transaction module
-pub fn prepare_tx_memory(store: &wasmer::Store) -> Result<wasmer::Memory> {
+/// Prepare memory for instantiating a transaction module with the given
+/// maximum number of pages
+pub fn prepare_tx_memory(
+    store: &wasmer::Store,
+    max_pages: u32,
+) -> Result<wasmer::Memory> {
     let mem_type = wasmer::MemoryType::new(
-        TX_MEMORY_INIT_PAGES,
-        Some(TX_MEMORY_MAX_PAGES),
+        TX_MEMORY_INIT_PAGES.min(max_pages),
+        Some(max_pages),
         false,
     );
     Memory::new(store, mem_type).map_err(Error::InitMemoryError)
 }
 
-/// Prepare memory for instantiating a validity predicate module
-pub fn prepare_vp_memory(store: &wasmer::Store) -> Result<wasmer::Memory> {
+/// Prepare memory for instantiating a validity predicate module with the
+/// given maximum number of pages
+pub fn prepare_vp_memory(
+    store: &wasmer::Store,
+    max_pages: u32,
+) -> Result<wasmer::Memory> {
     let mem_type = wasmer::MemoryType::new(
-        VP_MEMORY_INIT_PAGES,
-        Some(VP_MEMORY_MAX_PAGES),
+        VP_MEMORY_INIT_PAGES.min(max_pages),
+        Some(max_pages),
         false,
     );
     let memory =
@@ -254,16 +262,16 @@ impl VmMemory for WasmMemory {
     fn read_bytes(&self, offset: u64, len: usize) -> Result<(Vec<u8>, u64)> {
         let memory = self.inner.get_ref().ok_or(Error::UninitializedMemory)?;
         let bytes = read_memory_bytes(memory, offset, len)?;
-        let gas = bytes.len();
-        Ok((bytes, gas as _))
+        let gas = access_gas(bytes.len());
+        Ok((bytes, gas))
     }
 
     /// Write bytes into memory at the given offset and return the gas cost
     fn write_bytes(&self, offset: u64, bytes: impl AsRef<[u8]>) -> Result<u64> {
-        let gas = bytes.as_ref().len();
+        let gas = access_gas(bytes.as_ref().len());
         let memory = self.inner.get_ref().ok_or(Error::UninitializedMemory)?;
         write_memory_bytes(memory, offset, bytes)?;
-        Ok(gas as _)
+        Ok(gas)
     }
 
     /// Read string from memory at the given offset and bytes length, and return
@@ -273,7 +281,7 @@ impl VmMemory for WasmMemory {
         let string = std::str::from_utf8(&bytes)
             .map_err(Error::InvalidUtf8String)?
             .to_string();
-        Ok((string, gas as _))
+        Ok((string, gas))
     }
 
     /// Write string into memory at the given offset and return the gas cost
@@ -299,14 +307,16 @@ pub struct Limit<T: Tunables> {
 /// A [`Limit`] with memory limit setup for validity predicate WASM
 /// execution.
 pub fn vp_limit() -> Limit<BaseTunables> {
-    let base = BaseTunables::for_target(&Target::default());
-    let limit = Pages(VP_MEMORY_MAX_PAGES);
-    Limit { limit, base }
+    limit(VP_MEMORY_MAX_PAGES)
 }
 /// A [`Limit`] with memory limit setup for transaction WASM execution.
 pub fn tx_limit() -> Limit<BaseTunables> {
+    limit(TX_MEMORY_MAX_PAGES)
+}
+/// A [`Limit`] with the given maximum number of memory pages.
+pub fn limit(max_pages: u32) -> Limit<BaseTunables> {
     let base = BaseTunables::for_target(&Target::default());
-    let limit = Pages(TX_MEMORY_MAX_PAGES);
+    let limit = Pages(max_pages);
     Limit { limit, base }
 }
 
@@ -429,6 +439,9 @@ pub mod tests {
     };
 
     use super::*;
+    use crate::vm::memory::{
+        MEMORY_ACCESS_GAS_BASE, MEMORY_ACCESS_GAS_PER_BYTE,
+    };
 
     #[test]
     fn test_wasm_tunables_limit_memory() {
@@ -474,4 +487,35 @@ pub mod tests {
         println!("Memory of this instance: {:?}", first_memory);
         assert_eq!(first_memory.ty().maximum.unwrap(), limit);
     }
+
+    #[test]
+    fn test_memory_access_gas() {
+        let wat = br#"(module (memory 1) (export "memory" (memory 0)))"#;
+        let wasm_bytes = wat2wasm(wat).unwrap();
+        let store = Store::default();
+        let module = Module::new(&store, wasm_bytes).unwrap();
+        let instance = Instance::new(&module, &wasmer::imports! {}).unwrap();
+
+        let mut memory = WasmMemory::default();
+        memory.init_env_memory(&instance.exports).unwrap();
+
+        for len in [0_usize, 1, 32, 1000, 2 * wasmer::WASM_PAGE_SIZE] {
+            let expected_gas = MEMORY_ACCESS_GAS_BASE
+                + len as u64 * MEMORY_ACCESS_GAS_PER_BYTE;
+
+            let bytes = vec![1_u8; len];
+            let gas = memory.write_bytes(0, &bytes).unwrap();
+            assert_eq!(gas, expected_gas);
+            let (read, gas) = memory.read_bytes(0, len).unwrap();
+            assert_eq!(gas, expected_gas);
+            assert_eq!(read, bytes);
+
+            let string = "a".repeat(len);
+            let gas = memory.write_string(0, string.clone()).unwrap();
+            assert_eq!(gas, expected_gas);
+            let (read, gas) = memory.read_string(0, len).unwrap();
+            assert_eq!(gas, expected_gas);
+            assert_eq!(read, string);
+        }
+    }
 }
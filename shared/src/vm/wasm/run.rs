@@ -2,6 +2,7 @@
 
 use std::collections::BTreeSet;
 use std::marker::PhantomData;
+use std::time::Instant;
 
 use parity_wasm::elements;
 use pwasm_utils::{self, rules};
@@ -11,13 +12,15 @@ use wasmer::BaseTunables;
 use super::memory::{Limit, WasmMemory};
 use super::TxCache;
 use crate::ledger::gas::{BlockGasMeter, VpGasMeter};
+use crate::ledger::parameters::WasmLimitsParams;
 use crate::ledger::storage::write_log::WriteLog;
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::proto::Tx;
 use crate::types::address::Address;
 use crate::types::internal::HostEnvResult;
 use crate::types::storage::Key;
-use crate::vm::host_env::{TxEnv, VpCtx, VpEnv, VpEvaluator};
+use crate::types::transaction::{hash_tx, WasmTimings};
+use crate::vm::host_env::{TxEnv, TxRuntimeError, VpCtx, VpEnv, VpEvaluator};
 use crate::vm::prefix_iter::PrefixIterators;
 use crate::vm::types::VpInput;
 use crate::vm::wasm::host_env::{tx_imports, vp_imports};
@@ -28,7 +31,53 @@ use crate::vm::{
 
 const TX_ENTRYPOINT: &str = "_apply_tx";
 const VP_ENTRYPOINT: &str = "_validate_tx";
-const WASM_STACK_LIMIT: u32 = u16::MAX as u32;
+/// The default maximum height of the wasm stack
+pub const WASM_STACK_LIMIT: u32 = u16::MAX as u32;
+
+/// The limits of the resources available to the wasm code. The memory limit
+/// is applied when the code is instantiated and the stack limit is injected
+/// into the code when it's compiled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WasmLimits {
+    /// The maximum number of memory pages (64 KiB each)
+    pub max_memory_pages: u32,
+    /// The maximum height of the stack
+    pub max_stack_height: u32,
+}
+
+impl WasmLimits {
+    /// The default limits for transactions
+    pub fn tx_default() -> Self {
+        Self {
+            max_memory_pages: memory::TX_MEMORY_MAX_PAGES,
+            max_stack_height: WASM_STACK_LIMIT,
+        }
+    }
+
+    /// The default limits for validity predicates
+    pub fn vp_default() -> Self {
+        Self {
+            max_memory_pages: memory::VP_MEMORY_MAX_PAGES,
+            max_stack_height: WASM_STACK_LIMIT,
+        }
+    }
+
+    /// The limits for transactions set by the protocol parameters
+    pub fn tx_from_params(params: &WasmLimitsParams) -> Self {
+        Self {
+            max_memory_pages: params.tx_max_memory_pages,
+            max_stack_height: params.max_stack_height,
+        }
+    }
+
+    /// The limits for validity predicates set by the protocol parameters
+    pub fn vp_from_params(params: &WasmLimitsParams) -> Self {
+        Self {
+            max_memory_pages: params.vp_max_memory_pages,
+            max_stack_height: params.max_stack_height,
+        }
+    }
+}
 
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
@@ -63,6 +112,8 @@ pub enum Error {
     },
     #[error("Wasm validation error: {0}")]
     ValidationError(WasmValidationError),
+    #[error("Transaction aborted with code {code}: {message}")]
+    TxAborted { code: u64, message: String },
 }
 
 /// Result for functions that may fail
@@ -79,6 +130,36 @@ pub fn tx<DB, H, CA>(
     vp_wasm_cache: &mut VpCache<CA>,
     tx_wasm_cache: &mut TxCache<CA>,
 ) -> Result<BTreeSet<Address>>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    timed_tx(
+        storage,
+        write_log,
+        gas_meter,
+        tx_code,
+        tx_data,
+        vp_wasm_cache,
+        tx_wasm_cache,
+        &mut WasmTimings::default(),
+    )
+}
+
+/// Execute a transaction code like [`tx`] and add the time spent on its
+/// compilation and execution to the given timings.
+#[allow(clippy::too_many_arguments)]
+pub fn timed_tx<DB, H, CA>(
+    storage: &Storage<DB, H>,
+    write_log: &mut WriteLog,
+    gas_meter: &mut BlockGasMeter,
+    tx_code: impl AsRef<[u8]>,
+    tx_data: impl AsRef<[u8]>,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+    timings: &mut WasmTimings,
+) -> Result<BTreeSet<Address>>
 where
     DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
     H: 'static + StorageHasher,
@@ -88,11 +169,16 @@ where
 
     validate_untrusted_wasm(&tx_code).map_err(Error::ValidationError)?;
 
+    let compile_start = Instant::now();
     let (module, store) = tx_wasm_cache.fetch_or_compile(&tx_code)?;
+    timings.add_compile(compile_start.elapsed());
+    let execute_start = Instant::now();
+    let max_memory_pages = tx_wasm_cache.limits().max_memory_pages;
 
     let mut iterators: PrefixIterators<'_, DB> = PrefixIterators::default();
     let mut verifiers = BTreeSet::new();
     let mut result_buffer: Option<Vec<u8>> = None;
+    let tx_code_hash = hash_tx(tx_code.as_ref());
 
     let env = TxEnv::new(
         WasmMemory::default(),
@@ -102,12 +188,13 @@ where
         gas_meter,
         &mut verifiers,
         &mut result_buffer,
+        &tx_code_hash,
         vp_wasm_cache,
         tx_wasm_cache,
     );
 
-    let initial_memory =
-        memory::prepare_tx_memory(&store).map_err(Error::MemoryError)?;
+    let initial_memory = memory::prepare_tx_memory(&store, max_memory_pages)
+        .map_err(Error::MemoryError)?;
     let imports = tx_imports(&store, initial_memory, env);
 
     // Instantiate the wasm module
@@ -140,19 +227,67 @@ where
     {
         Err(Error::RuntimeError(err)) => {
             tracing::debug!("Tx WASM failed with {}", err);
-            Err(Error::RuntimeError(err))
+            Err(tx_runtime_error(err))
         }
         _ => Ok(()),
     }?;
+    timings.add_execute(execute_start.elapsed());
 
     Ok(verifiers)
 }
 
+/// Convert a runtime error of a tx wasm into [`Error::TxAborted`] when the tx
+/// has been aborted by calling the host environment
+fn tx_runtime_error(err: wasmer::RuntimeError) -> Error {
+    match err.downcast::<TxRuntimeError>() {
+        Ok(TxRuntimeError::Aborted { code, message }) => {
+            Error::TxAborted { code, message }
+        }
+        Ok(err) => {
+            Error::RuntimeError(wasmer::RuntimeError::user(Box::new(err)))
+        }
+        Err(err) => Error::RuntimeError(err),
+    }
+}
+
 /// Execute a validity predicate code. Returns whether the validity
 /// predicate accepted storage modifications performed by the transaction
 /// that triggered the execution.
 #[allow(clippy::too_many_arguments)]
 pub fn vp<DB, H, CA>(
+    vp_code: impl AsRef<[u8]>,
+    tx: &Tx,
+    address: &Address,
+    storage: &Storage<DB, H>,
+    write_log: &WriteLog,
+    gas_meter: &mut VpGasMeter,
+    keys_changed: &BTreeSet<Key>,
+    verifiers: &BTreeSet<Address>,
+    vp_wasm_cache: VpCache<CA>,
+) -> Result<bool>
+where
+    DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: 'static + WasmCacheAccess,
+{
+    timed_vp(
+        vp_code,
+        tx,
+        address,
+        storage,
+        write_log,
+        gas_meter,
+        keys_changed,
+        verifiers,
+        vp_wasm_cache,
+        &mut WasmTimings::default(),
+    )
+}
+
+/// Execute a validity predicate code like [`vp`] and add the time spent on
+/// its compilation and execution to the given timings.
+#[allow(clippy::too_many_arguments)]
+pub fn timed_vp<DB, H, CA>(
     vp_code: impl AsRef<[u8]>,
     tx: &Tx,
     address: &Address,
@@ -162,6 +297,7 @@ pub fn vp<DB, H, CA>(
     keys_changed: &BTreeSet<Key>,
     verifiers: &BTreeSet<Address>,
     mut vp_wasm_cache: VpCache<CA>,
+    timings: &mut WasmTimings,
 ) -> Result<bool>
 where
     DB: 'static + storage::DB + for<'iter> storage::DBIter<'iter>,
@@ -179,8 +315,12 @@ where
     validate_untrusted_wasm(vp_code).map_err(Error::ValidationError)?;
 
     // Compile the wasm module
+    let compile_start = Instant::now();
     let (module, store) = vp_wasm_cache.fetch_or_compile(&vp_code)?;
+    timings.add_compile(compile_start.elapsed());
+    let execute_start = Instant::now();
 
+    let max_memory_pages = vp_wasm_cache.limits().max_memory_pages;
     let mut iterators: PrefixIterators<'_, DB> = PrefixIterators::default();
     let mut result_buffer: Option<Vec<u8>> = None;
     let eval_runner = VpEvalWasm {
@@ -204,18 +344,20 @@ where
         &mut vp_wasm_cache,
     );
 
-    let initial_memory =
-        memory::prepare_vp_memory(&store).map_err(Error::MemoryError)?;
+    let initial_memory = memory::prepare_vp_memory(&store, max_memory_pages)
+        .map_err(Error::MemoryError)?;
     let imports = vp_imports(&store, initial_memory, env);
 
-    run_vp(
+    let is_valid = run_vp(
         module,
         imports,
         input_data,
         address,
         keys_changed,
         verifiers,
-    )
+    );
+    timings.add_execute(execute_start.elapsed());
+    is_valid
 }
 
 fn run_vp(
@@ -352,8 +494,10 @@ where
         // Compile the wasm module
         let (module, store) = vp_wasm_cache.fetch_or_compile(&vp_code)?;
 
+        let max_memory_pages = vp_wasm_cache.limits().max_memory_pages;
         let initial_memory =
-            memory::prepare_vp_memory(&store).map_err(Error::MemoryError)?;
+            memory::prepare_vp_memory(&store, max_memory_pages)
+                .map_err(Error::MemoryError)?;
 
         let imports = vp_imports(&store, initial_memory, env);
 
@@ -378,15 +522,19 @@ pub fn untrusted_wasm_store(limit: Limit<BaseTunables>) -> wasmer::Store {
     )
 }
 
-/// Inject gas counter and stack-height limiter into the given wasm code
-pub fn prepare_wasm_code<T: AsRef<[u8]>>(code: T) -> Result<Vec<u8>> {
+/// Inject gas counter and stack-height limiter with the given maximum stack
+/// height into the given wasm code
+pub fn prepare_wasm_code<T: AsRef<[u8]>>(
+    code: T,
+    max_stack_height: u32,
+) -> Result<Vec<u8>> {
     let module: elements::Module = elements::deserialize_buffer(code.as_ref())
         .map_err(Error::DeserializationError)?;
     let module =
         pwasm_utils::inject_gas_counter(module, &get_gas_rules(), "env")
             .map_err(|_original_module| Error::GasMeterInjection)?;
     let module =
-        pwasm_utils::stack_height::inject_limiter(module, WASM_STACK_LIMIT)
+        pwasm_utils::stack_height::inject_limiter(module, max_stack_height)
             .map_err(|_original_module| Error::StackLimiterInjection)?;
     elements::serialize(module).map_err(Error::SerializationError)
 }
@@ -639,6 +787,49 @@ mod tests {
         assert_stack_overflow(&error);
     }
 
+    /// Test that a lower memory limit configured on the VP cache is enforced
+    /// in the wasm execution.
+    #[test]
+    fn test_vp_configured_memory_limit() {
+        let mut storage = TestStorage::default();
+        let addr = storage.address_gen.generate_address("rng seed");
+        let write_log = WriteLog::default();
+        let mut gas_meter = VpGasMeter::new(0);
+        let keys_changed = BTreeSet::new();
+        let verifiers = BTreeSet::new();
+
+        // This code will allocate memory of the given size
+        let vp_code =
+            std::fs::read(VP_MEMORY_LIMIT_WASM).expect("cannot load wasm");
+
+        // Limit the memory to 100 pages, 6.4 MiB
+        let (vp_cache, _) = wasm::compilation_cache::common::testing::cache();
+        let vp_cache = vp_cache.with_limits(WasmLimits {
+            max_memory_pages: 100,
+            ..WasmLimits::vp_default()
+        });
+        assert_eq!(vp_cache.limits().max_memory_pages, 100);
+
+        // Allocating `2^23` (8 MiB) is below the default limit, but should be
+        // above the configured limit and should fail
+        let tx_data = 2_usize.pow(23).try_to_vec().unwrap();
+        let tx = Tx::new(vec![], Some(tx_data));
+        let error = vp(
+            vp_code,
+            &tx,
+            &addr,
+            &storage,
+            &write_log,
+            &mut gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_cache,
+        )
+        .expect_err("Expected to run out of memory");
+
+        assert_stack_overflow(&error);
+    }
+
     /// Test that when a transaction wasm goes over the wasm memory limit in the
     /// host input, the execution fails.
     #[test]
@@ -883,6 +1074,126 @@ mod tests {
         assert!(!passed);
     }
 
+    /// Test that a transaction that calls the host environment to abort fails
+    /// with the given error code and message.
+    #[test]
+    fn test_tx_abort() {
+        let message = "insufficient funds";
+        // A tx that aborts with code `42` and the message from its data
+        // segment
+        let tx_code = wasmer::wat2wasm(
+            format!(
+                r#"
+            (module
+                (type (;0;) (func (param i64 i64)))
+                (type (;1;) (func (param i64 i64 i64)))
+                (import "env" "anoma_tx_abort" (func $abort (type 1)))
+
+                (func $_apply_tx (type 0) (param i64 i64)
+                (call $abort (i64.const 42) (i64.const 0) (i64.const {})))
+
+                (memory (;0;) 16)
+                (data (i32.const 0) "{}")
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#,
+                message.len(),
+                message
+            )
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = BlockGasMeter::default();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let error = tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            tx_code,
+            vec![],
+            &mut vp_cache,
+            &mut tx_cache,
+        )
+        .expect_err("Expected the tx to abort");
+
+        match error {
+            Error::TxAborted { code, message } => {
+                assert_eq!(code, 42);
+                assert_eq!(message, "insufficient funds");
+            }
+            _ => panic!("Expected an aborted tx, got {}", error),
+        }
+    }
+
+    /// Test that a tx that tries to write to a malformed storage key is
+    /// rejected with the key's parsing error
+    #[test]
+    fn test_tx_invalid_key() {
+        let key = "test//key";
+        let tx_code = wasmer::wat2wasm(
+            format!(
+                r#"
+            (module
+                (type (;0;) (func (param i64 i64)))
+                (type (;1;) (func (param i64 i64 i64 i64)))
+                (import "env" "anoma_tx_write" (func $write (type 1)))
+
+                (func $_apply_tx (type 0) (param i64 i64)
+                (call $write
+                    (i64.const 0) (i64.const {})
+                    (i64.const 0) (i64.const 1)))
+
+                (memory (;0;) 16)
+                (data (i32.const 0) "{}")
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#,
+                key.len(),
+                key
+            )
+            .as_bytes(),
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = BlockGasMeter::default();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let error = tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            tx_code,
+            vec![],
+            &mut vp_cache,
+            &mut tx_cache,
+        )
+        .expect_err("Expected the tx to be rejected");
+
+        match error {
+            Error::RuntimeError(err) => {
+                assert!(matches!(
+                    err.downcast::<TxRuntimeError>(),
+                    Ok(TxRuntimeError::StorageDataError(
+                        crate::types::storage::Error::EmptyKeySeg
+                    ))
+                ));
+            }
+            _ => panic!("Expected a runtime error, got {}", error),
+        }
+    }
+
     fn loop_in_tx_wasm(loops: u32) -> Result<BTreeSet<Address>> {
         // A transaction with a recursive loop.
         // The boilerplate code is generated from tx_template.wasm using
@@ -10,6 +10,10 @@
 //!
 //! To keep the temporary files created by a test, use env var
 //! `ANOMA_E2E_KEEP_TEMP=true`.
+//!
+//! On a slow machine, the timeouts of the commands ran by the tests can be
+//! scaled by a whole number factor with env var `ANOMA_E2E_TIMEOUT_SCALE`,
+//! e.g. `ANOMA_E2E_TIMEOUT_SCALE=2`.
 
 pub mod gossip_tests;
 pub mod helpers;
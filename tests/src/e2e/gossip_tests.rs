@@ -23,7 +23,7 @@ use crate::e2e::helpers::{
     find_address, get_actor_rpc, get_gossiper_mm_server,
 };
 use crate::e2e::setup::{self, Bin, Who};
-use crate::{run, run_as};
+use crate::{run, run_as, run_ledger_as};
 
 /// Test that when we "run-gossip" a peer with no seeds should fail
 /// bootstrapping kademlia. A peer with a seed should be able to
@@ -118,8 +118,7 @@ fn match_intents() -> Result<()> {
     println!("Done building the matchmaker.");
 
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
     ledger.exp_string("No state could be found")?;
     // Wait to commit a block
     ledger.exp_regex(r"Committed block hash.*, height: [0-9]+")?;
@@ -13,11 +13,12 @@ use std::process::Command;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use anoma::proto::TxBuilder;
 use anoma::types::token;
 use anoma_apps::config::genesis::genesis_config::{
     GenesisConfig, ParametersConfig, PosParamsConfig,
 };
-use borsh::BorshSerialize;
+use anoma_apps::config::{Config, DbType, TendermintMode};
 use color_eyre::eyre::Result;
 use setup::constants::*;
 
@@ -25,7 +26,7 @@ use crate::e2e::helpers::{
     find_address, find_voting_power, get_actor_rpc, get_epoch,
 };
 use crate::e2e::setup::{self, sleep, Bin, Who};
-use crate::{run, run_as};
+use crate::{run, run_as, run_ledger_as};
 
 /// Test that when we "run-ledger" with all the possible command
 /// combinations from fresh state, the node starts-up successfully for both a
@@ -38,16 +39,14 @@ fn run_ledger() -> Result<()> {
     // Start the ledger as a validator
     for args in &cmd_combinations {
         let mut ledger =
-            run_as!(test, Who::Validator(0), Bin::Node, args, Some(40))?;
-        ledger.exp_string("Anoma ledger node started")?;
+            run_ledger_as!(test, Who::Validator(0), args, Some(40))?;
         ledger.exp_string("This node is a validator")?;
     }
 
     // Start the ledger as a non-validator
     for args in &cmd_combinations {
         let mut ledger =
-            run_as!(test, Who::NonValidator, Bin::Node, args, Some(40))?;
-        ledger.exp_string("Anoma ledger node started")?;
+            run_ledger_as!(test, Who::NonValidator, args, Some(40))?;
         if !cfg!(feature = "ABCI") {
             ledger.exp_string("This node is a fullnode")?;
         } else {
@@ -69,9 +68,7 @@ fn test_anoma_shuts_down_if_tendermint_dies() -> Result<()> {
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
 
     // 2. Kill the tendermint node
     sleep(1);
@@ -105,9 +102,7 @@ fn run_ledger_load_state_and_reset() -> Result<()> {
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
     // There should be no previous state
     ledger.exp_string("No state could be found")?;
     // Wait to commit a block
@@ -123,9 +118,7 @@ fn run_ledger_load_state_and_reset() -> Result<()> {
 
     // 3. Run the ledger again, it should load its previous state
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
 
     // There should be previous state now
     ledger.exp_string("Last state root hash:")?;
@@ -148,9 +141,7 @@ fn run_ledger_load_state_and_reset() -> Result<()> {
 
     // 6. Run the ledger again, it should start from fresh state
     let mut session =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    session.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
 
     // There should be no previous state
     session.exp_string("No state could be found")?;
@@ -158,6 +149,125 @@ fn run_ledger_load_state_and_reset() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Disable empty blocks in the validator's config
+/// 2. Run the ledger node
+/// 3. Check that the Tendermint config doesn't create empty blocks
+#[test]
+fn run_ledger_without_empty_blocks() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Disable empty blocks in the validator's config
+    let base_dir = test.get_base_dir(&Who::Validator(0));
+    let mut config = Config::load(
+        &base_dir,
+        &test.net.chain_id,
+        Some(TendermintMode::Validator),
+    );
+    config.ledger.tendermint.consensus_create_empty_blocks = false;
+    config.write(&base_dir, &test.net.chain_id, true)?;
+
+    // 2. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    // The Tendermint config is updated before its node is started
+    ledger.exp_string("Tendermint node started")?;
+
+    // 3. Check that the Tendermint config doesn't create empty blocks
+    let tm_config_path = config
+        .ledger
+        .tendermint_dir()
+        .join("config")
+        .join("config.toml");
+    let tm_config = std::fs::read_to_string(tm_config_path)?;
+    // Depending on the Tendermint version, the config keys are either in
+    // snake or kebab case
+    let create_empty_blocks = tm_config.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim().replace('-', "_") == "create_empty_blocks")
+            .then(|| value.trim().to_owned())
+    });
+    assert_eq!(create_empty_blocks.as_deref(), Some("false"));
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Configure the validator to use the in-memory DB
+/// 2. Run the ledger node
+/// 3. Check that it commits blocks
+/// 4. Check that no DB files are created
+#[test]
+fn run_ledger_with_in_memory_db() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Configure the validator to use the in-memory DB
+    let base_dir = test.get_base_dir(&Who::Validator(0));
+    let mut config = Config::load(
+        &base_dir,
+        &test.net.chain_id,
+        Some(TendermintMode::Validator),
+    );
+    config.ledger.shell.db_type = DbType::InMemory;
+    config.write(&base_dir, &test.net.chain_id, true)?;
+
+    // 2. Run the ledger node
+    let mut ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
+    ledger.exp_string("Using an in-memory DB")?;
+    ledger.exp_string("Anoma ledger node started")?;
+
+    // 3. Check that it commits blocks
+    ledger.exp_string("Committed block hash")?;
+    ledger.exp_string("Committed block hash")?;
+
+    // 4. Check that no DB files are created
+    assert!(!config.ledger.db_dir().exists());
+
+    Ok(())
+}
+
+/// In this test we:
+/// 1. Bind the Tendermint RPC port configured for the validator
+/// 2. Run the ledger node
+/// 3. Check that it fails to start with a descriptive error
+#[test]
+fn run_ledger_with_port_in_use() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Bind the Tendermint RPC port configured for the validator
+    let base_dir = test.get_base_dir(&Who::Validator(0));
+    let config = Config::load(
+        &base_dir,
+        &test.net.chain_id,
+        Some(TendermintMode::Validator),
+    );
+    let rpc_address = config.ledger.tendermint.rpc_address;
+    let _bound = std::net::TcpListener::bind(rpc_address)?;
+
+    // 2. Run the ledger node
+    let ledger =
+        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(20));
+
+    // 3. Check that it fails to start with a descriptive error. The node may
+    // exit before the harness checks its status, in which case its output is
+    // in the error.
+    let expected = format!(
+        "Port {} already in use, cannot use {} for the Tendermint RPC",
+        rpc_address.port(),
+        rpc_address
+    );
+    match ledger {
+        Ok(mut ledger) => {
+            ledger.exp_string(&expected)?;
+            ledger.exp_eof()?;
+        }
+        Err(err) => assert!(err.to_string().contains(&expected), "{}", err),
+    }
+
+    Ok(())
+}
+
 /// In this test we:
 /// 1. Run the ledger node
 /// 2. Submit a token transfer tx
@@ -165,15 +275,15 @@ fn run_ledger_load_state_and_reset() -> Result<()> {
 /// 4. Submit a custom tx
 /// 5. Submit a tx to initialize a new account
 /// 6. Query token balance
+/// 7. Query the raw storage value of a token balance
+/// 8. Query the raw ABCI response for a token balance
 #[test]
 fn ledger_txs_and_queries() -> Result<()> {
     let test = setup::network(|genesis| genesis, None)?;
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
     if !cfg!(feature = "ABCI") {
         ledger.exp_string("started node")?;
     } else {
@@ -286,6 +396,12 @@ fn ledger_txs_and_queries() -> Result<()> {
         }
     }
 
+    let bertha_balance_key = token::balance_key(
+        &find_address(&test, XAN)?,
+        &find_address(&test, BERTHA)?,
+    )
+    .to_string();
+    let bertha_balance_path = format!("value/{}", bertha_balance_key);
     let query_args_and_expected_response = vec![
         // 6. Query token balance
         (
@@ -301,6 +417,30 @@ fn ledger_txs_and_queries() -> Result<()> {
             // expect a decimal
             r"XAN: \d+(\.\d+)?",
         ),
+        // 7. Query the raw storage value of a token balance
+        (
+            vec![
+                "query-storage",
+                "--key",
+                &bertha_balance_key,
+                "--ledger-address",
+                &validator_one_rpc,
+            ],
+            // expect a decoded decimal rather than hex
+            r"(?m)^\d+(\.\d+)?\r?$",
+        ),
+        // 8. Query the raw ABCI response for a token balance
+        (
+            vec![
+                "query-raw",
+                "--path",
+                &bertha_balance_path,
+                "--ledger-address",
+                &validator_one_rpc,
+            ],
+            // expect a successful response with the borsh encoded amount
+            r"(?s)Code: 0.*Value: [0-9a-f]{16}",
+        ),
     ];
     for (query_args, expected) in &query_args_and_expected_response {
         let mut client = run!(test, Bin::Client, query_args, Some(40))?;
@@ -312,6 +452,104 @@ fn ledger_txs_and_queries() -> Result<()> {
     Ok(())
 }
 
+/// In this test we:
+/// 1. Run the ledger node
+/// 2. Add a new alias for an existing address to the wallet
+/// 3. Submit a token transfer tx using the new alias as the source
+/// 4. Query the balance using the new alias as the owner
+/// 5. Submit a token transfer tx with an unknown alias
+#[test]
+fn ledger_txs_with_address_alias() -> Result<()> {
+    let test = setup::single_node_net()?;
+
+    // 1. Run the ledger node
+    let mut ledger =
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
+    if !cfg!(feature = "ABCI") {
+        ledger.exp_string("started node")?;
+    } else {
+        ledger.exp_string("Started node")?;
+    }
+
+    let validator_one_rpc = get_actor_rpc(&test, &Who::Validator(0));
+
+    // 2. Add a new alias for an existing address
+    let alias = "Bertha-Alias";
+    let bertha = find_address(&test, BERTHA)?;
+    let bertha = bertha.encode();
+    let mut cmd = run!(
+        test,
+        Bin::Wallet,
+        &["address", "add", "--alias", alias, "--address", &bertha],
+        Some(20),
+    )?;
+    cmd.assert_success();
+
+    // 3. Submit a token transfer tx using the alias
+    let tx_args = [
+        "transfer",
+        "--source",
+        alias,
+        "--target",
+        ALBERT,
+        "--token",
+        XAN,
+        "--amount",
+        "10.1",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    if !cfg!(feature = "ABCI") {
+        client.exp_string("Transaction accepted")?;
+    }
+    client.exp_string("Transaction applied")?;
+    client.exp_string("Transaction is valid.")?;
+    client.assert_success();
+
+    // 4. Query the balance using the alias
+    let query_args = [
+        "balance",
+        "--owner",
+        alias,
+        "--token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_regex(r"XAN: \d+(\.\d+)?")?;
+    client.assert_success();
+
+    // 5. Submit a token transfer tx with an unknown alias
+    let tx_args = [
+        "transfer",
+        "--source",
+        "Unknown-Alias",
+        "--target",
+        ALBERT,
+        "--token",
+        XAN,
+        "--amount",
+        "10.1",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("Unknown address Unknown-Alias.")?;
+    client.exp_string("The known address aliases are:")?;
+    client.exp_string(&alias.to_lowercase())?;
+    client.exp_eof()?;
+
+    Ok(())
+}
+
 /// In this test we:
 /// 1. Run the ledger node
 /// 2. Submit an invalid transaction (disallowed by state machine)
@@ -324,8 +562,7 @@ fn invalid_transactions() -> Result<()> {
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
     if !cfg!(feature = "ABCI") {
         ledger.exp_string("started node")?;
     } else {
@@ -343,11 +580,10 @@ fn invalid_transactions() -> Result<()> {
         token: find_address(&test, XAN)?,
         amount: token::Amount::whole(1),
     };
-    let data = transfer
-        .try_to_vec()
-        .expect("Encoding unsigned transfer shouldn't fail");
+    // The client signs the transaction, so we only need its unsigned data
+    let tx = TxBuilder::new().data(&transfer).build();
     let tx_wasm_path = wasm_abs_path(TX_MINT_TOKENS_WASM);
-    std::fs::write(&tx_data_path, data).unwrap();
+    std::fs::write(&tx_data_path, tx.data.unwrap()).unwrap();
     let tx_wasm_path = tx_wasm_path.to_string_lossy();
     let tx_data_path = tx_data_path.to_string_lossy();
 
@@ -377,7 +613,12 @@ fn invalid_transactions() -> Result<()> {
     }
     client.exp_string("Transaction applied")?;
     client.exp_string("Transaction is invalid")?;
+    // The token's VP must be listed among the rejecting VPs
+    let token = transfer.token.encode();
+    client.exp_string(&format!("Rejected: {};", token))?;
     client.exp_string(r#""code": "1"#)?;
+    client.exp_string(r#""rejected_vps": ["#)?;
+    client.exp_string(&token)?;
 
     client.assert_success();
     ledger.exp_string("some VPs rejected apply_tx storage modification")?;
@@ -395,9 +636,7 @@ fn invalid_transactions() -> Result<()> {
 
     // 4. Restart the ledger
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
 
     // There should be previous state now
     ledger.exp_string("Last state root hash:")?;
@@ -447,11 +686,13 @@ fn invalid_transactions() -> Result<()> {
 /// 1. Run the ledger node with shorter epochs for faster progression
 /// 2. Submit a self-bond for the genesis validator
 /// 3. Submit a delegation to the genesis validator
-/// 4. Submit an unbond of the self-bond
-/// 5. Submit an unbond of the delegation
-/// 6. Wait for the unbonding epoch
-/// 7. Submit a withdrawal of the self-bond
-/// 8. Submit a withdrawal of the delegation
+/// 4. Query the delegation
+/// 5. Try to submit a delegation larger than the source's balance
+/// 6. Submit an unbond of the self-bond
+/// 7. Submit an unbond of the delegation
+/// 8. Wait for the unbonding epoch
+/// 9. Submit a withdrawal of the self-bond
+/// 10. Submit a withdrawal of the delegation
 #[test]
 fn pos_bonds() -> Result<()> {
     let unbonding_len = 2;
@@ -479,9 +720,7 @@ fn pos_bonds() -> Result<()> {
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
     if !cfg!(feature = "ABCI") {
         ledger.exp_string("started node")?;
     } else {
@@ -533,7 +772,44 @@ fn pos_bonds() -> Result<()> {
     client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 4. Submit an unbond of the self-bond
+    // 4. Query the delegation
+    let query_args = vec![
+        "bonds",
+        "--owner",
+        BERTHA,
+        "--validator",
+        "validator-0",
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, query_args, Some(40))?;
+    client.exp_string("Delegations:")?;
+    client.exp_string("Δ 10.1")?;
+    client.assert_success();
+
+    // 5. Try to submit a delegation larger than the source's balance
+    let tx_args = vec![
+        "bond",
+        "--validator",
+        "validator-0",
+        "--source",
+        BERTHA,
+        "--amount",
+        "900000000",
+        "--fee-amount",
+        "0",
+        "--gas-limit",
+        "0",
+        "--fee-token",
+        XAN,
+        "--ledger-address",
+        &validator_one_rpc,
+    ];
+    let mut client = run!(test, Bin::Client, tx_args, Some(40))?;
+    client.exp_string("is lower than the amount to be bonded")?;
+    client.exp_eof()?;
+
+    // 6. Submit an unbond of the self-bond
     let tx_args = vec![
         "unbond",
         "--validator",
@@ -554,7 +830,7 @@ fn pos_bonds() -> Result<()> {
     client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 5. Submit an unbond of the delegation
+    // 7. Submit an unbond of the delegation
     let tx_args = vec![
         "unbond",
         "--validator",
@@ -576,7 +852,7 @@ fn pos_bonds() -> Result<()> {
     client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 6. Wait for the unbonding epoch
+    // 8. Wait for the unbonding epoch
     let epoch = get_epoch(&test, &validator_one_rpc)?;
     let earliest_withdrawal_epoch = epoch + unbonding_len;
     println!(
@@ -598,7 +874,7 @@ fn pos_bonds() -> Result<()> {
         }
     }
 
-    // 7. Submit a withdrawal of the self-bond
+    // 9. Submit a withdrawal of the self-bond
     let tx_args = vec![
         "withdraw",
         "--validator",
@@ -617,7 +893,7 @@ fn pos_bonds() -> Result<()> {
     client.exp_string("Transaction is valid.")?;
     client.assert_success();
 
-    // 8. Submit a withdrawal of the delegation
+    // 10. Submit a withdrawal of the delegation
     let tx_args = vec![
         "withdraw",
         "--validator",
@@ -676,9 +952,7 @@ fn pos_init_validator() -> Result<()> {
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(test, Who::Validator(0), &["ledger"], Some(40))?;
     if !cfg!(feature = "ABCI") {
         ledger.exp_string("started node")?;
     } else {
@@ -842,9 +1116,7 @@ fn ledger_many_txs_in_a_block() -> Result<()> {
 
     // 1. Run the ledger node
     let mut ledger =
-        run_as!(*test, Who::Validator(0), Bin::Node, &["ledger"], Some(40))?;
-
-    ledger.exp_string("Anoma ledger node started")?;
+        run_ledger_as!(*test, Who::Validator(0), &["ledger"], Some(40))?;
     if !cfg!(feature = "ABCI") {
         ledger.exp_string("started node")?;
     } else {
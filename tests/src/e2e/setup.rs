@@ -31,6 +31,22 @@ pub const ENV_VAR_DEBUG: &str = "ANOMA_E2E_DEBUG";
 /// Env. var for keeping temporary files created by the E2E tests
 const ENV_VAR_KEEP_TEMP: &str = "ANOMA_E2E_KEEP_TEMP";
 
+/// Env. var for scaling the timeouts of the commands ran by the E2E tests by
+/// a whole number factor, e.g. `ANOMA_E2E_TIMEOUT_SCALE=2` doubles them on a
+/// slow machine
+pub const ENV_VAR_TIMEOUT_SCALE: &str = "ANOMA_E2E_TIMEOUT_SCALE";
+
+/// The number of times a node that didn't start within its timeout is started
+/// again before giving up
+pub const NODE_START_RETRIES: u64 = 2;
+
+/// The output of a ledger node once it has started
+pub const LEDGER_STARTED: &str = "Anoma ledger node started";
+
+/// The log filter of the commands ran by the E2E tests. The matchmakers log
+/// under their own crate names, which must be enabled too.
+const LOG_FILTER: &str = "anoma=debug,mm_token_exch=debug,mm_auction=debug";
+
 /// The E2E tests genesis config source.
 /// This file must contain a single validator with alias "validator-0".
 /// To add more validators, use the [`add_validators`] function in the call to
@@ -343,6 +359,40 @@ mod macros {
             $test.run_cmd_as($who, $bin, $args, $timeout_sec, loc)
         }};
     }
+
+    /// Run a ledger node and wait until it has started. A node that doesn't
+    /// start within the timeout, e.g. because of a transient slowness, is
+    /// killed and started again, up to [`super::NODE_START_RETRIES`] times.
+    ///
+    /// Arguments:
+    /// - the test [`super::Test`]
+    /// - who to run this command as [`super::Who`]
+    /// - arguments, which implement `IntoIterator<item = &str>`, e.g.
+    ///   `&["ledger"]`
+    /// - optional timeout in seconds `Option<u64>`
+    #[macro_export]
+    macro_rules! run_ledger_as {
+        (
+            $test:expr,
+            $who:expr,
+            $args:expr,
+            $timeout_sec:expr $(,)?
+        ) => {{
+            $crate::e2e::setup::spawn_until(
+                || {
+                    $crate::run_as!(
+                        $test,
+                        $who,
+                        $crate::e2e::setup::Bin::Node,
+                        $args,
+                        $timeout_sec
+                    )
+                },
+                $crate::e2e::setup::LEDGER_STARTED,
+                $crate::e2e::setup::NODE_START_RETRIES,
+            )
+        }};
+    }
 }
 
 pub enum Who {
@@ -590,7 +640,7 @@ where
         cmd.release()
     };
     let mut cmd = cmd.run().unwrap().command();
-    cmd.env("ANOMA_LOG", "anoma=debug")
+    cmd.env("ANOMA_LOG", LOG_FILTER)
         .current_dir(working_dir)
         .args(&[
             "--base-dir",
@@ -601,7 +651,7 @@ where
         .args(args);
     let cmd_str = format!("{:?}", cmd);
 
-    let timeout_ms = timeout_sec.map(|sec| sec * 1_000);
+    let timeout_ms = timeout_sec.map(|sec| sec * timeout_scale() * 1_000);
     println!("{}: {}", "Running".underline().green(), cmd_str);
     let mut session = spawn_command(cmd, timeout_ms).map_err(|e| {
         eyre!(
@@ -648,6 +698,102 @@ pub fn sleep(seconds: u64) {
     thread::sleep(time::Duration::from_secs(seconds));
 }
 
+/// The factor by which the timeouts of commands are scaled, configured with
+/// the [`ENV_VAR_TIMEOUT_SCALE`] env. var. Defaults to `1`.
+pub fn timeout_scale() -> u64 {
+    match env::var(ENV_VAR_TIMEOUT_SCALE) {
+        Ok(val) => match val.parse() {
+            Ok(scale) if scale > 0 => scale,
+            _ => panic!(
+                "The env. var {} must be a positive whole number, got {}",
+                ENV_VAR_TIMEOUT_SCALE, val
+            ),
+        },
+        _ => 1,
+    }
+}
+
+/// Spawn a command with `spawn` and wait until it outputs the `needle`. If it
+/// doesn't within the command's timeout, the command is killed and spawned
+/// again, up to `retries` times. If the command exits before outputting the
+/// `needle`, it has failed rather than being slow, so its error is returned
+/// right away. Use the `run_ledger_as!` macro to start a ledger node this way.
+pub fn spawn_until(
+    mut spawn: impl FnMut() -> Result<AnomaCmd>,
+    needle: &str,
+    retries: u64,
+) -> Result<AnomaCmd> {
+    let mut attempt = 0;
+    loop {
+        let mut cmd = spawn()?;
+        match cmd.session.exp_string(needle) {
+            Ok(_) => return Ok(cmd),
+            Err(err)
+                if attempt < retries
+                    && matches!(
+                        err.kind(),
+                        rexpect::errors::ErrorKind::Timeout(..)
+                    ) =>
+            {
+                attempt += 1;
+                println!(
+                    "{} ({}/{}): {}\n{}: {}",
+                    "Retrying".underline().yellow(),
+                    attempt,
+                    retries,
+                    cmd.cmd_str,
+                    "Error".underline().yellow(),
+                    err
+                );
+                // Kill the command before it gets dropped
+                let _ = cmd.session.process.exit();
+            }
+            Err(err) => return Err(eyre!(format!("{}", err))),
+        }
+    }
+}
+
+/// Check that a command that doesn't output the expected string within its
+/// timeout is spawned again by [`spawn_until`]
+#[test]
+fn test_spawn_until_retries_slow_cmd() {
+    let attempts = std::cell::Cell::new(0);
+    let spawn = || {
+        attempts.set(attempts.get() + 1);
+        // Only the first spawn is too slow for the timeout
+        let delay_sec = if attempts.get() == 1 { 10 } else { 0 };
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("sleep {}; echo {}", delay_sec, LEDGER_STARTED));
+        let cmd_str = format!("{:?}", cmd);
+        let session = spawn_command(cmd, Some(2_000))
+            .map_err(|e| eyre!(format!("{}", e)))?;
+        Ok(AnomaCmd { session, cmd_str })
+    };
+
+    spawn_until(spawn, LEDGER_STARTED, 1).unwrap();
+    assert_eq!(attempts.get(), 2);
+}
+
+/// Check that a command that exits without outputting the expected string is
+/// not spawned again by [`spawn_until`]
+#[test]
+fn test_spawn_until_fails_on_exit() {
+    let attempts = std::cell::Cell::new(0);
+    let spawn = || {
+        attempts.set(attempts.get() + 1);
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo crashed; exit 1");
+        let cmd_str = format!("{:?}", cmd);
+        let session = spawn_command(cmd, Some(2_000))
+            .map_err(|e| eyre!(format!("{}", e)))?;
+        Ok(AnomaCmd { session, cmd_str })
+    };
+
+    assert!(spawn_until(spawn, LEDGER_STARTED, 3).is_err());
+    assert_eq!(attempts.get(), 1);
+}
+
 #[allow(dead_code)]
 pub mod constants {
     use std::fs;
@@ -20,14 +20,18 @@ mod tests {
     use std::panic;
 
     use anoma::ibc::tx_msg::Msg;
+    use anoma::ledger::gas::GasSchedule;
     use anoma::ledger::ibc::handler::IbcActions;
-    use anoma::ledger::ibc::vp::Error as IbcError;
+    use anoma::ledger::ibc::vp::{Error as IbcError, IbcTokenError};
+    use anoma::ledger::parameters::{self, EpochDuration, Parameters};
     use anoma::proto::{SignedTxData, Tx};
     use anoma::tendermint_proto::Protobuf;
+    use anoma::types::internal::InternalAddressKind;
     use anoma::types::key::*;
     use anoma::types::storage::{self, BlockHash, BlockHeight, Key, KeySeg};
-    use anoma::types::time::DateTimeUtc;
+    use anoma::types::time::{DateTimeUtc, DurationSecs};
     use anoma::types::token::{self, Amount};
+    use anoma::types::transaction::hash_tx;
     use anoma::types::{address, key};
     use anoma_vm_env::tx_prelude::{
         BorshDeserialize, BorshSerialize, KeyValIterator,
@@ -80,6 +84,53 @@ mod tests {
         );
     }
 
+    /// Test that the same tx consumes a different amount of gas on chains
+    /// initialized with different gas schedules
+    #[test]
+    fn test_tx_gas_schedule() {
+        let run_tx = |gas_schedule: GasSchedule| -> u64 {
+            // Initialize the chain's parameters with the given gas schedule
+            let mut env = TestTxEnv::default();
+            let params = Parameters {
+                epoch_duration: EpochDuration {
+                    min_num_of_blocks: 1,
+                    min_duration: DurationSecs(5),
+                },
+                max_expected_time_per_block: DurationSecs(30),
+                vp_whitelist: vec![],
+                tx_whitelist: vec![],
+                gas_schedule,
+                wasm_limits: Default::default(),
+            };
+            parameters::init_genesis_storage(&mut env.storage, &params);
+            init_tx_env(&mut env);
+
+            let key = "key";
+            tx_host_env::write(key, "test".repeat(4));
+            let _value: Option<String> = tx_host_env::read(key);
+            assert!(tx_host_env::has_key(key));
+            tx_host_env::delete(key);
+
+            env.gas_meter.get_current_transaction_gas()
+        };
+
+        let default_gas = run_tx(GasSchedule::default());
+        let expensive_gas = run_tx(GasSchedule {
+            storage_read_base: 100,
+            storage_write_base: 200,
+            storage_per_byte: 10,
+            ..GasSchedule::default()
+        });
+        // The tx makes two reads and two writes, so the base costs alone make
+        // up the difference of 600 gas, on top of the higher cost per byte
+        assert!(
+            expensive_gas > default_gas + 600,
+            "expected more than {} gas, got {}",
+            default_gas + 600,
+            expensive_gas
+        );
+    }
+
     #[test]
     fn test_tx_has_key() {
         // The environment must be initialized first
@@ -102,6 +153,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_write_cas() {
+        // The environment must be initialized first
+        let mut env = TestTxEnv::default();
+        // Write some value to storage
+        let key = "key";
+        let storage_key = Key::parse(key).unwrap();
+        let value = "storage".to_string();
+        env.storage
+            .write(&storage_key, value.try_to_vec().unwrap())
+            .unwrap();
+        init_tx_env(&mut env);
+
+        // The value in storage matches the expected value
+        let new_value = "first".to_string();
+        assert!(
+            tx_host_env::write_cas(key, value.clone(), new_value.clone()),
+            "The CAS should succeed when the current value is the expected one"
+        );
+        let read_value: Option<String> = tx_host_env::read(key);
+        assert_eq!(Some(new_value.clone()), read_value);
+
+        // The value in the write log no longer matches the storage value
+        assert!(
+            !tx_host_env::write_cas(key, value, "second".to_string()),
+            "The CAS should fail when the current value is not the expected \
+             one"
+        );
+        let read_value: Option<String> = tx_host_env::read(key);
+        assert_eq!(
+            Some(new_value),
+            read_value,
+            "A failed CAS shouldn't write anything"
+        );
+
+        // A key that is not present doesn't match any expected value
+        let missing_key = "missing_key";
+        assert!(!tx_host_env::write_cas(
+            missing_key,
+            String::new(),
+            "value".to_string()
+        ));
+        assert!(!tx_host_env::has_key(missing_key));
+    }
+
     #[test]
     fn test_tx_delete() {
         // The environment must be initialized first
@@ -190,6 +286,17 @@ mod tests {
         );
     }
 
+    #[test]
+    #[should_panic(expected = "Aborted { code: 42, message: \"insufficient \
+                               funds\" }")]
+    fn test_tx_abort() {
+        // The environment must be initialized first
+        let mut env = TestTxEnv::default();
+        init_tx_env(&mut env);
+
+        tx_host_env::abort(42, "insufficient funds");
+    }
+
     #[test]
     #[should_panic]
     fn test_tx_init_account_with_invalid_vp() {
@@ -212,6 +319,60 @@ mod tests {
         tx_host_env::init_account(code);
     }
 
+    #[test]
+    fn test_tx_init_account_derived() {
+        let mut env = TestTxEnv::default();
+        let parent = address::testing::established_address_1();
+        env.storage
+            .write(&Key::validity_predicate(&parent), vec![])
+            .unwrap();
+        init_tx_env(&mut env);
+
+        let code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+        let addr_1 = tx_host_env::init_account_derived(&parent, &code);
+        let addr_2 = tx_host_env::init_account_derived(&parent, &code);
+        assert_ne!(addr_1, addr_2);
+        assert_ne!(addr_1, parent);
+        assert_ne!(addr_2, parent);
+    }
+
+    #[test]
+    fn test_tx_init_account_derived_with_invalid_parent() {
+        let mut env = TestTxEnv::default();
+        init_tx_env(&mut env);
+
+        let code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        // The parent account doesn't exist
+        let parent = address::testing::established_address_1();
+        assert!(
+            panic::catch_unwind(|| {
+                tx_host_env::init_account_derived(&parent, &code)
+            })
+            .err()
+            .map(|a| a.downcast_ref::<String>().cloned().unwrap())
+            .unwrap()
+            .contains("InitAccountUnknownParent")
+        );
+
+        // The parent is not an established address
+        let parent = address::testing::gen_implicit_address();
+        assert!(
+            panic::catch_unwind(|| {
+                tx_host_env::init_account_derived(&parent, &code)
+            })
+            .err()
+            .map(|a| a.downcast_ref::<String>().cloned().unwrap())
+            .unwrap()
+            .contains("InitAccountInvalidParent")
+        );
+
+        // Nothing has been written by the rejected calls
+        assert!(env.write_log.get_keys().is_empty());
+    }
+
     #[test]
     fn test_tx_get_metadata() {
         // The environment must be initialized first
@@ -233,6 +394,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_get_tx_code_hash() {
+        // Use some arbitrary bytes for tx code
+        let code = vec![4, 3, 2, 1, 0];
+        let mut env = TestTxEnv {
+            tx_code_hash: hash_tx(&code),
+            ..Default::default()
+        };
+        // The environment must be initialized first
+        init_tx_env(&mut env);
+
+        assert_eq!(tx_host_env::get_tx_code_hash(), hash_tx(&code));
+        assert_ne!(tx_host_env::get_tx_code_hash(), hash_tx(&[]));
+    }
+
+    #[test]
+    fn test_tx_is_internal_address() {
+        // The environment must be initialized first
+        let mut env = TestTxEnv::default();
+        init_tx_env(&mut env);
+
+        let pos = address::Address::Internal(address::InternalAddress::PoS);
+        assert_eq!(
+            tx_host_env::is_internal_address(&pos),
+            InternalAddressKind::PoS
+        );
+        let user = address::testing::established_address_1();
+        let kind = tx_host_env::is_internal_address(&user);
+        assert_eq!(kind, InternalAddressKind::NotInternal);
+        assert!(!kind.is_internal());
+    }
+
     /// An example how to write a VP host environment integration test
     #[test]
     fn test_vp_host_env() {
@@ -339,6 +532,164 @@ mod tests {
         );
     }
 
+    /// Test that the prior state read by a VP doesn't see the writes of a
+    /// tx applied earlier in the same block, including its temporary writes
+    #[test]
+    fn test_vp_read_pre_with_earlier_tx_in_block() {
+        let mut tx_env = TestTxEnv::default();
+
+        let addr = address::testing::established_address_1();
+        let addr_key = Key::from(addr.to_db_key());
+
+        // Write some value to storage
+        let key = addr_key.join(&Key::parse("key").unwrap());
+        let key_raw = key.to_string();
+        let existing_value = "existing".to_string();
+        tx_env
+            .storage
+            .write(&key, existing_value.try_to_vec().unwrap())
+            .unwrap();
+
+        // Apply the first tx in the block and commit its write log, as it's
+        // done when all of its VPs accept it
+        init_tx_env(&mut tx_env);
+        let first_value = "first".to_string();
+        tx_host_env::write(&key_raw, &first_value);
+        let temp_key_raw =
+            addr_key.join(&Key::parse("temp").unwrap()).to_string();
+        tx_host_env::write_temp(&temp_key_raw, "temp".to_string());
+        tx_env.write_log.commit_tx();
+
+        // Initialize the VP environment via the second tx in the block
+        // The `_vp_env` MUST NOT be dropped until the end of the test
+        let second_value = "second".to_string();
+        let _vp_env = init_vp_env_from_tx(addr, tx_env, |_addr| {
+            tx_host_env::write(&key_raw, &second_value);
+        });
+
+        let pre_value: Option<String> = vp_host_env::read_pre(&key_raw);
+        assert_eq!(
+            Some(existing_value),
+            pre_value,
+            "The value read from state before transaction shouldn't contain \
+             the writes of the earlier tx in the block"
+        );
+        // The temporary write of the earlier tx is held in the block write
+        // log, but it's not part of the prior state
+        let pre_temp_value: Option<String> =
+            vp_host_env::read_pre(&temp_key_raw);
+        assert_eq!(None, pre_temp_value);
+        assert!(!vp_host_env::has_key_pre(&temp_key_raw));
+        let post_value: Option<String> = vp_host_env::read_post(&key_raw);
+        assert_eq!(Some(second_value), post_value);
+    }
+
+    #[test]
+    fn test_vp_read_batch() {
+        let mut tx_env = TestTxEnv::default();
+
+        let addr = address::testing::established_address_1();
+        let addr_key = Key::from(addr.to_db_key());
+
+        // Write some values to storage
+        let existing_keys: Vec<String> = (0..5)
+            .map(|i| {
+                let key = addr_key
+                    .join(&Key::parse(format!("existing_key_{}", i)).unwrap());
+                tx_env.storage.write(&key, vec![i as u8; 10]).unwrap();
+                key.to_string()
+            })
+            .collect();
+
+        // In a transaction, override one of the existing keys, delete another
+        // one and add a new key
+        let new_key =
+            addr_key.join(&Key::parse("new_key").unwrap()).to_string();
+        let _vp_env = init_vp_env_from_tx(addr, tx_env, |_addr| {
+            tx_host_env::write(&existing_keys[0], "override");
+            tx_host_env::delete(&existing_keys[1]);
+            tx_host_env::write(&new_key, "new");
+        });
+
+        let mut keys = existing_keys.clone();
+        keys.push(new_key);
+        keys.push(addr_key.join(&Key::parse("missing").unwrap()).to_string());
+
+        let single_pre: Vec<Option<Vec<u8>>> =
+            keys.iter().map(vp_host_env::read_bytes_pre).collect();
+        let batch_pre = vp_host_env::read_batch_bytes_pre(&keys);
+        assert_eq!(single_pre, batch_pre);
+
+        let single_post: Vec<Option<Vec<u8>>> =
+            keys.iter().map(vp_host_env::read_bytes_post).collect();
+        let batch_post = vp_host_env::read_batch_bytes_post(&keys);
+        assert_eq!(single_post, batch_post);
+        assert_ne!(batch_pre, batch_post);
+    }
+
+    #[test]
+    fn test_vp_read_vp() {
+        let mut env = TestVpEnv::default();
+
+        // Assign a VP to an account as it would be done in genesis
+        let addr = address::testing::established_address_2();
+        let vp_key = Key::validity_predicate(&addr);
+        let genesis_vp =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+        env.storage.write(&vp_key, genesis_vp.clone()).unwrap();
+
+        // Update the VP in the write log, as if by a transaction
+        let updated_vp =
+            std::fs::read(VP_ALWAYS_FALSE_WASM).expect("cannot load wasm");
+        env.write_log.write(&vp_key, updated_vp.clone()).unwrap();
+        init_vp_env(&mut env);
+
+        assert_eq!(
+            vp_host_env::read_vp_pre(&addr),
+            Some(genesis_vp),
+            "The VP code before transaction should be the genesis one"
+        );
+        assert_eq!(
+            vp_host_env::read_vp_post(&addr),
+            Some(updated_vp),
+            "The VP code after transaction should be the updated one"
+        );
+
+        // An address without a VP
+        let unknown_addr = address::testing::established_address_3();
+        assert_eq!(vp_host_env::read_vp_pre(&unknown_addr), None);
+        assert_eq!(vp_host_env::read_vp_post(&unknown_addr), None);
+    }
+
+    /// Test that the bytes of a read VP code are only charged once, by the
+    /// storage read
+    #[test]
+    fn test_vp_read_vp_gas() {
+        let mut env = TestVpEnv::default();
+
+        // Two addresses of the same length with VPs of different lengths
+        let short_addr = address::testing::established_address_1();
+        let long_addr = address::testing::established_address_2();
+        let short_key = Key::validity_predicate(&short_addr);
+        let long_key = Key::validity_predicate(&long_addr);
+        env.storage.write(&short_key, vec![0_u8; 10]).unwrap();
+        env.storage.write(&long_key, vec![0_u8; 1010]).unwrap();
+        let schedule = &env.storage.gas_schedule;
+        let expected_gas_diff = schedule
+            .storage_read((long_key.len() + 1010) as u64)
+            - schedule.storage_read((short_key.len() + 10) as u64);
+        init_vp_env(&mut env);
+
+        let read_vp_gas = |addr: &address::Address| {
+            let gas_before = env.gas_meter.current_gas;
+            assert!(vp_host_env::read_vp_pre(addr).is_some());
+            env.gas_meter.current_gas - gas_before
+        };
+        let short_gas = read_vp_gas(&short_addr);
+        let long_gas = read_vp_gas(&long_addr);
+        assert_eq!(long_gas - short_gas, expected_gas_diff);
+    }
+
     #[test]
     fn test_vp_iter_prefix() {
         let mut tx_env = TestTxEnv::default();
@@ -386,6 +737,54 @@ mod tests {
         itertools::assert_equal(iter_post.sorted(), expected_post.sorted());
     }
 
+    #[test]
+    fn test_vp_prefix_count() {
+        let mut tx_env = TestTxEnv::default();
+
+        let addr = address::testing::established_address_1();
+        let addr_key = Key::from(addr.to_db_key());
+
+        // Write some value to storage
+        let prefix = addr_key.join(&Key::parse("prefix").unwrap());
+        let key_raw = |i: i32| format!("{}/{}", prefix, i);
+        for i in 0..10_i32 {
+            let key = Key::parse(key_raw(i)).unwrap();
+            let value = i.try_to_vec().unwrap();
+            tx_env.storage.write(&key, value).unwrap();
+        }
+        // A key whose segment only starts with the prefix's last segment
+        let other_prefix = addr_key.join(&Key::parse("prefixes").unwrap());
+        let other_key_raw = |i: i32| format!("{}/{}", other_prefix, i);
+        let other_key = Key::parse(other_key_raw(0)).unwrap();
+        tx_env
+            .storage
+            .write(&other_key, 0_i32.try_to_vec().unwrap())
+            .unwrap();
+        tx_env.storage.commit().unwrap();
+
+        // Initialize the VP environment via a transaction
+        // The `_vp_env` MUST NOT be dropped until the end of the test
+        let _vp_env = init_vp_env_from_tx(addr, tx_env, |_addr| {
+            // Delete some of the existing keys
+            tx_host_env::delete(key_raw(3));
+            tx_host_env::delete(key_raw(7));
+
+            // Override one of the existing keys
+            tx_host_env::write(key_raw(5), 100_i32);
+
+            // Write a new key-value under the same prefix
+            tx_host_env::write(key_raw(11), 11_i32);
+
+            // Write a new key-value that doesn't match the prefix
+            tx_host_env::write(other_key_raw(1), 1_i32);
+        });
+
+        // The 10 keys from storage, without the 2 deleted and with the new one
+        assert_eq!(vp_host_env::prefix_count(prefix.to_string()), 9);
+        let missing_prefix = addr_key.join(&Key::parse("missing").unwrap());
+        assert_eq!(vp_host_env::prefix_count(missing_prefix.to_string()), 0);
+    }
+
     #[test]
     fn test_vp_verify_tx_signature() {
         let mut env = TestVpEnv::default();
@@ -450,6 +849,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_vp_get_block_time() {
+        let mut env = TestVpEnv::default();
+        // Set the block header that the tx is being applied against
+        let header = ibc::tm_dummy_header();
+        env.storage.set_header(header.clone()).unwrap();
+        // The environment must be initialized first
+        init_vp_env(&mut env);
+
+        assert_eq!(vp_host_env::get_block_time().0, header.time.to_rfc3339());
+    }
+
+    #[test]
+    fn test_vp_get_tx_code_hash() {
+        // A VP that only accepts transactions with the approved code
+        let approved_code = vec![4, 3, 2, 1, 0];
+        let approved_hash = hash_tx(&approved_code);
+        let is_approved = || vp_host_env::get_tx_code_hash() == approved_hash;
+
+        let mut env = TestVpEnv {
+            tx: Tx::new(approved_code, None),
+            ..Default::default()
+        };
+        // The environment must be initialized first
+        init_vp_env(&mut env);
+        assert_eq!(vp_host_env::get_tx_code_hash(), approved_hash);
+        assert!(is_approved());
+
+        // A tx with any other code must be rejected
+        let mut env = TestVpEnv {
+            tx: Tx::new(vec![0, 1, 2, 3, 4], None),
+            ..Default::default()
+        };
+        init_vp_env(&mut env);
+        assert!(!is_approved());
+    }
+
+    #[test]
+    fn test_vp_is_internal_address() {
+        // The environment must be initialized first
+        let mut env = TestVpEnv::default();
+        init_vp_env(&mut env);
+
+        let pos = address::Address::Internal(address::InternalAddress::PoS);
+        assert_eq!(
+            vp_host_env::is_internal_address(&pos),
+            InternalAddressKind::PoS
+        );
+        let escrow = address::Address::Internal(
+            address::InternalAddress::ibc_escrow_address(
+                "transfer".to_string(),
+                "channel-0".to_string(),
+            ),
+        );
+        assert_eq!(
+            vp_host_env::is_internal_address(&escrow),
+            InternalAddressKind::IbcEscrow
+        );
+        let user = address::testing::gen_implicit_address();
+        assert_eq!(
+            vp_host_env::is_internal_address(&user),
+            InternalAddressKind::NotInternal
+        );
+    }
+
     #[test]
     fn test_vp_eval() {
         // The environment must be initialized first
@@ -1153,6 +1617,10 @@ mod tests {
             env.storage.write(&key, val).expect("write error");
         });
 
+        // Commit the initial state
+        env.write_log.commit_tx();
+        env.write_log.commit_block(&mut env.storage).unwrap();
+
         // Start a transaction to send a packet
         let msg =
             ibc::msg_transfer(port_id, channel_id, token.to_string(), &sender);
@@ -1224,6 +1692,146 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ibc_send_token_with_mismatched_escrow() {
+        // The environment must be initialized first
+        let mut env = TestTxEnv::default();
+        init_tx_env(&mut env);
+
+        // Set the initial state before starting transactions
+        let (token, sender) = ibc::init_storage(&mut env.storage);
+        let (client_id, _client_state, mut writes) = ibc::prepare_client();
+        let (conn_id, conn_writes) = ibc::prepare_opened_connection(&client_id);
+        writes.extend(conn_writes);
+        let (port_id, channel_id, channel_writes) =
+            ibc::prepare_opened_channel(&conn_id);
+        writes.extend(channel_writes);
+        writes.into_iter().for_each(|(key, val)| {
+            env.storage.write(&key, val).expect("write error");
+        });
+
+        // Commit the initial state
+        env.write_log.commit_tx();
+        env.write_log.commit_block(&mut env.storage).unwrap();
+
+        // Start a transaction to send a packet
+        let msg =
+            ibc::msg_transfer(port_id, channel_id, token.to_string(), &sender);
+        let mut tx_data = vec![];
+        msg.clone()
+            .to_any()
+            .encode(&mut tx_data)
+            .expect("encoding failed");
+        let tx = Tx {
+            code: vec![],
+            data: Some(tx_data.clone()),
+            timestamp: DateTimeUtc::now(),
+        }
+        .sign(&key::testing::keypair_1());
+        // send the token and a packet with the data
+        ibc::TestIbcActions
+            .dispatch(&tx_data)
+            .expect("sending a packet failed");
+
+        // Restore the sender's balance, so that the escrowed tokens come out
+        // of thin air
+        let sender_key = token::balance_key(&token, &sender);
+        let init_bal = Amount::from(1_000_000_000u64).try_to_vec().unwrap();
+        env.write_log.write(&sender_key, init_bal).unwrap();
+
+        // Check
+        let escrow = address::Address::Internal(
+            address::InternalAddress::ibc_escrow_address(
+                msg.source_port.to_string(),
+                msg.source_channel.to_string(),
+            ),
+        );
+        let (token_vp, _) = ibc::init_token_vp_from_tx(&env, &tx, &escrow);
+        let result = token_vp.validate(tx.data.as_ref().unwrap());
+        assert!(
+            matches!(result, Err(IbcTokenError::TokenTransfer(_))),
+            "The token VP should reject the transfer, got {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_ibc_send_token_near_amount_bound() {
+        // The environment must be initialized first
+        let mut env = TestTxEnv::default();
+        init_tx_env(&mut env);
+
+        // Set the initial state before starting transactions
+        let (token, sender) = ibc::init_storage(&mut env.storage);
+        // The sender owns the maximum amount of the token
+        let sender_key = token::balance_key(&token, &sender);
+        let max_bal = Amount::max().try_to_vec().unwrap();
+        env.write_log.write(&sender_key, max_bal).unwrap();
+        let (client_id, _client_state, mut writes) = ibc::prepare_client();
+        let (conn_id, conn_writes) = ibc::prepare_opened_connection(&client_id);
+        writes.extend(conn_writes);
+        let (port_id, channel_id, channel_writes) =
+            ibc::prepare_opened_channel(&conn_id);
+        writes.extend(channel_writes);
+        writes.into_iter().for_each(|(key, val)| {
+            env.storage.write(&key, val).expect("write error");
+        });
+
+        // Commit the initial state
+        env.write_log.commit_tx();
+        env.write_log.commit_block(&mut env.storage).unwrap();
+
+        // Start a transaction to send the whole balance
+        let mut msg =
+            ibc::msg_transfer(port_id, channel_id, token.to_string(), &sender);
+        if let Some(coin) = msg.token.as_mut() {
+            coin.amount = "18446744073709.551615".to_string();
+        }
+        let mut tx_data = vec![];
+        msg.clone()
+            .to_any()
+            .encode(&mut tx_data)
+            .expect("encoding failed");
+        let tx = Tx {
+            code: vec![],
+            data: Some(tx_data.clone()),
+            timestamp: DateTimeUtc::now(),
+        }
+        .sign(&key::testing::keypair_1());
+        // send the token and a packet with the data
+        ibc::TestIbcActions
+            .dispatch(&tx_data)
+            .expect("sending a packet failed");
+
+        // Check that escrowing the maximum amount is valid
+        let escrow = address::Address::Internal(
+            address::InternalAddress::ibc_escrow_address(
+                msg.source_port.to_string(),
+                msg.source_channel.to_string(),
+            ),
+        );
+        let (token_vp, _) = ibc::init_token_vp_from_tx(&env, &tx, &escrow);
+        assert!(
+            token_vp
+                .validate(tx.data.as_ref().unwrap())
+                .expect("token validation failed unexpectedly")
+        );
+
+        // Leave a single micro unit with the sender, so that the escrowed
+        // amount exceeds the sender's balance change by one
+        let one_bal = Amount::from(1u64).try_to_vec().unwrap();
+        env.write_log.write(&sender_key, one_bal).unwrap();
+
+        // Check
+        let (token_vp, _) = ibc::init_token_vp_from_tx(&env, &tx, &escrow);
+        let result = token_vp.validate(tx.data.as_ref().unwrap());
+        assert!(
+            matches!(result, Err(IbcTokenError::TokenTransfer(_))),
+            "The token VP should reject the transfer, got {:?}",
+            result
+        );
+    }
+
     #[test]
     fn test_ibc_burn_token() {
         // The environment must be initialized first
@@ -1366,6 +1974,10 @@ mod tests {
         let val = Amount::from(1_000_000_000u64).try_to_vec().unwrap();
         env.storage.write(&key, val).expect("write error");
 
+        // Commit the initial state
+        env.write_log.commit_tx();
+        env.write_log.commit_block(&mut env.storage).unwrap();
+
         // Set this chain as the source zone
         let token = format!(
             "{}/{}/{}",
@@ -7,8 +7,10 @@ use anoma::ledger::storage::mockdb::MockDB;
 use anoma::ledger::storage::testing::TestStorage;
 use anoma::ledger::storage::write_log::WriteLog;
 use anoma::types::address::Address;
+use anoma::types::hash::Hash;
 use anoma::types::storage::Key;
 use anoma::types::time::DurationSecs;
+use anoma::types::transaction::hash_tx;
 use anoma::types::{key, token};
 use anoma::vm::prefix_iter::PrefixIterators;
 use anoma::vm::wasm::{self, TxCache, VpCache};
@@ -34,6 +36,7 @@ pub struct TestTxEnv {
     pub verifiers: BTreeSet<Address>,
     pub gas_meter: BlockGasMeter,
     pub result_buffer: Option<Vec<u8>>,
+    pub tx_code_hash: Hash,
     pub vp_wasm_cache: VpCache<WasmCacheRwAccess>,
     pub vp_cache_dir: TempDir,
     pub tx_wasm_cache: TxCache<WasmCacheRwAccess>,
@@ -53,6 +56,7 @@ impl Default for TestTxEnv {
             gas_meter: BlockGasMeter::default(),
             verifiers: BTreeSet::default(),
             result_buffer: None,
+            tx_code_hash: hash_tx(&[]),
             vp_wasm_cache,
             vp_cache_dir,
             tx_wasm_cache,
@@ -87,6 +91,11 @@ impl TestTxEnv {
             &mut self.storage,
             vp_whitelist.unwrap_or_default(),
         );
+        let gas_schedule = self.storage.gas_schedule.clone();
+        let _ = parameters::update_gas_schedule_parameter(
+            &mut self.storage,
+            &gas_schedule,
+        );
     }
 
     /// Fake accounts existence by initializating their VP storage.
@@ -152,6 +161,7 @@ pub fn init_tx_env(
         verifiers,
         gas_meter,
         result_buffer,
+        tx_code_hash,
         vp_wasm_cache,
         vp_cache_dir: _,
         tx_wasm_cache,
@@ -167,6 +177,7 @@ pub fn init_tx_env(
                 verifiers,
                 gas_meter,
                 result_buffer,
+                tx_code_hash,
                 vp_wasm_cache,
                 tx_wasm_cache,
             )
@@ -250,6 +261,14 @@ mod native_tx_host_env {
         val_ptr: u64,
         val_len: u64
     ));
+    native_host_fn!(tx_write_cas(
+        key_ptr: u64,
+        key_len: u64,
+        expected_ptr: u64,
+        expected_len: u64,
+        val_ptr: u64,
+        val_len: u64
+    ) -> i64);
     native_host_fn!(tx_delete(key_ptr: u64, key_len: u64));
     native_host_fn!(tx_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64);
     native_host_fn!(tx_iter_next(iter_id: u64) -> i64);
@@ -265,11 +284,24 @@ mod native_tx_host_env {
         code_len: u64,
         result_ptr: u64
     ));
+    native_host_fn!(tx_init_account_derived(
+        parent_ptr: u64,
+        parent_len: u64,
+        code_ptr: u64,
+        code_len: u64,
+        result_ptr: u64
+    ));
     native_host_fn!(tx_emit_ibc_event(event_ptr: u64, event_len: u64));
     native_host_fn!(tx_get_chain_id(result_ptr: u64));
     native_host_fn!(tx_get_block_height() -> u64);
     native_host_fn!(tx_get_block_time() -> i64);
     native_host_fn!(tx_get_block_hash(result_ptr: u64));
+    native_host_fn!(tx_get_tx_code_hash(result_ptr: u64));
     native_host_fn!(tx_get_block_epoch() -> u64);
+    native_host_fn!(tx_is_internal_address(
+        addr_ptr: u64,
+        addr_len: u64
+    ) -> i64);
     native_host_fn!(tx_log_string(str_ptr: u64, str_len: u64));
+    native_host_fn!(tx_abort(code: u64, msg_ptr: u64, msg_len: u64));
 }
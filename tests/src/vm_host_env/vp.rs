@@ -269,17 +269,27 @@ mod native_vp_host_env {
     // [`anoma_vm_env::imports::vp`] `extern "C"` section.
     native_host_fn!(vp_read_pre(key_ptr: u64, key_len: u64) -> i64);
     native_host_fn!(vp_read_post(key_ptr: u64, key_len: u64) -> i64);
+    native_host_fn!(vp_read_batch_pre(keys_ptr: u64, keys_len: u64) -> i64);
+    native_host_fn!(vp_read_batch_post(keys_ptr: u64, keys_len: u64) -> i64);
+    native_host_fn!(vp_read_vp_pre(addr_ptr: u64, addr_len: u64) -> i64);
+    native_host_fn!(vp_read_vp_post(addr_ptr: u64, addr_len: u64) -> i64);
     native_host_fn!(vp_result_buffer(result_ptr: u64));
     native_host_fn!(vp_has_key_pre(key_ptr: u64, key_len: u64) -> i64);
     native_host_fn!(vp_has_key_post(key_ptr: u64, key_len: u64) -> i64);
     native_host_fn!(vp_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64);
+    native_host_fn!(vp_prefix_count(prefix_ptr: u64, prefix_len: u64) -> i64);
     native_host_fn!(vp_iter_pre_next(iter_id: u64) -> i64);
     native_host_fn!(vp_iter_post_next(iter_id: u64) -> i64);
     native_host_fn!(vp_get_chain_id(result_ptr: u64));
     native_host_fn!(vp_get_block_height() -> u64);
+    native_host_fn!(vp_get_block_time() -> i64);
     native_host_fn!(vp_get_block_hash(result_ptr: u64));
     native_host_fn!(vp_get_tx_code_hash(result_ptr: u64));
     native_host_fn!(vp_get_block_epoch() -> u64);
+    native_host_fn!(vp_is_internal_address(
+        addr_ptr: u64,
+        addr_len: u64
+    ) -> i64);
     native_host_fn!(vp_verify_tx_signature(
             pk_ptr: u64,
             pk_len: u64,
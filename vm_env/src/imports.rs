@@ -57,8 +57,9 @@ pub mod tx {
     use anoma::types::address;
     use anoma::types::address::Address;
     use anoma::types::chain::CHAIN_ID_LENGTH;
+    use anoma::types::hash::{Hash, HASH_LENGTH};
     use anoma::types::ibc::IbcEvent;
-    use anoma::types::internal::HostEnvResult;
+    use anoma::types::internal::{HostEnvResult, InternalAddressKind};
     use anoma::types::storage::{
         BlockHash, BlockHeight, Epoch, BLOCK_HASH_LENGTH,
     };
@@ -134,6 +135,42 @@ pub mod tx {
         };
     }
 
+    /// Write a value to storage at the given key only if its current value is
+    /// equal to the expected value. Returns `true` if the value has been
+    /// written, or `false` if the current value doesn't match or if the key
+    /// is not present.
+    pub fn write_cas<T: BorshSerialize>(
+        key: impl AsRef<str>,
+        expected: T,
+        val: T,
+    ) -> bool {
+        let expected = expected.try_to_vec().unwrap();
+        let val = val.try_to_vec().unwrap();
+        write_bytes_cas(key, expected, val)
+    }
+
+    /// Write a value as bytes to storage at the given key only if its current
+    /// value is equal to the expected bytes. Returns `true` if the value has
+    /// been written.
+    pub fn write_bytes_cas(
+        key: impl AsRef<str>,
+        expected: impl AsRef<[u8]>,
+        val: impl AsRef<[u8]>,
+    ) -> bool {
+        let key = key.as_ref();
+        let written = unsafe {
+            anoma_tx_write_cas(
+                key.as_ptr() as _,
+                key.len() as _,
+                expected.as_ref().as_ptr() as _,
+                expected.as_ref().len() as _,
+                val.as_ref().as_ptr() as _,
+                val.as_ref().len() as _,
+            )
+        };
+        HostEnvResult::is_success(written)
+    }
+
     /// Delete a value at the given key from storage.
     pub fn delete(key: impl AsRef<str>) {
         let key = key.as_ref();
@@ -212,6 +249,34 @@ pub mod tx {
             .expect("Decoding address created by the ledger shouldn't fail")
     }
 
+    // Initialize a new account with an address derived from the given parent
+    // address and the code of its validity predicate
+    pub fn init_account_derived(
+        parent: &Address,
+        code: impl AsRef<[u8]>,
+    ) -> Address {
+        let parent = parent.encode();
+        let code = code.as_ref();
+        let result = Vec::with_capacity(address::ESTABLISHED_ADDRESS_BYTES_LEN);
+        unsafe {
+            anoma_tx_init_account_derived(
+                parent.as_ptr() as _,
+                parent.len() as _,
+                code.as_ptr() as _,
+                code.len() as _,
+                result.as_ptr() as _,
+            )
+        };
+        let slice = unsafe {
+            slice::from_raw_parts(
+                result.as_ptr(),
+                address::ESTABLISHED_ADDRESS_BYTES_LEN,
+            )
+        };
+        Address::try_from_slice(slice)
+            .expect("Decoding address created by the ledger shouldn't fail")
+    }
+
     /// Emit an IBC event. There can be only one event per transaction. On
     /// multiple calls, only the last emitted event will be used.
     pub fn emit_ibc_event(event: &IbcEvent) {
@@ -261,11 +326,33 @@ pub mod tx {
         BlockHash::try_from(slice).expect("Cannot convert the hash")
     }
 
+    /// Get the SHA-256 hash of the code of the current transaction
+    pub fn get_tx_code_hash() -> Hash {
+        let result = Vec::with_capacity(HASH_LENGTH);
+        unsafe {
+            anoma_tx_get_tx_code_hash(result.as_ptr() as _);
+        }
+        let slice =
+            unsafe { slice::from_raw_parts(result.as_ptr(), HASH_LENGTH) };
+        Hash::try_from(slice).expect("Cannot convert the hash")
+    }
+
     /// Get epoch of the current block
     pub fn get_block_epoch() -> Epoch {
         Epoch(unsafe { anoma_tx_get_block_epoch() })
     }
 
+    /// Check if the given address is an internal address and which kind of
+    /// internal address it is
+    pub fn is_internal_address(addr: &Address) -> InternalAddressKind {
+        let addr = addr.encode();
+        let kind = unsafe {
+            anoma_tx_is_internal_address(addr.as_ptr() as _, addr.len() as _)
+        };
+        InternalAddressKind::from_i64(kind)
+            .expect("The host should return a valid internal address kind")
+    }
+
     /// Log a string. The message will be printed at the `tracing::Level::Info`.
     pub fn log_string<T: AsRef<str>>(msg: T) {
         let msg = msg.as_ref();
@@ -274,6 +361,17 @@ pub mod tx {
         }
     }
 
+    /// Abort the transaction with the given error code and message. Any
+    /// modifications made by the transaction are dropped and the code and
+    /// message are included in the transaction's result.
+    pub fn abort<T: AsRef<str>>(code: u64, msg: T) -> ! {
+        let msg = msg.as_ref();
+        unsafe {
+            anoma_tx_abort(code, msg.as_ptr() as _, msg.len() as _);
+        }
+        unreachable!("The host should stop the execution of an aborted tx")
+    }
+
     /// These host functions are implemented in the Anoma's [`host_env`]
     /// module. The environment provides calls to them via this C interface.
     extern "C" {
@@ -306,6 +404,17 @@ pub mod tx {
             val_len: u64,
         );
 
+        // Write key/value only if the current value is equal to the expected
+        // value. Returns 1 if the value has been written, or -1 otherwise.
+        fn anoma_tx_write_cas(
+            key_ptr: u64,
+            key_len: u64,
+            expected_ptr: u64,
+            expected_len: u64,
+            val_ptr: u64,
+            val_len: u64,
+        ) -> i64;
+
         // Delete the given key and its value
         fn anoma_tx_delete(key_ptr: u64, key_len: u64);
 
@@ -332,6 +441,15 @@ pub mod tx {
         // Initialize a new account
         fn anoma_tx_init_account(code_ptr: u64, code_len: u64, result_ptr: u64);
 
+        // Initialize a new account with an address derived from the parent
+        fn anoma_tx_init_account_derived(
+            parent_ptr: u64,
+            parent_len: u64,
+            code_ptr: u64,
+            code_len: u64,
+            result_ptr: u64,
+        );
+
         // Emit an IBC event
         fn anoma_tx_emit_ibc_event(event_ptr: u64, event_len: u64);
 
@@ -347,11 +465,20 @@ pub mod tx {
         // Get the current block hash
         fn anoma_tx_get_block_hash(result_ptr: u64);
 
+        // Get the current tx code hash
+        fn anoma_tx_get_tx_code_hash(result_ptr: u64);
+
         // Get the current block epoch
         fn anoma_tx_get_block_epoch() -> u64;
 
+        // Get the kind of the given address, see `InternalAddressKind`
+        fn anoma_tx_is_internal_address(addr_ptr: u64, addr_len: u64) -> i64;
+
         // Requires a node running with "Info" log level
         fn anoma_tx_log_string(str_ptr: u64, str_len: u64);
+
+        // Abort the tx with the given error code and message
+        fn anoma_tx_abort(code: u64, msg_ptr: u64, msg_len: u64);
     }
 }
 
@@ -361,13 +488,15 @@ pub mod vp {
     use std::convert::TryFrom;
     use std::marker::PhantomData;
 
+    use anoma::types::address::Address;
     use anoma::types::chain::CHAIN_ID_LENGTH;
     use anoma::types::hash::{Hash, HASH_LENGTH};
-    use anoma::types::internal::HostEnvResult;
+    use anoma::types::internal::{HostEnvResult, InternalAddressKind};
     use anoma::types::key::*;
     use anoma::types::storage::{
         BlockHash, BlockHeight, Epoch, BLOCK_HASH_LENGTH,
     };
+    use anoma::types::time::Rfc3339String;
     pub use borsh::{BorshDeserialize, BorshSerialize};
 
     pub struct PreKeyValIterator<T>(pub u64, pub PhantomData<T>);
@@ -431,6 +560,68 @@ pub mod vp {
         super::read_from_buffer(read_result, anoma_vp_result_buffer)
     }
 
+    /// Read variable-length values as bytes at the given keys from storage
+    /// before transaction execution in a single host call. The values are
+    /// returned in the same order as the keys.
+    pub fn read_batch_bytes_pre(
+        keys: &[impl AsRef<str>],
+    ) -> Vec<Option<Vec<u8>>> {
+        let keys = encode_batch_keys(keys);
+        let read_result = unsafe {
+            anoma_vp_read_batch_pre(keys.as_ptr() as _, keys.len() as _)
+        };
+        decode_batch_values(read_result)
+    }
+
+    /// Read variable-length values as bytes at the given keys from storage
+    /// after transaction execution in a single host call. The values are
+    /// returned in the same order as the keys.
+    pub fn read_batch_bytes_post(
+        keys: &[impl AsRef<str>],
+    ) -> Vec<Option<Vec<u8>>> {
+        let keys = encode_batch_keys(keys);
+        let read_result = unsafe {
+            anoma_vp_read_batch_post(keys.as_ptr() as _, keys.len() as _)
+        };
+        decode_batch_values(read_result)
+    }
+
+    fn encode_batch_keys(keys: &[impl AsRef<str>]) -> Vec<u8> {
+        keys.iter()
+            .map(|key| key.as_ref().to_owned())
+            .collect::<Vec<String>>()
+            .try_to_vec()
+            .unwrap()
+    }
+
+    fn decode_batch_values(read_result: i64) -> Vec<Option<Vec<u8>>> {
+        let values =
+            super::read_from_buffer(read_result, anoma_vp_result_buffer)
+                .expect("The batch read values should be present");
+        BorshDeserialize::try_from_slice(&values[..])
+            .expect("The batch read values should be decodable")
+    }
+
+    /// Try to read the validity predicate code of the given address from
+    /// storage before transaction execution.
+    pub fn read_vp_pre(addr: &Address) -> Option<Vec<u8>> {
+        let addr = addr.encode();
+        let read_result = unsafe {
+            anoma_vp_read_vp_pre(addr.as_ptr() as _, addr.len() as _)
+        };
+        super::read_from_buffer(read_result, anoma_vp_result_buffer)
+    }
+
+    /// Try to read the validity predicate code of the given address from
+    /// storage after transaction execution.
+    pub fn read_vp_post(addr: &Address) -> Option<Vec<u8>> {
+        let addr = addr.encode();
+        let read_result = unsafe {
+            anoma_vp_read_vp_post(addr.as_ptr() as _, addr.len() as _)
+        };
+        super::read_from_buffer(read_result, anoma_vp_result_buffer)
+    }
+
     /// Check if the given key was present in storage before transaction
     /// execution.
     pub fn has_key_pre(key: impl AsRef<str>) -> bool {
@@ -489,6 +680,19 @@ pub mod vp {
         }
     }
 
+    /// Count the keys with the given prefix in posterior state (after tx
+    /// execution), without reading their values. Unlike
+    /// [`iter_prefix_post`], the keys that are written by the tx, but are not
+    /// yet in the storage are counted too.
+    pub fn prefix_count(prefix: impl AsRef<str>) -> u64 {
+        let prefix = prefix.as_ref();
+        let count = unsafe {
+            anoma_vp_prefix_count(prefix.as_ptr() as _, prefix.len() as _)
+        };
+        u64::try_from(count)
+            .expect("The host should return a non-negative count")
+    }
+
     /// Get the chain ID
     pub fn get_chain_id() -> String {
         let result = Vec::with_capacity(CHAIN_ID_LENGTH);
@@ -505,6 +709,18 @@ pub mod vp {
         BlockHeight(unsafe { anoma_vp_get_block_height() })
     }
 
+    /// Get time of the current block header as rfc 3339 string
+    pub fn get_block_time() -> Rfc3339String {
+        let read_result = unsafe { anoma_vp_get_block_time() };
+        let time_value =
+            super::read_from_buffer(read_result, anoma_vp_result_buffer)
+                .expect("The block time should exist");
+        Rfc3339String(
+            String::try_from_slice(&time_value[..])
+                .expect("The conversion shouldn't fail"),
+        )
+    }
+
     /// Get a block hash
     pub fn get_block_hash() -> BlockHash {
         let result = Vec::with_capacity(BLOCK_HASH_LENGTH);
@@ -533,6 +749,17 @@ pub mod vp {
         Epoch(unsafe { anoma_vp_get_block_epoch() })
     }
 
+    /// Check if the given address is an internal address and which kind of
+    /// internal address it is
+    pub fn is_internal_address(addr: &Address) -> InternalAddressKind {
+        let addr = addr.encode();
+        let kind = unsafe {
+            anoma_vp_is_internal_address(addr.as_ptr() as _, addr.len() as _)
+        };
+        InternalAddressKind::from_i64(kind)
+            .expect("The host should return a valid internal address kind")
+    }
+
     /// Verify a transaction signature. The signature is expected to have been
     /// produced on the encoded transaction [`anoma::proto::Tx`]
     /// using [`anoma::proto::Tx::sign`].
@@ -603,6 +830,30 @@ pub mod vp {
         // we know its size.
         fn anoma_vp_read_temp(key_ptr: u64, key_len: u64) -> i64;
 
+        // Read variable-length prior state of the Borsh encoded `Vec<String>`
+        // of keys, returns the size of the Borsh encoded
+        // `Vec<Option<Vec<u8>>>` of values, which will be placed in the
+        // result buffer.
+        fn anoma_vp_read_batch_pre(keys_ptr: u64, keys_len: u64) -> i64;
+
+        // Read variable-length posterior state of the Borsh encoded
+        // `Vec<String>` of keys, returns the size of the Borsh encoded
+        // `Vec<Option<Vec<u8>>>` of values, which will be placed in the
+        // result buffer.
+        fn anoma_vp_read_batch_post(keys_ptr: u64, keys_len: u64) -> i64;
+
+        // Read the validity predicate code of the given address in prior
+        // state, returns the size of the code, or -1 if the address doesn't
+        // have a validity predicate. If found, the code will be placed in
+        // the result buffer.
+        fn anoma_vp_read_vp_pre(addr_ptr: u64, addr_len: u64) -> i64;
+
+        // Read the validity predicate code of the given address in posterior
+        // state, returns the size of the code, or -1 if the address doesn't
+        // have a validity predicate. If found, the code will be placed in
+        // the result buffer.
+        fn anoma_vp_read_vp_post(addr_ptr: u64, addr_len: u64) -> i64;
+
         // Read a value from result buffer.
         fn anoma_vp_result_buffer(result_ptr: u64);
 
@@ -615,6 +866,9 @@ pub mod vp {
         // Get an ID of a data iterator with key prefix
         fn anoma_vp_iter_prefix(prefix_ptr: u64, prefix_len: u64) -> u64;
 
+        // Returns the number of keys with the given prefix in posterior state
+        fn anoma_vp_prefix_count(prefix_ptr: u64, prefix_len: u64) -> i64;
+
         // Read variable-length prior state when we don't know the size
         // up-front, returns the size of the value (can be 0), or -1 if
         // the key is not present. If a value is found, it will be placed in the
@@ -635,6 +889,9 @@ pub mod vp {
         // Get the current block height
         fn anoma_vp_get_block_height() -> u64;
 
+        // Get the time of the current block header
+        fn anoma_vp_get_block_time() -> i64;
+
         // Get the current block hash
         fn anoma_vp_get_block_hash(result_ptr: u64);
 
@@ -644,6 +901,9 @@ pub mod vp {
         // Get the current block epoch
         fn anoma_vp_get_block_epoch() -> u64;
 
+        // Get the kind of the given address, see `InternalAddressKind`
+        fn anoma_vp_is_internal_address(addr_ptr: u64, addr_len: u64) -> i64;
+
         // Verify a transaction signature
         fn anoma_vp_verify_tx_signature(
             pk_ptr: u64,